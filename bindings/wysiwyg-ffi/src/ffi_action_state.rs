@@ -10,7 +10,30 @@ impl From<&wysiwyg::ActionState> for ActionState {
         match inner {
             wysiwyg::ActionState::Enabled => Self::Enabled,
             wysiwyg::ActionState::Reversed => Self::Reversed,
-            wysiwyg::ActionState::Disabled => Self::Disabled,
+            wysiwyg::ActionState::Disabled(_) => Self::Disabled,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DisabledReason {
+    NothingToUndo,
+    NothingToRedo,
+    NotInList,
+    FirstListItem,
+    MaxListDepth,
+    NotInNestedList,
+}
+
+impl From<wysiwyg::DisabledReason> for DisabledReason {
+    fn from(inner: wysiwyg::DisabledReason) -> Self {
+        match inner {
+            wysiwyg::DisabledReason::NothingToUndo => Self::NothingToUndo,
+            wysiwyg::DisabledReason::NothingToRedo => Self::NothingToRedo,
+            wysiwyg::DisabledReason::NotInList => Self::NotInList,
+            wysiwyg::DisabledReason::FirstListItem => Self::FirstListItem,
+            wysiwyg::DisabledReason::MaxListDepth => Self::MaxListDepth,
+            wysiwyg::DisabledReason::NotInNestedList => Self::NotInNestedList,
         }
     }
 }
@@ -0,0 +1,18 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlockType {
+    Paragraph,
+    OrderedList,
+    UnorderedList,
+    TableCell,
+}
+
+impl From<wysiwyg::BlockType> for BlockType {
+    fn from(block_type: wysiwyg::BlockType) -> Self {
+        match block_type {
+            wysiwyg::BlockType::Paragraph => Self::Paragraph,
+            wysiwyg::BlockType::OrderedList => Self::OrderedList,
+            wysiwyg::BlockType::UnorderedList => Self::UnorderedList,
+            wysiwyg::BlockType::TableCell => Self::TableCell,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use widestring::Utf16String;
+
+pub struct ClipboardContent {
+    pub html: String,
+    pub text: String,
+}
+
+impl From<wysiwyg::ClipboardContent<Utf16String>> for ClipboardContent {
+    fn from(content: wysiwyg::ClipboardContent<Utf16String>) -> Self {
+        Self {
+            html: content.html.to_string(),
+            text: content.text.to_string(),
+        }
+    }
+}
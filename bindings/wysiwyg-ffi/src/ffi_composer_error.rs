@@ -0,0 +1,40 @@
+use std::fmt;
+
+/// Why the composer refused to apply an action. The message describes
+/// the problem for logging.
+#[derive(Debug)]
+pub enum ComposerError {
+    UnsupportedSelection(String),
+    InvalidDom(String),
+    InvalidSavedState(String),
+}
+
+impl std::error::Error for ComposerError {}
+
+impl fmt::Display for ComposerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedSelection(message)
+            | Self::InvalidDom(message)
+            | Self::InvalidSavedState(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<wysiwyg::ComposerError> for ComposerError {
+    fn from(inner: wysiwyg::ComposerError) -> Self {
+        let message = inner.to_string();
+        match inner {
+            wysiwyg::ComposerError::UnsupportedSelection(_) => {
+                Self::UnsupportedSelection(message)
+            }
+            wysiwyg::ComposerError::Dom(_) => Self::InvalidDom(message),
+        }
+    }
+}
+
+impl From<wysiwyg::DecodeError> for ComposerError {
+    fn from(inner: wysiwyg::DecodeError) -> Self {
+        Self::InvalidSavedState(inner.to_string())
+    }
+}
@@ -0,0 +1,29 @@
+use widestring::Utf16String;
+
+use crate::ffi_composer_state::ComposerState;
+
+pub struct ComposerHistory {
+    pub previous: Vec<ComposerState>,
+    pub current: ComposerState,
+    pub next: Vec<ComposerState>,
+}
+
+impl From<wysiwyg::ComposerHistory<Utf16String>> for ComposerHistory {
+    fn from(history: wysiwyg::ComposerHistory<Utf16String>) -> Self {
+        Self {
+            previous: history.previous.into_iter().map(Into::into).collect(),
+            current: history.current.into(),
+            next: history.next.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ComposerHistory> for wysiwyg::ComposerHistory<Utf16String> {
+    fn from(history: ComposerHistory) -> Self {
+        Self {
+            previous: history.previous.into_iter().map(Into::into).collect(),
+            current: history.current.into(),
+            next: history.next.into_iter().map(Into::into).collect(),
+        }
+    }
+}
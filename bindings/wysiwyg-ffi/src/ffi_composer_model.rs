@@ -3,10 +3,25 @@ use std::sync::{Arc, Mutex};
 
 use widestring::Utf16String;
 
+use crate::ffi_clipboard_content::ClipboardContent;
+use crate::ffi_composer_error::ComposerError;
+use crate::ffi_composer_history::ComposerHistory;
 use crate::ffi_composer_state::ComposerState;
 use crate::ffi_composer_update::ComposerUpdate;
+use crate::ffi_content_counts::ContentCounts;
+use crate::ffi_link_at_selection::LinkAtSelection;
+use crate::ffi_menu_action::SuggestionPattern;
+use crate::ffi_parse_error::ParseCorrection;
+use crate::ffi_sanitize_policy::SanitizePolicy;
+use crate::ffi_sanitize_report::SanitizeReport;
+use crate::ffi_shortcut::Shortcut;
+use crate::ffi_suggestion_trigger::SuggestionTrigger;
+use crate::ffi_text_range::TextRange;
 use crate::into_ffi::IntoFfi;
-use crate::{ActionState, ComposerAction};
+use crate::{
+    ActionState, ComposerAction, DisabledReason, EnterMode, ListNumberingType,
+    MoveDirection, MoveGranularity, TextCase,
+};
 
 pub struct ComposerModel {
     inner: Mutex<wysiwyg::ComposerModel<Utf16String>>,
@@ -29,6 +44,29 @@ impl ComposerModel {
         ))
     }
 
+    pub fn set_content_from_html_lenient(
+        self: &Arc<Self>,
+        html: String,
+    ) -> Arc<ComposerUpdate> {
+        let html = Utf16String::from_str(&html);
+        Arc::new(ComposerUpdate::from(
+            self.inner
+                .lock()
+                .unwrap()
+                .set_content_from_html_lenient(&html),
+        ))
+    }
+
+    pub fn last_parse_corrections(self: &Arc<Self>) -> Vec<ParseCorrection> {
+        self.inner
+            .lock()
+            .unwrap()
+            .last_parse_corrections()
+            .iter()
+            .map(ParseCorrection::from)
+            .collect()
+    }
+
     pub fn set_content_from_markdown(
         self: &Arc<Self>,
         markdown: String,
@@ -54,6 +92,18 @@ impl ComposerModel {
             .to_string()
     }
 
+    pub fn get_selected_html(self: &Arc<Self>) -> String {
+        self.inner.lock().unwrap().get_selected_html().to_string()
+    }
+
+    pub fn copy(self: &Arc<Self>) -> ClipboardContent {
+        ClipboardContent::from(self.inner.lock().unwrap().copy())
+    }
+
+    pub fn cut(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().cut()))
+    }
+
     pub fn clear(self: &Arc<Self>) -> Arc<ComposerUpdate> {
         Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().clear()))
     }
@@ -75,6 +125,83 @@ impl ComposerModel {
         ))
     }
 
+    pub fn utf16_codeunits_to_graphemes(
+        self: &Arc<Self>,
+        utf16_codeunit: u32,
+    ) -> u32 {
+        let location =
+            wysiwyg::Location::from(usize::try_from(utf16_codeunit).unwrap());
+        let graphemes = self
+            .inner
+            .lock()
+            .unwrap()
+            .state
+            .dom
+            .grapheme_location(location);
+        u32::try_from(usize::from(graphemes)).unwrap()
+    }
+
+    pub fn graphemes_to_utf16_codeunits(
+        self: &Arc<Self>,
+        grapheme: u32,
+    ) -> u32 {
+        let location =
+            wysiwyg::GraphemeLocation::from(usize::try_from(grapheme).unwrap());
+        let code_units = self
+            .inner
+            .lock()
+            .unwrap()
+            .state
+            .dom
+            .location_of_grapheme(location);
+        u32::try_from(usize::from(code_units)).unwrap()
+    }
+
+    pub fn select_word(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().select_word(),
+        ))
+    }
+
+    pub fn select_block(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().select_block(),
+        ))
+    }
+
+    pub fn select_all(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().select_all(),
+        ))
+    }
+
+    pub fn collapse_to_start(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().collapse_to_start(),
+        ))
+    }
+
+    pub fn collapse_to_end(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().collapse_to_end(),
+        ))
+    }
+
+    pub fn move_cursor(
+        self: &Arc<Self>,
+        direction: MoveDirection,
+        granularity: MoveGranularity,
+        extend: bool,
+    ) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().move_cursor(
+                direction.into(),
+                granularity.into(),
+                extend,
+            ),
+        ))
+    }
+
     pub fn replace_text(
         self: &Arc<Self>,
         new_text: String,
@@ -104,6 +231,105 @@ impl ComposerModel {
         ))
     }
 
+    pub fn paste_html(self: &Arc<Self>, html: String) -> Arc<ComposerUpdate> {
+        let html = Utf16String::from_str(&html);
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().paste_html(&html),
+        ))
+    }
+
+    pub fn paste_text(self: &Arc<Self>, text: String) -> Arc<ComposerUpdate> {
+        let text = Utf16String::from_str(&text);
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().paste_text(&text),
+        ))
+    }
+
+    pub fn insert_html_at_cursor(
+        self: &Arc<Self>,
+        html: String,
+    ) -> Arc<ComposerUpdate> {
+        let html = Utf16String::from_str(&html);
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().insert_html_at_cursor(&html),
+        ))
+    }
+
+    pub fn set_composition(
+        self: &Arc<Self>,
+        text: String,
+        start: u32,
+        end: u32,
+    ) -> Arc<ComposerUpdate> {
+        let start = usize::try_from(start).unwrap();
+        let end = usize::try_from(end).unwrap();
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().set_composition(
+                Utf16String::from_str(&text),
+                start,
+                end,
+            ),
+        ))
+    }
+
+    pub fn commit_composition(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().commit_composition(),
+        ))
+    }
+
+    pub fn get_composition(self: &Arc<Self>) -> Option<TextRange> {
+        self.inner
+            .lock()
+            .unwrap()
+            .get_composition()
+            .map(TextRange::from)
+    }
+
+    pub fn find(self: &Arc<Self>, pattern: String) -> Vec<TextRange> {
+        self.inner
+            .lock()
+            .unwrap()
+            .find(&Utf16String::from_str(&pattern))
+            .into_iter()
+            .map(TextRange::from)
+            .collect()
+    }
+
+    pub fn replace_all(
+        self: &Arc<Self>,
+        pattern: String,
+        replacement: String,
+    ) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().replace_all(
+                &Utf16String::from_str(&pattern),
+                &Utf16String::from_str(&replacement),
+            ),
+        ))
+    }
+
+    pub fn transform_case(
+        self: &Arc<Self>,
+        case: TextCase,
+        locale: Option<String>,
+    ) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner
+                .lock()
+                .unwrap()
+                .transform_case_in_locale(case.into(), locale.as_deref()),
+        ))
+    }
+
+    pub fn counts(self: &Arc<Self>) -> ContentCounts {
+        ContentCounts::from(self.inner.lock().unwrap().counts())
+    }
+
+    pub fn is_content_empty(self: &Arc<Self>) -> bool {
+        self.inner.lock().unwrap().is_content_empty()
+    }
+
     pub fn backspace(self: &Arc<Self>) -> Arc<ComposerUpdate> {
         Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().backspace()))
     }
@@ -112,6 +338,30 @@ impl ComposerModel {
         Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().delete()))
     }
 
+    pub fn delete_word_backward(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().delete_word_backward(),
+        ))
+    }
+
+    pub fn delete_word_forward(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().delete_word_forward(),
+        ))
+    }
+
+    pub fn delete_to_start_of_block(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().delete_to_start_of_block(),
+        ))
+    }
+
+    pub fn delete_to_end_of_block(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().delete_to_end_of_block(),
+        ))
+    }
+
     pub fn delete_in(
         self: &Arc<Self>,
         start: u32,
@@ -128,6 +378,44 @@ impl ComposerModel {
         Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().enter()))
     }
 
+    pub fn shift_enter(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().shift_enter(),
+        ))
+    }
+
+    pub fn insert_soft_break(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().insert_soft_break(),
+        ))
+    }
+
+    pub fn set_enter_mode(self: &Arc<Self>, enter_mode: EnterMode) {
+        self.inner.lock().unwrap().set_enter_mode(enter_mode.into());
+    }
+
+    pub fn set_nfc_normalization(self: &Arc<Self>, enabled: bool) {
+        self.inner.lock().unwrap().set_nfc_normalization(enabled);
+    }
+
+    pub fn set_matrix_sanitization(self: &Arc<Self>, enabled: bool) {
+        self.inner.lock().unwrap().set_matrix_sanitization(enabled);
+    }
+
+    pub fn set_sanitize_policy(
+        self: &Arc<Self>,
+        policy: Option<SanitizePolicy>,
+    ) {
+        self.inner
+            .lock()
+            .unwrap()
+            .set_sanitize_policy(policy.map(wysiwyg::SanitizePolicy::from));
+    }
+
+    pub fn last_sanitize_report(self: &Arc<Self>) -> SanitizeReport {
+        SanitizeReport::from(self.inner.lock().unwrap().last_sanitize_report())
+    }
+
     pub fn bold(self: &Arc<Self>) -> Arc<ComposerUpdate> {
         Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().bold()))
     }
@@ -152,16 +440,18 @@ impl ComposerModel {
         ))
     }
 
-    pub fn ordered_list(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().ordered_list(),
-        ))
+    pub fn ordered_list(
+        self: &Arc<Self>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().ordered_list()?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
     }
 
-    pub fn unordered_list(self: &Arc<Self>) -> Arc<ComposerUpdate> {
-        Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().unordered_list(),
-        ))
+    pub fn unordered_list(
+        self: &Arc<Self>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().unordered_list()?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
     }
 
     pub fn undo(self: &Arc<Self>) -> Arc<ComposerUpdate> {
@@ -172,10 +462,219 @@ impl ComposerModel {
         Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().redo()))
     }
 
-    pub fn set_link(self: &Arc<Self>, link: String) -> Arc<ComposerUpdate> {
+    pub fn begin_transaction(self: &Arc<Self>) {
+        self.inner.lock().unwrap().begin_transaction();
+    }
+
+    pub fn end_transaction(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().end_transaction(),
+        ))
+    }
+
+    pub fn mark_typing_pause(self: &Arc<Self>) {
+        self.inner.lock().unwrap().mark_typing_pause();
+    }
+
+    pub fn set_history_limit(
+        self: &Arc<Self>,
+        history_limit: Option<u32>,
+    ) -> Arc<ComposerUpdate> {
+        let history_limit =
+            history_limit.map(|limit| usize::try_from(limit).unwrap());
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().set_history_limit(history_limit),
+        ))
+    }
+
+    pub fn history_depth(self: &Arc<Self>) -> u32 {
+        u32::try_from(self.inner.lock().unwrap().history_depth()).unwrap()
+    }
+
+    pub fn can_undo(self: &Arc<Self>) -> bool {
+        self.inner.lock().unwrap().can_undo()
+    }
+
+    pub fn can_redo(self: &Arc<Self>) -> bool {
+        self.inner.lock().unwrap().can_redo()
+    }
+
+    pub fn clear_history(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().clear_history(),
+        ))
+    }
+
+    pub fn export_history(self: &Arc<Self>) -> ComposerHistory {
+        self.inner.lock().unwrap().export_history().into()
+    }
+
+    pub fn import_history(
+        self: &Arc<Self>,
+        history: ComposerHistory,
+    ) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().import_history(&history.into()),
+        ))
+    }
+
+    pub fn to_bytes(self: &Arc<Self>, include_history: bool) -> Vec<u8> {
+        self.inner.lock().unwrap().to_bytes(include_history)
+    }
+
+    pub fn restore_from_bytes(
+        self: &Arc<Self>,
+        bytes: Vec<u8>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().restore_from_bytes(&bytes)?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn checkpoint(self: &Arc<Self>, label: String) {
+        let label = Utf16String::from_str(&label);
+        self.inner.lock().unwrap().checkpoint(&label);
+    }
+
+    pub fn revert_to_checkpoint(
+        self: &Arc<Self>,
+        label: String,
+    ) -> Arc<ComposerUpdate> {
+        let label = Utf16String::from_str(&label);
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().revert_to_checkpoint(&label),
+        ))
+    }
+
+    pub fn set_link(
+        self: &Arc<Self>,
+        link: String,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
         let link = Utf16String::from_str(&link);
+        let update = self.inner.lock().unwrap().set_link(link)?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn set_link_with_text(
+        self: &Arc<Self>,
+        link: String,
+        text: String,
+    ) -> Arc<ComposerUpdate> {
+        let link = Utf16String::from_str(&link);
+        let text = Utf16String::from_str(&text);
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().set_link_with_text(link, text),
+        ))
+    }
+
+    pub fn remove_links(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().remove_links(),
+        ))
+    }
+
+    pub fn insert_mention(
+        self: &Arc<Self>,
+        url: String,
+        display_text: String,
+    ) -> Arc<ComposerUpdate> {
+        let url = Utf16String::from_str(&url);
+        let display_text = Utf16String::from_str(&display_text);
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().insert_mention(url, display_text),
+        ))
+    }
+
+    pub fn insert_image(
+        self: &Arc<Self>,
+        attributes: HashMap<String, String>,
+    ) -> Arc<ComposerUpdate> {
+        let attributes = attributes
+            .iter()
+            .map(|(n, v)| (Utf16String::from_str(n), Utf16String::from_str(v)))
+            .collect();
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().insert_image(attributes),
+        ))
+    }
+
+    pub fn insert_table(
+        self: &Arc<Self>,
+        rows: u32,
+        cols: u32,
+    ) -> Arc<ComposerUpdate> {
+        let rows = usize::try_from(rows).unwrap();
+        let cols = usize::try_from(cols).unwrap();
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().insert_table(rows, cols),
+        ))
+    }
+
+    pub fn table_next_cell(self: &Arc<Self>) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().table_next_cell(),
+        ))
+    }
+
+    pub fn table_previous_cell(self: &Arc<Self>) -> Arc<ComposerUpdate> {
         Arc::new(ComposerUpdate::from(
-            self.inner.lock().unwrap().set_link(link),
+            self.inner.lock().unwrap().table_previous_cell(),
+        ))
+    }
+
+    pub fn table_insert_row_before(
+        self: &Arc<Self>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().table_insert_row_before()?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn table_insert_row_after(
+        self: &Arc<Self>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().table_insert_row_after()?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn table_insert_column(
+        self: &Arc<Self>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().table_insert_column()?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn table_delete_row(
+        self: &Arc<Self>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().table_delete_row()?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn table_delete_column(
+        self: &Arc<Self>,
+    ) -> Result<Arc<ComposerUpdate>, ComposerError> {
+        let update = self.inner.lock().unwrap().table_delete_column()?;
+        Ok(Arc::new(ComposerUpdate::from(update)))
+    }
+
+    pub fn set_ordered_list_start(
+        self: &Arc<Self>,
+        start: u32,
+    ) -> Arc<ComposerUpdate> {
+        let start = usize::try_from(start).unwrap();
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().set_ordered_list_start(start),
+        ))
+    }
+
+    pub fn set_ordered_list_numbering_type(
+        self: &Arc<Self>,
+        numbering_type: ListNumberingType,
+    ) -> Arc<ComposerUpdate> {
+        Arc::new(ComposerUpdate::from(
+            self.inner
+                .lock()
+                .unwrap()
+                .set_ordered_list_numbering_type(numbering_type.into()),
         ))
     }
 
@@ -187,6 +686,58 @@ impl ComposerModel {
         Arc::new(ComposerUpdate::from(self.inner.lock().unwrap().unindent()))
     }
 
+    pub fn set_max_list_depth(
+        self: &Arc<Self>,
+        max_depth: Option<u32>,
+    ) -> Arc<ComposerUpdate> {
+        let max_depth = max_depth.map(|depth| usize::try_from(depth).unwrap());
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().set_max_list_depth(max_depth),
+        ))
+    }
+
+    pub fn suggestion_triggers(self: &Arc<Self>) -> Vec<SuggestionTrigger> {
+        self.inner
+            .lock()
+            .unwrap()
+            .suggestion_triggers()
+            .iter()
+            .map(SuggestionTrigger::from)
+            .collect()
+    }
+
+    pub fn set_suggestion_triggers(
+        self: &Arc<Self>,
+        triggers: Vec<SuggestionTrigger>,
+    ) -> Arc<ComposerUpdate> {
+        let triggers = triggers.into_iter().map(|t| t.into()).collect();
+        Arc::new(ComposerUpdate::from(
+            self.inner.lock().unwrap().set_suggestion_triggers(triggers),
+        ))
+    }
+
+    pub fn accept_emoji_suggestion(
+        self: &Arc<Self>,
+        emoji: String,
+        suggestion: SuggestionPattern,
+    ) -> Arc<ComposerUpdate> {
+        let emoji = Utf16String::from_str(&emoji);
+        Arc::new(ComposerUpdate::from(
+            self.inner
+                .lock()
+                .unwrap()
+                .accept_emoji_suggestion(emoji, &suggestion.into()),
+        ))
+    }
+
+    pub fn get_link_at_selection(self: &Arc<Self>) -> Option<LinkAtSelection> {
+        self.inner
+            .lock()
+            .unwrap()
+            .get_link_at_selection()
+            .map(LinkAtSelection::from)
+    }
+
     pub fn to_tree(self: &Arc<Self>) -> String {
         self.inner.lock().unwrap().to_tree().to_string()
     }
@@ -205,4 +756,44 @@ impl ComposerModel {
     ) -> HashMap<ComposerAction, ActionState> {
         self.inner.lock().unwrap().action_states().into_ffi()
     }
+
+    /// Why each disabled action is disabled, e.g. to explain it in a
+    /// tooltip
+    pub fn action_disabled_reasons(
+        self: &Arc<Self>,
+    ) -> HashMap<ComposerAction, DisabledReason> {
+        self.inner
+            .lock()
+            .unwrap()
+            .action_states()
+            .iter()
+            .filter_map(|(action, state)| match state {
+                wysiwyg::ActionState::Disabled(reason) => {
+                    Some((action.into(), (*reason).into()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn shortcuts(self: &Arc<Self>) -> HashMap<ComposerAction, Shortcut> {
+        self.inner
+            .lock()
+            .unwrap()
+            .shortcuts()
+            .iter()
+            .map(|(action, shortcut)| (action.into(), shortcut.into()))
+            .collect()
+    }
+
+    pub fn set_shortcut(
+        self: &Arc<Self>,
+        action: ComposerAction,
+        shortcut: Option<Shortcut>,
+    ) {
+        self.inner
+            .lock()
+            .unwrap()
+            .set_shortcut((&action).into(), shortcut.map(Shortcut::into));
+    }
 }
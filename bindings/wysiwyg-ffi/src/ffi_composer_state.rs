@@ -18,3 +18,23 @@ impl From<wysiwyg::ComposerState<Utf16String>> for ComposerState {
         }
     }
 }
+
+impl From<wysiwyg::HistoryEntry<Utf16String>> for ComposerState {
+    fn from(entry: wysiwyg::HistoryEntry<Utf16String>) -> Self {
+        Self {
+            html: entry.html.into_vec(),
+            start: u32::try_from(entry.start).unwrap(),
+            end: u32::try_from(entry.end).unwrap(),
+        }
+    }
+}
+
+impl From<ComposerState> for wysiwyg::HistoryEntry<Utf16String> {
+    fn from(state: ComposerState) -> Self {
+        Self {
+            html: Utf16String::from_str(&String::from_utf16_lossy(&state.html)),
+            start: usize::try_from(state.start).unwrap(),
+            end: usize::try_from(state.end).unwrap(),
+        }
+    }
+}
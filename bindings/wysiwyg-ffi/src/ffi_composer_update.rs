@@ -1,5 +1,9 @@
 use widestring::Utf16String;
 
+use crate::ffi_clipboard_content::ClipboardContent;
+use crate::ffi_dom_changes::DomChanges;
+use crate::ffi_dom_selection::DomSelection;
+use crate::ffi_menu_action::MenuAction;
 use crate::ffi_menu_state::MenuState;
 use crate::ffi_text_update::TextUpdate;
 
@@ -19,24 +23,45 @@ impl ComposerUpdate {
     pub fn menu_state(&self) -> MenuState {
         MenuState::from(self.inner.menu_state.clone())
     }
+
+    pub fn menu_action(&self) -> MenuAction {
+        self.inner.menu_action.clone().into()
+    }
+
+    pub fn dom_selection(&self) -> Option<DomSelection> {
+        self.inner.dom_selection.clone().map(DomSelection::from)
+    }
+
+    pub fn dom_changes(&self) -> DomChanges {
+        self.inner.dom_changes.clone().into()
+    }
+
+    pub fn clipboard(&self) -> Option<ClipboardContent> {
+        self.inner.clipboard.clone().map(ClipboardContent::from)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::{collections::HashMap, sync::Arc};
 
-    use crate::{ActionState, ComposerAction, ComposerModel, MenuState};
+    use crate::{
+        ActionState, BlockType, ComposerAction, ComposerModel, MenuState,
+    };
 
     #[test]
-    fn initial_menu_update_is_populated() {
+    fn first_menu_update_holds_only_the_changed_actions() {
         let model = Arc::new(ComposerModel::new());
         let update = model.replace_text(String::from(""));
 
-        // Only Redo is disabled
+        // Only Undo became enabled
         assert_eq!(
             update.menu_state(),
-            MenuState::Update {
-                action_states: redo_disabled()
+            MenuState::PartialUpdate {
+                action_states: HashMap::from([(
+                    ComposerAction::Undo,
+                    ActionState::Enabled
+                )]),
             }
         );
     }
@@ -50,7 +75,10 @@ mod test {
         assert_eq!(
             update.menu_state(),
             MenuState::Update {
-                action_states: undo_and_redo_disabled()
+                action_states: undo_and_redo_disabled(),
+                block_type: BlockType::Paragraph,
+                link_url: None,
+                spans_multiple_links: false,
             }
         );
     }
@@ -67,7 +95,10 @@ mod test {
         assert_eq!(
             update.menu_state(),
             MenuState::Update {
-                action_states: undo_and_redo_disabled()
+                action_states: undo_and_redo_disabled(),
+                block_type: BlockType::Paragraph,
+                link_url: None,
+                spans_multiple_links: false,
             }
         );
     }
@@ -81,28 +112,14 @@ mod test {
         assert_eq!(
             update.menu_state(),
             MenuState::Update {
-                action_states: undo_and_redo_disabled()
+                action_states: undo_and_redo_disabled(),
+                block_type: BlockType::Paragraph,
+                link_url: None,
+                spans_multiple_links: false,
             }
         );
     }
 
-    fn redo_disabled() -> HashMap<ComposerAction, ActionState> {
-        HashMap::from([
-            (ComposerAction::Bold, ActionState::Enabled),
-            (ComposerAction::Indent, ActionState::Enabled),
-            (ComposerAction::InlineCode, ActionState::Enabled),
-            (ComposerAction::Italic, ActionState::Enabled),
-            (ComposerAction::Link, ActionState::Enabled),
-            (ComposerAction::OrderedList, ActionState::Enabled),
-            (ComposerAction::Redo, ActionState::Disabled),
-            (ComposerAction::StrikeThrough, ActionState::Enabled),
-            (ComposerAction::UnIndent, ActionState::Enabled),
-            (ComposerAction::Underline, ActionState::Enabled),
-            (ComposerAction::Undo, ActionState::Enabled),
-            (ComposerAction::UnorderedList, ActionState::Enabled),
-        ])
-    }
-
     fn undo_and_redo_disabled() -> HashMap<ComposerAction, ActionState> {
         HashMap::from([
             (ComposerAction::Bold, ActionState::Enabled),
@@ -113,6 +130,7 @@ mod test {
             (ComposerAction::OrderedList, ActionState::Enabled),
             (ComposerAction::Redo, ActionState::Disabled),
             (ComposerAction::StrikeThrough, ActionState::Enabled),
+            (ComposerAction::Table, ActionState::Enabled),
             (ComposerAction::UnIndent, ActionState::Enabled),
             (ComposerAction::Underline, ActionState::Enabled),
             (ComposerAction::Undo, ActionState::Disabled),
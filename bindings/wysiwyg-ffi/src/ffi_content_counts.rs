@@ -0,0 +1,15 @@
+pub struct ContentCounts {
+    pub characters: u32,
+    pub words: u32,
+    pub code_units: u32,
+}
+
+impl From<wysiwyg::ContentCounts> for ContentCounts {
+    fn from(counts: wysiwyg::ContentCounts) -> Self {
+        Self {
+            characters: u32::try_from(counts.characters).unwrap(),
+            words: u32::try_from(counts.words).unwrap(),
+            code_units: u32::try_from(counts.code_units).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MoveDirection {
+    Backward,
+    Forward,
+}
+
+impl From<MoveDirection> for wysiwyg::MoveDirection {
+    fn from(direction: MoveDirection) -> Self {
+        match direction {
+            MoveDirection::Backward => Self::Backward,
+            MoveDirection::Forward => Self::Forward,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MoveGranularity {
+    Character,
+    Word,
+    Block,
+}
+
+impl From<MoveGranularity> for wysiwyg::MoveGranularity {
+    fn from(granularity: MoveGranularity) -> Self {
+        match granularity {
+            MoveGranularity::Character => Self::Character,
+            MoveGranularity::Word => Self::Word,
+            MoveGranularity::Block => Self::Block,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use wysiwyg::DomHandle;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DomChanges {
+    pub created: Vec<Vec<u32>>,
+    pub modified: Vec<Vec<u32>>,
+    pub deleted: Vec<Vec<u32>>,
+}
+
+impl From<wysiwyg::DomChanges> for DomChanges {
+    fn from(changes: wysiwyg::DomChanges) -> Self {
+        Self {
+            created: paths(&changes.created),
+            modified: paths(&changes.modified),
+            deleted: paths(&changes.deleted),
+        }
+    }
+}
+
+fn paths(handles: &[DomHandle]) -> Vec<Vec<u32>> {
+    handles
+        .iter()
+        .map(|handle| {
+            handle
+                .raw()
+                .iter()
+                .map(|i| u32::try_from(*i).unwrap())
+                .collect()
+        })
+        .collect()
+}
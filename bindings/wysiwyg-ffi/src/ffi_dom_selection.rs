@@ -0,0 +1,34 @@
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DomSelection {
+    pub anchor: DomPoint,
+    pub focus: DomPoint,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DomPoint {
+    pub path: Vec<u32>,
+    pub offset: u32,
+}
+
+impl From<wysiwyg::DomSelection> for DomSelection {
+    fn from(selection: wysiwyg::DomSelection) -> Self {
+        Self {
+            anchor: selection.anchor.into(),
+            focus: selection.focus.into(),
+        }
+    }
+}
+
+impl From<wysiwyg::DomPoint> for DomPoint {
+    fn from(point: wysiwyg::DomPoint) -> Self {
+        Self {
+            path: point
+                .handle
+                .raw()
+                .iter()
+                .map(|i| u32::try_from(*i).unwrap())
+                .collect(),
+            offset: u32::try_from(point.offset).unwrap(),
+        }
+    }
+}
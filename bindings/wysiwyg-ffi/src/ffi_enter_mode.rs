@@ -0,0 +1,14 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EnterMode {
+    Document,
+    Message,
+}
+
+impl From<EnterMode> for wysiwyg::EnterMode {
+    fn from(enter_mode: EnterMode) -> Self {
+        match enter_mode {
+            EnterMode::Document => Self::Document,
+            EnterMode::Message => Self::Message,
+        }
+    }
+}
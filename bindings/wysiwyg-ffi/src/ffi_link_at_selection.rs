@@ -0,0 +1,15 @@
+use widestring::Utf16String;
+
+pub struct LinkAtSelection {
+    pub url: String,
+    pub text: String,
+}
+
+impl From<(Utf16String, Utf16String)> for LinkAtSelection {
+    fn from((url, text): (Utf16String, Utf16String)) -> Self {
+        Self {
+            url: url.to_string(),
+            text: text.to_string(),
+        }
+    }
+}
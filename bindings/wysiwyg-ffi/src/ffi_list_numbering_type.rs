@@ -0,0 +1,20 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ListNumberingType {
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl From<ListNumberingType> for wysiwyg::ListNumberingType {
+    fn from(numbering_type: ListNumberingType) -> Self {
+        match numbering_type {
+            ListNumberingType::Decimal => Self::Decimal,
+            ListNumberingType::LowerAlpha => Self::LowerAlpha,
+            ListNumberingType::UpperAlpha => Self::UpperAlpha,
+            ListNumberingType::LowerRoman => Self::LowerRoman,
+            ListNumberingType::UpperRoman => Self::UpperRoman,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+#[derive(Debug, PartialEq)]
+pub enum MenuAction {
+    Keep,
+    None,
+    Suggestion {
+        suggestion_pattern: SuggestionPattern,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuggestionPattern {
+    pub key: PatternKey,
+    pub text: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PatternKey {
+    At,
+    Hash,
+    Slash,
+    Colon,
+    Custom { prefix: String },
+}
+
+impl From<wysiwyg::MenuAction> for MenuAction {
+    fn from(inner: wysiwyg::MenuAction) -> Self {
+        match inner {
+            wysiwyg::MenuAction::Keep => Self::Keep,
+            wysiwyg::MenuAction::None => Self::None,
+            wysiwyg::MenuAction::Suggestion(pattern) => Self::Suggestion {
+                suggestion_pattern: pattern.into(),
+            },
+        }
+    }
+}
+
+impl From<wysiwyg::SuggestionPattern> for SuggestionPattern {
+    fn from(inner: wysiwyg::SuggestionPattern) -> Self {
+        Self {
+            key: inner.key.into(),
+            text: inner.text,
+            start: u32::try_from(inner.start).unwrap(),
+            end: u32::try_from(inner.end).unwrap(),
+        }
+    }
+}
+
+impl From<wysiwyg::PatternKey> for PatternKey {
+    fn from(key: wysiwyg::PatternKey) -> Self {
+        match key {
+            wysiwyg::PatternKey::At => Self::At,
+            wysiwyg::PatternKey::Hash => Self::Hash,
+            wysiwyg::PatternKey::Slash => Self::Slash,
+            wysiwyg::PatternKey::Colon => Self::Colon,
+            wysiwyg::PatternKey::Custom(prefix) => Self::Custom { prefix },
+        }
+    }
+}
+
+impl From<SuggestionPattern> for wysiwyg::SuggestionPattern {
+    fn from(pattern: SuggestionPattern) -> Self {
+        Self {
+            key: pattern.key.into(),
+            text: pattern.text,
+            start: usize::try_from(pattern.start).unwrap(),
+            end: usize::try_from(pattern.end).unwrap(),
+        }
+    }
+}
+
+impl From<PatternKey> for wysiwyg::PatternKey {
+    fn from(key: PatternKey) -> Self {
+        match key {
+            PatternKey::At => Self::At,
+            PatternKey::Hash => Self::Hash,
+            PatternKey::Slash => Self::Slash,
+            PatternKey::Colon => Self::Colon,
+            PatternKey::Custom { prefix } => Self::Custom(prefix),
+        }
+    }
+}
@@ -1,13 +1,19 @@
 use std::collections::HashMap;
 
 use crate::into_ffi::IntoFfi;
-use crate::{ActionState, ComposerAction};
+use crate::{ActionState, BlockType, ComposerAction};
 
 #[derive(Debug, PartialEq)]
 pub enum MenuState {
     Keep,
     Update {
         action_states: HashMap<ComposerAction, ActionState>,
+        block_type: BlockType,
+        link_url: Option<String>,
+        spans_multiple_links: bool,
+    },
+    PartialUpdate {
+        action_states: HashMap<ComposerAction, ActionState>,
     },
 }
 
@@ -17,7 +23,15 @@ impl MenuState {
             wysiwyg::MenuState::Keep => Self::Keep,
             wysiwyg::MenuState::Update(menu_update) => Self::Update {
                 action_states: menu_update.action_states.into_ffi(),
+                block_type: menu_update.block_type.into(),
+                link_url: menu_update.link_url,
+                spans_multiple_links: menu_update.spans_multiple_links,
             },
+            wysiwyg::MenuState::PartialUpdate(action_states) => {
+                Self::PartialUpdate {
+                    action_states: action_states.into_ffi(),
+                }
+            }
         }
     }
 }
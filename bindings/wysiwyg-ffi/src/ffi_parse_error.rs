@@ -0,0 +1,50 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParseErrorKind {
+    UnclosedTag,
+    UnexpectedEndTag,
+    DisallowedNesting,
+    Other,
+}
+
+impl From<wysiwyg::ParseErrorKind> for ParseErrorKind {
+    fn from(kind: wysiwyg::ParseErrorKind) -> Self {
+        match kind {
+            wysiwyg::ParseErrorKind::UnclosedTag => Self::UnclosedTag,
+            wysiwyg::ParseErrorKind::UnexpectedEndTag => Self::UnexpectedEndTag,
+            wysiwyg::ParseErrorKind::DisallowedNesting => {
+                Self::DisallowedNesting
+            }
+            wysiwyg::ParseErrorKind::Other => Self::Other,
+        }
+    }
+}
+
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub position: Option<u32>,
+    pub description: String,
+}
+
+impl From<wysiwyg::ParseError> for ParseError {
+    fn from(error: wysiwyg::ParseError) -> Self {
+        Self {
+            kind: error.kind.into(),
+            position: error.position.map(|p| u32::try_from(p).unwrap()),
+            description: error.description,
+        }
+    }
+}
+
+pub struct ParseCorrection {
+    pub position: Option<u32>,
+    pub description: String,
+}
+
+impl From<&wysiwyg::ParseCorrection> for ParseCorrection {
+    fn from(correction: &wysiwyg::ParseCorrection) -> Self {
+        Self {
+            position: correction.position.map(|p| u32::try_from(p).unwrap()),
+            description: correction.description.clone(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UnknownTagStrategy {
+    KeepContent,
+    Drop,
+}
+
+impl From<UnknownTagStrategy> for wysiwyg::UnknownTagStrategy {
+    fn from(strategy: UnknownTagStrategy) -> Self {
+        match strategy {
+            UnknownTagStrategy::KeepContent => Self::KeepContent,
+            UnknownTagStrategy::Drop => Self::Drop,
+        }
+    }
+}
+
+impl From<wysiwyg::UnknownTagStrategy> for UnknownTagStrategy {
+    fn from(strategy: wysiwyg::UnknownTagStrategy) -> Self {
+        match strategy {
+            wysiwyg::UnknownTagStrategy::KeepContent => Self::KeepContent,
+            wysiwyg::UnknownTagStrategy::Drop => Self::Drop,
+        }
+    }
+}
+
+pub struct AllowedAttribute {
+    pub tag: String,
+    pub name: String,
+    pub value_prefix: Option<String>,
+}
+
+pub struct SanitizePolicy {
+    pub allowed_tags: Vec<String>,
+    pub allowed_attributes: Vec<AllowedAttribute>,
+    pub link_schemes: Vec<String>,
+    pub image_schemes: Vec<String>,
+    pub max_depth: Option<u32>,
+    pub unknown_tags: UnknownTagStrategy,
+}
+
+impl From<wysiwyg::SanitizePolicy> for SanitizePolicy {
+    fn from(policy: wysiwyg::SanitizePolicy) -> Self {
+        Self {
+            allowed_tags: policy.allowed_tags,
+            allowed_attributes: policy
+                .allowed_attributes
+                .into_iter()
+                .map(|attribute| AllowedAttribute {
+                    tag: attribute.tag,
+                    name: attribute.name,
+                    value_prefix: attribute.value_prefix,
+                })
+                .collect(),
+            link_schemes: policy.link_schemes,
+            image_schemes: policy.image_schemes,
+            max_depth: policy.max_depth.map(|d| u32::try_from(d).unwrap()),
+            unknown_tags: policy.unknown_tags.into(),
+        }
+    }
+}
+
+impl From<SanitizePolicy> for wysiwyg::SanitizePolicy {
+    fn from(policy: SanitizePolicy) -> Self {
+        Self {
+            allowed_tags: policy.allowed_tags,
+            allowed_attributes: policy
+                .allowed_attributes
+                .into_iter()
+                .map(|attribute| wysiwyg::AllowedAttribute {
+                    tag: attribute.tag,
+                    name: attribute.name,
+                    value_prefix: attribute.value_prefix,
+                })
+                .collect(),
+            link_schemes: policy.link_schemes,
+            image_schemes: policy.image_schemes,
+            max_depth: policy.max_depth.map(|d| d as usize),
+            unknown_tags: policy.unknown_tags.into(),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+pub struct RemovedAttribute {
+    pub tag: String,
+    pub name: String,
+}
+
+pub struct SanitizeReport {
+    pub removed_tags: Vec<String>,
+    pub removed_attributes: Vec<RemovedAttribute>,
+    pub removed_too_deep: u32,
+}
+
+impl From<&wysiwyg::SanitizeReport> for SanitizeReport {
+    fn from(report: &wysiwyg::SanitizeReport) -> Self {
+        Self {
+            removed_tags: report.removed_tags.clone(),
+            removed_attributes: report
+                .removed_attributes
+                .iter()
+                .map(|attribute| RemovedAttribute {
+                    tag: attribute.tag.clone(),
+                    name: attribute.name.clone(),
+                })
+                .collect(),
+            removed_too_deep: u32::try_from(report.removed_too_deep).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Shortcut {
+    pub key: String,
+    pub mod_key: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub descriptor: String,
+}
+
+impl From<&wysiwyg::Shortcut> for Shortcut {
+    fn from(shortcut: &wysiwyg::Shortcut) -> Self {
+        Self {
+            key: shortcut.key.clone(),
+            mod_key: shortcut.mod_key,
+            shift: shortcut.shift,
+            alt: shortcut.alt,
+            descriptor: shortcut.to_string(),
+        }
+    }
+}
+
+impl From<Shortcut> for wysiwyg::Shortcut {
+    fn from(shortcut: Shortcut) -> Self {
+        Self {
+            key: shortcut.key,
+            mod_key: shortcut.mod_key,
+            shift: shortcut.shift,
+            alt: shortcut.alt,
+        }
+    }
+}
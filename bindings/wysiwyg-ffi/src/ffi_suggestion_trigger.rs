@@ -0,0 +1,53 @@
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SuggestionTrigger {
+    pub prefix: String,
+    pub min_length: u32,
+    pub boundary: TriggerBoundary,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TriggerBoundary {
+    StartOfMessage,
+    StartOfWord,
+    Anywhere,
+}
+
+impl From<SuggestionTrigger> for wysiwyg::SuggestionTrigger {
+    fn from(trigger: SuggestionTrigger) -> Self {
+        Self {
+            prefix: trigger.prefix,
+            min_length: usize::try_from(trigger.min_length).unwrap(),
+            boundary: trigger.boundary.into(),
+        }
+    }
+}
+
+impl From<&wysiwyg::SuggestionTrigger> for SuggestionTrigger {
+    fn from(trigger: &wysiwyg::SuggestionTrigger) -> Self {
+        Self {
+            prefix: trigger.prefix.clone(),
+            min_length: u32::try_from(trigger.min_length).unwrap(),
+            boundary: trigger.boundary.into(),
+        }
+    }
+}
+
+impl From<TriggerBoundary> for wysiwyg::TriggerBoundary {
+    fn from(boundary: TriggerBoundary) -> Self {
+        match boundary {
+            TriggerBoundary::StartOfMessage => Self::StartOfMessage,
+            TriggerBoundary::StartOfWord => Self::StartOfWord,
+            TriggerBoundary::Anywhere => Self::Anywhere,
+        }
+    }
+}
+
+impl From<wysiwyg::TriggerBoundary> for TriggerBoundary {
+    fn from(boundary: wysiwyg::TriggerBoundary) -> Self {
+        match boundary {
+            wysiwyg::TriggerBoundary::StartOfMessage => Self::StartOfMessage,
+            wysiwyg::TriggerBoundary::StartOfWord => Self::StartOfWord,
+            wysiwyg::TriggerBoundary::Anywhere => Self::Anywhere,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TextCase {
+    Upper,
+    Lower,
+    Title,
+}
+
+impl From<TextCase> for wysiwyg::TextCase {
+    fn from(case: TextCase) -> Self {
+        match case {
+            TextCase::Upper => Self::Upper,
+            TextCase::Lower => Self::Lower,
+            TextCase::Title => Self::Title,
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use wysiwyg::Location;
+
+pub struct TextRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl From<(Location, Location)> for TextRange {
+    fn from((start, end): (Location, Location)) -> Self {
+        let start: usize = start.into();
+        let end: usize = end.into();
+        Self {
+            start: u32::try_from(start).unwrap(),
+            end: u32::try_from(end).unwrap(),
+        }
+    }
+}
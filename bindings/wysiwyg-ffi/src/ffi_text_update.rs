@@ -7,10 +7,18 @@ pub enum TextUpdate {
         start_utf16_codeunit: u32,
         end_utf16_codeunit: u32,
     },
+    ReplaceRange {
+        start_utf16_codeunit: u32,
+        end_utf16_codeunit: u32,
+        replacement_html: Vec<u16>,
+        selection_start_utf16_codeunit: u32,
+        selection_end_utf16_codeunit: u32,
+    },
     Select {
         start_utf16_codeunit: u32,
         end_utf16_codeunit: u32,
     },
+    Send,
 }
 
 impl TextUpdate {
@@ -28,6 +36,29 @@ impl TextUpdate {
                         .unwrap(),
                 }
             }
+            wysiwyg::TextUpdate::ReplaceRange(replace_range) => {
+                let start_utf16_codeunit: usize = replace_range.start.into();
+                let end_utf16_codeunit: usize = replace_range.end.into();
+                let selection_start_utf16_codeunit: usize =
+                    replace_range.selection_start.into();
+                let selection_end_utf16_codeunit: usize =
+                    replace_range.selection_end.into();
+                Self::ReplaceRange {
+                    start_utf16_codeunit: u32::try_from(start_utf16_codeunit)
+                        .unwrap(),
+                    end_utf16_codeunit: u32::try_from(end_utf16_codeunit)
+                        .unwrap(),
+                    replacement_html: replace_range.replacement_html.into_vec(),
+                    selection_start_utf16_codeunit: u32::try_from(
+                        selection_start_utf16_codeunit,
+                    )
+                    .unwrap(),
+                    selection_end_utf16_codeunit: u32::try_from(
+                        selection_end_utf16_codeunit,
+                    )
+                    .unwrap(),
+                }
+            }
             wysiwyg::TextUpdate::Select(selection) => {
                 let start_utf16_codeunit: usize = selection.start.into();
                 let end_utf16_codeunit: usize = selection.end.into();
@@ -38,6 +69,7 @@ impl TextUpdate {
                         .unwrap(),
                 }
             }
+            wysiwyg::TextUpdate::Send => Self::Send,
         }
     }
 }
@@ -15,24 +15,79 @@
 uniffi_macros::include_scaffolding!("wysiwyg_composer");
 
 mod ffi_action_state;
+mod ffi_block_type;
+mod ffi_clipboard_content;
 mod ffi_composer_action;
+mod ffi_composer_error;
+mod ffi_composer_history;
 mod ffi_composer_model;
 mod ffi_composer_state;
 mod ffi_composer_update;
+mod ffi_content_counts;
+mod ffi_cursor_movement;
+mod ffi_dom_changes;
+mod ffi_dom_selection;
+mod ffi_enter_mode;
+mod ffi_link_at_selection;
+mod ffi_list_numbering_type;
+mod ffi_menu_action;
 mod ffi_menu_state;
+mod ffi_parse_error;
+mod ffi_sanitize_policy;
+mod ffi_sanitize_report;
+mod ffi_shortcut;
+mod ffi_suggestion_trigger;
+mod ffi_text_case;
+mod ffi_text_range;
 mod ffi_text_update;
 mod into_ffi;
 
 use std::sync::Arc;
 
-pub use crate::ffi_action_state::ActionState;
+use widestring::Utf16String;
+
+pub use crate::ffi_action_state::{ActionState, DisabledReason};
+pub use crate::ffi_block_type::BlockType;
+pub use crate::ffi_clipboard_content::ClipboardContent;
 pub use crate::ffi_composer_action::ComposerAction;
+pub use crate::ffi_composer_error::ComposerError;
+pub use crate::ffi_composer_history::ComposerHistory;
 pub use crate::ffi_composer_model::ComposerModel;
 pub use crate::ffi_composer_state::ComposerState;
 pub use crate::ffi_composer_update::ComposerUpdate;
+pub use crate::ffi_content_counts::ContentCounts;
+pub use crate::ffi_cursor_movement::{MoveDirection, MoveGranularity};
+pub use crate::ffi_dom_changes::DomChanges;
+pub use crate::ffi_dom_selection::{DomPoint, DomSelection};
+pub use crate::ffi_enter_mode::EnterMode;
+pub use crate::ffi_link_at_selection::LinkAtSelection;
+pub use crate::ffi_list_numbering_type::ListNumberingType;
+pub use crate::ffi_menu_action::{MenuAction, PatternKey, SuggestionPattern};
 pub use crate::ffi_menu_state::MenuState;
+pub use crate::ffi_parse_error::{ParseCorrection, ParseError, ParseErrorKind};
+pub use crate::ffi_sanitize_policy::{
+    AllowedAttribute, SanitizePolicy, UnknownTagStrategy,
+};
+pub use crate::ffi_sanitize_report::{RemovedAttribute, SanitizeReport};
+pub use crate::ffi_shortcut::Shortcut;
+pub use crate::ffi_suggestion_trigger::{SuggestionTrigger, TriggerBoundary};
+pub use crate::ffi_text_case::TextCase;
+pub use crate::ffi_text_range::TextRange;
 pub use crate::ffi_text_update::TextUpdate;
 
 pub fn new_composer_model() -> Arc<ComposerModel> {
     Arc::new(ComposerModel::new())
 }
+
+pub fn matrix_sanitize_policy() -> SanitizePolicy {
+    SanitizePolicy::from(wysiwyg::SanitizePolicy::matrix())
+}
+
+/// The problems found parsing `html`, e.g. for logging malformed HTML in an
+/// incoming event. Empty if it is well-formed.
+pub fn html_parse_errors(html: String) -> Vec<ParseError> {
+    match wysiwyg::parse::<Utf16String>(&html) {
+        Ok(_) => Vec::new(),
+        Err(e) => e.parse_errors.into_iter().map(ParseError::from).collect(),
+    }
+}
@@ -44,6 +44,20 @@ pub fn new_composer_model_from_html(
     }
 }
 
+/// The problems found parsing `html`, as an array of ParseError, e.g. for
+/// logging malformed HTML in an incoming event. Empty if it is well-formed.
+#[wasm_bindgen]
+pub fn html_parse_errors(html: &str) -> js_sys::Array {
+    match wysiwyg::parse::<Utf16String>(html) {
+        Ok(_) => js_sys::Array::new(),
+        Err(e) => e
+            .parse_errors
+            .into_iter()
+            .map(|error| JsValue::from(ParseError::from(error)))
+            .collect(),
+    }
+}
+
 #[wasm_bindgen]
 pub enum ActionState {
     /// The button can be clicked, and will perform its normal action
@@ -103,6 +117,18 @@ impl ComposerModel {
         self.inner.get_content_as_markdown().to_string()
     }
 
+    pub fn get_selected_html(&self) -> String {
+        self.inner.get_selected_html().to_string()
+    }
+
+    pub fn copy(&self) -> ClipboardContent {
+        ClipboardContent::from(self.inner.copy())
+    }
+
+    pub fn cut(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.cut())
+    }
+
     pub fn document(&self) -> DomHandle {
         DomHandle {
             inner: self.inner.state.dom.document().handle(),
@@ -113,6 +139,46 @@ impl ComposerModel {
         self.inner.action_states().into_ffi()
     }
 
+    /// Why each disabled action is disabled, as a reason name like
+    /// "NotInList", e.g. to explain it in a tooltip
+    pub fn action_disabled_reasons(&self) -> js_sys::Map {
+        let ret = js_sys::Map::new();
+        for (action, state) in self.inner.action_states() {
+            if let wysiwyg::ActionState::Disabled(reason) = state {
+                ret.set(&action.as_ref().into(), &reason.as_ref().into());
+            }
+        }
+        ret
+    }
+
+    /// The keyboard shortcut for each action that has one, as a descriptor
+    /// like "Mod+Shift+Z"
+    pub fn shortcuts(&self) -> js_sys::Map {
+        let ret = js_sys::Map::new();
+        for (action, shortcut) in self.inner.shortcuts() {
+            ret.set(&action.as_ref().into(), &shortcut.to_string().into());
+        }
+        ret
+    }
+
+    pub fn set_shortcut(
+        &mut self,
+        action: ComposerAction,
+        key: &str,
+        mod_key: bool,
+        shift: bool,
+        alt: bool,
+    ) {
+        self.inner.set_shortcut(
+            (&action).into(),
+            Some(wysiwyg::Shortcut::new(key, mod_key, shift, alt)),
+        );
+    }
+
+    pub fn remove_shortcut(&mut self, action: ComposerAction) {
+        self.inner.set_shortcut((&action).into(), None);
+    }
+
     pub fn select(
         &mut self,
         start_utf16_codeunit: u32,
@@ -128,6 +194,53 @@ impl ComposerModel {
         ))
     }
 
+    pub fn utf16_codeunits_to_graphemes(&self, utf16_codeunit: u32) -> u32 {
+        let location =
+            wysiwyg::Location::from(usize::try_from(utf16_codeunit).unwrap());
+        let graphemes = self.inner.state.dom.grapheme_location(location);
+        u32::try_from(usize::from(graphemes)).unwrap()
+    }
+
+    pub fn graphemes_to_utf16_codeunits(&self, grapheme: u32) -> u32 {
+        let location =
+            wysiwyg::GraphemeLocation::from(usize::try_from(grapheme).unwrap());
+        let code_units = self.inner.state.dom.location_of_grapheme(location);
+        u32::try_from(usize::from(code_units)).unwrap()
+    }
+
+    pub fn select_word(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.select_word())
+    }
+
+    pub fn select_block(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.select_block())
+    }
+
+    pub fn select_all(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.select_all())
+    }
+
+    pub fn collapse_to_start(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.collapse_to_start())
+    }
+
+    pub fn collapse_to_end(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.collapse_to_end())
+    }
+
+    pub fn move_cursor(
+        &mut self,
+        direction: MoveDirection,
+        granularity: MoveGranularity,
+        extend: bool,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.move_cursor(
+            direction.into(),
+            granularity.into(),
+            extend,
+        ))
+    }
+
     pub fn selection_start(&self) -> u32 {
         let ret: usize = self.inner.state.start.into();
         ret as u32
@@ -148,6 +261,99 @@ impl ComposerModel {
         )
     }
 
+    pub fn paste_html(&mut self, html: &str) -> ComposerUpdate {
+        ComposerUpdate::from(
+            self.inner.paste_html(&Utf16String::from_str(html)),
+        )
+    }
+
+    pub fn paste_text(&mut self, text: &str) -> ComposerUpdate {
+        ComposerUpdate::from(
+            self.inner.paste_text(&Utf16String::from_str(text)),
+        )
+    }
+
+    pub fn insert_html_at_cursor(&mut self, html: &str) -> ComposerUpdate {
+        ComposerUpdate::from(
+            self.inner
+                .insert_html_at_cursor(&Utf16String::from_str(html)),
+        )
+    }
+
+    pub fn set_composition(
+        &mut self,
+        text: &str,
+        start: u32,
+        end: u32,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.set_composition(
+            Utf16String::from_str(text),
+            usize::try_from(start).unwrap(),
+            usize::try_from(end).unwrap(),
+        ))
+    }
+
+    pub fn commit_composition(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.commit_composition())
+    }
+
+    pub fn composition_start(&self) -> Option<u32> {
+        self.inner.get_composition().map(|(start, _)| {
+            let ret: usize = start.into();
+            ret as u32
+        })
+    }
+
+    pub fn composition_end(&self) -> Option<u32> {
+        self.inner.get_composition().map(|(_, end)| {
+            let ret: usize = end.into();
+            ret as u32
+        })
+    }
+
+    /// The start and end of each match of `pattern`, one after another
+    pub fn find(&self, pattern: &str) -> Vec<u32> {
+        self.inner
+            .find(&Utf16String::from_str(pattern))
+            .into_iter()
+            .flat_map(|(start, end)| {
+                let start: usize = start.into();
+                let end: usize = end.into();
+                [start as u32, end as u32]
+            })
+            .collect()
+    }
+
+    pub fn replace_all(
+        &mut self,
+        pattern: &str,
+        replacement: &str,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.replace_all(
+            &Utf16String::from_str(pattern),
+            &Utf16String::from_str(replacement),
+        ))
+    }
+
+    pub fn transform_case(
+        &mut self,
+        case: TextCase,
+        locale: Option<String>,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(
+            self.inner
+                .transform_case_in_locale(case.into(), locale.as_deref()),
+        )
+    }
+
+    pub fn counts(&self) -> ContentCounts {
+        ContentCounts::from(self.inner.counts())
+    }
+
+    pub fn is_content_empty(&self) -> bool {
+        self.inner.is_content_empty()
+    }
+
     pub fn set_content_from_html(&mut self, text: &str) -> ComposerUpdate {
         ComposerUpdate::from(
             self.inner
@@ -155,6 +361,26 @@ impl ComposerModel {
         )
     }
 
+    pub fn set_content_from_html_lenient(
+        &mut self,
+        text: &str,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(
+            self.inner
+                .set_content_from_html_lenient(&Utf16String::from_str(text)),
+        )
+    }
+
+    /// The corrections made by the last set_content_from_html_lenient(),
+    /// as an array of ParseCorrection
+    pub fn last_parse_corrections(&self) -> js_sys::Array {
+        self.inner
+            .last_parse_corrections()
+            .iter()
+            .map(|correction| JsValue::from(ParseCorrection::from(correction)))
+            .collect()
+    }
+
     pub fn set_content_from_markdown(&mut self, text: &str) -> ComposerUpdate {
         ComposerUpdate::from(
             self.inner
@@ -170,6 +396,76 @@ impl ComposerModel {
         ComposerUpdate::from(self.inner.enter())
     }
 
+    pub fn shift_enter(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.shift_enter())
+    }
+
+    pub fn insert_soft_break(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.insert_soft_break())
+    }
+
+    pub fn set_enter_mode(&mut self, enter_mode: EnterMode) {
+        self.inner.set_enter_mode(enter_mode.into());
+    }
+
+    pub fn set_nfc_normalization(&mut self, enabled: bool) {
+        self.inner.set_nfc_normalization(enabled);
+    }
+
+    pub fn set_matrix_sanitization(&mut self, enabled: bool) {
+        self.inner.set_matrix_sanitization(enabled);
+    }
+
+    pub fn set_sanitize_policy(&mut self, policy: Option<SanitizePolicy>) {
+        self.inner
+            .set_sanitize_policy(policy.map(|policy| policy.inner));
+    }
+
+    pub fn last_sanitize_report(&self) -> SanitizeReport {
+        SanitizeReport::from(self.inner.last_sanitize_report())
+    }
+
+    pub fn set_max_list_depth(
+        &mut self,
+        max_depth: Option<u32>,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.set_max_list_depth(
+            max_depth.map(|depth| usize::try_from(depth).unwrap()),
+        ))
+    }
+
+    /// Add a prefix that starts suggestion patterns to the ones in use,
+    /// which are @, # and / by default
+    pub fn add_suggestion_trigger(
+        &mut self,
+        prefix: &str,
+        min_length: u32,
+        boundary: TriggerBoundary,
+    ) -> ComposerUpdate {
+        let mut triggers = self.inner.suggestion_triggers().to_vec();
+        triggers.push(wysiwyg::SuggestionTrigger::new(
+            prefix,
+            usize::try_from(min_length).unwrap(),
+            boundary.into(),
+        ));
+        ComposerUpdate::from(self.inner.set_suggestion_triggers(triggers))
+    }
+
+    pub fn accept_emoji_suggestion(
+        &mut self,
+        emoji: &str,
+        suggestion: &SuggestionPattern,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.accept_emoji_suggestion(
+            Utf16String::from_str(emoji),
+            &suggestion.into(),
+        ))
+    }
+
+    pub fn clear_suggestion_triggers(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.set_suggestion_triggers(Vec::new()))
+    }
+
     pub fn backspace(&mut self) -> ComposerUpdate {
         ComposerUpdate::from(self.inner.backspace())
     }
@@ -178,6 +474,22 @@ impl ComposerModel {
         ComposerUpdate::from(self.inner.delete())
     }
 
+    pub fn delete_word_backward(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.delete_word_backward())
+    }
+
+    pub fn delete_word_forward(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.delete_word_forward())
+    }
+
+    pub fn delete_to_start_of_block(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.delete_to_start_of_block())
+    }
+
+    pub fn delete_to_end_of_block(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.delete_to_end_of_block())
+    }
+
     pub fn bold(&mut self) -> ComposerUpdate {
         ComposerUpdate::from(self.inner.bold())
     }
@@ -206,12 +518,66 @@ impl ComposerModel {
         ComposerUpdate::from(self.inner.redo())
     }
 
-    pub fn ordered_list(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.ordered_list())
+    pub fn begin_transaction(&mut self) {
+        self.inner.begin_transaction();
+    }
+
+    pub fn end_transaction(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.end_transaction())
+    }
+
+    pub fn mark_typing_pause(&mut self) {
+        self.inner.mark_typing_pause();
+    }
+
+    pub fn set_history_limit(
+        &mut self,
+        history_limit: Option<u32>,
+    ) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.set_history_limit(
+            history_limit.map(|limit| usize::try_from(limit).unwrap()),
+        ))
+    }
+
+    pub fn history_depth(&self) -> u32 {
+        u32::try_from(self.inner.history_depth()).unwrap()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.inner.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.inner.can_redo()
     }
 
-    pub fn unordered_list(&mut self) -> ComposerUpdate {
-        ComposerUpdate::from(self.inner.unordered_list())
+    pub fn clear_history(&mut self) -> ComposerUpdate {
+        ComposerUpdate::from(self.inner.clear_history())
+    }
+
+    pub fn checkpoint(&mut self, label: &str) {
+        self.inner.checkpoint(&Utf16String::from_str(label));
+    }
+
+    pub fn revert_to_checkpoint(&mut self, label: &str) -> ComposerUpdate {
+        ComposerUpdate::from(
+            self.inner
+                .revert_to_checkpoint(&Utf16String::from_str(label)),
+        )
+    }
+
+    pub fn ordered_list(&mut self) -> Result<ComposerUpdate, JsError> {
+        self.inner
+            .ordered_list()
+            .map(ComposerUpdate::from)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    pub fn unordered_list(&mut self) -> Result<ComposerUpdate, JsError> {
+        self.inner
+            .unordered_list()
+            .map(ComposerUpdate::from)
+            .map_err(|e| JsError::new(&e.to_string()))
     }
 }
 
@@ -235,13 +601,102 @@ impl ComposerUpdate {
     pub fn menu_state(&self) -> MenuState {
         MenuState::from(self.inner.menu_state.clone())
     }
+
+    pub fn menu_action(&self) -> MenuAction {
+        MenuAction::from(self.inner.menu_action.clone())
+    }
+
+    pub fn dom_selection(&self) -> Option<DomSelection> {
+        self.inner.dom_selection.as_ref().map(DomSelection::from)
+    }
+
+    pub fn dom_changes(&self) -> DomChanges {
+        DomChanges::from(&self.inner.dom_changes)
+    }
+
+    pub fn clipboard(&self) -> Option<ClipboardContent> {
+        self.inner.clipboard.clone().map(ClipboardContent::from)
+    }
+}
+
+/// The paths of the nodes changed, each a Uint32Array of the indices of
+/// the node and its ancestors from the root
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct DomChanges {
+    pub created: js_sys::Array,
+    pub modified: js_sys::Array,
+    pub deleted: js_sys::Array,
+}
+
+impl DomChanges {
+    fn from(inner: &wysiwyg::DomChanges) -> Self {
+        fn paths(handles: &[wysiwyg::DomHandle]) -> js_sys::Array {
+            handles
+                .iter()
+                .map(|handle| {
+                    let path: Vec<u32> = handle
+                        .raw()
+                        .iter()
+                        .map(|i| u32::try_from(*i).unwrap())
+                        .collect();
+                    js_sys::Uint32Array::from(path.as_slice())
+                })
+                .collect()
+        }
+        Self {
+            created: paths(&inner.created),
+            modified: paths(&inner.modified),
+            deleted: paths(&inner.deleted),
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct DomSelection {
+    pub anchor: DomPoint,
+    pub focus: DomPoint,
+}
+
+impl DomSelection {
+    fn from(inner: &wysiwyg::DomSelection) -> Self {
+        Self {
+            anchor: DomPoint::from(&inner.anchor),
+            focus: DomPoint::from(&inner.focus),
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct DomPoint {
+    /// The indices of the node and its ancestors, from the root
+    pub path: Vec<u32>,
+    pub offset: u32,
+}
+
+impl DomPoint {
+    fn from(inner: &wysiwyg::DomPoint) -> Self {
+        Self {
+            path: inner
+                .handle
+                .raw()
+                .iter()
+                .map(|i| u32::try_from(*i).unwrap())
+                .collect(),
+            offset: u32::try_from(inner.offset).unwrap(),
+        }
+    }
 }
 
 #[wasm_bindgen(getter_with_clone)]
 pub struct TextUpdate {
     pub keep: Option<Keep>,
     pub replace_all: Option<ReplaceAll>,
+    pub replace_range: Option<ReplaceRange>,
     pub select: Option<Selection>,
+    pub send: Option<SendMessage>,
 }
 
 impl TextUpdate {
@@ -250,7 +705,9 @@ impl TextUpdate {
             wysiwyg::TextUpdate::Keep => Self {
                 keep: Some(Keep),
                 replace_all: None,
+                replace_range: None,
                 select: None,
+                send: None,
             },
             wysiwyg::TextUpdate::ReplaceAll(r) => {
                 let start_utf16_codeunit: usize = r.start.into();
@@ -266,7 +723,40 @@ impl TextUpdate {
                         end_utf16_codeunit: u32::try_from(end_utf16_codeunit)
                             .unwrap(),
                     }),
+                    replace_range: None,
+                    select: None,
+                    send: None,
+                }
+            }
+            wysiwyg::TextUpdate::ReplaceRange(r) => {
+                let start_utf16_codeunit: usize = r.start.into();
+                let end_utf16_codeunit: usize = r.end.into();
+                let selection_start_utf16_codeunit: usize =
+                    r.selection_start.into();
+                let selection_end_utf16_codeunit: usize =
+                    r.selection_end.into();
+                Self {
+                    keep: None,
+                    replace_all: None,
+                    replace_range: Some(ReplaceRange {
+                        start_utf16_codeunit: u32::try_from(
+                            start_utf16_codeunit,
+                        )
+                        .unwrap(),
+                        end_utf16_codeunit: u32::try_from(end_utf16_codeunit)
+                            .unwrap(),
+                        replacement_html: r.replacement_html.to_string(),
+                        selection_start_utf16_codeunit: u32::try_from(
+                            selection_start_utf16_codeunit,
+                        )
+                        .unwrap(),
+                        selection_end_utf16_codeunit: u32::try_from(
+                            selection_end_utf16_codeunit,
+                        )
+                        .unwrap(),
+                    }),
                     select: None,
+                    send: None,
                 }
             }
             wysiwyg::TextUpdate::Select(s) => {
@@ -275,6 +765,7 @@ impl TextUpdate {
                 Self {
                     keep: None,
                     replace_all: None,
+                    replace_range: None,
                     select: Some(Selection {
                         start_utf16_codeunit: u32::try_from(
                             start_utf16_codeunit,
@@ -283,8 +774,16 @@ impl TextUpdate {
                         end_utf16_codeunit: u32::try_from(end_utf16_codeunit)
                             .unwrap(),
                     }),
+                    send: None,
                 }
             }
+            wysiwyg::TextUpdate::Send => Self {
+                keep: None,
+                replace_all: None,
+                replace_range: None,
+                select: None,
+                send: Some(SendMessage),
+            },
         }
     }
 }
@@ -301,6 +800,16 @@ pub struct ReplaceAll {
     pub end_utf16_codeunit: u32,
 }
 
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct ReplaceRange {
+    pub start_utf16_codeunit: u32,
+    pub end_utf16_codeunit: u32,
+    pub replacement_html: String,
+    pub selection_start_utf16_codeunit: u32,
+    pub selection_end_utf16_codeunit: u32,
+}
+
 #[derive(Clone)]
 #[wasm_bindgen(getter_with_clone)]
 pub struct Selection {
@@ -308,6 +817,316 @@ pub struct Selection {
     pub end_utf16_codeunit: u32,
 }
 
+#[derive(Clone)]
+#[wasm_bindgen]
+pub struct SendMessage;
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum EnterMode {
+    Document,
+    Message,
+}
+
+impl From<EnterMode> for wysiwyg::EnterMode {
+    fn from(enter_mode: EnterMode) -> Self {
+        match enter_mode {
+            EnterMode::Document => Self::Document,
+            EnterMode::Message => Self::Message,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum TextCase {
+    Upper,
+    Lower,
+    Title,
+}
+
+impl From<TextCase> for wysiwyg::TextCase {
+    fn from(case: TextCase) -> Self {
+        match case {
+            TextCase::Upper => Self::Upper,
+            TextCase::Lower => Self::Lower,
+            TextCase::Title => Self::Title,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum ParseErrorKind {
+    UnclosedTag,
+    UnexpectedEndTag,
+    DisallowedNesting,
+    Other,
+}
+
+impl From<wysiwyg::ParseErrorKind> for ParseErrorKind {
+    fn from(kind: wysiwyg::ParseErrorKind) -> Self {
+        match kind {
+            wysiwyg::ParseErrorKind::UnclosedTag => Self::UnclosedTag,
+            wysiwyg::ParseErrorKind::UnexpectedEndTag => Self::UnexpectedEndTag,
+            wysiwyg::ParseErrorKind::DisallowedNesting => {
+                Self::DisallowedNesting
+            }
+            wysiwyg::ParseErrorKind::Other => Self::Other,
+        }
+    }
+}
+
+/// A problem found parsing HTML, with the offset in bytes of the tag at
+/// fault in its UTF-8 form if known
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub position: Option<u32>,
+    pub description: String,
+}
+
+impl From<wysiwyg::ParseError> for ParseError {
+    fn from(error: wysiwyg::ParseError) -> Self {
+        Self {
+            kind: error.kind.into(),
+            position: error.position.map(|p| u32::try_from(p).unwrap()),
+            description: error.description,
+        }
+    }
+}
+
+/// A change made to malformed HTML to parse it, with the offset in bytes
+/// in its UTF-8 form where it was made if known
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct ParseCorrection {
+    pub position: Option<u32>,
+    pub description: String,
+}
+
+impl From<&wysiwyg::ParseCorrection> for ParseCorrection {
+    fn from(correction: &wysiwyg::ParseCorrection) -> Self {
+        Self {
+            position: correction.position.map(|p| u32::try_from(p).unwrap()),
+            description: correction.description.clone(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum UnknownTagStrategy {
+    KeepContent,
+    Drop,
+}
+
+impl From<UnknownTagStrategy> for wysiwyg::UnknownTagStrategy {
+    fn from(strategy: UnknownTagStrategy) -> Self {
+        match strategy {
+            UnknownTagStrategy::KeepContent => Self::KeepContent,
+            UnknownTagStrategy::Drop => Self::Drop,
+        }
+    }
+}
+
+/// Which HTML may be kept when it is loaded or pasted, starting from what
+/// the Matrix spec allows in a message
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct SanitizePolicy {
+    inner: wysiwyg::SanitizePolicy,
+}
+
+#[wasm_bindgen]
+impl SanitizePolicy {
+    #[wasm_bindgen(constructor)]
+    pub fn matrix() -> Self {
+        Self {
+            inner: wysiwyg::SanitizePolicy::matrix(),
+        }
+    }
+
+    pub fn allow_tag(&mut self, tag: &str) {
+        self.inner.allowed_tags.push(tag.to_owned());
+    }
+
+    pub fn allow_attribute(
+        &mut self,
+        tag: &str,
+        name: &str,
+        value_prefix: Option<String>,
+    ) {
+        let mut attribute = wysiwyg::AllowedAttribute::new(tag, name);
+        attribute.value_prefix = value_prefix;
+        self.inner.allowed_attributes.push(attribute);
+    }
+
+    pub fn allow_link_scheme(&mut self, scheme: &str) {
+        self.inner.link_schemes.push(scheme.to_owned());
+    }
+
+    pub fn allow_image_scheme(&mut self, scheme: &str) {
+        self.inner.image_schemes.push(scheme.to_owned());
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: Option<u32>) {
+        self.inner.max_depth = max_depth.map(|d| d as usize);
+    }
+
+    pub fn set_unknown_tags(&mut self, strategy: UnknownTagStrategy) {
+        self.inner.unknown_tags = strategy.into();
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum MoveDirection {
+    Backward,
+    Forward,
+}
+
+impl From<MoveDirection> for wysiwyg::MoveDirection {
+    fn from(direction: MoveDirection) -> Self {
+        match direction {
+            MoveDirection::Backward => Self::Backward,
+            MoveDirection::Forward => Self::Forward,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum MoveGranularity {
+    Character,
+    Word,
+    Block,
+}
+
+impl From<MoveGranularity> for wysiwyg::MoveGranularity {
+    fn from(granularity: MoveGranularity) -> Self {
+        match granularity {
+            MoveGranularity::Character => Self::Character,
+            MoveGranularity::Word => Self::Word,
+            MoveGranularity::Block => Self::Block,
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct MenuAction {
+    inner: wysiwyg::MenuAction,
+}
+
+impl MenuAction {
+    pub fn from(inner: wysiwyg::MenuAction) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen]
+impl MenuAction {
+    pub fn keep(&self) -> bool {
+        matches!(self.inner, wysiwyg::MenuAction::Keep)
+    }
+
+    pub fn suggestion(&self) -> Option<SuggestionPattern> {
+        match &self.inner {
+            wysiwyg::MenuAction::Suggestion(pattern) => {
+                Some(SuggestionPattern::from(pattern))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug)]
+pub struct SuggestionPattern {
+    pub key: PatternKey,
+    /// The prefix, if key is Custom
+    pub custom_prefix: Option<String>,
+    pub text: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl From<&SuggestionPattern> for wysiwyg::SuggestionPattern {
+    fn from(pattern: &SuggestionPattern) -> Self {
+        let key = match pattern.key {
+            PatternKey::At => wysiwyg::PatternKey::At,
+            PatternKey::Hash => wysiwyg::PatternKey::Hash,
+            PatternKey::Slash => wysiwyg::PatternKey::Slash,
+            PatternKey::Colon => wysiwyg::PatternKey::Colon,
+            PatternKey::Custom => wysiwyg::PatternKey::Custom(
+                pattern.custom_prefix.clone().unwrap_or_default(),
+            ),
+        };
+        Self {
+            key,
+            text: pattern.text.clone(),
+            start: usize::try_from(pattern.start).unwrap(),
+            end: usize::try_from(pattern.end).unwrap(),
+        }
+    }
+}
+
+impl SuggestionPattern {
+    pub fn from(inner: &wysiwyg::SuggestionPattern) -> Self {
+        Self {
+            key: PatternKey::from(&inner.key),
+            custom_prefix: match &inner.key {
+                wysiwyg::PatternKey::Custom(prefix) => Some(prefix.clone()),
+                _ => None,
+            },
+            text: inner.text.clone(),
+            start: u32::try_from(inner.start).unwrap(),
+            end: u32::try_from(inner.end).unwrap(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub enum PatternKey {
+    At,
+    Hash,
+    Slash,
+    Colon,
+    Custom,
+}
+
+impl From<&wysiwyg::PatternKey> for PatternKey {
+    fn from(key: &wysiwyg::PatternKey) -> Self {
+        match key {
+            wysiwyg::PatternKey::At => Self::At,
+            wysiwyg::PatternKey::Hash => Self::Hash,
+            wysiwyg::PatternKey::Slash => Self::Slash,
+            wysiwyg::PatternKey::Colon => Self::Colon,
+            wysiwyg::PatternKey::Custom(_) => Self::Custom,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub enum TriggerBoundary {
+    StartOfMessage,
+    StartOfWord,
+    Anywhere,
+}
+
+impl From<TriggerBoundary> for wysiwyg::TriggerBoundary {
+    fn from(boundary: TriggerBoundary) -> Self {
+        match boundary {
+            TriggerBoundary::StartOfMessage => Self::StartOfMessage,
+            TriggerBoundary::StartOfWord => Self::StartOfWord,
+            TriggerBoundary::Anywhere => Self::Anywhere,
+        }
+    }
+}
+
 #[wasm_bindgen]
 pub struct MenuState {
     inner: wysiwyg::MenuState,
@@ -336,18 +1155,55 @@ impl MenuState {
             _ => None,
         }
     }
+
+    /// The states of just the actions that changed, if nothing else in
+    /// the menu state did
+    pub fn partial_update(&self) -> Option<js_sys::Map> {
+        match &self.inner {
+            wysiwyg::MenuState::PartialUpdate(action_states) => {
+                Some(action_states.into_ffi())
+            }
+            _ => None,
+        }
+    }
 }
 
 #[wasm_bindgen(getter_with_clone)]
 #[derive(Debug)]
 pub struct MenuStateUpdate {
     pub action_states: js_sys::Map,
+    pub block_type: BlockType,
+    pub link_url: Option<String>,
+    pub spans_multiple_links: bool,
 }
 
 impl MenuStateUpdate {
     pub fn from(inner: &wysiwyg::MenuStateUpdate) -> Self {
         Self {
             action_states: inner.action_states.into_ffi(),
+            block_type: inner.block_type.into(),
+            link_url: inner.link_url.clone(),
+            spans_multiple_links: inner.spans_multiple_links,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub enum BlockType {
+    Paragraph,
+    OrderedList,
+    UnorderedList,
+    TableCell,
+}
+
+impl From<wysiwyg::BlockType> for BlockType {
+    fn from(block_type: wysiwyg::BlockType) -> Self {
+        match block_type {
+            wysiwyg::BlockType::Paragraph => Self::Paragraph,
+            wysiwyg::BlockType::OrderedList => Self::OrderedList,
+            wysiwyg::BlockType::UnorderedList => Self::UnorderedList,
+            wysiwyg::BlockType::TableCell => Self::TableCell,
         }
     }
 }
@@ -367,6 +1223,7 @@ pub enum ComposerAction {
     UnorderedList,
     Indent,
     UnIndent,
+    Table,
 }
 
 impl ComposerAction {
@@ -384,6 +1241,7 @@ impl ComposerAction {
             wysiwyg::ComposerAction::UnorderedList => Self::UnorderedList,
             wysiwyg::ComposerAction::Indent => Self::Indent,
             wysiwyg::ComposerAction::UnIndent => Self::UnIndent,
+            wysiwyg::ComposerAction::Table => Self::Table,
         }
     }
 }
@@ -403,6 +1261,74 @@ impl From<&ComposerAction> for wysiwyg::ComposerAction {
             ComposerAction::UnorderedList => Self::UnorderedList,
             ComposerAction::Indent => Self::Indent,
             ComposerAction::UnIndent => Self::UnIndent,
+            ComposerAction::Table => Self::Table,
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct ClipboardContent {
+    pub html: String,
+    pub text: String,
+}
+
+impl From<wysiwyg::ClipboardContent<Utf16String>> for ClipboardContent {
+    fn from(content: wysiwyg::ClipboardContent<Utf16String>) -> Self {
+        Self {
+            html: content.html.to_string(),
+            text: content.text.to_string(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct ContentCounts {
+    pub characters: u32,
+    pub words: u32,
+    pub code_units: u32,
+}
+
+impl From<wysiwyg::ContentCounts> for ContentCounts {
+    fn from(counts: wysiwyg::ContentCounts) -> Self {
+        Self {
+            characters: u32::try_from(counts.characters).unwrap(),
+            words: u32::try_from(counts.words).unwrap(),
+            code_units: u32::try_from(counts.code_units).unwrap(),
+        }
+    }
+}
+
+/// What was removed from HTML that the Matrix spec doesn't allow. Each
+/// removed attribute is an array of its tag and its name.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone)]
+pub struct SanitizeReport {
+    pub removed_tags: js_sys::Array,
+    pub removed_attributes: js_sys::Array,
+    pub removed_too_deep: u32,
+}
+
+impl From<&wysiwyg::SanitizeReport> for SanitizeReport {
+    fn from(report: &wysiwyg::SanitizeReport) -> Self {
+        Self {
+            removed_tags: report
+                .removed_tags
+                .iter()
+                .map(|tag| JsValue::from_str(tag))
+                .collect(),
+            removed_attributes: report
+                .removed_attributes
+                .iter()
+                .map(|attribute| {
+                    js_sys::Array::of2(
+                        &JsValue::from_str(&attribute.tag),
+                        &JsValue::from_str(&attribute.name),
+                    )
+                })
+                .collect(),
+            removed_too_deep: u32::try_from(report.removed_too_deep).unwrap(),
         }
     }
 }
@@ -443,8 +1369,8 @@ pub struct DomHandle {
 
 #[wasm_bindgen]
 impl DomHandle {
-    /// Returns "container", "line_break" or "text" depending on the type of
-    /// node we refer to.
+    /// Returns "container", "line_break", "mention", "image" or "text"
+    /// depending on the type of node we refer to.
     /// Panics if we are not a valid reference (because the model has changed
     /// since we were created, or because you passed in a different model
     /// from the one that created us.)
@@ -453,6 +1379,8 @@ impl DomHandle {
         String::from(match node {
             wysiwyg::DomNode::Container(_) => "container",
             wysiwyg::DomNode::LineBreak(_) => "line_break",
+            wysiwyg::DomNode::Mention(_) => "mention",
+            wysiwyg::DomNode::Image(_) => "image",
             wysiwyg::DomNode::Text(_) => "text",
         })
     }
@@ -476,8 +1404,9 @@ impl DomHandle {
         }
     }
 
-    /// Returns the text of this node, or an empty string if this is a
-    /// container or line break.
+    /// Returns the text of this node (the display text for a mention, or the
+    /// alt text for an image), or an empty string if this is a container or
+    /// line break.
     /// Panics if we are not a valid reference (because the model has changed
     /// since we were created, or because you passed in a different model
     /// from the one that created us.)
@@ -486,6 +1415,8 @@ impl DomHandle {
         match node {
             wysiwyg::DomNode::Container(_) => String::from(""),
             wysiwyg::DomNode::LineBreak(_) => String::from(""),
+            wysiwyg::DomNode::Mention(node) => node.display_text().to_string(),
+            wysiwyg::DomNode::Image(node) => node.alt().to_string(),
             wysiwyg::DomNode::Text(node) => node.data().to_string(),
         }
     }
@@ -499,6 +1430,8 @@ impl DomHandle {
         match node {
             wysiwyg::DomNode::Container(node) => node.name().to_string(),
             wysiwyg::DomNode::LineBreak(node) => node.name().to_string(),
+            wysiwyg::DomNode::Mention(node) => node.name().to_string(),
+            wysiwyg::DomNode::Image(node) => node.name().to_string(),
             wysiwyg::DomNode::Text(_) => String::from("-text-"),
         }
     }
@@ -0,0 +1,25 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The kind of block the start of the selection is in, e.g. for a toolbar
+/// to show which block format is active. Only the closest block counts, so
+/// a list inside a table cell is a list.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlockType {
+    /// Text that isn't inside any other kind of block
+    Paragraph,
+    OrderedList,
+    UnorderedList,
+    TableCell,
+}
@@ -0,0 +1,49 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt};
+
+use crate::dom::DomError;
+use crate::ComposerAction;
+
+/// Why the model refused to apply an action. The model is left as it was
+/// before the action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComposerError {
+    /// The action can't yet be applied to the kind of content selected,
+    /// e.g. making a list from a selection spanning several nodes.
+    UnsupportedSelection(ComposerAction),
+    Dom(DomError),
+}
+
+impl Error for ComposerError {}
+
+impl fmt::Display for ComposerError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedSelection(action) => write!(
+                formatter,
+                "Can't apply {:?} to the selected content yet",
+                action
+            ),
+            Self::Dom(error) => error.fmt(formatter),
+        }
+    }
+}
+
+impl From<DomError> for ComposerError {
+    fn from(error: DomError) -> Self {
+        Self::Dom(error)
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::UnicodeString;
+
+/// The content and selection of one state of the composer, in a form that
+/// can be stored outside the model.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HistoryEntry<S>
+where
+    S: UnicodeString,
+{
+    pub html: S,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The current state of the composer along with its undo and redo
+/// history, e.g. for a host to persist and restore after its process is
+/// killed. See ComposerModel::export_history().
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ComposerHistory<S>
+where
+    S: UnicodeString,
+{
+    /// States that undo() goes back to, oldest first
+    pub previous: Vec<HistoryEntry<S>>,
+    pub current: HistoryEntry<S>,
+    /// States that redo() goes forward to, the next one last
+    pub next: Vec<HistoryEntry<S>>,
+}
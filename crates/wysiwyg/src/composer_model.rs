@@ -14,15 +14,31 @@
 
 pub mod action_state;
 pub mod base;
+pub mod clipboard;
+pub mod composition;
+pub mod counts;
 pub mod delete_text;
 pub mod example_format;
+pub mod find_replace;
 pub mod format;
+pub mod history;
 pub mod hyperlinks;
+pub mod images;
+pub mod indentation;
 pub mod join_nodes;
 pub mod lists;
+pub mod mentions;
 pub mod menu_state;
+pub mod normalization;
+pub mod paste;
 pub mod replace_text;
+pub mod sanitization;
 pub mod selection;
+pub mod shortcuts;
+pub mod suggestions;
+pub mod tables;
+pub mod text_case;
 pub mod undo_redo;
+pub mod words;
 
 pub use base::ComposerModel;
@@ -12,11 +12,43 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
+
 use strum_macros::AsRefStr;
 
 #[derive(AsRefStr, Clone, Debug, PartialEq)]
 pub enum ActionState {
     Enabled,
     Reversed,
-    Disabled,
+    Disabled(DisabledReason),
+}
+
+/// Why an action is disabled, so that UIs can explain it to the user
+#[derive(AsRefStr, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisabledReason {
+    /// The undo history is empty
+    NothingToUndo,
+    /// Nothing has been undone since the last change
+    NothingToRedo,
+    /// The selection is not inside a list
+    NotInList,
+    /// The first item of a list has no item before it to nest under
+    FirstListItem,
+    /// The list is already as deeply nested as allowed
+    MaxListDepth,
+    /// The selection is not inside a list nested in another one
+    NotInNestedList,
+}
+
+impl fmt::Display for DisabledReason {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::NothingToUndo => "Nothing to undo",
+            Self::NothingToRedo => "Nothing to redo",
+            Self::NotInList => "Not in a list",
+            Self::FirstListItem => "The first item of a list can't be indented",
+            Self::MaxListDepth => "Lists can't be nested any deeper",
+            Self::NotInNestedList => "Not in a nested list",
+        })
+    }
 }
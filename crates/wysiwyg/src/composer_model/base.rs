@@ -15,11 +15,16 @@
 use crate::composer_model::action_state::ActionState;
 use crate::composer_model::menu_state::MenuStateComputeType;
 use crate::composer_state::ComposerState;
-use crate::dom::parser::parse;
-use crate::dom::UnicodeString;
+use crate::dom::diff::DomChanges;
+use crate::dom::parser::{parse_content, parse_content_lenient};
+use crate::dom::{
+    Dom, ParseCorrection, SanitizePolicy, SanitizeReport, UnicodeString,
+};
 use crate::markdown_html_parser::MarkdownHTMLParser;
+use crate::state_stack::StateStack;
 use crate::{
-    ComposerAction, ComposerUpdate, Location, ToHtml, ToMarkdown, ToTree,
+    BlockType, ComposerAction, ComposerUpdate, EnterMode, Location, Shortcut,
+    SuggestionTrigger, ToHtml, ToMarkdown, ToTree,
 };
 use std::collections::HashMap;
 
@@ -32,13 +37,74 @@ where
     pub state: ComposerState<S>,
 
     /// Old states that may be restored by calling undo()
-    pub(crate) previous_states: Vec<ComposerState<S>>,
+    pub(crate) previous_states: StateStack<S>,
 
     /// States after the current one that may be restored by calling redo()
-    pub(crate) next_states: Vec<ComposerState<S>>,
+    pub(crate) next_states: StateStack<S>,
+
+    /// How many previous states are kept, if limited
+    pub(crate) history_limit: Option<usize>,
+
+    /// States saved by checkpoint(), by label
+    pub(crate) checkpoints: HashMap<String, ComposerState<S>>,
 
     /// The states of the buttons for each action e.g. bold, undo
     pub(crate) action_states: HashMap<ComposerAction, ActionState>,
+
+    /// The kind of block the selection was in when the menu state was
+    /// last computed
+    pub(crate) block_type: BlockType,
+
+    /// The link state of the selection when the menu state was last
+    /// computed: the URL of the link around it and whether it spans more
+    /// than one link
+    pub(crate) link_url: Option<String>,
+    pub(crate) spans_multiple_links: bool,
+
+    /// What enter() does
+    pub(crate) enter_mode: EnterMode,
+
+    /// How many lists deep indent() may nest list items, if limited
+    pub(crate) max_list_depth: Option<usize>,
+
+    /// Where the provisional text of an ongoing IME composition is
+    pub(crate) composition: Option<(Location, Location)>,
+
+    /// How many transactions are open, see begin_transaction()
+    pub(crate) transaction_depth: usize,
+
+    /// Where the cursor was left by typing that later typing may be
+    /// coalesced with into the same undo step
+    pub(crate) typing_end: Option<usize>,
+
+    /// The prefixes that start suggestion patterns
+    pub(crate) suggestion_triggers: Vec<SuggestionTrigger>,
+
+    /// The keyboard shortcut for each action that has one
+    pub(crate) shortcuts: HashMap<ComposerAction, Shortcut>,
+
+    /// The document as of the last update sent to the platform, to work
+    /// out which nodes the next one changes. It shares the nodes that
+    /// haven't changed since with the document, so only those that have
+    /// are copied or compared.
+    pub(crate) reported_dom: Dom<S>,
+
+    /// Whether nodes are given stable ids, see set_node_ids_enabled()
+    pub(crate) node_ids_enabled: bool,
+
+    /// Whether replace_text() converts its input to NFC, see
+    /// set_nfc_normalization()
+    pub(crate) nfc_normalization: bool,
+
+    /// What HTML loaded or pasted is restricted to, if anything, see
+    /// set_sanitize_policy()
+    pub(crate) sanitize_policy: Option<SanitizePolicy>,
+
+    /// What was removed from the HTML most recently loaded or pasted
+    pub(crate) sanitize_report: SanitizeReport,
+
+    /// The corrections made to the HTML most recently loaded leniently
+    pub(crate) parse_corrections: Vec<ParseCorrection>,
 }
 
 impl<S> ComposerModel<S>
@@ -48,20 +114,57 @@ where
     pub fn new() -> Self {
         let mut instance = Self {
             state: ComposerState::new(),
-            previous_states: Vec::new(),
-            next_states: Vec::new(),
+            previous_states: StateStack::new(),
+            next_states: StateStack::new(),
+            history_limit: None,
+            checkpoints: HashMap::new(),
             action_states: HashMap::new(), // TODO: Calculate state based on ComposerState
+            block_type: BlockType::Paragraph,
+            link_url: None,
+            spans_multiple_links: false,
+            enter_mode: EnterMode::default(),
+            max_list_depth: None,
+            composition: None,
+            transaction_depth: 0,
+            typing_end: None,
+            suggestion_triggers: SuggestionTrigger::defaults(),
+            shortcuts: Shortcut::defaults(),
+            reported_dom: Dom::new(Vec::new()),
+            node_ids_enabled: false,
+            nfc_normalization: false,
+            sanitize_policy: None,
+            sanitize_report: SanitizeReport::default(),
+            parse_corrections: Vec::new(),
         };
         instance.compute_menu_state(MenuStateComputeType::AlwaysUpdate);
         instance
     }
 
     pub fn from_state(state: ComposerState<S>) -> Self {
+        let reported_dom = state.dom.clone();
         Self {
             state,
-            previous_states: Vec::new(),
-            next_states: Vec::new(),
+            previous_states: StateStack::new(),
+            next_states: StateStack::new(),
+            history_limit: None,
+            checkpoints: HashMap::new(),
             action_states: HashMap::new(), // TODO: Calculate state based on ComposerState
+            block_type: BlockType::Paragraph,
+            link_url: None,
+            spans_multiple_links: false,
+            enter_mode: EnterMode::default(),
+            max_list_depth: None,
+            composition: None,
+            transaction_depth: 0,
+            typing_end: None,
+            suggestion_triggers: SuggestionTrigger::defaults(),
+            shortcuts: Shortcut::defaults(),
+            reported_dom,
+            node_ids_enabled: false,
+            nfc_normalization: false,
+            sanitize_policy: None,
+            sanitize_report: SanitizeReport::default(),
+            parse_corrections: Vec::new(),
         }
     }
 
@@ -74,15 +177,34 @@ where
     ) -> Self {
         let mut model = Self {
             state: ComposerState {
-                dom: parse(html).expect("HTML parsing failed"),
+                dom: parse_content(html).expect("HTML parsing failed"),
                 start: Location::from(start_codeunit),
                 end: Location::from(end_codeunit),
                 toggled_format_types: Vec::new(),
             },
-            previous_states: Vec::new(),
-            next_states: Vec::new(),
+            previous_states: StateStack::new(),
+            next_states: StateStack::new(),
+            history_limit: None,
+            checkpoints: HashMap::new(),
             action_states: HashMap::new(), // TODO: Calculate state based on ComposerState
+            block_type: BlockType::Paragraph,
+            link_url: None,
+            spans_multiple_links: false,
+            enter_mode: EnterMode::default(),
+            max_list_depth: None,
+            composition: None,
+            transaction_depth: 0,
+            typing_end: None,
+            suggestion_triggers: SuggestionTrigger::defaults(),
+            shortcuts: Shortcut::defaults(),
+            reported_dom: Dom::new(Vec::new()),
+            node_ids_enabled: false,
+            nfc_normalization: false,
+            sanitize_policy: None,
+            sanitize_report: SanitizeReport::default(),
+            parse_corrections: Vec::new(),
         };
+        model.reported_dom = model.state.dom.clone();
         model.compute_menu_state(MenuStateComputeType::AlwaysUpdate);
         model
     }
@@ -91,20 +213,17 @@ where
     /// This will remove all previous and next states, effectively disabling
     /// undo and redo until further updates.
     pub fn set_content_from_html(&mut self, html: &S) -> ComposerUpdate<S> {
-        let dom = parse(&html.to_string());
+        let dom = parse_content(&html.to_string());
 
         match dom {
-            Ok(dom) => {
-                self.state.dom = dom;
-                self.state.start = Location::from(self.state.dom.text_len());
-                self.state.end = self.state.start;
-                self.previous_states.clear();
-                self.next_states.clear();
-                self.create_update_replace_all_with_menu_state()
-            }
-            Err(e) => {
+            Ok(dom) => self.set_content_from_dom(dom),
+            Err(mut e) => {
                 // We should log here - internal task PSU-741
-                self.state.dom = e.dom;
+                self.sanitize_html(&mut e.dom);
+                self.composition = None;
+                self.transaction_depth = 0;
+                self.typing_end = None;
+                self.state.dom = *e.dom;
                 self.previous_states.clear();
                 self.next_states.clear();
                 self.create_update_replace_all_with_menu_state()
@@ -112,6 +231,40 @@ where
         }
     }
 
+    /// Like set_content_from_html(), for HTML that may be malformed, e.g.
+    /// from an incoming event. Its problems are corrected by the rules of
+    /// [crate::parse_lenient], and the corrections made are kept for
+    /// last_parse_corrections().
+    pub fn set_content_from_html_lenient(
+        &mut self,
+        html: &S,
+    ) -> ComposerUpdate<S> {
+        let parsed = parse_content_lenient(&html.to_string());
+        self.parse_corrections = parsed.corrections;
+        self.set_content_from_dom(parsed.dom)
+    }
+
+    /// The corrections made to the HTML most recently given to
+    /// set_content_from_html_lenient(). Empty if it was well-formed.
+    pub fn last_parse_corrections(&self) -> &[ParseCorrection] {
+        &self.parse_corrections
+    }
+
+    /// Replace the entire content with `dom`, putting the cursor at the
+    /// end, as for set_content_from_html()
+    fn set_content_from_dom(&mut self, mut dom: Dom<S>) -> ComposerUpdate<S> {
+        self.sanitize_html(&mut dom);
+        self.composition = None;
+        self.transaction_depth = 0;
+        self.typing_end = None;
+        self.state.dom = dom;
+        self.state.start = Location::from(self.state.dom.text_len());
+        self.state.end = self.state.start;
+        self.previous_states.clear();
+        self.next_states.clear();
+        self.create_update_replace_all_with_menu_state()
+    }
+
     pub fn set_content_from_markdown(
         &mut self,
         markdown: &S,
@@ -136,27 +289,71 @@ where
 
     #[cfg(test)]
     pub(crate) fn action_is_disabled(&self, action: ComposerAction) -> bool {
-        self.action_states.get(&action) == Some(&ActionState::Disabled)
+        matches!(
+            self.action_states.get(&action),
+            Some(ActionState::Disabled(_))
+        )
     }
 
     pub(crate) fn create_update_replace_all(&mut self) -> ComposerUpdate<S> {
-        ComposerUpdate::replace_all(
+        self.normalize_format_nodes();
+        self.report_invariant_violations();
+        let mut update = ComposerUpdate::replace_all(
             self.state.dom.to_html(),
             self.state.start,
             self.state.end,
             self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
-        )
+        );
+        update.dom_selection = Some(self.dom_selection());
+        update.dom_changes = self.take_dom_changes();
+        update
     }
 
     pub(crate) fn create_update_replace_all_with_menu_state(
         &mut self,
     ) -> ComposerUpdate<S> {
-        ComposerUpdate::replace_all(
+        self.normalize_format_nodes();
+        self.report_invariant_violations();
+        let mut update = ComposerUpdate::replace_all(
             self.state.dom.to_html(),
             self.state.start,
             self.state.end,
             self.compute_menu_state(MenuStateComputeType::AlwaysUpdate),
-        )
+        );
+        update.dom_selection = Some(self.dom_selection());
+        update.dom_changes = self.take_dom_changes();
+        update
+    }
+
+    /// Give every node of the document a stable id, which it keeps across
+    /// edits until it is removed, so that platforms can track nodes whose
+    /// handles change. Nodes created later are given ids as each update is
+    /// sent. See [Dom::assign_node_ids].
+    pub fn set_node_ids_enabled(&mut self, enabled: bool) {
+        self.node_ids_enabled = enabled;
+        self.assign_node_ids_if_enabled();
+    }
+
+    pub(crate) fn assign_node_ids_if_enabled(&mut self) {
+        if self.node_ids_enabled {
+            self.state.dom.assign_node_ids();
+        }
+    }
+
+    /// With the `assert-invariants` feature, check the document before it
+    /// is sent to the platform. See [crate::dom::invariants].
+    pub(crate) fn report_invariant_violations(&self) {
+        #[cfg(feature = "assert-invariants")]
+        crate::dom::invariants::report_violations(&self.state.dom);
+    }
+
+    /// The nodes changed since the last update sent to the platform. Nodes
+    /// still shared with the last reported document aren't visited.
+    pub(crate) fn take_dom_changes(&mut self) -> DomChanges {
+        self.assign_node_ids_if_enabled();
+        let changes = self.state.dom.changes_since(&self.reported_dom);
+        self.reported_dom = self.state.dom.clone();
+        changes
     }
 
     pub fn get_selection(&self) -> (Location, Location) {
@@ -167,6 +364,14 @@ where
         self.state.dom.to_html()
     }
 
+    /// The HTML of just the selected part of the content, e.g. for the
+    /// clipboard. Formatting that starts or ends outside the selection is
+    /// closed around the part inside it.
+    pub fn get_selected_html(&self) -> S {
+        let (start, end) = self.safe_selection();
+        self.state.dom.fragment(start, end).to_html()
+    }
+
     pub fn get_content_as_markdown(&self) -> S {
         self.state.dom.to_markdown().unwrap()
     }
@@ -0,0 +1,49 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    ClipboardContent, ComposerModel, ComposerUpdate, ToHtml, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The selected part of the content as it should go on the clipboard.
+    /// Both the HTML and the text are taken from the same copy of the
+    /// selection, so pasting either back gives what was selected.
+    pub fn copy(&self) -> ClipboardContent<S> {
+        let (start, end) = self.safe_selection();
+        let fragment = self.state.dom.fragment(start, end);
+        ClipboardContent {
+            html: fragment.to_html(),
+            text: fragment.text_in(0, fragment.text_len()),
+        }
+    }
+
+    /// Remove the selected part of the content, returning it in the
+    /// update's clipboard field as [copy](Self::copy) would. Does nothing
+    /// if the selection is empty.
+    pub fn cut(&mut self) -> ComposerUpdate<S> {
+        let (start, end) = self.safe_selection();
+        if start == end {
+            return ComposerUpdate::keep();
+        }
+
+        let content = self.copy();
+        let mut update = self.delete();
+        update.clipboard = Some(content);
+        update
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Show the provisional text of an input method (IME) composition,
+    /// replacing the selection, or the previous provisional text if a
+    /// composition is already ongoing. `start` and `end` give the selection
+    /// within `text`. The whole composition is a single undo step, however
+    /// many times it changes before commit_composition() is called.
+    pub fn set_composition(
+        &mut self,
+        text: S,
+        start: usize,
+        end: usize,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = match self.composition {
+            Some((s, e)) => (s.into(), e.into()),
+            None => {
+                let (s, e) = self.safe_selection();
+                if s == e && text.is_empty() {
+                    return ComposerUpdate::keep();
+                }
+                self.push_state_to_history();
+                (s, e)
+            }
+        };
+
+        let len = text.len();
+        self.do_replace_text_in(text, s, e);
+        self.composition = Some((Location::from(s), Location::from(s + len)));
+        self.state.start = Location::from(s + start.min(len));
+        self.state.end = Location::from(s + end.min(len));
+        self.create_update_replace_all()
+    }
+
+    /// Finish the ongoing composition, keeping its text as it is and
+    /// placing the cursor after it.
+    pub fn commit_composition(&mut self) -> ComposerUpdate<S> {
+        let end = match self.composition.take() {
+            Some((_, end)) => end,
+            None => return ComposerUpdate::keep(),
+        };
+        // A composition that changed nothing shouldn't leave an undo step
+        if self.previous_states.last().map(|state| &state.dom)
+            == Some(&self.state.dom)
+        {
+            self.previous_states.pop();
+        }
+        self.state.start = end;
+        self.state.end = end;
+        self.create_update_replace_all()
+    }
+
+    /// Where the provisional text of the ongoing composition is, if any,
+    /// e.g. so that it can be underlined.
+    pub fn get_composition(&self) -> Option<(Location, Location)> {
+        self.composition
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use once_cell::sync::OnceCell;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::dom::nodes::container_node::ContainerNodeKind;
+use crate::dom::nodes::{ContainerNode, DomNode};
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::DomHandle;
+use crate::{ComposerModel, ContentCounts, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Count the characters, words and code units of the content. Each
+    /// block keeps its counts until it is edited, so this is cheap enough
+    /// to call after every update, with no need to serialize the content
+    /// as HTML.
+    pub fn counts(&self) -> ContentCounts {
+        self.state.dom.document().line_counts().counts
+    }
+
+    /// Whether the content has nothing worth sending: only whitespace,
+    /// line breaks, empty list items or placeholders. Mentions and images
+    /// are content.
+    pub fn is_content_empty(&self) -> bool {
+        let range = self.state.dom.find_range(0, self.state.dom.text_len());
+        let has_inline_node = range.leaves().any(|leaf| {
+            matches!(
+                self.state.dom.lookup_node(&leaf.node_handle),
+                DomNode::Mention(_) | DomNode::Image(_)
+            )
+        });
+        !has_inline_node && self.visible_text().trim().is_empty()
+    }
+
+    /// The text a reader sees, with a line break between blocks like list
+    /// items. Placeholders only exist to hold the cursor, so are left out.
+    fn visible_text(&self) -> String {
+        let range = self.state.dom.find_range(0, self.state.dom.text_len());
+        let mut text = String::new();
+        let mut last_block: Option<DomHandle> = None;
+        for leaf in range.leaves() {
+            let block = self
+                .state
+                .dom
+                .find_block_ancestor_or_self(&leaf.node_handle);
+            if last_block.is_some() && last_block.as_ref() != Some(&block) {
+                text.push('\n');
+            }
+            last_block = Some(block);
+
+            match self.state.dom.lookup_node(&leaf.node_handle) {
+                DomNode::Text(node) => text.push_str(&node.data().to_string()),
+                DomNode::LineBreak(_) => text.push('\n'),
+                DomNode::Mention(node) => {
+                    text.push_str(&node.display_text().to_string())
+                }
+                DomNode::Image(_) | DomNode::Container(_) => {}
+            }
+        }
+        text.replace('\u{200b}', "")
+    }
+}
+
+/// The counts of the text of a block, as lines: a line for each run of
+/// text in the same block, so that text in different blocks, e.g. list
+/// items, is never counted as the same word.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LineCounts {
+    /// The counts of all the lines, and of a line break between each
+    pub counts: ContentCounts,
+    pub lines: usize,
+}
+
+impl LineCounts {
+    fn of_line(text: &str, code_units: usize) -> Self {
+        Self {
+            counts: ContentCounts {
+                characters: text.graphemes(true).count(),
+                words: count_words(text),
+                code_units,
+            },
+            lines: 1,
+        }
+    }
+
+    fn append(&mut self, other: LineCounts) {
+        if self.lines > 0 && other.lines > 0 {
+            self.counts.characters += 1;
+            self.counts.code_units += 1;
+        }
+        self.counts.characters += other.counts.characters;
+        self.counts.words += other.counts.words;
+        self.counts.code_units += other.counts.code_units;
+        self.lines += other.lines;
+    }
+}
+
+/// The [LineCounts] of a block, kept by the block and counted again only
+/// after its children change, like its
+/// [crate::dom::find_range::ChildPositions]. Blocks inside it keep their
+/// own, so only the blocks along the path of an edit are counted again.
+#[derive(Clone, Default)]
+pub(crate) struct CachedCounts(OnceCell<LineCounts>);
+
+impl CachedCounts {
+    pub(crate) fn get_or_count<S>(&self, block: &ContainerNode<S>) -> LineCounts
+    where
+        S: UnicodeString,
+    {
+        *self.0.get_or_init(|| {
+            let mut counter = LineCounter::default();
+            for child in block.children().iter() {
+                counter.add(child);
+            }
+            counter.finish::<S>()
+        })
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.take();
+    }
+}
+
+impl fmt::Debug for CachedCounts {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("CachedCounts")
+    }
+}
+
+/// Collects the text of a block into lines, taking the counts of the
+/// blocks inside it from them
+#[derive(Default)]
+struct LineCounter {
+    counts: LineCounts,
+    line: Option<String>,
+}
+
+impl LineCounter {
+    fn add<S>(&mut self, node: &DomNode<S>)
+    where
+        S: UnicodeString,
+    {
+        match node {
+            DomNode::Container(container) if is_block(container) => {
+                self.end_line::<S>();
+                self.counts.append(container.line_counts());
+            }
+            DomNode::Container(container) => {
+                for child in container.children().iter() {
+                    self.add(child);
+                }
+            }
+            // Empty text is only there to hold the cursor
+            DomNode::Text(node) if node.data().is_empty() => {}
+            DomNode::Text(node) => {
+                self.line().push_str(&node.data().to_string())
+            }
+            DomNode::LineBreak(_) => self.line().push('\n'),
+            DomNode::Mention(node) => {
+                self.line().push_str(&node.display_text().to_string())
+            }
+            DomNode::Image(_) => {
+                self.line();
+            }
+        }
+    }
+
+    fn line(&mut self) -> &mut String {
+        self.line.get_or_insert_with(String::new)
+    }
+
+    /// Placeholders only exist to hold the cursor, so are left out
+    fn end_line<S>(&mut self)
+    where
+        S: UnicodeString,
+    {
+        if let Some(line) = self.line.take() {
+            let text = line.replace('\u{200b}', "");
+            let code_units = S::from(text.as_str()).len();
+            self.counts.append(LineCounts::of_line(&text, code_units));
+        }
+    }
+
+    fn finish<S>(mut self) -> LineCounts
+    where
+        S: UnicodeString,
+    {
+        self.end_line::<S>();
+        self.counts
+    }
+}
+
+/// Whether the text of `container` is on lines of its own, the same blocks
+/// as [crate::dom::Dom::find_block_ancestor_or_self] finds
+fn is_block<S>(container: &ContainerNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    matches!(
+        container.kind(),
+        ContainerNodeKind::Generic
+            | ContainerNodeKind::ListItem
+            | ContainerNodeKind::TableCell
+    )
+}
+
+/// The number of segments between word boundaries in `text` that contain
+/// a letter or digit, as opposed to whitespace or punctuation
+fn count_words(text: &str) -> usize {
+    let boundaries: Vec<usize> = text.word_boundaries().collect();
+    boundaries
+        .windows(2)
+        .filter(|bounds| {
+            text[bounds[0]..bounds[1]]
+                .chars()
+                .any(char::is_alphanumeric)
+        })
+        .count()
+}
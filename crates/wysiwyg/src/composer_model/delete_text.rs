@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dom::nodes::{DomNode, TextNode};
-use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt};
 use crate::dom::{DomHandle, DomLocation, Range};
 use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
 
@@ -24,7 +24,7 @@ where
     pub fn backspace(&mut self) -> ComposerUpdate<S> {
         let (s, e) = self.safe_selection();
 
-        if s == e {
+        let mut update = if s == e {
             // We have no selection - check for special list behaviour
             // TODO: should probably also get inside here if our selection
             // only contains a zero-wdith space.
@@ -32,7 +32,9 @@ where
             self.backspace_single_cursor(range, e)
         } else {
             self.do_backspace()
-        }
+        };
+        update.menu_action = self.compute_menu_action();
+        update
     }
 
     fn backspace_single_cursor(
@@ -45,6 +47,21 @@ where
         // selection that spans multiple leaves.
         let first_leaf = range.locations.iter().find(|loc| loc.is_leaf);
         if let Some(leaf) = first_leaf {
+            // An empty table cell must keep its placeholder so that the
+            // cursor can still be placed inside it.
+            if let Some(cell_handle) = self
+                .state
+                .dom
+                .find_parent_table_cell_or_self(&leaf.node_handle)
+            {
+                let cell = self.state.dom.lookup_node(&cell_handle);
+                if cell.has_only_placeholder_text_child() {
+                    return ComposerUpdate::keep();
+                }
+                self.do_backspace();
+                return self.restore_table_cell_placeholder(&cell_handle);
+            }
+
             // We are backspacing inside a text node with no
             // selection - we might need special behaviour, if
             // we are at the start of a list item.
@@ -54,6 +71,8 @@ where
                 .find_parent_list_item_or_self(&leaf.node_handle);
             if let Some(parent_handle) = parent_list_item_handle {
                 self.do_backspace_in_list(&parent_handle, end_position)
+            } else if let Some(update) = self.do_backspace_after_list(leaf) {
+                update
             } else {
                 self.do_backspace()
             }
@@ -62,6 +81,126 @@ where
         }
     }
 
+    /// Deletes the word before the cursor, along with any whitespace
+    /// between it and the cursor. If there is no text to delete this
+    /// behaves like [backspace], e.g. to remove a mention or join blocks.
+    pub fn delete_word_backward(&mut self) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if s != e {
+            return self.backspace();
+        }
+        match self.word_start_before(s) {
+            Some(word_start) => self.delete_range(word_start, s),
+            None => self.backspace(),
+        }
+    }
+
+    /// Deletes the word after the cursor, along with any whitespace
+    /// between it and the cursor. If there is no text to delete this
+    /// behaves like [delete].
+    pub fn delete_word_forward(&mut self) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if s != e {
+            return self.delete();
+        }
+        match self.word_end_after(s) {
+            Some(word_end) => self.delete_range(s, word_end),
+            None => self.delete(),
+        }
+    }
+
+    /// Deletes from the cursor back to the start of the line it is on,
+    /// stopping at line breaks and at the edges of the enclosing block
+    /// (list item, table cell or document). At the start of a line this
+    /// behaves like [backspace].
+    pub fn delete_to_start_of_block(&mut self) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if s != e {
+            return self.backspace();
+        }
+        let (line_start, _) = self.line_bounds_at(s);
+        if line_start < s {
+            self.delete_range(line_start, s)
+        } else {
+            self.backspace()
+        }
+    }
+
+    /// Deletes from the cursor to the end of the line it is on, stopping
+    /// at line breaks and at the edges of the enclosing block. At the end
+    /// of a line this behaves like [delete].
+    pub fn delete_to_end_of_block(&mut self) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if s != e {
+            return self.delete();
+        }
+        let (_, line_end) = self.line_bounds_at(s);
+        if line_end > s {
+            self.delete_range(s, line_end)
+        } else {
+            self.delete()
+        }
+    }
+
+    /// Returns the start and end of the line containing `pos` within its
+    /// block, leaving out any placeholder character the line starts with.
+    pub(crate) fn line_bounds_at(&self, pos: usize) -> (usize, usize) {
+        let dom = &self.state.dom;
+        let range = dom.find_range(pos, pos);
+        let cursor_leaf = match range.leaves().next() {
+            Some(leaf) => leaf,
+            None => return (pos, pos),
+        };
+        let block = dom.find_block_ancestor_or_self(&cursor_leaf.node_handle);
+
+        let all = dom.find_range(0, dom.text_len());
+        // The document itself isn't one of the locations in a range
+        let (mut start, mut end) = all
+            .locations
+            .iter()
+            .find(|l| l.node_handle == block)
+            .map_or((0, dom.text_len()), |l| {
+                (l.position, l.position + l.length)
+            });
+        for leaf in all.leaves() {
+            let leaf_end = leaf.position + leaf.length;
+            let in_block =
+                dom.find_block_ancestor_or_self(&leaf.node_handle) == block;
+            let is_line_break = matches!(
+                dom.lookup_node(&leaf.node_handle),
+                DomNode::LineBreak(_)
+            );
+            if leaf_end <= pos && leaf_end > start && in_block && is_line_break
+            {
+                start = leaf_end;
+            } else if leaf.position >= pos
+                && leaf.position < end
+                && (is_line_break || !in_block)
+            {
+                end = leaf.position;
+            }
+        }
+
+        // Keep the placeholder that holds an otherwise empty line open
+        let first_leaf = all
+            .leaves()
+            .find(|l| l.position == start && l.length > 0 && l.position < end);
+        if let Some(leaf) = first_leaf {
+            if let DomNode::Text(text) = dom.lookup_node(&leaf.node_handle) {
+                if text.data().chars().next() == Some('\u{200b}') {
+                    start = (start + S::from("\u{200b}").len()).min(pos);
+                }
+            }
+        }
+        (start, end)
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) -> ComposerUpdate<S> {
+        // Store current Dom
+        self.push_state_to_history();
+        self.do_replace_text_in(S::default(), start, end)
+    }
+
     /// Deletes text in an arbitrary start..end range.
     pub fn delete_in(&mut self, start: usize, end: usize) -> ComposerUpdate<S> {
         self.state.end = Location::from(start);
@@ -72,19 +211,14 @@ where
     pub fn delete(&mut self) -> ComposerUpdate<S> {
         if self.state.start == self.state.end {
             let (s, _) = self.safe_selection();
-            // If we're dealing with complex graphemes, this value might not be 1
-            let next_char_len =
-                if let Some((text_node, loc)) = self.get_selected_text_node() {
-                    let selection_start_in_str = s - loc.position;
-                    Self::find_next_char_len(
-                        selection_start_in_str,
-                        &text_node.data(),
-                    ) as isize
-                } else {
-                    1
-                };
-            // Go forward `next_char_len` positions from the current location
-            self.state.end += next_char_len;
+            // Never split a grapheme, so this might be more than 1 character
+            if let Some((start, end)) = self.grapheme_range_at(s, true) {
+                self.state.start = Location::from(start);
+                self.state.end = Location::from(end);
+            } else {
+                self.state.end +=
+                    self.atomic_inline_len_after(s).unwrap_or(1) as isize;
+            }
         }
 
         self.replace_text(S::default())
@@ -118,63 +252,116 @@ where
     pub(crate) fn do_backspace(&mut self) -> ComposerUpdate<S> {
         if self.state.start == self.state.end {
             let (_, e) = self.safe_selection();
-            // If we're dealing with complex graphemes, this value might not be 1
-            let prev_char_len =
-                if let Some((text_node, loc)) = self.get_selected_text_node() {
-                    let selection_end_in_str = e - loc.position;
-                    Self::find_previous_char_len(
-                        selection_end_in_str,
-                        &text_node.data(),
-                    ) as isize
-                } else {
-                    1
-                };
-            // Go back `prev_char_len` positions from the current location
-            self.state.start -= prev_char_len;
+            // Never split a grapheme, so this might be more than 1 character
+            if let Some((start, end)) = self.grapheme_range_at(e, false) {
+                self.state.start = Location::from(start);
+                self.state.end = Location::from(end);
+            } else {
+                self.state.start -=
+                    self.atomic_inline_len_before(e).unwrap_or(1) as isize;
+            }
         }
 
         self.replace_text(S::default())
     }
 
-    /// Returns the currently selected TextNode if it's the only leaf node and the cursor is inside
-    /// its range.
-    fn get_selected_text_node(&self) -> Option<(&TextNode<S>, DomLocation)> {
-        let (s, e) = self.safe_selection();
-        let range = self.state.dom.find_range(s, e);
-        let leaves: Vec<&DomLocation> = range.leaves().collect();
-        if s == e && leaves.len() == 1 {
-            let leaf = leaves[0];
-            if let DomNode::Text(text_node) =
-                self.state.dom.lookup_node(&leaf.node_handle)
-            {
-                return Some((text_node, leaf.clone()));
+    /// Returns the range that deleting the grapheme before [pos], or after
+    /// it if [forward], should remove, if there is text there. If [pos] is
+    /// inside a grapheme, e.g. between the parts of an emoji ZWJ sequence or
+    /// before a combining mark, the whole grapheme is removed.
+    fn grapheme_range_at(
+        &self,
+        pos: usize,
+        forward: bool,
+    ) -> Option<(usize, usize)> {
+        // A range of zero length may not include the leaves on either side
+        let range = self.state.dom.find_range(
+            pos.saturating_sub(1),
+            (pos + 1).min(self.state.dom.text_len()),
+        );
+        let grapheme_range = range.leaves().find_map(|leaf| {
+            let text = match self.state.dom.lookup_node(&leaf.node_handle) {
+                DomNode::Text(text) => text,
+                _ => return None,
+            };
+            let leaf_end = leaf.position + leaf.length;
+            let has_text_there = if forward {
+                leaf.position <= pos && pos < leaf_end
+            } else {
+                leaf.position < pos && pos <= leaf_end
+            };
+            if !has_text_there {
+                return None;
             }
-        }
-        None
+
+            let offset = pos - leaf.position;
+            let boundaries: Vec<usize> =
+                text.data().grapheme_boundaries().collect();
+            let before = boundaries
+                .iter()
+                .rev()
+                .find(|b| **b < offset)
+                .map_or(0, |b| *b);
+            let after = boundaries
+                .iter()
+                .find(|b| **b > offset)
+                .map_or(leaf.length, |b| *b);
+            let (start, end) = match (boundaries.contains(&offset), forward) {
+                (true, false) => (before, offset),
+                (true, true) => (offset, after),
+                (false, _) => (before, after),
+            };
+            Some((leaf.position + start, leaf.position + end))
+        });
+        grapheme_range
     }
 
-    /// Returns the length of the [char] for the current [S] string encoding before the given [pos].
-    fn find_previous_char_len(pos: usize, str: &S::Str) -> usize {
-        let graphemes = str.find_graphemes_at(pos);
-        // Take the grapheme before the position
-        if let Some(last_grapheme) = graphemes.0 {
-            last_grapheme.len()
-        } else {
-            // Default length for characters
-            1
-        }
+    /// Returns the length of the atomic inline node ending at [pos], if any,
+    /// so that backspace removes it as a whole.
+    pub(crate) fn atomic_inline_len_before(&self, pos: usize) -> Option<usize> {
+        self.atomic_inline_len_matching(pos, |l| l.position + l.length == pos)
     }
 
-    /// Returns the length of the [char] for the current [S] string encoding after the given [pos].
-    fn find_next_char_len(pos: usize, str: &S::Str) -> usize {
-        let graphemes = str.find_graphemes_at(pos);
-        // Take the grapheme after the position
-        if let Some(first_grapheme) = graphemes.1 {
-            first_grapheme.len()
-        } else {
-            // Default length for characters
-            1
-        }
+    /// Returns the length of the atomic inline node starting at [pos], if
+    /// any, so that delete removes it as a whole.
+    pub(crate) fn atomic_inline_len_after(&self, pos: usize) -> Option<usize> {
+        self.atomic_inline_len_matching(pos, |l| l.position == pos)
+    }
+
+    fn atomic_inline_len_matching(
+        &self,
+        pos: usize,
+        predicate: impl Fn(&DomLocation) -> bool,
+    ) -> Option<usize> {
+        let range = self.state.dom.find_range(pos, pos);
+        let leaf = range.leaves().find(|l| {
+            predicate(l)
+                && self
+                    .state
+                    .dom
+                    .lookup_node(&l.node_handle)
+                    .is_atomic_inline()
+        });
+        leaf.map(|l| l.length)
+    }
+
+    /// Returns the length of the grapheme for the current [S] string
+    /// encoding before the given [pos], or of the part of it before [pos]
+    /// if [pos] is inside one.
+    pub(crate) fn find_previous_char_len(pos: usize, str: &S::Str) -> usize {
+        str.grapheme_boundaries()
+            .take_while(|boundary| *boundary < pos)
+            .last()
+            .map_or(1, |boundary| pos - boundary)
+    }
+
+    /// Returns the length of the grapheme for the current [S] string
+    /// encoding after the given [pos], or of the part of it after [pos]
+    /// if [pos] is inside one.
+    pub(crate) fn find_next_char_len(pos: usize, str: &S::Str) -> usize {
+        str.grapheme_boundaries()
+            .find(|boundary| *boundary > pos)
+            .map_or(1, |boundary| boundary - pos)
     }
 }
 
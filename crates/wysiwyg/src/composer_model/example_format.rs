@@ -131,6 +131,7 @@ impl ComposerModel<Utf16String> {
             model.state.start = Location::from(curs);
             model.state.end = Location::from(curs);
         }
+        model.reported_dom = model.state.dom.clone();
         model.compute_menu_state(MenuStateComputeType::KeepIfUnchanged);
 
         model
@@ -215,6 +216,24 @@ impl SelectionWriter {
         }
     }
 
+    pub fn write_selection_atomic_inline_node<S: UnicodeString>(
+        &mut self,
+        buf: &mut S,
+        pos: usize,
+        handle: &DomHandle,
+        length: usize,
+    ) {
+        if let Some(loc) = self.locations.get(handle) {
+            let strings_to_add = self.state.advance(loc, length);
+            for (str, i) in strings_to_add.into_iter().rev() {
+                // Atomic nodes are written whole, so a marker is either
+                // before the node's HTML or after all of it.
+                let i = if i == 0 { pos } else { buf.len() };
+                buf.insert(i, &S::from(str));
+            }
+        }
+    }
+
     pub fn is_selection_written(&self) -> bool {
         self.state.done_first
     }
@@ -233,9 +252,10 @@ fn utf16_code_unit(s: &str) -> u16 {
 fn find_char(haystack: &[u16], needle: &str) -> Option<usize> {
     let mut skip_count = 0; // How many tag characters we have seen
     let mut in_tag = false; // Are we in a tag now?
+    let mut in_mention = false; // Are we inside the text of a mention?
 
     // Track the contents of the tag we are inside, so we know whether we've
-    // seen a br tag.
+    // seen a br or img tag, or the start of a mention.
     let mut tag_contents: Vec<u16> = Vec::new();
 
     let needle = utf16_code_unit(needle);
@@ -245,19 +265,41 @@ fn find_char(haystack: &[u16], needle: &str) -> Option<usize> {
     let forward_slash = utf16_code_unit("/");
 
     let br_tag = Utf16String::from_str("br").into_vec();
+    let img_tag = Utf16String::from_str("img").into_vec();
+    let mention_marker =
+        Utf16String::from_str("contenteditable=\"false\"").into_vec();
 
     for (i, &ch) in haystack.iter().enumerate() {
+        if in_mention && ch != open && !in_tag {
+            // The text of a mention is not counted - the mention is worth 1
+            // code unit, which we counted at the end of its opening tag.
+            skip_count += 1;
+            continue;
+        }
         if ch == needle {
             return Some(i - skip_count);
         } else if ch == open {
             in_tag = true;
         } else if ch == close {
-            // Skip this character (>), unless we've found a br tag, in which
-            // case the whole tag will be worth 1 code unit, so we don't
-            // increase skip count.
-            if tag_contents != br_tag {
+            // Skip this character (>), unless we've found a br or img tag or
+            // the opening tag of a mention, in which case the whole tag will
+            // be worth 1 code unit, so we don't increase skip count.
+            let is_mention_start = tag_contents.first()
+                == Some(&utf16_code_unit("a"))
+                && tag_contents
+                    .windows(mention_marker.len())
+                    .any(|w| w == mention_marker);
+            if tag_contents != br_tag
+                && !tag_contents.starts_with(&img_tag)
+                && !is_mention_start
+            {
                 skip_count += 1;
             }
+            if is_mention_start {
+                in_mention = true;
+            } else if tag_contents.first() == Some(&utf16_code_unit("a")) {
+                in_mention = false;
+            }
             in_tag = false;
             tag_contents.clear();
         }
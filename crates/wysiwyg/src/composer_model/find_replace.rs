@@ -0,0 +1,106 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Find every occurrence of `pattern` in the text of the document,
+    /// returning where each of them starts and ends. Matches may span
+    /// differently formatted text, but not line breaks, mentions or
+    /// blocks like list items.
+    pub fn find(&self, pattern: &S) -> Vec<(Location, Location)> {
+        self.find_matches(pattern)
+            .into_iter()
+            .map(|(start, end)| (Location::from(start), Location::from(end)))
+            .collect()
+    }
+
+    /// Replace every occurrence of `pattern` with `replacement`, as a
+    /// single undo step. Each replacement takes the formatting of the
+    /// start of the text it replaces.
+    pub fn replace_all(
+        &mut self,
+        pattern: &S,
+        replacement: &S,
+    ) -> ComposerUpdate<S> {
+        let matches = self.find_matches(pattern);
+        if matches.is_empty() {
+            return ComposerUpdate::keep();
+        }
+        // Store current Dom
+        self.push_state_to_history();
+
+        let (s, e) = self.safe_selection();
+        let new_s = position_after_replacing(s, &matches, replacement.len());
+        let new_e = position_after_replacing(e, &matches, replacement.len());
+
+        // Replacing isn't typing, so formats toggled for the cursor stay
+        // pending
+        let pending_formats =
+            std::mem::take(&mut self.state.toggled_format_types);
+        // Work backwards so that earlier matches stay where they are
+        for (start, end) in matches.into_iter().rev() {
+            self.state.start = Location::from(start);
+            self.state.end = Location::from(end);
+            self.do_replace_text_in(replacement.clone(), start, end);
+        }
+        self.state.toggled_format_types = pending_formats;
+
+        self.state.start = Location::from(new_s);
+        self.state.end = Location::from(new_e);
+        self.create_update_replace_all()
+    }
+
+    fn find_matches(&self, pattern: &S) -> Vec<(usize, usize)> {
+        let pattern = pattern.to_string();
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = Vec::new();
+        for run in self.text_runs() {
+            let text = run.text.to_string();
+            for (byte_index, found) in text.match_indices(&pattern) {
+                let start = run.start + S::from(&text[..byte_index]).len();
+                matches.push((start, start + S::from(found).len()));
+            }
+        }
+        matches
+    }
+}
+
+/// Where `pos` ends up once every match in `matches` is replaced by text
+/// of length `replacement_len`. A position inside a match moves to the end
+/// of its replacement.
+fn position_after_replacing(
+    pos: usize,
+    matches: &[(usize, usize)],
+    replacement_len: usize,
+) -> usize {
+    let (mut added, mut removed) = (0, 0);
+    for (start, end) in matches {
+        if pos >= *end {
+            added += replacement_len;
+            removed += end - start;
+        } else if pos > *start {
+            return start + added - removed + replacement_len;
+        } else {
+            break;
+        }
+    }
+    pos + added - removed
+}
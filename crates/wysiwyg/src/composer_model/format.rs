@@ -390,8 +390,11 @@ where
                     };
                     (before, after)
                 }
+            } else if position == 0 {
+                // Other nodes can't be split, so stay whole on one side
+                (None, Some(node))
             } else {
-                panic!("Node was not a text node so can't be split!");
+                (Some(node), None)
             }
         } else {
             (None, None)
@@ -400,16 +403,19 @@ where
 
     /**
      * If the supplied node is a text node with zero length, modify it to
-     * contain a zero width space and return 1.
+     * contain a zero width space and return its length.
      * Otherwise, return 0 and don't modify anything.
      *
-     * Returns the number of characters added, which will either be 0 or 1.
+     * Returns the number of code units added, which will either be 0 or
+     * the length of a zero width space.
      */
     fn insert_zwspace_if_needed(node: &mut DomNode<S>) -> isize {
         if let DomNode::Text(text) = node {
             if text.data().is_empty() {
-                text.set_data("\u{200B}".into());
-                1
+                let zwsp = S::from("\u{200B}");
+                let len = zwsp.len() as isize;
+                text.set_data(zwsp);
+                len
             } else {
                 0
             }
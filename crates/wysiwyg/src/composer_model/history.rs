@@ -0,0 +1,144 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_state::ComposerState;
+use crate::dom::parser::parse;
+use crate::state_encoding::{Decoder, Encoder, FORMAT_VERSION};
+use crate::state_stack::StateStack;
+use crate::{
+    ComposerHistory, ComposerModel, ComposerUpdate, DecodeError, HistoryEntry,
+    Location, ToHtml, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The current content of the model along with its undo and redo
+    /// history, which can be given to import_history() to restore them.
+    /// Formatting toggled at the cursor is not included.
+    pub fn export_history(&self) -> ComposerHistory<S> {
+        ComposerHistory {
+            previous: self
+                .previous_states
+                .states()
+                .iter()
+                .map(to_entry)
+                .collect(),
+            current: to_entry(&self.state),
+            next: self.next_states.states().iter().map(to_entry).collect(),
+        }
+    }
+
+    /// Replace the content of the model and its undo and redo history with
+    /// ones from export_history(). Any history beyond the limit set with
+    /// set_history_limit() is dropped, oldest first.
+    pub fn import_history(
+        &mut self,
+        history: &ComposerHistory<S>,
+    ) -> ComposerUpdate<S> {
+        self.composition = None;
+        self.transaction_depth = 0;
+        self.typing_end = None;
+        self.state = from_entry(&history.current);
+        self.previous_states =
+            history.previous.iter().map(from_entry).collect();
+        self.next_states = history.next.iter().map(from_entry).collect();
+        self.enforce_history_limit();
+        self.create_update_replace_all_with_menu_state()
+    }
+
+    /// Save the content, selection and toggled formatting of the model in
+    /// a compact binary form that from_bytes() can restore, e.g. when a
+    /// platform view is destroyed. The undo and redo history is included
+    /// if asked for. Settings like the history limit are not saved.
+    pub fn to_bytes(&self, include_history: bool) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.write_u8(FORMAT_VERSION);
+        encoder.write_state(&self.state);
+        encoder.write_bool(include_history);
+        if include_history {
+            self.previous_states.encode(&mut encoder);
+            self.next_states.encode(&mut encoder);
+        }
+        encoder.into_bytes()
+    }
+
+    /// Create a model from one saved by to_bytes()
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut model = Self::new();
+        model.restore_from_bytes(bytes)?;
+        Ok(model)
+    }
+
+    /// Replace the content, selection and history of the model with ones
+    /// saved by to_bytes(), keeping its settings. If the bytes can't be
+    /// read the model is left as it was.
+    pub fn restore_from_bytes(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<ComposerUpdate<S>, DecodeError> {
+        let mut decoder = Decoder::new(bytes);
+        let version = decoder.read_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let state = decoder.read_state()?;
+        let (previous_states, next_states) = if decoder.read_bool()? {
+            (
+                StateStack::decode(&mut decoder)?,
+                StateStack::decode(&mut decoder)?,
+            )
+        } else {
+            (StateStack::new(), StateStack::new())
+        };
+        decoder.finish()?;
+
+        self.composition = None;
+        self.transaction_depth = 0;
+        self.typing_end = None;
+        self.state = state;
+        self.previous_states = previous_states;
+        self.next_states = next_states;
+        self.enforce_history_limit();
+        Ok(self.create_update_replace_all_with_menu_state())
+    }
+}
+
+fn to_entry<S>(state: &ComposerState<S>) -> HistoryEntry<S>
+where
+    S: UnicodeString,
+{
+    HistoryEntry {
+        html: state.dom.to_html(),
+        start: state.start.into(),
+        end: state.end.into(),
+    }
+}
+
+/// Restore a state, keeping its selection within the content in case the
+/// HTML was changed or could only be partly parsed.
+fn from_entry<S>(entry: &HistoryEntry<S>) -> ComposerState<S>
+where
+    S: UnicodeString,
+{
+    let dom = parse(&entry.html.to_string()).unwrap_or_else(|e| *e.dom);
+    let len = dom.text_len();
+    ComposerState {
+        dom,
+        start: Location::from(entry.start.min(len)),
+        end: Location::from(entry.end.min(len)),
+        toggled_format_types: Vec::new(),
+    }
+}
@@ -12,60 +12,169 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dom::nodes::DomNode;
+use crate::dom::nodes::{ContainerNodeKind, DomNode};
 use crate::dom::unicode_string::UnicodeStrExt;
-use crate::dom::{DomLocation, Range};
-use crate::{ComposerModel, ComposerUpdate, UnicodeString};
+use crate::dom::{DomError, DomHandle, DomLocation, Range};
+use crate::{
+    ComposerAction, ComposerError, ComposerModel, ComposerUpdate, ToRawText,
+    UnicodeString,
+};
 
 impl<S> ComposerModel<S>
 where
     S: UnicodeString,
 {
-    pub fn set_link(&mut self, link: S) -> ComposerUpdate<S> {
+    /// Turn the selection into a link. Returns an error, leaving the model
+    /// unchanged, if the selection spans content that can't be linked yet.
+    pub fn set_link(
+        &mut self,
+        link: S,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        self.set_link_with_attributes(link, Vec::new())
+    }
+
+    /// Turn the selection into a link whose `<a>` tag also carries the
+    /// supplied attributes, e.g. `("rel", "noopener")`.
+    pub fn set_link_with_attributes(
+        &mut self,
+        link: S,
+        attributes: Vec<(S, S)>,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
         let (s, e) = self.safe_selection();
         // Can't add a link to an empty selection
         if s == e {
+            return Ok(ComposerUpdate::keep());
+        }
+
+        let range = self.state.dom.find_range(s, e);
+        self.set_link_range(range, link, attributes)
+    }
+
+    /// Insert a new link displaying the supplied text at the cursor, and
+    /// place the cursor after it. Any selected text is replaced.
+    pub fn set_link_with_text(
+        &mut self,
+        link: S,
+        text: S,
+    ) -> ComposerUpdate<S> {
+        if text.is_empty() {
+            return ComposerUpdate::keep();
+        }
+        // Store current Dom
+        self.push_state_to_history();
+
+        self.do_insert_node_at_cursor(DomNode::new_link(
+            link,
+            vec![DomNode::new_text(text)],
+        ));
+        self.create_update_replace_all()
+    }
+
+    /// Remove every link that intersects the current selection, keeping the
+    /// text (and any formatting) that was inside it.
+    pub fn remove_links(&mut self) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_range(s, e);
+
+        let mut link_handles: Vec<DomHandle> = range
+            .locations
+            .iter()
+            .map(|l| &l.node_handle)
+            .filter(|h| self.state.dom.lookup_node(h).is_link())
+            .cloned()
+            .collect();
+        if link_handles.is_empty() {
             return ComposerUpdate::keep();
         }
         // Store current Dom
         self.push_state_to_history();
 
+        // Unwrap in reverse order so that earlier handles stay valid
+        link_handles.sort();
+        let mut parent_handles = Vec::new();
+        for handle in link_handles.iter().rev() {
+            self.state.dom.remove_and_keep_children(handle);
+            parent_handles.push(handle.parent_handle());
+        }
+
+        // The unwrapped text may now sit next to other text nodes
+        parent_handles.sort();
+        parent_handles.dedup();
+        for parent_handle in parent_handles.iter().rev() {
+            self.join_text_nodes_in_parent(parent_handle);
+        }
+
+        self.create_update_replace_all()
+    }
+
+    /// Return the url and text of the link containing the selection, if the
+    /// whole selection lies inside a single link.
+    pub fn get_link_at_selection(&self) -> Option<(S, S)> {
+        let (s, e) = self.safe_selection();
         let range = self.state.dom.find_range(s, e);
-        self.set_link_range(range, link)
+        range
+            .locations
+            .iter()
+            .filter(|l| {
+                let end = l.position + l.length;
+                if s == e {
+                    // A cursor just before or after a link is not in it
+                    l.position < s && s < end
+                } else {
+                    l.position <= s && e <= end
+                }
+            })
+            .find_map(|l| match self.state.dom.lookup_node(&l.node_handle) {
+                DomNode::Container(container) => match container.kind() {
+                    ContainerNodeKind::Link(url) => {
+                        Some((url.clone(), container.to_raw_text()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
     }
 
-    fn set_link_range(&mut self, range: Range, link: S) -> ComposerUpdate<S> {
+    fn set_link_range(
+        &mut self,
+        range: Range,
+        link: S,
+        attributes: Vec<(S, S)>,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
         let leaves: Vec<&DomLocation> = range.leaves().collect();
-        if leaves.len() == 1 {
-            let location = leaves[0];
-            let handle = &location.node_handle;
+        if leaves.len() != 1 {
+            return Err(ComposerError::UnsupportedSelection(
+                ComposerAction::Link,
+            ));
+        }
+        let location = leaves[0];
+        let handle = &location.node_handle;
 
-            // TODO: set link should be able to wrap container nodes, unlike formatting
-            let node = self.state.dom.lookup_node(handle);
-            if let DomNode::Text(t) = node {
-                let text = t.data();
-                let before = text[..location.start_offset].to_owned();
-                let during =
-                    text[location.start_offset..location.end_offset].to_owned();
-                let after = text[location.end_offset..].to_owned();
-                let mut new_nodes = Vec::new();
-                if !before.is_empty() {
-                    new_nodes.push(DomNode::new_text(before));
-                }
-                new_nodes.push(DomNode::new_link(
-                    link,
-                    vec![DomNode::new_text(during)],
-                ));
-                if !after.is_empty() {
-                    new_nodes.push(DomNode::new_text(after));
-                }
-                self.state.dom.replace(handle, new_nodes);
-                self.create_update_replace_all()
-            } else {
-                panic!("Trying to linkify a non-text node")
-            }
-        } else {
-            panic!("Can't add link in complex object models yet")
+        // TODO: set link should be able to wrap container nodes, unlike formatting
+        let text = match self.state.dom.try_lookup_node(handle)? {
+            DomNode::Text(t) => t.data(),
+            _ => return Err(DomError::NotATextNode(handle.clone()).into()),
+        };
+        let before = text[..location.start_offset].to_owned();
+        let during =
+            text[location.start_offset..location.end_offset].to_owned();
+        let after = text[location.end_offset..].to_owned();
+        let mut new_nodes = Vec::new();
+        if !before.is_empty() {
+            new_nodes.push(DomNode::new_text(before));
         }
+        new_nodes.push(DomNode::new_link_with_attributes(
+            link,
+            attributes,
+            vec![DomNode::new_text(during)],
+        ));
+        if !after.is_empty() {
+            new_nodes.push(DomNode::new_text(after));
+        }
+
+        // Store current Dom
+        self.push_state_to_history();
+        self.state.dom.replace(handle, new_nodes);
+        Ok(self.create_update_replace_all())
     }
 }
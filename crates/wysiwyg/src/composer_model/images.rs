@@ -0,0 +1,37 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::{DomNode, ImageNode};
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::{ComposerModel, ComposerUpdate, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Insert an inline image at the cursor, replacing any selected text,
+    /// and place the cursor after it. `attrs` may contain `src`, `alt`,
+    /// `width` and `height`; an image without a `src` is not inserted.
+    pub fn insert_image(&mut self, attrs: Vec<(S, S)>) -> ComposerUpdate<S> {
+        let image = ImageNode::new(attrs);
+        if image.src().is_empty() {
+            return ComposerUpdate::keep();
+        }
+        // Store current Dom
+        self.push_state_to_history();
+
+        self.do_insert_node_at_cursor(DomNode::Image(image));
+        self.create_update_replace_all()
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Indenting paragraphs and quotes. List items are indented by nesting
+//! them, see [ComposerModel::indent], but the editor has no nodes of its
+//! own for paragraphs and quotes, which are kept as the elements they were
+//! loaded as. They are given an indentation level attribute instead.
+
+use crate::dom::nodes::container_node::ContainerNodeKind;
+use crate::dom::nodes::{ContainerNode, DomNode};
+use crate::dom::{DomHandle, DomLocation};
+use crate::{ComposerModel, UnicodeString};
+
+/// The attribute holding how many levels a paragraph or quote is indented
+const INDENT_ATTRIBUTE: &str = "data-indent";
+
+/// The elements outside lists that can be indented
+const INDENTABLE_TAGS: [&str; 2] = ["p", "blockquote"];
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The paragraphs and quotes holding the text at `locations`, each
+    /// once, or None if any of it isn't in one, or is in a list item inside
+    /// one.
+    pub(crate) fn indentable_blocks(
+        &self,
+        locations: &[DomLocation],
+    ) -> Option<Vec<DomHandle>> {
+        let mut blocks: Vec<DomHandle> = Vec::new();
+        for location in locations.iter().filter(|loc| loc.is_leaf) {
+            let block = self.indentable_block(&location.node_handle)?;
+            if !blocks.contains(&block) {
+                blocks.push(block);
+            }
+        }
+        if blocks.is_empty() {
+            None
+        } else {
+            Some(blocks)
+        }
+    }
+
+    /// The closest paragraph or quote containing the node at `handle`,
+    /// unless it is in a list item that is closer
+    fn indentable_block(&self, handle: &DomHandle) -> Option<DomHandle> {
+        let mut handle = handle.clone();
+        while handle.has_parent() {
+            handle = handle.parent_handle();
+            if let DomNode::Container(container) =
+                self.state.dom.lookup_node(&handle)
+            {
+                if container.is_list_item() {
+                    return None;
+                } else if is_indentable(container) {
+                    return Some(handle);
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether all the paragraphs and quotes at `blocks` are indented, so
+    /// can be unindented
+    pub(crate) fn blocks_are_indented(&self, blocks: &[DomHandle]) -> bool {
+        blocks.iter().all(|handle| {
+            matches!(
+                self.state.dom.lookup_node(handle),
+                DomNode::Container(block) if indent_level(block) > 0
+            )
+        })
+    }
+
+    pub(crate) fn indent_blocks(&mut self, blocks: &[DomHandle]) {
+        for handle in blocks {
+            if let DomNode::Container(block) =
+                self.state.dom.lookup_node_mut(handle)
+            {
+                let level = indent_level(block) + 1;
+                set_indent_level(block, level);
+            }
+        }
+    }
+
+    pub(crate) fn unindent_blocks(&mut self, blocks: &[DomHandle]) {
+        for handle in blocks {
+            if let DomNode::Container(block) =
+                self.state.dom.lookup_node_mut(handle)
+            {
+                let level = indent_level(block).saturating_sub(1);
+                set_indent_level(block, level);
+            }
+        }
+    }
+}
+
+fn is_indentable<S>(container: &ContainerNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    *container.kind() == ContainerNodeKind::Unknown
+        && INDENTABLE_TAGS.contains(&container.name().to_string().as_str())
+}
+
+/// How many levels `block` is indented. An attribute that isn't a number
+/// counts as no indentation.
+fn indent_level<S>(block: &ContainerNode<S>) -> usize
+where
+    S: UnicodeString,
+{
+    block
+        .get_attribute(INDENT_ATTRIBUTE)
+        .and_then(|level| level.to_string().parse().ok())
+        .unwrap_or(0)
+}
+
+fn set_indent_level<S>(block: &mut ContainerNode<S>, level: usize)
+where
+    S: UnicodeString,
+{
+    if level == 0 {
+        block.remove_attribute(INDENT_ATTRIBUTE);
+    } else {
+        block.set_attribute(
+            INDENT_ATTRIBUTE.into(),
+            level.to_string().as_str().into(),
+        );
+    }
+}
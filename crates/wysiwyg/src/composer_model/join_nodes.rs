@@ -13,10 +13,10 @@
 // limitations under the License.
 
 use crate::dom::action_list::{DomAction, DomActionList};
-use crate::dom::nodes::{ContainerNodeKind, DomNode};
-use crate::dom::unicode_string::UnicodeStringExt;
+use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode};
+use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
 use crate::dom::{DomHandle, DomLocation, Range};
-use crate::{ComposerModel, UnicodeString};
+use crate::{ComposerModel, InlineFormatType, UnicodeString};
 
 /// Handles joining together nodes after an edit event.
 ///
@@ -48,6 +48,31 @@ where
         self.join_format_nodes_at_level(handle, 0, action_list);
     }
 
+    /// Put the formatting nodes of the document into their simplest form,
+    /// see [Self::unwrap_nested_format_nodes] and
+    /// [Self::join_adjacent_format_nodes]. Returns whether anything
+    /// changed.
+    pub(crate) fn normalize_format_nodes(&mut self) -> bool {
+        let unwrapped = self.unwrap_nested_format_nodes();
+        let joined = self.join_adjacent_format_nodes();
+        unwrapped || joined
+    }
+
+    /// Replace each formatting node inside another of the same format with
+    /// its children, e.g. `<b><b>x</b></b>` or `<em><i>x</i></em>` become
+    /// `<b>x</b>` and `<em>x</em>`. Returns whether any were replaced.
+    pub(crate) fn unwrap_nested_format_nodes(&mut self) -> bool {
+        unwrap_format_children(self.state.dom.document_mut(), &mut Vec::new())
+    }
+
+    /// Join every pair of sibling formatting nodes of the same format in
+    /// the document, e.g. `<b>a</b><b>b</b>` becomes `<b>ab</b>`, however
+    /// the edit that put them next to each other came about. Returns
+    /// whether any were joined.
+    pub(crate) fn join_adjacent_format_nodes(&mut self) -> bool {
+        join_format_children(self.state.dom.document_mut())
+    }
+
     fn join_structure_nodes(
         &mut self,
         start_handle: &DomHandle,
@@ -368,3 +393,246 @@ where
         self.find_text_handle(range.locations.iter())
     }
 }
+
+/// Join the sibling formatting nodes of the same format among `parent`'s
+/// children, and then inside each child. Returns whether any were joined.
+fn join_format_children<S>(parent: &mut ContainerNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    let mut joined = false;
+    let mut index = 1;
+    while index < parent.children().len() {
+        // An empty text node left behind by a deletion doesn't keep the
+        // nodes either side of it apart
+        if index + 1 < parent.children().len()
+            && is_empty_text(&parent.children()[index])
+            && is_same_format(
+                &parent.children()[index - 1],
+                &parent.children()[index + 1],
+            )
+        {
+            parent.remove_child(index);
+        }
+        if is_same_format(
+            &parent.children()[index - 1],
+            &parent.children()[index],
+        ) {
+            let next = parent.remove_child(index);
+            if let (Some(DomNode::Container(prev)), DomNode::Container(next)) =
+                (parent.get_child_mut(index - 1), next)
+            {
+                for child in next.take_children() {
+                    append_joining_text(prev, child);
+                }
+            }
+            joined = true;
+        } else {
+            index += 1;
+        }
+    }
+    for index in 0..parent.children().len() {
+        // Containers with nothing to join are left alone, so that they stay
+        // shared with earlier copies of the document
+        if !matches!(
+            &parent.children()[index],
+            DomNode::Container(child) if has_format_children_to_join(child)
+        ) {
+            continue;
+        }
+        if let Some(DomNode::Container(child)) = parent.get_child_mut(index) {
+            joined |= join_format_children(child);
+        }
+    }
+    joined
+}
+
+/// Whether [join_format_children] would join anything in `parent`
+fn has_format_children_to_join<S>(parent: &ContainerNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    let children = parent.children();
+    let joins_here = (1..children.len()).any(|index| {
+        is_same_format(&children[index - 1], &children[index])
+            || (index + 1 < children.len()
+                && is_empty_text(&children[index])
+                && is_same_format(&children[index - 1], &children[index + 1]))
+    });
+    joins_here
+        || children.iter().any(|child| {
+            matches!(
+                child,
+                DomNode::Container(child) if has_format_children_to_join(child)
+            )
+        })
+}
+
+/// Replace the formatting nodes among `parent`'s descendants whose format
+/// is in `formats`, or in that of a formatting node between them and
+/// `parent`, with their children. Returns whether any were replaced.
+fn unwrap_format_children<S>(
+    parent: &mut ContainerNode<S>,
+    formats: &mut Vec<InlineFormatType>,
+) -> bool
+where
+    S: UnicodeString,
+{
+    let mut unwrapped = false;
+    let mut index = 0;
+    while index < parent.children().len() {
+        let format = match &parent.children()[index] {
+            DomNode::Container(child) => match child.kind() {
+                ContainerNodeKind::Formatting(format) => Some(format.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        match format {
+            Some(format) if formats.contains(&format) => {
+                if let DomNode::Container(child) = parent.remove_child(index) {
+                    for (i, grandchild) in
+                        child.take_children().into_iter().enumerate()
+                    {
+                        parent.insert_child(index + i, grandchild);
+                    }
+                }
+                unwrapped = true;
+                // Look at the moved children in this node's place next
+                continue;
+            }
+            Some(format) => {
+                formats.push(format);
+                unwrapped |=
+                    unwrap_child_format_children(parent, index, formats);
+                formats.pop();
+            }
+            None => {
+                unwrapped |=
+                    unwrap_child_format_children(parent, index, formats);
+            }
+        }
+        index += 1;
+    }
+    if unwrapped {
+        join_text_children(parent);
+    }
+    unwrapped
+}
+
+/// Call [unwrap_format_children] on the child of `parent` at `index`, if it
+/// is a container with anything to unwrap. The others are left alone, so
+/// that they stay shared with earlier copies of the document.
+fn unwrap_child_format_children<S>(
+    parent: &mut ContainerNode<S>,
+    index: usize,
+    formats: &mut Vec<InlineFormatType>,
+) -> bool
+where
+    S: UnicodeString,
+{
+    if !matches!(
+        &parent.children()[index],
+        DomNode::Container(child) if has_format_children_to_unwrap(child, formats)
+    ) {
+        return false;
+    }
+    match parent.get_child_mut(index) {
+        Some(DomNode::Container(child)) => {
+            unwrap_format_children(child, formats)
+        }
+        _ => false,
+    }
+}
+
+/// Whether [unwrap_format_children] would replace anything in `parent`
+fn has_format_children_to_unwrap<S>(
+    parent: &ContainerNode<S>,
+    formats: &mut Vec<InlineFormatType>,
+) -> bool
+where
+    S: UnicodeString,
+{
+    parent.children().iter().any(|child| match child {
+        DomNode::Container(child) => match child.kind() {
+            ContainerNodeKind::Formatting(format) => {
+                if formats.contains(format) {
+                    return true;
+                }
+                formats.push(format.clone());
+                let found = has_format_children_to_unwrap(child, formats);
+                formats.pop();
+                found
+            }
+            _ => has_format_children_to_unwrap(child, formats),
+        },
+        _ => false,
+    })
+}
+
+/// Join the text nodes among `parent`'s children that are next to each
+/// other
+fn join_text_children<S>(parent: &mut ContainerNode<S>)
+where
+    S: UnicodeString,
+{
+    let mut index = 1;
+    while index < parent.children().len() {
+        if matches!(parent.children()[index - 1], DomNode::Text(_))
+            && matches!(parent.children()[index], DomNode::Text(_))
+        {
+            let next = parent.remove_child(index);
+            if let (Some(DomNode::Text(prev)), DomNode::Text(next)) =
+                (parent.get_child_mut(index - 1), next)
+            {
+                let mut data = prev.data().to_owned();
+                data.push(next.data().to_owned());
+                prev.set_data(data);
+            }
+        } else {
+            index += 1;
+        }
+    }
+}
+
+fn is_empty_text<S>(node: &DomNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    matches!(node, DomNode::Text(text) if text.data().is_empty())
+}
+
+fn is_same_format<S>(prev: &DomNode<S>, next: &DomNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    match (prev, next) {
+        (DomNode::Container(prev), DomNode::Container(next)) => {
+            match (prev.kind(), next.kind()) {
+                (
+                    ContainerNodeKind::Formatting(prev_format),
+                    ContainerNodeKind::Formatting(next_format),
+                ) => prev_format == next_format,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Append `child` to `parent`, adding its text to the last child instead
+/// if both are text nodes
+fn append_joining_text<S>(parent: &mut ContainerNode<S>, child: DomNode<S>)
+where
+    S: UnicodeString,
+{
+    if let (Some(DomNode::Text(last)), DomNode::Text(text)) =
+        (parent.last_child_mut(), &child)
+    {
+        let mut data = last.data().to_owned();
+        data.push(text.data().to_owned());
+        last.set_data(data);
+    } else {
+        parent.append_child(child);
+    }
+}
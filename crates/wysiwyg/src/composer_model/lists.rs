@@ -14,24 +14,90 @@
 
 use std::collections::HashMap;
 
+use crate::composer_model::menu_state::MenuStateComputeType;
 use crate::dom::nodes::{ContainerNode, DomNode};
 use crate::dom::to_raw_text::ToRawText;
-use crate::dom::unicode_string::UnicodeStrExt;
-use crate::dom::{DomHandle, DomLocation, Range};
-use crate::{ComposerModel, ComposerUpdate, ListType, Location, UnicodeString};
+use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt};
+use crate::dom::{DomError, DomHandle, DomLocation, Range};
+use crate::{
+    ComposerAction, ComposerError, ComposerModel, ComposerUpdate,
+    DisabledReason, ListNumberingType, ListType, Location, UnicodeString,
+};
 
 impl<S> ComposerModel<S>
 where
     S: UnicodeString,
 {
-    pub fn ordered_list(&mut self) -> ComposerUpdate<S> {
+    /// Toggle an ordered list at the selection. Returns an error, leaving
+    /// the model unchanged, if the selection spans content that can't be
+    /// turned into a list yet.
+    pub fn ordered_list(&mut self) -> Result<ComposerUpdate<S>, ComposerError> {
         self.toggle_list(ListType::Ordered)
     }
 
-    pub fn unordered_list(&mut self) -> ComposerUpdate<S> {
+    /// Toggle an unordered list at the selection. See [Self::ordered_list].
+    pub fn unordered_list(
+        &mut self,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
         self.toggle_list(ListType::Unordered)
     }
 
+    /// Make the ordered list containing the cursor count its items from
+    /// `start`, e.g. to continue the numbering of a list that was split.
+    pub fn set_ordered_list_start(
+        &mut self,
+        start: usize,
+    ) -> ComposerUpdate<S> {
+        // 1 is where lists count from anyway, so we don't store it
+        let start = if start == 1 { None } else { Some(start) };
+        self.update_ordered_list_at_cursor(|list| list.set_list_start(start))
+    }
+
+    /// Choose how the ordered list containing the cursor numbers its items.
+    pub fn set_ordered_list_numbering_type(
+        &mut self,
+        numbering_type: ListNumberingType,
+    ) -> ComposerUpdate<S> {
+        // Decimal is the default numbering, so we don't store it
+        let numbering_type = match numbering_type {
+            ListNumberingType::Decimal => None,
+            other => Some(other),
+        };
+        self.update_ordered_list_at_cursor(|list| {
+            list.set_list_numbering_type(numbering_type)
+        })
+    }
+
+    fn update_ordered_list_at_cursor(
+        &mut self,
+        update: impl FnOnce(&mut ContainerNode<S>),
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_range(s, e);
+        let list_handle = match range.leaves().next().and_then(|l| {
+            self.state.dom.find_closest_list_ancestor(&l.node_handle)
+        }) {
+            Some(handle) => handle,
+            None => return ComposerUpdate::keep(),
+        };
+        if let DomNode::Container(list) =
+            self.state.dom.lookup_node(&list_handle)
+        {
+            if !list.is_list_of_type(ListType::Ordered) {
+                return ComposerUpdate::keep();
+            }
+        }
+        // Store current Dom
+        self.push_state_to_history();
+
+        if let DomNode::Container(list) =
+            self.state.dom.lookup_node_mut(&list_handle)
+        {
+            update(list);
+        }
+        self.create_update_replace_all()
+    }
+
     pub(crate) fn do_backspace_in_list(
         &mut self,
         parent_handle: &DomHandle,
@@ -50,6 +116,11 @@ where
                     false,
                 );
                 self.create_update_replace_all()
+            } else if self.is_at_start_of_list_item(parent_handle, location) {
+                // Store current Dom
+                self.push_state_to_history();
+                self.merge_list_item_with_previous_block(parent_handle);
+                self.create_update_replace_all()
             } else {
                 self.do_backspace()
             }
@@ -58,6 +129,181 @@ where
         }
     }
 
+    /// Backspace at the start of the line following a list: move the line
+    /// into the last item of that list. Returns None if the text node in
+    /// `leaf` doesn't start such a line.
+    pub(crate) fn do_backspace_after_list(
+        &mut self,
+        leaf: &DomLocation,
+    ) -> Option<ComposerUpdate<S>> {
+        let handle = &leaf.node_handle;
+        if handle.is_root() || handle.index_in_parent() == 0 {
+            return None;
+        }
+        let text = match self.state.dom.lookup_node(handle) {
+            DomNode::Text(text) => text,
+            _ => return None,
+        };
+        if leaf.start_offset != placeholder_len_at_start::<S>(text.data()) {
+            return None;
+        }
+        let list_handle = handle.prev_sibling();
+        match self.state.dom.lookup_node(&list_handle) {
+            DomNode::Container(list) if list.is_list() => {}
+            _ => return None,
+        }
+
+        // Store current Dom
+        self.push_state_to_history();
+
+        // Take the rest of the line, up to the next line break or block
+        let parent = self.state.dom.parent_mut(handle);
+        let index = handle.index_in_parent();
+        let mut line = Vec::new();
+        while let Some(node) = parent.children().get(index) {
+            if matches!(node, DomNode::LineBreak(_)) || node.is_block_node() {
+                break;
+            }
+            line.push(parent.remove_child(index));
+        }
+        remove_leading_placeholder(&mut line);
+
+        let item_handle = self.deepest_last_list_item(&list_handle);
+        self.append_to_list_item(&item_handle, line);
+        let position = leaf.position;
+        self.state.start = Location::from(position);
+        self.state.end = self.state.start;
+        Some(self.create_update_replace_all())
+    }
+
+    /// Whether the cursor at `location` is before any content of the list
+    /// item, ignoring the placeholder it may start with.
+    fn is_at_start_of_list_item(
+        &self,
+        list_item_handle: &DomHandle,
+        location: usize,
+    ) -> bool {
+        let range = self.state.dom.find_range(location, location);
+        let item = match range
+            .locations
+            .iter()
+            .find(|l| l.node_handle == *list_item_handle)
+        {
+            Some(item) => item,
+            None => return false,
+        };
+        let placeholder_len = match self.state.dom.lookup_node(list_item_handle)
+        {
+            DomNode::Container(list_item) => match list_item.children().first()
+            {
+                Some(DomNode::Text(text)) => {
+                    placeholder_len_at_start::<S>(text.data())
+                }
+                _ => 0,
+            },
+            _ => 0,
+        };
+        item.start_offset == placeholder_len
+    }
+
+    /// Merge the contents of a list item into the block before it: the
+    /// previous item of its list or, for the first item, whatever comes
+    /// before the list. A nested first item is unindented instead.
+    fn merge_list_item_with_previous_block(
+        &mut self,
+        list_item_handle: &DomHandle,
+    ) {
+        let (s, _) = self.safe_selection();
+        let first_child = list_item_handle.child_handle(0);
+        let index = list_item_handle.index_in_parent();
+        if index == 0
+            && self.state.dom.lookup_node(&first_child).is_text_node()
+            && self.can_unindent_handle(&first_child)
+        {
+            self.unindent_handles(&[first_child]);
+            return;
+        }
+
+        let list_handle = list_item_handle.parent_handle();
+        let item_start = s - self.list_item_placeholder_len(list_item_handle);
+        let list_item = self.state.dom.parent_mut(list_item_handle);
+        let mut children = match list_item.remove_child(index) {
+            DomNode::Container(list_item) => list_item.take_children(),
+            _ => panic!("List item should be a container"),
+        };
+        remove_leading_placeholder(&mut children);
+
+        if index > 0 {
+            let item_handle = self
+                .deepest_last_list_item(&list_handle.child_handle(index - 1));
+            self.append_to_list_item(&item_handle, children);
+        } else {
+            // Lift the contents out of the list, in front of it
+            let parent = self.state.dom.parent_mut(&list_handle);
+            let at = list_handle.index_in_parent();
+            if let Some(DomNode::Container(list)) = parent.get_child_mut(at) {
+                if list.children().is_empty() {
+                    parent.remove_child(at);
+                }
+            }
+            for (i, child) in children.into_iter().enumerate() {
+                parent.insert_child(at + i, child);
+            }
+            self.join_text_nodes_in_parent(&list_handle.parent_handle());
+        }
+        self.state.start = Location::from(item_start);
+        self.state.end = self.state.start;
+    }
+
+    fn list_item_placeholder_len(&self, list_item_handle: &DomHandle) -> usize {
+        match self
+            .state
+            .dom
+            .lookup_node(&list_item_handle.child_handle(0))
+        {
+            DomNode::Text(text) => placeholder_len_at_start::<S>(text.data()),
+            _ => 0,
+        }
+    }
+
+    /// Follow nested lists at the end of a list item (or at the end of the
+    /// list at `handle`) down to the innermost last item.
+    fn deepest_last_list_item(&self, handle: &DomHandle) -> DomHandle {
+        let mut handle = handle.clone();
+        loop {
+            let container = match self.state.dom.lookup_node(&handle) {
+                DomNode::Container(container) => container,
+                _ => return handle,
+            };
+            match container.children().last() {
+                Some(DomNode::Container(last)) if last.is_list() => {
+                    handle = last.handle();
+                }
+                Some(DomNode::Container(last))
+                    if container.is_list() && last.is_list_item() =>
+                {
+                    handle = last.handle();
+                }
+                _ => return handle,
+            }
+        }
+    }
+
+    fn append_to_list_item(
+        &mut self,
+        list_item_handle: &DomHandle,
+        nodes: Vec<DomNode<S>>,
+    ) {
+        if let DomNode::Container(list_item) =
+            self.state.dom.lookup_node_mut(list_item_handle)
+        {
+            for node in nodes {
+                list_item.append_child(node);
+            }
+        }
+        self.join_text_nodes_in_parent(list_item_handle);
+    }
+
     /// Insert a newline in some text.
     /// handle is a DomHandle to a text node
     /// offset is the number of code units into the text to insert a newline
@@ -89,7 +335,14 @@ where
         let list_item_node = self.state.dom.lookup_node(list_item_handle);
         let list_handle = list_item_node.handle().parent_handle();
         if let DomNode::Container(list_item_node) = list_item_node {
-            if list_item_node.is_empty_list_item() {
+            if list_item_node.is_empty_list_item()
+                && text_node_handle.parent_handle() == *list_item_handle
+                && self.can_unindent_handle(text_node_handle)
+            {
+                // Pressing enter in an empty item of a nested list means
+                // you want to go back to the outer list.
+                self.unindent_handles(std::slice::from_ref(text_node_handle));
+            } else if list_item_node.is_empty_list_item() {
                 // Pressing enter in an empty list item means you want to
                 // end the list.
                 self.remove_list_item(
@@ -115,7 +368,10 @@ where
         }
     }
 
-    fn toggle_list(&mut self, list_type: ListType) -> ComposerUpdate<S> {
+    fn toggle_list(
+        &mut self,
+        list_type: ListType,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
         let (s, e) = self.safe_selection();
         let range = self.state.dom.find_range(s, e);
 
@@ -130,79 +386,80 @@ where
         &mut self,
         list_type: ListType,
         range: Range,
-    ) -> ComposerUpdate<S> {
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
         let leaves: Vec<&DomLocation> = range.leaves().collect();
-        if leaves.len() == 1 {
-            let handle = &leaves[0].node_handle;
-
-            let parent_list_item_handle =
-                self.state.dom.find_parent_list_item_or_self(handle);
-            if let Some(list_item_handle) = parent_list_item_handle {
-                let list = self.state.dom.parent(&list_item_handle);
-                if list.is_list_of_type(list_type.clone()) {
-                    self.move_list_item_content_to_list_parent(
-                        &list_item_handle,
-                    )
-                } else {
-                    let list_node_handle = list.handle();
-                    self.update_list_type(&list_node_handle, list_type)
-                }
+        if leaves.len() != 1 {
+            return Err(ComposerError::UnsupportedSelection(list_action(
+                &list_type,
+            )));
+        }
+        let handle = &leaves[0].node_handle;
+
+        let parent_list_item_handle =
+            self.state.dom.find_parent_list_item_or_self(handle);
+        if let Some(list_item_handle) = parent_list_item_handle {
+            let list = self.state.dom.try_parent(&list_item_handle)?;
+            if list.is_list_of_type(list_type.clone()) {
+                self.move_list_item_content_to_list_parent(&list_item_handle)
             } else {
-                self.create_list(list_type)
+                let list_node_handle = list.handle();
+                self.update_list_type(&list_node_handle, list_type)
             }
         } else {
-            panic!("Can't toggle list in complex object models yet")
+            self.create_list(list_type)
         }
     }
 
     fn move_list_item_content_to_list_parent(
         &mut self,
         list_item_handle: &DomHandle,
-    ) -> ComposerUpdate<S> {
-        let list_item_node = self.state.dom.lookup_node(list_item_handle);
-        if let DomNode::Container(list_item) = list_item_node {
-            let list_item_children = list_item.children().clone();
-            let list_handle = list_item_handle.parent_handle();
-            let list_index_in_parent = list_handle.index_in_parent();
-            let list_parent = self.state.dom.parent_mut(&list_handle);
-            for child in list_item_children.iter().rev() {
-                list_parent
-                    .insert_child(list_index_in_parent + 1, child.clone());
-            }
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        let list_item_children = self
+            .state
+            .dom
+            .try_lookup_container(list_item_handle)?
+            .children()
+            .clone();
+        let list_handle = list_item_handle.parent_handle();
+        self.state.dom.try_lookup_container(&list_handle)?;
 
-            let list_item_index_in_parent = list_item_handle.index_in_parent();
-            let list_node = self.state.dom.lookup_node_mut(&list_handle);
-            if let DomNode::Container(list) = list_node {
-                list.remove_child(list_item_index_in_parent);
-            } else {
-                panic!("List node is not a container")
-            }
-        } else {
-            panic!("List item is not a container")
+        let list_index_in_parent = list_handle.index_in_parent();
+        let list_parent = self.state.dom.try_parent_mut(&list_handle)?;
+        for child in list_item_children.iter().rev() {
+            list_parent.insert_child(list_index_in_parent + 1, child.clone());
         }
 
-        self.create_update_replace_all()
+        let list_item_index_in_parent = list_item_handle.index_in_parent();
+        self.state
+            .dom
+            .try_lookup_container_mut(&list_handle)?
+            .remove_child(list_item_index_in_parent);
+
+        Ok(self.create_update_replace_all())
     }
 
     fn update_list_type(
         &mut self,
         list_handle: &DomHandle,
         list_type: ListType,
-    ) -> ComposerUpdate<S> {
-        let list_node = self.state.dom.lookup_node_mut(list_handle);
-        if let DomNode::Container(list) = list_node {
-            list.set_list_type(list_type);
-        }
-        self.create_update_replace_all()
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        self.state
+            .dom
+            .try_lookup_container_mut(list_handle)?
+            .set_list_type(list_type);
+        Ok(self.create_update_replace_all())
     }
 
-    fn create_list(&mut self, list_type: ListType) -> ComposerUpdate<S> {
-        // Store current Dom
-        self.push_state_to_history();
+    fn create_list(
+        &mut self,
+        list_type: ListType,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
         let (s, e) = self.safe_selection();
         let range = self.state.dom.find_range(s, e);
 
         if range.is_empty() {
+            // Store current Dom
+            self.push_state_to_history();
             self.state.dom.append_child(DomNode::new_list(
                 list_type,
                 vec![DomNode::Container(ContainerNode::new_list_item(
@@ -210,7 +467,7 @@ where
                     vec![DomNode::new_text(S::default())],
                 ))],
             ));
-            self.create_update_replace_all()
+            Ok(self.create_update_replace_all())
         } else {
             self.create_list_range(list_type, range)
         }
@@ -220,41 +477,42 @@ where
         &mut self,
         list_type: ListType,
         range: Range,
-    ) -> ComposerUpdate<S> {
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
         let leaves: Vec<&DomLocation> = range.leaves().collect();
-        if leaves.len() == 1 {
-            let handle = &leaves[0].node_handle;
-            let node = self.state.dom.lookup_node(handle);
-            if let DomNode::Text(t) = node {
-                let text = t.data();
-                let index_in_parent = handle.index_in_parent();
-                let list_item =
-                    DomNode::Container(ContainerNode::new_list_item(
-                        "li".into(),
-                        vec![DomNode::new_text(text.to_owned())],
-                    ));
-                if index_in_parent > 0 {
-                    let previous_handle = handle.prev_sibling();
-                    let previous_node =
-                        self.state.dom.lookup_node_mut(&previous_handle);
-                    if let DomNode::Container(previous) = previous_node {
-                        if previous.is_list_of_type(list_type.clone()) {
-                            previous.append_child(list_item);
-                            let parent = self.state.dom.parent_mut(handle);
-                            parent.remove_child(index_in_parent);
-                            return self.create_update_replace_all();
-                        }
-                    }
-                }
+        if leaves.len() != 1 {
+            return Err(ComposerError::UnsupportedSelection(list_action(
+                &list_type,
+            )));
+        }
+        let handle = &leaves[0].node_handle;
+        let text = match self.state.dom.try_lookup_node(handle)? {
+            DomNode::Text(t) => t.data().to_owned(),
+            _ => return Err(DomError::NotATextNode(handle.clone()).into()),
+        };
 
-                self.replace_node_with_new_list(handle, list_type, list_item);
-                self.create_update_replace_all()
-            } else {
-                panic!("Can't create a list from a non-text node")
+        // Store current Dom
+        self.push_state_to_history();
+        let index_in_parent = handle.index_in_parent();
+        let list_item = DomNode::Container(ContainerNode::new_list_item(
+            "li".into(),
+            vec![DomNode::new_text(text)],
+        ));
+        if index_in_parent > 0 {
+            let previous_handle = handle.prev_sibling();
+            let previous_node =
+                self.state.dom.try_lookup_node_mut(&previous_handle)?;
+            if let DomNode::Container(previous) = previous_node {
+                if previous.is_list_of_type(list_type.clone()) {
+                    previous.append_child(list_item);
+                    let parent = self.state.dom.try_parent_mut(handle)?;
+                    parent.remove_child(index_in_parent);
+                    return Ok(self.create_update_replace_all());
+                }
             }
-        } else {
-            panic!("Can't create ordered list in complex object models yet")
         }
+
+        self.replace_node_with_new_list(handle, list_type, list_item);
+        Ok(self.create_update_replace_all())
     }
 
     fn replace_node_with_new_list(
@@ -294,8 +552,9 @@ where
                     })],
                 ));
                 if add_zwsp {
-                    self.state.start = Location::from(location + 1);
-                    self.state.end = Location::from(location + 1);
+                    let zwsp_len = S::from("\u{200b}").len();
+                    self.state.start = Location::from(location + zwsp_len);
+                    self.state.end = Location::from(location + zwsp_len);
                 }
             }
         }
@@ -324,13 +583,33 @@ where
                 self.state.start = new_location;
                 self.state.end = new_location;
             } else {
+                // Any items after the removed one become a separate list
+                let mut items_after = Vec::new();
+                if insert_trailing_text_node {
+                    while list.children().len() > li_index + 1 {
+                        items_after.push(list.remove_child(li_index + 1));
+                    }
+                }
+                let list_after = if items_after.is_empty() {
+                    None
+                } else {
+                    Some(Self::new_list_continuing(list, li_index, items_after))
+                };
                 list.remove_child(li_index);
                 if insert_trailing_text_node {
                     let parent = self.state.dom.parent_mut(list_handle);
-                    // TODO: should probably append a paragraph instead
-                    parent.append_child(DomNode::new_text("\u{200b}".into()));
+                    let index = list_handle.index_in_parent() + 1;
+                    // TODO: should probably insert a paragraph instead
+                    parent.insert_child(
+                        index,
+                        DomNode::new_text("\u{200b}".into()),
+                    );
+                    if let Some(list_after) = list_after {
+                        parent.insert_child(index + 1, list_after);
+                    }
                     let new_location = Location::from(
-                        current_cursor_global_location - li_len + 1,
+                        current_cursor_global_location - li_len
+                            + S::from("\u{200b}").len(),
                     );
                     self.state.start = new_location;
                     self.state.end = new_location;
@@ -344,34 +623,122 @@ where
         }
     }
 
-    pub fn can_indent(&self, locations: &Vec<DomLocation>) -> bool {
-        for loc in locations {
-            if loc.is_leaf && !self.can_indent_handle(&loc.node_handle) {
-                return false;
-            }
+    /// Create a list of the same kind as `list` holding `items`, numbered
+    /// so that it carries on from the first `items_before` items of `list`.
+    fn new_list_continuing(
+        list: &ContainerNode<S>,
+        items_before: usize,
+        items: Vec<DomNode<S>>,
+    ) -> DomNode<S> {
+        let list_type = ListType::from(list.name().to_owned());
+        let mut new_list = ContainerNode::new_list(list_type.clone(), items);
+        if list_type == ListType::Ordered {
+            let start = list.list_start().unwrap_or(1) + items_before;
+            new_list.set_list_start(Some(start));
+            new_list.set_list_numbering_type(list.list_numbering_type());
         }
-        true
+        DomNode::Container(new_list)
     }
 
+    pub fn can_indent(&self, locations: &[DomLocation]) -> bool {
+        self.indent_disabled_reason(locations).is_none()
+    }
+
+    #[cfg(test)]
     pub(crate) fn can_indent_handle(&self, handle: &DomHandle) -> bool {
+        self.indent_disabled_reason_for_handle(handle).is_none()
+    }
+
+    /// Why the list items, paragraphs or quotes at `locations` can't be
+    /// indented, if they can't
+    pub(crate) fn indent_disabled_reason(
+        &self,
+        locations: &[DomLocation],
+    ) -> Option<DisabledReason> {
+        locations
+            .iter()
+            .filter(|loc| loc.is_leaf)
+            .find_map(|loc| {
+                self.indent_disabled_reason_for_handle(&loc.node_handle)
+            })
+            .filter(|_| self.indentable_blocks(locations).is_none())
+    }
+
+    fn indent_disabled_reason_for_handle(
+        &self,
+        handle: &DomHandle,
+    ) -> Option<DisabledReason> {
         let parent = self.state.dom.parent(handle);
-        if parent.is_list_item() {
-            handle.parent_handle().index_in_parent() > 0
+        if !parent.is_list_item() {
+            Some(DisabledReason::NotInList)
+        } else if handle.parent_handle().index_in_parent() == 0 {
+            Some(DisabledReason::FirstListItem)
+        } else if self
+            .max_list_depth
+            .map_or(false, |max| self.list_depth(handle) >= max)
+        {
+            Some(DisabledReason::MaxListDepth)
         } else {
-            false
+            None
         }
     }
 
-    pub fn can_unindent(&self, locations: &Vec<DomLocation>) -> bool {
-        for loc in locations {
-            if loc.is_leaf && !self.can_unindent_handle(&loc.node_handle) {
-                return false;
-            }
+    /// The number of lists the node at `handle` is nested in.
+    fn list_depth(&self, handle: &DomHandle) -> usize {
+        let mut depth = 0;
+        let mut handle = handle.clone();
+        while let Some(list) =
+            self.state.dom.find_closest_list_ancestor(&handle)
+        {
+            depth += 1;
+            handle = list;
         }
-        true
+        depth
+    }
+
+    pub fn max_list_depth(&self) -> Option<usize> {
+        self.max_list_depth
+    }
+
+    /// Limit how many lists deep indent() may nest list items, or remove
+    /// the limit with None. Lists that are already deeper are kept.
+    pub fn set_max_list_depth(
+        &mut self,
+        max_depth: Option<usize>,
+    ) -> ComposerUpdate<S> {
+        self.max_list_depth = max_depth;
+        ComposerUpdate::update_menu_state(
+            self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+        )
+    }
+
+    pub fn can_unindent(&self, locations: &[DomLocation]) -> bool {
+        self.unindent_disabled_reason(locations).is_none()
+    }
+
+    /// Why the list items, paragraphs or quotes at `locations` can't be
+    /// unindented, if they can't
+    pub(crate) fn unindent_disabled_reason(
+        &self,
+        locations: &[DomLocation],
+    ) -> Option<DisabledReason> {
+        let blocks_are_indented = self
+            .indentable_blocks(locations)
+            .map_or(false, |blocks| self.blocks_are_indented(&blocks));
+        (!blocks_are_indented
+            && locations
+                .iter()
+                .filter(|loc| loc.is_leaf)
+                .any(|loc| !self.can_unindent_handle(&loc.node_handle)))
+        .then(|| DisabledReason::NotInNestedList)
     }
 
     pub(crate) fn can_unindent_handle(&self, handle: &DomHandle) -> bool {
+        // Only text directly in a list item can be moved with it, not text
+        // in a table or formatting inside one
+        if !self.state.dom.parent(handle).is_list_item() {
+            return false;
+        }
         // Check that there are at least 2 ancestor lists
         if let Some(closest_list_handle) =
             self.state.dom.find_closest_list_ancestor(handle)
@@ -385,26 +752,41 @@ where
         }
     }
 
+    /// Indent the selected list items by nesting them in the previous one,
+    /// or the selected paragraphs and quotes by one more level, see
+    /// [crate::composer_model::indentation]. Other text is left alone.
     pub fn indent(&mut self) -> ComposerUpdate<S> {
         let (s, e) = self.safe_selection();
         let range = self.state.dom.find_range(s, e);
-        if !range.locations.is_empty() && self.can_indent(&range.locations) {
-            self.indent_locations(&range.locations);
-            self.create_update_replace_all()
+        if range.locations.is_empty() || !self.can_indent(&range.locations) {
+            return ComposerUpdate::keep();
+        }
+        if let Some(blocks) = self.indentable_blocks(&range.locations) {
+            self.push_state_to_history();
+            self.indent_blocks(&blocks);
         } else {
-            ComposerUpdate::keep()
+            self.indent_locations(&range.locations);
         }
+        self.create_update_replace_all()
     }
 
+    /// Move the selected nested list items out one level, or the selected
+    /// paragraphs and quotes back one level. As with [indent], other text
+    /// is left alone.
     pub fn unindent(&mut self) -> ComposerUpdate<S> {
         let (s, e) = self.safe_selection();
         let range = self.state.dom.find_range(s, e);
-        if self.can_unindent(&range.locations) {
-            self.unindent_locations(&range.locations);
-            self.create_update_replace_all()
-        } else {
-            ComposerUpdate::keep()
+        if !self.can_unindent(&range.locations) {
+            return ComposerUpdate::keep();
         }
+        match self.indentable_blocks(&range.locations) {
+            Some(blocks) if self.blocks_are_indented(&blocks) => {
+                self.push_state_to_history();
+                self.unindent_blocks(&blocks)
+            }
+            _ => self.unindent_locations(&range.locations),
+        }
+        self.create_update_replace_all()
     }
 
     fn indent_locations(&mut self, locations: &[DomLocation]) {
@@ -634,6 +1016,37 @@ where
     }
 }
 
+/// The length of the placeholder character `data` starts with, or 0 if
+/// it doesn't start with one.
+fn placeholder_len_at_start<S: UnicodeString>(data: &S::Str) -> usize {
+    match data.chars().next() {
+        Some(c @ '\u{200b}') => data.char_len(&c),
+        _ => 0,
+    }
+}
+
+fn remove_leading_placeholder<S: UnicodeString>(nodes: &mut Vec<DomNode<S>>) {
+    if let Some(DomNode::Text(text)) = nodes.first_mut() {
+        let len = placeholder_len_at_start::<S>(text.data());
+        if len > 0 {
+            if text.data().len() == len {
+                nodes.remove(0);
+            } else {
+                let rest = text.data()[len..].to_owned();
+                text.set_data(rest);
+            }
+        }
+    }
+}
+
+/// The action that toggles lists of the given type
+fn list_action(list_type: &ListType) -> ComposerAction {
+    match list_type {
+        ListType::Ordered => ComposerAction::OrderedList,
+        ListType::Unordered => ComposerAction::UnorderedList,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tests::testutils_composer_model::{cm, tx};
@@ -19,12 +19,12 @@ use crate::dom::{DomLocation, Range};
 use crate::menu_state::MenuStateUpdate;
 use crate::ComposerAction::{Indent, UnIndent};
 use crate::{
-    ComposerAction, ComposerModel, DomHandle, DomNode, InlineFormatType,
-    ListType, MenuState, UnicodeString,
+    BlockType, ComposerAction, ComposerModel, DomHandle, DomNode,
+    InlineFormatType, ListType, MenuState, UnicodeString,
 };
 use std::collections::{HashMap, HashSet};
 
-use super::action_state::ActionState;
+use super::action_state::{ActionState, DisabledReason};
 
 pub(crate) enum MenuStateComputeType {
     AlwaysUpdate,
@@ -43,14 +43,101 @@ where
         let range = self.state.dom.find_range(s, e);
 
         let action_states = self.compute_action_states(&range);
+        let block_type = self.compute_block_type(&range);
+        let link_url =
+            self.get_link_at_selection().map(|(url, _)| url.to_string());
+        let spans_multiple_links = self.count_links_in_selection(&range) > 1;
 
-        if action_states == self.action_states
-            && matches!(compute_type, MenuStateComputeType::KeepIfUnchanged)
-        {
-            MenuState::Keep
+        let only_actions_changed = block_type == self.block_type
+            && link_url == self.link_url
+            && spans_multiple_links == self.spans_multiple_links
+            && matches!(compute_type, MenuStateComputeType::KeepIfUnchanged);
+        if only_actions_changed {
+            let changed: HashMap<ComposerAction, ActionState> = action_states
+                .into_iter()
+                .filter(|(action, state)| {
+                    self.action_states.get(action) != Some(state)
+                })
+                .collect();
+            if changed.is_empty() {
+                MenuState::Keep
+            } else {
+                self.action_states.extend(changed.clone());
+                MenuState::PartialUpdate(changed)
+            }
         } else {
             self.action_states = action_states.clone();
-            MenuState::Update(MenuStateUpdate { action_states })
+            self.block_type = block_type;
+            self.link_url = link_url.clone();
+            self.spans_multiple_links = spans_multiple_links;
+            MenuState::Update(MenuStateUpdate {
+                action_states,
+                block_type,
+                link_url,
+                spans_multiple_links,
+            })
+        }
+    }
+
+    /// Whether the menu state last computed still holds after typing
+    /// strictly inside a text node, which only changes the undo and redo
+    /// history. Saves recomputing it on most key presses.
+    pub(crate) fn menu_state_survives_typing(&self) -> bool {
+        let is_disabled = |action| {
+            matches!(
+                self.action_states.get(&action),
+                Some(ActionState::Disabled(_))
+            )
+        };
+        is_disabled(ComposerAction::Undo) != self.can_undo()
+            && is_disabled(ComposerAction::Redo) != self.can_redo()
+    }
+
+    /// How many links have some of their text selected
+    fn count_links_in_selection(&self, range: &Range) -> usize {
+        let (s, e) = self.safe_selection();
+        range
+            .locations
+            .iter()
+            .filter(|l| l.position < e && s < l.position + l.length)
+            .filter(|l| {
+                matches!(
+                    self.state.dom.lookup_node(&l.node_handle),
+                    DomNode::Container(container)
+                        if matches!(container.kind(), ContainerNodeKind::Link(_))
+                )
+            })
+            .count()
+    }
+
+    /// The kind of the closest block around the start of `range`
+    fn compute_block_type(&self, range: &Range) -> BlockType {
+        let deepest = range.leaves().next().or_else(|| {
+            range
+                .locations
+                .iter()
+                .max_by_key(|l| l.node_handle.raw().len())
+        });
+        let mut handle = match deepest {
+            Some(location) => location.node_handle.clone(),
+            None => return BlockType::Paragraph,
+        };
+        loop {
+            if let DomNode::Container(container) =
+                self.state.dom.lookup_node(&handle)
+            {
+                if container.is_list_of_type(ListType::Ordered) {
+                    return BlockType::OrderedList;
+                } else if container.is_list_of_type(ListType::Unordered) {
+                    return BlockType::UnorderedList;
+                } else if container.is_table_cell() {
+                    return BlockType::TableCell;
+                }
+            }
+            if !handle.has_parent() {
+                return BlockType::Paragraph;
+            }
+            handle = handle.parent_handle();
         }
     }
 
@@ -64,8 +151,8 @@ where
         let disabled = self.compute_disabled_actions();
 
         for action in ComposerAction::iter() {
-            let state = if disabled.contains(&action) {
-                ActionState::Disabled
+            let state = if let Some(reason) = disabled.get(&action) {
+                ActionState::Disabled(*reason)
             } else if reversed.contains(&action) {
                 ActionState::Reversed
             } else {
@@ -163,17 +250,22 @@ where
                     ListType::Unordered => Some(ComposerAction::UnorderedList),
                 }
             }
+            ContainerNodeKind::Table => Some(ComposerAction::Table),
             _ => None,
         }
     }
 
-    fn compute_disabled_actions(&self) -> HashSet<ComposerAction> {
-        let mut disabled_actions = HashSet::new();
-        if self.previous_states.is_empty() {
-            disabled_actions.insert(ComposerAction::Undo);
+    fn compute_disabled_actions(
+        &self,
+    ) -> HashMap<ComposerAction, DisabledReason> {
+        let mut disabled_actions = HashMap::new();
+        if !self.can_undo() {
+            disabled_actions
+                .insert(ComposerAction::Undo, DisabledReason::NothingToUndo);
         }
-        if self.next_states.is_empty() {
-            disabled_actions.insert(ComposerAction::Redo);
+        if !self.can_redo() {
+            disabled_actions
+                .insert(ComposerAction::Redo, DisabledReason::NothingToRedo);
         }
 
         let (s, e) = self.safe_selection();
@@ -186,14 +278,14 @@ where
 
     fn compute_disabled_actions_for_locations(
         &self,
-        locations: &Vec<DomLocation>,
-    ) -> HashSet<ComposerAction> {
-        let mut disabled_actions = HashSet::new();
-        if !self.can_indent(locations) {
-            disabled_actions.insert(Indent);
+        locations: &[DomLocation],
+    ) -> HashMap<ComposerAction, DisabledReason> {
+        let mut disabled_actions = HashMap::new();
+        if let Some(reason) = self.indent_disabled_reason(locations) {
+            disabled_actions.insert(Indent, reason);
         }
-        if !self.can_unindent(locations) {
-            disabled_actions.insert(UnIndent);
+        if let Some(reason) = self.unindent_disabled_reason(locations) {
+            disabled_actions.insert(UnIndent, reason);
         }
         disabled_actions
     }
@@ -0,0 +1,76 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::{ComposerModel, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Whether replace_text() converts its input to Unicode Normalization
+    /// Form C, so that e.g. an e followed by a combining acute accent is
+    /// stored as a single é and compares equal to the same text typed on
+    /// other platforms. Off by default.
+    pub fn set_nfc_normalization(&mut self, enabled: bool) {
+        self.nfc_normalization = enabled;
+    }
+
+    pub fn nfc_normalization(&self) -> bool {
+        self.nfc_normalization
+    }
+
+    /// Internal: the NFC form of new_text, to be inserted at start. If
+    /// new_text begins with something that composes with the character
+    /// before start, such as a combining mark typed on its own, that
+    /// character is included and the returned start moves back over it.
+    pub(crate) fn normalize_nfc(
+        &self,
+        new_text: S,
+        start: usize,
+    ) -> (S, usize) {
+        let text = new_text.to_string();
+        if is_nfc_quick(text.chars()) == IsNormalized::Yes {
+            return (new_text, start);
+        }
+        if let Some(previous) = self.char_before(start) {
+            let joined: String =
+                Some(previous).into_iter().chain(text.chars()).collect();
+            let composed: String = joined.nfc().collect();
+            if !composed.starts_with(previous) {
+                let previous_len = S::from(previous.to_string()).len();
+                return (S::from(composed), start - previous_len);
+            }
+        }
+        (S::from(text.nfc().collect::<String>()), start)
+    }
+
+    /// The character before location in the text node containing it, if
+    /// any
+    fn char_before(&self, location: usize) -> Option<char> {
+        let range = self.state.dom.find_range(location, location);
+        let previous = range.leaves().find_map(|leaf| {
+            match self.state.dom.lookup_node(&leaf.node_handle) {
+                DomNode::Text(node) if leaf.start_offset > 0 => {
+                    node.data()[..leaf.start_offset].to_string().chars().last()
+                }
+                _ => None,
+            }
+        });
+        previous
+    }
+}
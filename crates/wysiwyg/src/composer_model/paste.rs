@@ -0,0 +1,156 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::Range;
+
+use crate::dom::nodes::DomNode;
+use crate::dom::parser::parse_external;
+use crate::{ComposerModel, ComposerUpdate, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Insert HTML from outside the editor, e.g. from the clipboard, at the
+    /// cursor, replacing any selected text. Scripts and other invisible
+    /// content are removed, inline styles become formatting and tags the
+    /// editor doesn't support are replaced by their content.
+    pub fn paste_html(&mut self, html: &S) -> ComposerUpdate<S> {
+        self.insert_html_at_cursor(html)
+    }
+
+    /// Insert a prebuilt rich snippet, e.g. a canned reply or a signature,
+    /// at the cursor, replacing any selected text, and place the cursor
+    /// after it. The HTML is cleaned up in the same way as by
+    /// [paste_html](Self::paste_html).
+    pub fn insert_html_at_cursor(&mut self, html: &S) -> ComposerUpdate<S> {
+        let mut dom = match parse_external(&html.to_string()) {
+            Ok(dom) => dom,
+            // We should log here - internal task PSU-741
+            Err(e) => *e.dom,
+        };
+        self.sanitize_html(&mut dom);
+
+        let mut nodes = dom.take_children();
+        // The last paragraph doesn't need a line after it
+        while matches!(nodes.last(), Some(DomNode::LineBreak(_))) {
+            nodes.pop();
+        }
+        if nodes.is_empty() {
+            return ComposerUpdate::keep();
+        }
+
+        self.push_state_to_history();
+        self.do_insert_nodes_at_cursor(nodes);
+        self.create_update_replace_all()
+    }
+
+    /// Insert plain text from outside the editor, e.g. from the clipboard,
+    /// at the cursor, replacing any selected text. Each line of the text
+    /// goes on a line of its own and web addresses in it become links.
+    pub fn paste_text(&mut self, text: &S) -> ComposerUpdate<S> {
+        let text = text.to_string().replace("\r\n", "\n").replace('\r', "\n");
+        if text.is_empty() {
+            return ComposerUpdate::keep();
+        }
+
+        let mut nodes = Vec::new();
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                nodes.push(DomNode::new_line_break());
+            }
+            let mut pos = 0;
+            for (range, url) in find_links(line) {
+                if range.start > pos {
+                    nodes.push(DomNode::new_text(S::from(
+                        &line[pos..range.start],
+                    )));
+                }
+                nodes.push(DomNode::new_link(
+                    S::from(url.as_str()),
+                    vec![DomNode::new_text(S::from(&line[range.clone()]))],
+                ));
+                pos = range.end;
+            }
+            if pos < line.len() {
+                nodes.push(DomNode::new_text(S::from(&line[pos..])));
+            }
+        }
+
+        self.push_state_to_history();
+        self.do_insert_nodes_at_cursor(nodes);
+        self.create_update_replace_all()
+    }
+}
+
+/// Find the web addresses in `line`, returning where each of them is and
+/// the url it should link to.
+fn find_links(line: &str) -> Vec<(Range<usize>, String)> {
+    let mut links = Vec::new();
+    let mut word_start = None;
+    for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (c.is_whitespace(), word_start) {
+            (false, None) => word_start = Some(i),
+            (true, Some(start)) => {
+                links.extend(link_in_word(line, start..i));
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    links
+}
+
+/// If the word at `range` in `line` is a web address, ignoring any
+/// punctuation around it, return where it is and the url it links to.
+fn link_in_word(
+    line: &str,
+    range: Range<usize>,
+) -> Option<(Range<usize>, String)> {
+    let word = &line[range.clone()];
+    let trimmed_start = word.trim_start_matches(['(', '<', '[', '"', '\'']);
+    let start = range.start + word.len() - trimmed_start.len();
+
+    let lowercase = trimmed_start.to_ascii_lowercase();
+    let prefix_len = ["https://", "http://", "www."]
+        .iter()
+        .find(|prefix| lowercase.starts_with(*prefix))?
+        .len();
+
+    let mut end = range.end;
+    loop {
+        let url = &line[start..end];
+        let last = url.chars().last()?;
+        let unbalanced_bracket = (last == ')'
+            && url.matches('(').count() < url.matches(')').count())
+            || (last == ']'
+                && url.matches('[').count() < url.matches(']').count());
+        if ".,;:!?'\">".contains(last) || unbalanced_bracket {
+            end -= last.len_utf8();
+        } else {
+            break;
+        }
+    }
+    if end - start <= prefix_len {
+        return None;
+    }
+
+    let url = &line[start..end];
+    let href = if lowercase.starts_with("www.") {
+        format!("https://{url}")
+    } else {
+        url.to_owned()
+    };
+    Some((start..end, href))
+}
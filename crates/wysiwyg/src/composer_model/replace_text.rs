@@ -12,11 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::composer_model::menu_state::MenuStateComputeType;
 use crate::dom::action_list::{DomAction, DomActionList};
+use crate::dom::diff::DomChanges;
 use crate::dom::nodes::DomNode;
 use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
 use crate::dom::{DomHandle, DomLocation, Range};
-use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
+use crate::{
+    ComposerModel, ComposerUpdate, EnterMode, Location, MenuState,
+    UnicodeString,
+};
 
 impl<S> ComposerModel<S>
 where
@@ -37,16 +44,65 @@ where
         start: usize,
         end: usize,
     ) -> ComposerUpdate<S> {
-        // Store current Dom
-        self.push_state_to_history();
-        self.do_replace_text_in(new_text, start, end)
+        let (new_text, start) = if self.nfc_normalization {
+            self.normalize_nfc(new_text, start)
+        } else {
+            (new_text, start)
+        };
+        let len = new_text.len();
+        let is_typing = is_typed_character(&new_text);
+        let ends_word = new_text.to_string().chars().all(char::is_whitespace);
+        let continues_typing = is_typing
+            && start == end
+            && self.typing_end == Some(start)
+            && self.state.toggled_format_types.is_empty();
+        if !continues_typing {
+            // Store current Dom
+            self.push_state_to_history();
+        }
+
+        let mut update = self.do_replace_text_in(new_text, start, end);
+        update.menu_action = self.compute_menu_action();
+        // Each word typed, with the whitespace after it, is one undo step
+        self.typing_end = if is_typing && !ends_word {
+            Some(start + len)
+        } else {
+            None
+        };
+        update
     }
 
+    /// Handle the Enter key. In document mode this starts a new line, and
+    /// in message mode it asks for the message to be sent instead.
     pub fn enter(&mut self) -> ComposerUpdate<S> {
+        match self.enter_mode {
+            EnterMode::Document => self.shift_enter(),
+            EnterMode::Message => ComposerUpdate::send(MenuState::Keep),
+        }
+    }
+
+    /// Handle Shift+Enter, which starts a new line whatever the enter mode.
+    pub fn shift_enter(&mut self) -> ComposerUpdate<S> {
         self.push_state_to_history();
         self.do_enter()
     }
 
+    /// Insert a line break at the cursor, replacing any selection. Unlike
+    /// enter(), this never starts a new block such as a list item.
+    pub fn insert_soft_break(&mut self) -> ComposerUpdate<S> {
+        self.push_state_to_history();
+        self.do_insert_node_at_cursor(DomNode::new_line_break());
+        self.create_update_replace_all()
+    }
+
+    pub fn enter_mode(&self) -> &EnterMode {
+        &self.enter_mode
+    }
+
+    pub fn set_enter_mode(&mut self, enter_mode: EnterMode) {
+        self.enter_mode = enter_mode;
+    }
+
     fn do_enter(&mut self) -> ComposerUpdate<S> {
         let (s, e) = self.safe_selection();
 
@@ -150,6 +206,8 @@ where
         } else {
             let len = new_text.len();
             let range = self.state.dom.find_range(start, end);
+            let edit =
+                self.expected_text_node_edit(&range, &new_text, start, end);
             if range.is_empty() {
                 if !new_text.is_empty() {
                     self.state.dom.append_child(DomNode::new_text(new_text));
@@ -163,13 +221,167 @@ where
 
             self.state.start = Location::from(start + len);
             self.state.end = self.state.start;
+
+            // Joining or unwrapping nodes changes more than the one text
+            // node
+            let normalized = self.normalize_format_nodes();
+            if let Some(edit) = edit.filter(|_| !normalized) {
+                // The changes are worked out once, for both the check and
+                // the update
+                let changes = self.take_dom_changes();
+                if self.is_only_change(&edit, &changes) {
+                    self.report_invariant_violations();
+                    let menu_state = if edit.is_inside_node
+                        && self.menu_state_survives_typing()
+                    {
+                        MenuState::Keep
+                    } else {
+                        self.compute_menu_state(
+                            MenuStateComputeType::KeepIfUnchanged,
+                        )
+                    };
+                    let mut update = ComposerUpdate::replace_range(
+                        Location::from(edit.start),
+                        Location::from(edit.end),
+                        edit.replacement_html,
+                        self.state.start,
+                        self.state.end,
+                        menu_state,
+                    );
+                    update.dom_selection = Some(self.dom_selection());
+                    update.dom_changes = changes;
+                    return update;
+                }
+                let mut update = self.create_update_replace_all();
+                update.dom_changes = changes;
+                return update;
+            }
         }
 
-        // TODO: for now, we replace every time, to check ourselves, but
-        // at least some of the time we should not
         self.create_update_replace_all()
     }
 
+    /// If replacing start..end, found in `range`, with `new_text` may only
+    /// change the text of a single text node, what that change would be.
+    /// Typing usually does, and then the platform doesn't need to replace
+    /// all the content.
+    ///
+    /// The text node must have text before start, so the platform can give
+    /// the new text the formatting of the character before it.
+    fn expected_text_node_edit(
+        &self,
+        range: &Range,
+        new_text: &S,
+        start: usize,
+        end: usize,
+    ) -> Option<TextNodeEdit<S>> {
+        if !self.state.toggled_format_types.is_empty() {
+            return None;
+        }
+        let dom = &self.state.dom;
+        let mut candidates = Vec::new();
+        let mut is_inside_node = false;
+        for leaf in range.leaves() {
+            let (leaf_start, leaf_end) =
+                (leaf.position, leaf.position + leaf.length);
+            if start <= leaf_start || end > leaf_end {
+                continue;
+            }
+            if let DomNode::Text(text) = dom.lookup_node(&leaf.node_handle) {
+                let data = text.data();
+                let mut new_data = data[..start - leaf_start].to_owned();
+                new_data.push(new_text.deref());
+                new_data.push(&data[end - leaf_start..]);
+                candidates.push((leaf.node_handle.clone(), new_data));
+                is_inside_node = leaf_start < start && end < leaf_end;
+            }
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+        let candidates_len = candidates.len();
+        Some(TextNodeEdit {
+            start,
+            end,
+            replacement_html: S::from(
+                html_escape::encode_text(&new_text.to_string()).as_ref(),
+            ),
+            candidates,
+            is_inside_node: candidates_len == 1
+                && is_inside_node
+                && start == end
+                && self.safe_selection() == (start, end),
+        })
+    }
+
+    /// Whether `edit` is the only change in `changes`, the changes since
+    /// the last update, changing the text of one of its candidate nodes.
+    fn is_only_change(
+        &self,
+        edit: &TextNodeEdit<S>,
+        changes: &DomChanges,
+    ) -> bool {
+        let dom = &self.state.dom;
+        if !changes.created.is_empty()
+            || !changes.deleted.is_empty()
+            || changes.modified.len() != 1
+        {
+            return false;
+        }
+        let handle = &changes.modified[0];
+        edit.candidates.iter().any(|(candidate, new_data)| {
+            candidate == handle
+                && matches!(
+                    dom.lookup_node(candidate),
+                    DomNode::Text(text) if text.data() == new_data.deref()
+                )
+        })
+    }
+
+    /// Internal: replace the selection with node, and put the cursor after
+    /// it, without modifying the undo/redo state.
+    pub(crate) fn do_insert_node_at_cursor(&mut self, node: DomNode<S>) {
+        self.do_insert_nodes_at_cursor(vec![node]);
+    }
+
+    /// Insert `nodes` one after another at the cursor, replacing any
+    /// selected text, and place the cursor after the last of them.
+    pub(crate) fn do_insert_nodes_at_cursor(&mut self, nodes: Vec<DomNode<S>>) {
+        let (mut s, e) = self.safe_selection();
+        if s != e {
+            self.do_replace_text_in(S::default(), s, e);
+            (s, _) = self.safe_selection();
+        }
+
+        let nodes_len: usize = nodes.iter().map(|node| node.text_len()).sum();
+        let range = self.state.dom.find_range(s, s);
+        if let Some(leaf) = range.leaves().next() {
+            self.state.dom.insert_nodes_into_text(
+                &leaf.node_handle,
+                leaf.start_offset,
+                nodes,
+            );
+            self.join_text_nodes_in_parent(&leaf.node_handle.parent_handle());
+        } else {
+            for node in nodes {
+                self.state.dom.append_child(node);
+            }
+        }
+
+        self.state.start = Location::from(s + nodes_len);
+        self.state.end = self.state.start;
+    }
+
+    /// Internal: delete start..end from the document and join what is
+    /// left either side, without touching the selection or undo/redo
+    /// state. See [Dom::remove_range].
+    pub(crate) fn remove_range_in_dom(&mut self, start: usize, end: usize) {
+        let range = self.state.dom.find_range(start, end);
+        if !range.is_empty() {
+            self.replace_multiple_nodes(range, S::default());
+        }
+    }
+
     fn replace_multiple_nodes(&mut self, range: Range, new_text: S) {
         let len = new_text.len();
         let action_list = self.replace_in_text_nodes(range.clone(), new_text);
@@ -213,7 +425,10 @@ where
         }
     }
 
-    fn join_text_nodes_in_parent(&mut self, parent_handle: &DomHandle) {
+    pub(crate) fn join_text_nodes_in_parent(
+        &mut self,
+        parent_handle: &DomHandle,
+    ) {
         let child_count = if let DomNode::Container(parent) =
             self.state.dom.lookup_node(parent_handle)
         {
@@ -267,41 +482,51 @@ where
                 DomNode::Container(_) => {
                     // Nothing to do for container nodes
                 }
-                DomNode::LineBreak(_) => {
+                DomNode::LineBreak(_)
+                | DomNode::Mention(_)
+                | DomNode::Image(_) => {
+                    // Line breaks and atomic inline nodes can't be split,
+                    // so they are either entirely selected or not at all.
                     match (loc.start_offset, loc.end_offset) {
-                        (0, 1) => {
-                            // Whole line break is selected, delete it
+                        (0, e) if e == loc.length => {
+                            // Whole node is selected, delete it
                             action_list.push(DomAction::remove_node(
                                 loc.node_handle.clone(),
                             ));
                         }
-                        (1, 1) => {
-                            // Cursor is after line break, no need to delete
+                        (s, e) if s == loc.length && e == loc.length => {
+                            // Cursor is after the node, no need to delete
                         }
                         (0, 0) => {
-                            let node =
-                                DomNode::new_text(new_text.clone().into());
-                            action_list.push(DomAction::add_node(
-                                loc.node_handle.parent_handle(),
-                                loc.node_handle.index_in_parent(),
-                                node,
-                            ));
+                            // Cursor is before the node. Insert the text
+                            // here, unless an earlier text node took it.
+                            if first_text_node {
+                                let node =
+                                    DomNode::new_text(new_text.clone().into());
+                                action_list.push(DomAction::add_node(
+                                    loc.node_handle.parent_handle(),
+                                    loc.node_handle.index_in_parent(),
+                                    node,
+                                ));
+                                first_text_node = false;
+                            }
                         }
                         _ => panic!(
-                            "Tried to insert text into a line break with offset != 0 or 1. \
+                            "Tried to insert text into the middle of an unsplittable node. \
                             Start offset: {}, end offset: {}",
                             loc.start_offset,
                             loc.end_offset,
                         ),
                     }
-                    if start >= loc.position && end == loc.position + 1 {
+                    if start >= loc.position && end == loc.position + loc.length
+                    {
                         // NOTE: if you add something else to `action_list` you will
                         // probably break our assumptions in the method that
                         // calls this one!
                         // We are assuming we only add nodes AFTER all the
                         // deleted nodes. (That is true in this case, because
                         // we are checking that the selection ends inside this
-                        // line break.)
+                        // node.)
                         action_list.push(DomAction::add_node(
                             loc.node_handle.parent_handle(),
                             loc.node_handle.index_in_parent() + 1,
@@ -342,48 +567,65 @@ where
     }
 }
 
+/// A change to the text of one of the `candidates` text nodes, replacing
+/// start..end with `replacement_html`
+struct TextNodeEdit<S>
+where
+    S: UnicodeString,
+{
+    start: usize,
+    end: usize,
+    replacement_html: S,
+    /// The text nodes that may change, with their text after the change
+    candidates: Vec<(DomHandle, S)>,
+    /// Whether the edit inserts text at the cursor, strictly inside a text
+    /// node, so the formatting around the cursor stays the same
+    is_inside_node: bool,
+}
+
+/// Whether `text` is what typing a single key inserts: one grapheme that
+/// isn't a line break
+fn is_typed_character<S: UnicodeString>(text: &S) -> bool {
+    let text = text.to_string();
+    !text.contains('\n') && text.graphemes(true).count() == 1
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::HashMap;
     use widestring::Utf16String;
 
     use crate::composer_model::action_state::ActionState;
-    use crate::menu_state::MenuStateUpdate;
+    use crate::dom::diff::DomChanges;
     use crate::tests::testutils_composer_model::cm;
     use crate::tests::testutils_conversion::utf16;
-    use crate::{ComposerAction, ComposerUpdate, Location, MenuState};
+    use crate::{
+        ComposerAction, ComposerUpdate, Location, MenuAction, MenuState,
+    };
 
     #[test]
     fn composer_update_contains_escaped_html() {
-        let mut model = cm("|");
-        let update = model.replace_text(Utf16String::from_str("<"));
+        let mut model = cm("a|");
+        let mut update = model.replace_text(Utf16String::from_str("<"));
+        assert_eq!(update.menu_action, MenuAction::None);
+        assert!(update.dom_selection.is_some());
+        assert_eq!(update.dom_changes.modified.len(), 1);
+        update.menu_action = MenuAction::Keep;
+        update.dom_selection = None;
+        update.dom_changes = DomChanges::default();
         assert_eq!(
             update,
-            ComposerUpdate::replace_all(
-                utf16("&lt;"),
+            ComposerUpdate::replace_range(
                 Location::from(1),
                 Location::from(1),
-                MenuState::Update(MenuStateUpdate {
-                    action_states: indent_unindent_redo_disabled()
-                }),
+                utf16("&lt;"),
+                Location::from(2),
+                Location::from(2),
+                MenuState::PartialUpdate(HashMap::from([(
+                    ComposerAction::Undo,
+                    ActionState::Enabled
+                )])),
             )
         );
     }
-
-    fn indent_unindent_redo_disabled() -> HashMap<ComposerAction, ActionState> {
-        HashMap::from([
-            (ComposerAction::Bold, ActionState::Enabled),
-            (ComposerAction::Italic, ActionState::Enabled),
-            (ComposerAction::StrikeThrough, ActionState::Enabled),
-            (ComposerAction::Underline, ActionState::Enabled),
-            (ComposerAction::InlineCode, ActionState::Enabled),
-            (ComposerAction::Link, ActionState::Enabled),
-            (ComposerAction::Undo, ActionState::Enabled),
-            (ComposerAction::Redo, ActionState::Disabled),
-            (ComposerAction::OrderedList, ActionState::Enabled),
-            (ComposerAction::UnorderedList, ActionState::Enabled),
-            (ComposerAction::Indent, ActionState::Disabled),
-            (ComposerAction::UnIndent, ActionState::Disabled),
-        ])
-    }
 }
@@ -0,0 +1,57 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::{Dom, SanitizePolicy, SanitizeReport};
+use crate::{ComposerModel, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Restrict the HTML given to set_content_from_html() and paste_html()
+    /// to what `policy` allows, or stop restricting it if `None`. Scripts
+    /// and links that would run them are removed from pasted HTML either
+    /// way. Off by default.
+    pub fn set_sanitize_policy(&mut self, policy: Option<SanitizePolicy>) {
+        self.sanitize_policy = policy;
+    }
+
+    pub fn sanitize_policy(&self) -> Option<&SanitizePolicy> {
+        self.sanitize_policy.as_ref()
+    }
+
+    /// Whether set_content_from_html() and paste_html() remove the tags,
+    /// attributes and URLs that the Matrix spec doesn't allow in the
+    /// `formatted_body` of a message, and anything nested more than 100
+    /// tags deep, see [SanitizePolicy::matrix]. Off by default.
+    pub fn set_matrix_sanitization(&mut self, enabled: bool) {
+        self.set_sanitize_policy(enabled.then(SanitizePolicy::matrix));
+    }
+
+    /// What was removed from the HTML most recently given to
+    /// set_content_from_html() or paste_html(). Always empty unless a
+    /// sanitize policy is set.
+    pub fn last_sanitize_report(&self) -> &SanitizeReport {
+        &self.sanitize_report
+    }
+
+    /// Internal: sanitize the parsed `dom` of HTML being loaded or pasted
+    /// by the policy set, if any, and keep the report of what was removed.
+    pub(crate) fn sanitize_html(&mut self, dom: &mut Dom<S>) {
+        self.sanitize_report = match &self.sanitize_policy {
+            Some(policy) => dom.sanitize(policy),
+            None => SanitizeReport::default(),
+        };
+    }
+}
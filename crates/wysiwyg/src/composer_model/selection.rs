@@ -13,7 +13,11 @@
 // limitations under the License.
 
 use crate::composer_model::menu_state::MenuStateComputeType;
-use crate::{ComposerModel, ComposerUpdate, Location, UnicodeString};
+use crate::dom::nodes::DomNode;
+use crate::{
+    ComposerModel, ComposerUpdate, DomPoint, DomSelection, Location,
+    MoveDirection, MoveGranularity, UnicodeString,
+};
 
 impl<S> ComposerModel<S>
 where
@@ -26,16 +30,226 @@ where
         start: Location,
         end: Location,
     ) -> ComposerUpdate<S> {
+        let start = self.snap_out_of_atomic_inline(start);
+        let end = self.snap_out_of_atomic_inline(end);
         if self.state.start == start && self.state.end == end {
             return ComposerUpdate::keep();
         }
         self.state.toggled_format_types.clear();
+        self.typing_end = None;
         self.state.start = start;
         self.state.end = end;
 
         let menu_state =
             self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged);
-        ComposerUpdate::update_selection(start, end, menu_state)
+        let mut update =
+            ComposerUpdate::update_selection(start, end, menu_state);
+        update.menu_action = self.compute_menu_action();
+        update.dom_selection = Some(self.dom_selection());
+        update
+    }
+
+    /// Select the whole of the content.
+    pub fn select_all(&mut self) -> ComposerUpdate<S> {
+        let len = self.state.dom.text_len();
+        self.select(Location::from(0), Location::from(len))
+    }
+
+    /// Collapse the selection to a cursor at its start.
+    pub fn collapse_to_start(&mut self) -> ComposerUpdate<S> {
+        let (s, _) = self.safe_selection();
+        self.select(Location::from(s), Location::from(s))
+    }
+
+    /// Collapse the selection to a cursor at its end.
+    pub fn collapse_to_end(&mut self) -> ComposerUpdate<S> {
+        let (_, e) = self.safe_selection();
+        self.select(Location::from(e), Location::from(e))
+    }
+
+    /// Expand the cursor to select the word around it. A mention or other
+    /// atomic node next to the cursor is selected as a whole instead.
+    pub fn select_word(&mut self) -> ComposerUpdate<S> {
+        let (s, _) = self.safe_selection();
+        let range = self.state.dom.find_range(s, s);
+        let atomic_leaf = range.leaves().find(|l| {
+            self.state
+                .dom
+                .lookup_node(&l.node_handle)
+                .is_atomic_inline()
+        });
+        let bounds = atomic_leaf
+            .map(|l| (l.position, l.position + l.length))
+            .or_else(|| self.word_around(s));
+        match bounds {
+            Some((start, end)) => {
+                self.select(Location::from(start), Location::from(end))
+            }
+            None => ComposerUpdate::keep(),
+        }
+    }
+
+    /// Select the whole block around the cursor, e.g. for a triple click.
+    /// The model has no paragraph nodes, so this is the line the cursor is
+    /// on within its list item, table cell or the document.
+    pub fn select_block(&mut self) -> ComposerUpdate<S> {
+        let (s, _) = self.safe_selection();
+        let (start, end) = self.line_bounds_at(s);
+        self.select(Location::from(start), Location::from(end))
+    }
+
+    /// Move the cursor one step of `granularity` in `direction`. With
+    /// `extend`, the end of the selection moves and its start stays put.
+    /// Without it, moving by a character collapses a selection to its
+    /// start or end.
+    pub fn move_cursor(
+        &mut self,
+        direction: MoveDirection,
+        granularity: MoveGranularity,
+        extend: bool,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if !extend && s != e {
+            let edge = match direction {
+                MoveDirection::Backward => s,
+                MoveDirection::Forward => e,
+            };
+            let target = match granularity {
+                MoveGranularity::Character => edge,
+                _ => self.cursor_target(edge, direction, granularity),
+            };
+            return self.select(Location::from(target), Location::from(target));
+        }
+
+        let focus = usize::from(self.state.end).min(self.state.dom.text_len());
+        let target =
+            Location::from(self.cursor_target(focus, direction, granularity));
+        let anchor = if extend { self.state.start } else { target };
+        self.select(anchor, target)
+    }
+
+    fn cursor_target(
+        &self,
+        pos: usize,
+        direction: MoveDirection,
+        granularity: MoveGranularity,
+    ) -> usize {
+        let target = match (direction, granularity) {
+            (_, MoveGranularity::Character) => None,
+            (MoveDirection::Backward, MoveGranularity::Word) => {
+                self.word_start_before(pos)
+            }
+            (MoveDirection::Forward, MoveGranularity::Word) => {
+                self.word_end_after(pos)
+            }
+            (MoveDirection::Backward, MoveGranularity::Block) => {
+                Some(self.line_bounds_at(pos).0).filter(|start| *start < pos)
+            }
+            (MoveDirection::Forward, MoveGranularity::Block) => {
+                Some(self.line_bounds_at(pos).1).filter(|end| *end > pos)
+            }
+        };
+        // At the edge of a word or line, move on to the next one
+        target.unwrap_or_else(|| self.character_target(pos, direction))
+    }
+
+    fn character_target(&self, pos: usize, direction: MoveDirection) -> usize {
+        let range = self.state.dom.find_range(pos, pos);
+        let text_leaf_where = |contains: &dyn Fn(usize, usize) -> bool| {
+            range.leaves().find_map(|l| {
+                match self.state.dom.lookup_node(&l.node_handle) {
+                    DomNode::Text(text)
+                        if contains(l.position, l.position + l.length) =>
+                    {
+                        Some((pos - l.position, text.data()))
+                    }
+                    _ => None,
+                }
+            })
+        };
+        match direction {
+            MoveDirection::Backward => {
+                if pos == 0 {
+                    return 0;
+                }
+                let len = match text_leaf_where(&|start, end| {
+                    start < pos && pos <= end
+                }) {
+                    Some((offset, data)) => {
+                        Self::find_previous_char_len(offset, data)
+                    }
+                    None => self.atomic_inline_len_before(pos).unwrap_or(1),
+                };
+                pos - len
+            }
+            MoveDirection::Forward => {
+                let text_len = self.state.dom.text_len();
+                if pos >= text_len {
+                    return text_len;
+                }
+                let len = match text_leaf_where(&|start, end| {
+                    start <= pos && pos < end
+                }) {
+                    Some((offset, data)) => {
+                        Self::find_next_char_len(offset, data)
+                    }
+                    None => self.atomic_inline_len_after(pos).unwrap_or(1),
+                };
+                pos + len
+            }
+        }
+    }
+
+    /// If location falls inside an atomic inline node, move it to the end of
+    /// that node, since the cursor can't be placed inside one.
+    fn snap_out_of_atomic_inline(&self, location: Location) -> Location {
+        let pos: usize = location.into();
+        let range = self.state.dom.find_range(pos, pos);
+        let atomic_leaf = range.leaves().find(|l| {
+            l.position < pos
+                && pos < l.position + l.length
+                && self
+                    .state
+                    .dom
+                    .lookup_node(&l.node_handle)
+                    .is_atomic_inline()
+        });
+        match atomic_leaf {
+            Some(leaf) => Location::from(leaf.position + leaf.length),
+            None => location,
+        }
+    }
+
+    /// The selection as positions inside nodes. The anchor is at the start
+    /// location and the focus at the end, even if the end comes first.
+    pub fn dom_selection(&self) -> DomSelection {
+        let len = self.state.dom.text_len();
+        let start: usize = self.state.start.into();
+        let end: usize = self.state.end.into();
+        DomSelection {
+            anchor: self.dom_point_at(start.min(len)),
+            focus: self.dom_point_at(end.min(len)),
+        }
+    }
+
+    /// The leaf `pos` is in, and how far into it. At the boundary between
+    /// two leaves this is the end of the first one, like in browsers.
+    fn dom_point_at(&self, pos: usize) -> DomPoint {
+        let range = self.state.dom.find_range(pos, pos);
+        let leaf = range
+            .leaves()
+            .find(|l| l.position < pos && pos <= l.position + l.length)
+            .or_else(|| range.leaves().next());
+        match leaf {
+            Some(leaf) => DomPoint {
+                handle: leaf.node_handle.clone(),
+                offset: pos - leaf.position,
+            },
+            None => DomPoint {
+                handle: self.state.dom.document_handle(),
+                offset: 0,
+            },
+        }
     }
 
     /// Return the start and end of the selection, ensuring the first number
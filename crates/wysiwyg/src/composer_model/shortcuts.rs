@@ -0,0 +1,43 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use crate::{ComposerAction, ComposerModel, Shortcut, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// The keyboard shortcut for each action that has one
+    pub fn shortcuts(&self) -> &HashMap<ComposerAction, Shortcut> {
+        &self.shortcuts
+    }
+
+    pub fn shortcut(&self, action: &ComposerAction) -> Option<&Shortcut> {
+        self.shortcuts.get(action)
+    }
+
+    /// Override the shortcut for `action`, or remove it with None.
+    pub fn set_shortcut(
+        &mut self,
+        action: ComposerAction,
+        shortcut: Option<Shortcut>,
+    ) {
+        match shortcut {
+            Some(shortcut) => self.shortcuts.insert(action, shortcut),
+            None => self.shortcuts.remove(&action),
+        };
+    }
+}
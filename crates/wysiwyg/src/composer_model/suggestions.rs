@@ -0,0 +1,120 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::{
+    ComposerModel, ComposerUpdate, MenuAction, PatternKey, SuggestionPattern,
+    SuggestionTrigger, TriggerBoundary, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    pub fn suggestion_triggers(&self) -> &[SuggestionTrigger] {
+        &self.suggestion_triggers
+    }
+
+    /// Replace the prefixes that start suggestion patterns, e.g. to add
+    /// `:` for emoji to the defaults from [SuggestionTrigger::defaults].
+    pub fn set_suggestion_triggers(
+        &mut self,
+        triggers: Vec<SuggestionTrigger>,
+    ) -> ComposerUpdate<S> {
+        self.suggestion_triggers = triggers;
+        let mut update = ComposerUpdate::keep();
+        update.menu_action = self.compute_menu_action();
+        update
+    }
+
+    /// Replace an emoji shortcode the user has typed, as reported in a
+    /// [MenuAction::Suggestion], with the `emoji` they picked. Does nothing
+    /// if the shortcode has changed since.
+    pub fn accept_emoji_suggestion(
+        &mut self,
+        emoji: S,
+        suggestion: &SuggestionPattern,
+    ) -> ComposerUpdate<S> {
+        if suggestion.key != PatternKey::Colon
+            || self.suggestion_pattern_at(suggestion.end).as_ref()
+                != Some(suggestion)
+        {
+            return ComposerUpdate::keep();
+        }
+        self.push_state_to_history();
+        self.typing_end = None;
+        let mut update =
+            self.do_replace_text_in(emoji, suggestion.start, suggestion.end);
+        update.menu_action = self.compute_menu_action();
+        update
+    }
+
+    /// Whether the platform should offer suggestions for what the user is
+    /// typing at the cursor.
+    pub(crate) fn compute_menu_action(&self) -> MenuAction {
+        let (s, e) = self.safe_selection();
+        if s != e {
+            return MenuAction::None;
+        }
+        match self.suggestion_pattern_at(s) {
+            Some(pattern) => MenuAction::Suggestion(pattern),
+            None => MenuAction::None,
+        }
+    }
+
+    /// The pattern in the word around `pos` started by one of the
+    /// triggers, if any. If several match, the one that starts closest to
+    /// `pos` wins, then the one with the longest prefix.
+    fn suggestion_pattern_at(&self, pos: usize) -> Option<SuggestionPattern> {
+        let run = self.text_run_at(|start, end| start < pos && pos <= end)?;
+        let offset = pos - run.start;
+        let before = run.text[..offset].to_string();
+        let after = run.text[offset..].to_string();
+        let word_before = before.rsplit(char::is_whitespace).next()?;
+        let word_after = after.split(char::is_whitespace).next()?;
+        let word_start = pos - S::from(word_before).len();
+
+        self.suggestion_triggers
+            .iter()
+            .filter_map(|trigger| {
+                let prefix_index = match trigger.boundary {
+                    TriggerBoundary::Anywhere => {
+                        word_before.rfind(trigger.prefix.as_str())?
+                    }
+                    TriggerBoundary::StartOfWord => 0,
+                    TriggerBoundary::StartOfMessage if word_start == 0 => 0,
+                    TriggerBoundary::StartOfMessage => return None,
+                };
+                let rest = word_before[prefix_index..]
+                    .strip_prefix(trigger.prefix.as_str())?;
+                let text = format!("{}{}", rest, word_after);
+                if trigger.prefix.is_empty()
+                    || text.chars().count() < trigger.min_length
+                {
+                    return None;
+                }
+                let start =
+                    word_start + S::from(&word_before[..prefix_index]).len();
+                let pattern = SuggestionPattern {
+                    key: PatternKey::from_prefix(&trigger.prefix),
+                    text,
+                    start,
+                    end: pos + S::from(word_after).len(),
+                };
+                Some((trigger.prefix.len(), pattern))
+            })
+            .max_by_key(|(prefix_len, pattern)| (pattern.start, *prefix_len))
+            .map(|(_, pattern)| pattern)
+    }
+}
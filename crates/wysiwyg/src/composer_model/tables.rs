@@ -0,0 +1,436 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::dom::unicode_string::UnicodeStrExt;
+
+use crate::dom::nodes::DomNode;
+use crate::dom::{DomError, DomHandle, DomLocation};
+use crate::{
+    ComposerError, ComposerModel, ComposerUpdate, Location, UnicodeString,
+};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Insert a table with the supplied number of rows and columns at the
+    /// cursor, replacing any selected text, and put the cursor in its first
+    /// cell.
+    pub fn insert_table(
+        &mut self,
+        rows: usize,
+        cols: usize,
+    ) -> ComposerUpdate<S> {
+        if rows == 0 || cols == 0 {
+            return ComposerUpdate::keep();
+        }
+        // Store current Dom
+        self.push_state_to_history();
+
+        let (mut s, e) = self.safe_selection();
+        if s != e {
+            self.do_replace_text_in(S::default(), s, e);
+            (s, _) = self.safe_selection();
+        }
+        let table = DomNode::new_table(
+            (0..rows).map(|_| Self::new_empty_table_row(cols)).collect(),
+        );
+        self.insert_block_at(s, table);
+
+        // Each empty cell holds a single placeholder character
+        self.state.start = Location::from(s + 1);
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+
+    /// Insert `block` at `pos`. A table can't be inside formatting or a
+    /// link, so any inline containers around `pos` are split, and `block`
+    /// goes between the halves, directly inside the nearest block.
+    fn insert_block_at(&mut self, pos: usize, block: DomNode<S>) {
+        let range = self.state.dom.find_range(pos, pos);
+        let leaf = if let Some(leaf) = range.leaves().next() {
+            leaf.node_handle.clone()
+        } else {
+            self.state.dom.append_child(block);
+            return;
+        };
+        let parent = self.state.dom.find_block_ancestor_or_self(&leaf);
+        if leaf.parent_handle() == parent {
+            self.do_insert_node_at_cursor(block);
+            return;
+        }
+
+        // The inline container directly inside the block that holds `pos`
+        let inline =
+            DomHandle::from_raw(leaf.raw()[..parent.raw().len() + 1].to_vec());
+        let inline_start = range
+            .locations
+            .iter()
+            .find(|loc| loc.node_handle == inline)
+            .map_or(pos, |loc| loc.position);
+        let after =
+            match self.state.dom.split_sub_tree(&inline, pos - inline_start) {
+                Ok(after) => after,
+                Err(_) => {
+                    self.do_insert_node_at_cursor(block);
+                    return;
+                }
+            };
+
+        let after_is_empty = self.state.dom.lookup_node(&after).text_len() == 0;
+        let before_is_empty =
+            self.state.dom.lookup_node(&inline).text_len() == 0;
+        if let DomNode::Container(parent) =
+            self.state.dom.lookup_node_mut(&parent)
+        {
+            if after_is_empty {
+                parent.remove_child(after.index_in_parent());
+            }
+            parent.insert_child(after.index_in_parent(), block);
+            if before_is_empty {
+                parent.remove_child(inline.index_in_parent());
+            }
+        }
+    }
+
+    /// Move the cursor to the end of the next cell of the table it is in.
+    pub fn table_next_cell(&mut self) -> ComposerUpdate<S> {
+        self.move_to_adjacent_table_cell(true)
+    }
+
+    /// Move the cursor to the end of the previous cell of the table it is
+    /// in.
+    pub fn table_previous_cell(&mut self) -> ComposerUpdate<S> {
+        self.move_to_adjacent_table_cell(false)
+    }
+
+    /// Insert an empty row above the row containing the cursor. Returns an
+    /// error, leaving the model unchanged, if the table the cursor is in
+    /// can't be found, as do the other table edits.
+    pub fn table_insert_row_before(
+        &mut self,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        self.table_insert_row(false)
+    }
+
+    /// Insert an empty row below the row containing the cursor.
+    pub fn table_insert_row_after(
+        &mut self,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        self.table_insert_row(true)
+    }
+
+    /// Insert an empty column to the right of the column containing the
+    /// cursor.
+    pub fn table_insert_column(
+        &mut self,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        let cursor = match self.begin_table_edit()? {
+            Some(cursor) => cursor,
+            None => return Ok(ComposerUpdate::keep()),
+        };
+        let table = self.state.dom.try_lookup_container_mut(&cursor.table)?;
+        for row in 0..table.children().len() {
+            if let Some(DomNode::Container(row)) = table.get_child_mut(row) {
+                let index = usize::min(cursor.col + 1, row.children().len());
+                row.insert_child(index, Self::new_empty_table_cell());
+            }
+        }
+
+        self.place_cursor_in_table_cell(&cursor, cursor.row, cursor.col);
+        Ok(self.create_update_replace_all())
+    }
+
+    /// Remove the row containing the cursor, or the whole table if it is
+    /// the only row.
+    pub fn table_delete_row(
+        &mut self,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        let cursor = match self.begin_table_edit()? {
+            Some(cursor) => cursor,
+            None => return Ok(ComposerUpdate::keep()),
+        };
+        let table = self.state.dom.try_lookup_container_mut(&cursor.table)?;
+        if table.children().len() == 1 {
+            return Ok(self.delete_table(&cursor));
+        }
+        table.remove_child(cursor.row);
+        let row = usize::min(cursor.row, table.children().len() - 1);
+
+        self.place_cursor_at_end_of_table_cell(&cursor, row, cursor.col);
+        Ok(self.create_update_replace_all())
+    }
+
+    /// Remove the column containing the cursor, or the whole table if it is
+    /// the only column.
+    pub fn table_delete_column(
+        &mut self,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        let cursor = match self.begin_table_edit()? {
+            Some(cursor) => cursor,
+            None => return Ok(ComposerUpdate::keep()),
+        };
+        let table = self.state.dom.try_lookup_container_mut(&cursor.table)?;
+        for row in (0..table.children().len()).rev() {
+            let row_is_empty = match table.get_child_mut(row) {
+                Some(DomNode::Container(row)) => {
+                    if cursor.col < row.children().len() {
+                        row.remove_child(cursor.col);
+                    }
+                    row.children().is_empty()
+                }
+                _ => false,
+            };
+            if row_is_empty {
+                table.remove_child(row);
+            }
+        }
+        if table.children().is_empty() {
+            return Ok(self.delete_table(&cursor));
+        }
+        let row = usize::min(cursor.row, table.children().len() - 1);
+        let col = cursor.col.saturating_sub(1);
+
+        self.place_cursor_at_end_of_table_cell(&cursor, row, col);
+        Ok(self.create_update_replace_all())
+    }
+
+    pub(crate) fn new_empty_table_row(cols: usize) -> DomNode<S> {
+        DomNode::new_table_row(
+            (0..cols).map(|_| Self::new_empty_table_cell()).collect(),
+        )
+    }
+
+    pub(crate) fn new_empty_table_cell() -> DomNode<S> {
+        DomNode::new_table_cell(
+            "td".into(),
+            vec![DomNode::new_text("\u{200b}".into())],
+        )
+    }
+
+    /// Find the table cell containing the start of the selection. A cursor
+    /// on the boundary between two cells is considered to be at the end of
+    /// the first one, but a selection belongs to the cell it selects from.
+    pub(crate) fn current_table_cell(&self) -> Option<DomHandle> {
+        let (s, e) = self.safe_selection();
+        let range = self.state.dom.find_range(s, e);
+        let leaf = if s == e {
+            range.leaves().next()
+        } else {
+            range.leaves().find(|l| l.start_offset < l.length)
+        }?;
+        self.state
+            .dom
+            .find_parent_table_cell_or_self(&leaf.node_handle)
+    }
+
+    /// Return the locations of all the cells of the table at table_handle,
+    /// in document order.
+    pub(crate) fn table_cell_locations(
+        &self,
+        table_handle: &DomHandle,
+    ) -> Vec<DomLocation> {
+        let range = self.state.dom.find_range(0, self.state.dom.text_len());
+        let mut cells: Vec<DomLocation> = range
+            .locations
+            .into_iter()
+            .filter(|l| {
+                l.node_handle.raw().len() == table_handle.raw().len() + 2
+                    && l.node_handle.parent_handle().parent_handle()
+                        == *table_handle
+                    && self
+                        .state
+                        .dom
+                        .lookup_node(&l.node_handle)
+                        .is_table_cell()
+            })
+            .collect();
+        cells.sort_by(|a, b| a.node_handle.cmp(&b.node_handle));
+        cells
+    }
+
+    /// If the cell at cell_handle has been emptied, give it back its
+    /// placeholder and put the cursor after it.
+    pub(crate) fn restore_table_cell_placeholder(
+        &mut self,
+        cell_handle: &DomHandle,
+    ) -> ComposerUpdate<S> {
+        if let DomNode::Container(cell) =
+            self.state.dom.lookup_node_mut(cell_handle)
+        {
+            if cell.text_len() == 0 {
+                while !cell.children().is_empty() {
+                    cell.remove_child(0);
+                }
+                let placeholder = S::from("\u{200b}");
+                let len = placeholder.len();
+                cell.append_child(DomNode::new_text(placeholder));
+                self.state.start += len as isize;
+                self.state.end = self.state.start;
+            }
+        }
+        self.create_update_replace_all()
+    }
+
+    /// Find where the cursor is within the table it is in, if any.
+    fn table_cursor(&self) -> Option<TableCursor> {
+        let cell_handle = self.current_table_cell()?;
+        let row_handle = cell_handle.parent_handle();
+        let table = row_handle.parent_handle();
+        let cells = self.table_cell_locations(&table);
+        let cell = cells.iter().find(|l| l.node_handle == cell_handle)?;
+        let (s, e) = self.safe_selection();
+        Some(TableCursor {
+            table_position: cells[0].position,
+            row: row_handle.index_in_parent(),
+            col: cell_handle.index_in_parent(),
+            start_offset: usize::min(s - cell.position, cell.length),
+            end_offset: usize::min(e - cell.position, cell.length),
+            table,
+        })
+    }
+
+    fn table_insert_row(
+        &mut self,
+        after: bool,
+    ) -> Result<ComposerUpdate<S>, ComposerError> {
+        let cursor = match self.begin_table_edit()? {
+            Some(cursor) => cursor,
+            None => return Ok(ComposerUpdate::keep()),
+        };
+        let table = self.state.dom.try_lookup_container_mut(&cursor.table)?;
+        let cols = match &table.children()[cursor.row] {
+            DomNode::Container(row) => row.children().len(),
+            _ => 1,
+        };
+        let index = if after { cursor.row + 1 } else { cursor.row };
+        table.insert_child(index, Self::new_empty_table_row(cols));
+        let row = if after { cursor.row } else { cursor.row + 1 };
+
+        self.place_cursor_in_table_cell(&cursor, row, cursor.col);
+        Ok(self.create_update_replace_all())
+    }
+
+    /// Find where the cursor is within the table it is in, if any, and
+    /// store the current state in the history before the table is changed.
+    /// Returns an error, leaving the model unchanged, if the table can't be
+    /// found.
+    fn begin_table_edit(&mut self) -> Result<Option<TableCursor>, DomError> {
+        let cursor = match self.table_cursor() {
+            Some(cursor) => cursor,
+            None => return Ok(None),
+        };
+        self.state.dom.try_lookup_container(&cursor.table)?;
+        // Store current Dom
+        self.push_state_to_history();
+        Ok(Some(cursor))
+    }
+
+    /// Remove the table the cursor is in, and put the cursor where it was.
+    fn delete_table(&mut self, cursor: &TableCursor) -> ComposerUpdate<S> {
+        self.state.dom.remove(&cursor.table);
+        self.join_text_nodes_in_parent(&cursor.table.parent_handle());
+
+        self.state.start = Location::from(cursor.table_position);
+        self.state.end = self.state.start;
+        self.create_update_replace_all()
+    }
+
+    /// Select the same offsets within the cell at (row, col) as the cursor
+    /// had in its original cell, so that the selection stays put when the
+    /// table changes around it.
+    fn place_cursor_in_table_cell(
+        &mut self,
+        cursor: &TableCursor,
+        row: usize,
+        col: usize,
+    ) {
+        if let Some(cell) = self.table_cell_location(&cursor.table, row, col) {
+            self.state.start =
+                Location::from(cell.position + cursor.start_offset);
+            self.state.end = Location::from(cell.position + cursor.end_offset);
+        }
+    }
+
+    fn place_cursor_at_end_of_table_cell(
+        &mut self,
+        cursor: &TableCursor,
+        row: usize,
+        col: usize,
+    ) {
+        let cells = self.table_cell_locations(&cursor.table);
+        // Rows may have different lengths, so fall back to the last cell
+        // in the row if there is no cell in this column.
+        let cell = cells
+            .iter()
+            .filter(|l| l.node_handle.parent_handle().index_in_parent() == row)
+            .take_while(|l| l.node_handle.index_in_parent() <= col)
+            .last();
+        if let Some(cell) = cell {
+            self.state.start = Location::from(cell.position + cell.length);
+            self.state.end = self.state.start;
+        }
+    }
+
+    fn table_cell_location(
+        &self,
+        table_handle: &DomHandle,
+        row: usize,
+        col: usize,
+    ) -> Option<DomLocation> {
+        let cell_handle = table_handle.child_handle(row).child_handle(col);
+        self.table_cell_locations(table_handle)
+            .into_iter()
+            .find(|l| l.node_handle == cell_handle)
+    }
+
+    fn move_to_adjacent_table_cell(
+        &mut self,
+        forwards: bool,
+    ) -> ComposerUpdate<S> {
+        let cell_handle = match self.current_table_cell() {
+            Some(handle) => handle,
+            None => return ComposerUpdate::keep(),
+        };
+        let table_handle = cell_handle.parent_handle().parent_handle();
+        let cells = self.table_cell_locations(&table_handle);
+        let index =
+            match cells.iter().position(|c| c.node_handle == cell_handle) {
+                Some(index) => index,
+                None => return ComposerUpdate::keep(),
+            };
+        let target = if forwards {
+            cells.get(index + 1)
+        } else {
+            index.checked_sub(1).and_then(|i| cells.get(i))
+        };
+        match target {
+            Some(cell) => {
+                let end = Location::from(cell.position + cell.length);
+                self.select(end, end)
+            }
+            None => ComposerUpdate::keep(),
+        }
+    }
+}
+
+/// Where the cursor is within a table: which cell it is in, and how far
+/// into that cell the selection starts and ends.
+struct TableCursor {
+    table: DomHandle,
+    table_position: usize,
+    row: usize,
+    col: usize,
+    start_offset: usize,
+    end_offset: usize,
+}
@@ -0,0 +1,183 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt, UnicodeStringExt};
+use crate::dom::{DomHandle, DomLocation};
+use crate::{ComposerModel, ComposerUpdate, Location, TextCase, UnicodeString};
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Change the case of the selected text, as a single undo step. The
+    /// formatting, links and mentions around the text are kept, and words
+    /// are found across formatting boundaries for title case.
+    pub fn transform_case(&mut self, case: TextCase) -> ComposerUpdate<S> {
+        self.transform_case_in_locale(case, None)
+    }
+
+    /// Like transform_case(), using the casing rules of `locale` (a BCP 47
+    /// language tag such as "tr-TR") where they differ from the default
+    /// ones. Currently this affects the dotted and dotless i of Turkish
+    /// and Azerbaijani.
+    pub fn transform_case_in_locale(
+        &mut self,
+        case: TextCase,
+        locale: Option<&str>,
+    ) -> ComposerUpdate<S> {
+        let (s, e) = self.safe_selection();
+        if s == e {
+            return ComposerUpdate::keep();
+        }
+        let turkic = locale.map_or(false, is_turkic);
+        let word_starts = if case == TextCase::Title {
+            self.word_starts()
+        } else {
+            HashSet::new()
+        };
+
+        let range = self.state.dom.find_range(s, e);
+        let leaves: Vec<DomLocation> = range
+            .leaves()
+            .filter(|leaf| leaf.start_offset < leaf.end_offset)
+            .cloned()
+            .collect();
+        let mut new_texts: Vec<(DomHandle, S)> = Vec::new();
+        let mut len_change: isize = 0;
+        for leaf in leaves {
+            let node = match self.state.dom.lookup_node(&leaf.node_handle) {
+                DomNode::Text(node) => node,
+                _ => continue,
+            };
+            let data = node.data();
+            // Change whole graphemes, so that a selection inside one never
+            // splits a surrogate pair
+            let boundaries: Vec<usize> = data.grapheme_boundaries().collect();
+            let start = boundaries
+                .iter()
+                .rev()
+                .find(|b| **b <= leaf.start_offset)
+                .map_or(0, |b| *b);
+            let end = boundaries
+                .iter()
+                .find(|b| **b >= leaf.end_offset)
+                .map_or(data.len(), |b| *b);
+            let selected = &data[start..end];
+            let transformed = match case {
+                TextCase::Upper => to_upper(&selected.to_string(), turkic),
+                TextCase::Lower => to_lower(&selected.to_string(), turkic),
+                TextCase::Title => to_title(
+                    selected,
+                    leaf.position + start,
+                    &word_starts,
+                    turkic,
+                ),
+            };
+            if *selected == *transformed.as_str() {
+                continue;
+            }
+            let transformed = S::from(transformed);
+            len_change += transformed.len() as isize - (end - start) as isize;
+            let mut new_text = data[..start].to_owned();
+            new_text.push(transformed);
+            new_text.push(data[end..].to_owned());
+            new_texts.push((leaf.node_handle, new_text));
+        }
+        if new_texts.is_empty() {
+            return ComposerUpdate::keep();
+        }
+
+        self.push_state_to_history();
+        for (handle, new_text) in new_texts {
+            if let DomNode::Text(node) = self.state.dom.lookup_node_mut(&handle)
+            {
+                node.set_data(new_text);
+            }
+        }
+        let mut new_e = Location::from(e);
+        new_e += len_change;
+        if self.state.start <= self.state.end {
+            self.state.end = new_e;
+        } else {
+            self.state.start = new_e;
+        }
+        self.create_update_replace_all()
+    }
+
+    /// The positions where words start, by the Unicode word boundary
+    /// rules, in every run of text in the document
+    fn word_starts(&self) -> HashSet<usize> {
+        self.text_runs()
+            .into_iter()
+            .flat_map(|run| {
+                let start = run.start;
+                run.text
+                    .word_boundaries()
+                    .map(move |boundary| start + boundary)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// Whether `locale` is Turkish or Azerbaijani, which case i differently
+fn is_turkic(locale: &str) -> bool {
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    language.eq_ignore_ascii_case("tr") || language.eq_ignore_ascii_case("az")
+}
+
+fn to_upper(text: &str, turkic: bool) -> String {
+    if turkic {
+        text.replace('i', "\u{130}").to_uppercase()
+    } else {
+        text.to_uppercase()
+    }
+}
+
+fn to_lower(text: &str, turkic: bool) -> String {
+    if turkic {
+        text.replace('I', "\u{131}")
+            .replace('\u{130}', "i")
+            .to_lowercase()
+    } else {
+        text.to_lowercase()
+    }
+}
+
+/// Upper case the characters of `text` that start a word and lower case
+/// the rest. `text` starts at `position` in the document.
+fn to_title<Str>(
+    text: &Str,
+    position: usize,
+    word_starts: &HashSet<usize>,
+    turkic: bool,
+) -> String
+where
+    Str: UnicodeStr + ?Sized,
+{
+    let mut title = String::new();
+    let mut position = position;
+    for c in text.chars() {
+        if word_starts.contains(&position) {
+            title.push_str(&to_upper(&c.to_string(), turkic));
+        } else {
+            title.push_str(&to_lower(&c.to_string(), turkic));
+        }
+        position += text.char_len(&c);
+    }
+    title
+}
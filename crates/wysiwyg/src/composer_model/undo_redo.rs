@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::composer_model::menu_state::MenuStateComputeType;
 use crate::{ComposerModel, ComposerUpdate, UnicodeString};
 
 impl<S> ComposerModel<S>
@@ -20,6 +21,9 @@ where
 {
     pub fn undo(&mut self) -> ComposerUpdate<S> {
         if let Some(prev) = self.previous_states.pop() {
+            self.composition = None;
+            self.transaction_depth = 0;
+            self.typing_end = None;
             self.next_states.push(self.state.clone());
             self.state = prev;
             self.create_update_replace_all()
@@ -30,6 +34,9 @@ where
 
     pub fn redo(&mut self) -> ComposerUpdate<S> {
         if let Some(next) = self.next_states.pop() {
+            self.composition = None;
+            self.transaction_depth = 0;
+            self.typing_end = None;
             self.previous_states.push(self.state.clone());
             self.state = next;
             self.create_update_replace_all()
@@ -38,10 +45,132 @@ where
         }
     }
 
+    /// Whether undo() would change anything
+    pub fn can_undo(&self) -> bool {
+        !self.previous_states.is_empty()
+    }
+
+    /// Whether redo() would change anything
+    pub fn can_redo(&self) -> bool {
+        !self.next_states.is_empty()
+    }
+
+    /// Start grouping changes, so that everything done until the matching
+    /// end_transaction() is undone in one step. Transactions may be nested,
+    /// in which case the outermost one makes the undo step. Undo and redo
+    /// end all open transactions.
+    pub fn begin_transaction(&mut self) {
+        if self.transaction_depth == 0 {
+            self.push_state_to_history();
+        }
+        self.transaction_depth += 1;
+    }
+
+    /// Stop grouping changes started by begin_transaction(). A transaction
+    /// that changed nothing leaves no undo step.
+    pub fn end_transaction(&mut self) -> ComposerUpdate<S> {
+        if self.transaction_depth == 0 {
+            return ComposerUpdate::keep();
+        }
+        self.transaction_depth -= 1;
+        self.typing_end = None;
+        if self.transaction_depth == 0
+            && self.previous_states.last() == Some(&self.state)
+        {
+            self.previous_states.pop();
+        }
+        ComposerUpdate::update_menu_state(
+            self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+        )
+    }
+
+    /// Run `f` on the model within a transaction, so that all the changes
+    /// it makes are undone in one step.
+    pub fn transact<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.begin_transaction();
+        let result = f(self);
+        self.end_transaction();
+        result
+    }
+
+    /// Stop coalescing typing into the current undo step, e.g. after the
+    /// user pauses, so that the next character typed starts a new one.
+    pub fn mark_typing_pause(&mut self) {
+        self.typing_end = None;
+    }
+
+    /// How many undo steps are currently available
+    pub fn history_depth(&self) -> usize {
+        self.previous_states.len()
+    }
+
+    pub fn history_limit(&self) -> Option<usize> {
+        self.history_limit
+    }
+
+    /// Limit how many undo steps are kept, or remove the limit with None.
+    /// When there are more, the oldest ones are forgotten first.
+    pub fn set_history_limit(
+        &mut self,
+        history_limit: Option<usize>,
+    ) -> ComposerUpdate<S> {
+        self.history_limit = history_limit;
+        self.enforce_history_limit();
+        ComposerUpdate::update_menu_state(
+            self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+        )
+    }
+
+    /// Forget all the states that undo() and redo() could restore, keeping
+    /// the current content, e.g. once a message has been sent.
+    pub fn clear_history(&mut self) -> ComposerUpdate<S> {
+        self.typing_end = None;
+        self.previous_states.clear();
+        self.next_states.clear();
+        ComposerUpdate::update_menu_state(
+            self.compute_menu_state(MenuStateComputeType::KeepIfUnchanged),
+        )
+    }
+
+    /// Remember the current state under `label`, replacing any state
+    /// already remembered under it, so that revert_to_checkpoint() can
+    /// return to it however many changes are made in between.
+    pub fn checkpoint(&mut self, label: &S) {
+        self.checkpoints
+            .insert(label.to_string(), self.state.clone());
+    }
+
+    /// Return to the state remembered by checkpoint() under `label`. This
+    /// is a change like any other, so it can be undone. Does nothing if
+    /// there is no such checkpoint.
+    pub fn revert_to_checkpoint(&mut self, label: &S) -> ComposerUpdate<S> {
+        let state = match self.checkpoints.get(&label.to_string()) {
+            Some(state) => state.clone(),
+            None => return ComposerUpdate::keep(),
+        };
+        self.push_state_to_history();
+        self.state = state;
+        self.create_update_replace_all()
+    }
+
     pub(crate) fn push_state_to_history(&mut self) {
+        // Any other change ends an ongoing composition and run of typing
+        self.composition = None;
+        self.typing_end = None;
+        // A transaction already stored the state from before it began
+        if self.transaction_depth > 0 {
+            return;
+        }
         // Clear future events as they're no longer valid
         self.next_states.clear();
         // Store a copy of the current state in the previous_states
         self.previous_states.push(self.state.clone());
+        self.enforce_history_limit();
+    }
+
+    pub(crate) fn enforce_history_limit(&mut self) {
+        if let Some(limit) = self.history_limit {
+            self.previous_states.truncate_oldest(limit);
+        }
     }
 }
@@ -0,0 +1,176 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt, UnicodeStringExt};
+use crate::dom::DomHandle;
+use crate::{ComposerModel, UnicodeString};
+
+/// A stretch of text uninterrupted by line breaks, atomic nodes like
+/// mentions or block boundaries. Words never extend beyond one.
+pub(crate) struct TextRun<S>
+where
+    S: UnicodeString,
+{
+    pub start: usize,
+    pub text: S,
+}
+
+/// A segment of a [TextRun] between two Unicode word boundaries.
+pub(crate) struct WordSegment {
+    pub start: usize,
+    pub end: usize,
+    pub is_whitespace: bool,
+}
+
+impl<S> ComposerModel<S>
+where
+    S: UnicodeString,
+{
+    /// Returns the position of the start of the word before `pos`,
+    /// skipping any whitespace in between. Returns None if there is no
+    /// text before `pos` in its run.
+    pub(crate) fn word_start_before(&self, pos: usize) -> Option<usize> {
+        let run = self.text_run_at(|start, end| start < pos && pos <= end)?;
+        let mut word_start = None;
+        for segment in run.word_segments().iter().rev() {
+            if segment.start >= pos {
+                continue;
+            }
+            word_start = Some(segment.start);
+            if !segment.is_whitespace {
+                break;
+            }
+        }
+        word_start.filter(|start| *start < pos)
+    }
+
+    /// Returns the position of the end of the word after `pos`, skipping
+    /// any whitespace in between. Returns None if there is no text after
+    /// `pos` in its run.
+    pub(crate) fn word_end_after(&self, pos: usize) -> Option<usize> {
+        let run = self.text_run_at(|start, end| start <= pos && pos < end)?;
+        let mut word_end = None;
+        for segment in run.word_segments().iter() {
+            if segment.end <= pos {
+                continue;
+            }
+            word_end = Some(segment.end);
+            if !segment.is_whitespace {
+                break;
+            }
+        }
+        word_end.filter(|end| *end > pos)
+    }
+
+    /// Returns the start and end of the word containing `pos`, or of the
+    /// word that ends at `pos` if there is none.
+    pub(crate) fn word_around(&self, pos: usize) -> Option<(usize, usize)> {
+        let run = self.text_run_at(|start, end| start <= pos && pos <= end)?;
+        let words: Vec<WordSegment> = run
+            .word_segments()
+            .into_iter()
+            .filter(|segment| !segment.is_whitespace)
+            .collect();
+        words
+            .iter()
+            .find(|word| word.start <= pos && pos < word.end)
+            .or_else(|| words.iter().find(|word| word.end == pos))
+            .map(|word| (word.start, word.end))
+    }
+
+    /// Finds the first run of text for which `contains(start, end)` holds, if
+    /// any.
+    pub(crate) fn text_run_at(
+        &self,
+        contains: impl Fn(usize, usize) -> bool,
+    ) -> Option<TextRun<S>> {
+        self.text_runs()
+            .into_iter()
+            .find(|run| contains(run.start, run.start + run.text.len()))
+    }
+
+    /// All the runs of text in the document, in order. Placeholder
+    /// characters at the start of a run are left out of it so that word
+    /// operations don't remove them.
+    pub(crate) fn text_runs(&self) -> Vec<TextRun<S>> {
+        let range = self.state.dom.find_range(0, self.state.dom.text_len());
+        let mut runs: Vec<TextRun<S>> = Vec::new();
+        let mut last_block: Option<DomHandle> = None;
+        let mut continues_run = false;
+        for leaf in range.leaves() {
+            let text = match self.state.dom.lookup_node(&leaf.node_handle) {
+                DomNode::Text(text) => text,
+                _ => {
+                    continues_run = false;
+                    continue;
+                }
+            };
+            let block = self
+                .state
+                .dom
+                .find_block_ancestor_or_self(&leaf.node_handle);
+            match runs.last_mut() {
+                Some(run)
+                    if continues_run && last_block.as_ref() == Some(&block) =>
+                {
+                    run.text.push(text.data().to_owned());
+                }
+                _ => runs.push(TextRun {
+                    start: leaf.position,
+                    text: text.data().to_owned(),
+                }),
+            }
+            last_block = Some(block);
+            continues_run = true;
+        }
+
+        runs.into_iter()
+            .map(|run| run.without_leading_placeholder())
+            .collect()
+    }
+}
+
+impl<S> TextRun<S>
+where
+    S: UnicodeString,
+{
+    fn without_leading_placeholder(self) -> Self {
+        if self.text.to_string().starts_with('\u{200b}') {
+            let len = S::from("\u{200b}").len();
+            Self {
+                start: self.start + len,
+                text: self.text[len..].to_owned(),
+            }
+        } else {
+            self
+        }
+    }
+
+    /// Splits the run at Unicode word boundaries, with positions in the
+    /// code units of `S`.
+    pub(crate) fn word_segments(&self) -> Vec<WordSegment> {
+        let boundaries: Vec<usize> = self.text.word_boundaries().collect();
+        boundaries
+            .windows(2)
+            .map(|bounds| WordSegment {
+                start: self.start + bounds[0],
+                end: self.start + bounds[1],
+                is_whitespace: self.text[bounds[0]..bounds[1]]
+                    .chars()
+                    .all(char::is_whitespace),
+            })
+            .collect()
+    }
+}
@@ -16,6 +16,8 @@ use crate::dom::{Dom, UnicodeString};
 use crate::{InlineFormatType, Location};
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct ComposerState<S>
 where
     S: UnicodeString,
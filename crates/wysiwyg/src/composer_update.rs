@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::dom::diff::DomChanges;
 use crate::dom::UnicodeString;
-use crate::{Location, MenuState, ReplaceAll, Selection, TextUpdate};
+use crate::{
+    ClipboardContent, DomSelection, Location, MenuAction, MenuState,
+    ReplaceAll, ReplaceRange, Selection, TextUpdate,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ComposerUpdate<S>
@@ -22,6 +26,15 @@ where
 {
     pub text_update: TextUpdate<S>,
     pub menu_state: MenuState,
+    pub menu_action: MenuAction,
+    /// Where the selection is in terms of nodes, whenever the text update
+    /// moves it
+    pub dom_selection: Option<DomSelection>,
+    /// The nodes changed since the last update
+    pub dom_changes: DomChanges,
+    /// What was cut from the content, for the platform to put on the
+    /// clipboard
+    pub clipboard: Option<ClipboardContent<S>>,
 }
 
 impl<S> ComposerUpdate<S>
@@ -32,6 +45,10 @@ where
         Self {
             text_update: TextUpdate::<S>::Keep,
             menu_state: MenuState::Keep,
+            menu_action: MenuAction::Keep,
+            dom_selection: None,
+            dom_changes: DomChanges::default(),
+            clipboard: None,
         }
     }
 
@@ -39,6 +56,21 @@ where
         Self {
             text_update: TextUpdate::<S>::Keep,
             menu_state: menu_state,
+            menu_action: MenuAction::Keep,
+            dom_selection: None,
+            dom_changes: DomChanges::default(),
+            clipboard: None,
+        }
+    }
+
+    pub fn send(menu_state: MenuState) -> Self {
+        Self {
+            text_update: TextUpdate::<S>::Send,
+            menu_state,
+            menu_action: MenuAction::Keep,
+            dom_selection: None,
+            dom_changes: DomChanges::default(),
+            clipboard: None,
         }
     }
 
@@ -50,6 +82,10 @@ where
         Self {
             text_update: TextUpdate::<S>::Select(Selection { start, end }),
             menu_state,
+            menu_action: MenuAction::Keep,
+            dom_selection: None,
+            dom_changes: DomChanges::default(),
+            clipboard: None,
         }
     }
 
@@ -66,6 +102,34 @@ where
                 end,
             }),
             menu_state,
+            menu_action: MenuAction::Keep,
+            dom_selection: None,
+            dom_changes: DomChanges::default(),
+            clipboard: None,
+        }
+    }
+
+    pub fn replace_range(
+        start: Location,
+        end: Location,
+        replacement_html: S,
+        selection_start: Location,
+        selection_end: Location,
+        menu_state: MenuState,
+    ) -> Self {
+        Self {
+            text_update: TextUpdate::ReplaceRange(ReplaceRange {
+                start,
+                end,
+                replacement_html,
+                selection_start,
+                selection_end,
+            }),
+            menu_state,
+            menu_action: MenuAction::Keep,
+            dom_selection: None,
+            dom_changes: DomChanges::default(),
+            clipboard: None,
         }
     }
 }
@@ -0,0 +1,25 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// How long the content of the composer is, e.g. for showing a counter.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ContentCounts {
+    /// User-perceived characters (grapheme clusters), counting a line
+    /// break as one and a mention as the characters of its text.
+    pub characters: usize,
+    /// Words, as found by the Unicode word boundary rules.
+    pub words: usize,
+    /// Code units of the text in the model's string encoding, e.g. UTF-16.
+    pub code_units: usize,
+}
@@ -0,0 +1,31 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Which way to move the cursor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MoveDirection {
+    Backward,
+    Forward,
+}
+
+/// How far to move the cursor in one step.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MoveGranularity {
+    /// One grapheme, or a whole mention or other atomic node.
+    Character,
+    /// To the start or end of a word, skipping any whitespace.
+    Word,
+    /// To the start or end of the current line in its block.
+    Block,
+}
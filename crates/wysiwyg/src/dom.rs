@@ -13,28 +13,65 @@
 // limitations under the License.
 
 pub mod action_list;
+pub mod diff;
+pub mod dom_builder;
 pub mod dom_creation_error;
+pub mod dom_error;
 pub mod dom_handle;
 pub mod dom_struct;
 pub mod find_range;
+#[cfg(test)]
 pub mod find_result;
+pub mod formatting_context;
+pub mod graphemes;
+pub mod html_sanitizer;
+pub mod invariants;
+pub mod iter;
+pub mod node_id;
+pub mod node_metadata;
 pub mod nodes;
+pub mod offsets;
 pub mod parser;
 pub mod range;
+pub mod semantic_equality;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod split;
+pub mod sub_tree;
+pub mod to_dot;
 pub mod to_html;
 pub mod to_markdown;
 pub mod to_raw_text;
 pub mod to_tree;
 pub mod unicode_string;
+pub mod visitor;
 
-pub use dom_creation_error::DomCreationError;
+pub use dom_builder::DomBuilder;
+pub use dom_creation_error::{
+    DomCreationError, LenientParse, ParseCorrection, ParseError, ParseErrorKind,
+};
+pub use dom_error::DomError;
 pub use dom_handle::DomHandle;
 pub use dom_struct::Dom;
+#[cfg(test)]
 pub use find_result::FindResult;
+pub use formatting_context::FormattingContext;
+pub use html_sanitizer::{
+    AllowedAttribute, RemovedAttribute, SanitizePolicy, SanitizeReport,
+    UnknownTagStrategy,
+};
+#[cfg(feature = "assert-invariants")]
+pub use invariants::{set_invariant_sink, InvariantReport};
+pub use invariants::{Invariant, InvariantViolation};
+pub use node_id::NodeId;
+pub use node_metadata::NodeMetadata;
+pub use offsets::OffsetUnit;
+pub use range::BlockSlice;
 pub use range::DomLocation;
 pub use range::Range;
+pub use sub_tree::SubTreeMapping;
 pub use to_html::ToHtml;
 pub use to_markdown::{MarkdownError, ToMarkdown};
 pub use to_raw_text::ToRawText;
 pub use to_tree::ToTree;
-pub use unicode_string::UnicodeString;
+pub use unicode_string::{UnicodeStr, UnicodeStrExt, UnicodeString};
@@ -0,0 +1,510 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Working out which nodes an action changed, so that platforms only need
+//! to re-render those, and the operations that turn one document into
+//! another.
+
+use crate::dom::nodes::{ContainerNode, DomNode};
+use crate::dom::{Dom, DomError, DomHandle, ToHtml, UnicodeString};
+
+/// The nodes an action changed. Created and modified nodes are given by
+/// their handles after the action, and deleted ones by their handles
+/// before it. The descendants of created and deleted nodes aren't listed
+/// separately.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DomChanges {
+    pub created: Vec<DomHandle>,
+    pub modified: Vec<DomHandle>,
+    pub deleted: Vec<DomHandle>,
+}
+
+impl DomChanges {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty()
+            && self.modified.is_empty()
+            && self.deleted.is_empty()
+    }
+}
+
+/// One step in turning a document into another. The handle of each step
+/// is where its node is once the steps before it have been applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DomPatch<S>
+where
+    S: UnicodeString,
+{
+    /// Insert the node so that it has the handle given
+    Insert(DomHandle, DomNode<S>),
+    /// Delete the node with the handle given
+    Delete(DomHandle),
+    /// Replace the node with the handle given by the one given
+    Update(DomHandle, DomNode<S>),
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// The changes that turn `old` into this document. Containers whose
+    /// children were added or removed count as modified.
+    pub(crate) fn changes_since(&self, old: &Dom<S>) -> DomChanges {
+        let mut changes = DomChanges::default();
+        diff_containers(old.document(), self.document(), &mut changes);
+        changes
+    }
+
+    /// The steps that turn this document into `other`, in the order they
+    /// should be applied. Children that are the same in both documents are
+    /// kept, so as few nodes as possible are inserted and deleted.
+    pub fn diff(&self, other: &Dom<S>) -> Vec<DomPatch<S>> {
+        let mut patches = Vec::new();
+        patch_containers(
+            self.document(),
+            other.document(),
+            &self.document_handle(),
+            &mut patches,
+        );
+        patches
+    }
+
+    /// Apply steps, e.g. from [Dom::diff], to this document. The handles
+    /// of the nodes after each inserted or deleted node are updated as it
+    /// goes.
+    ///
+    /// If any step can't be applied, e.g. because its handle doesn't point
+    /// at a node, an error is returned and this document is left as it was.
+    pub fn apply(&mut self, patches: &[DomPatch<S>]) -> Result<(), DomError> {
+        let mut patched = self.clone();
+        for patch in patches {
+            patched.apply_patch(patch)?;
+        }
+        *self = patched;
+        Ok(())
+    }
+
+    fn apply_patch(&mut self, patch: &DomPatch<S>) -> Result<(), DomError> {
+        match patch {
+            DomPatch::Insert(handle, node) => {
+                let parent = self.try_parent_mut(handle)?;
+                let index = handle.index_in_parent();
+                if index > parent.children().len() {
+                    return Err(DomError::InvalidHandle(handle.clone()));
+                }
+                parent.insert_child(index, node.clone());
+            }
+            DomPatch::Delete(handle) => {
+                self.check_replaceable(handle)?;
+                self.remove(handle);
+            }
+            DomPatch::Update(handle, node) => {
+                self.check_replaceable(handle)?;
+                self.replace(handle, vec![node.clone()]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `handle` points at a node that isn't the document itself
+    fn check_replaceable(&self, handle: &DomHandle) -> Result<(), DomError> {
+        if !handle.is_set() || !handle.has_parent() {
+            return Err(DomError::InvalidHandle(handle.clone()));
+        }
+        self.try_lookup_node(handle).map(|_| ())
+    }
+}
+
+fn diff_nodes<S>(old: &DomNode<S>, new: &DomNode<S>, changes: &mut DomChanges)
+where
+    S: UnicodeString,
+{
+    match (old, new) {
+        (DomNode::Container(o), DomNode::Container(n))
+            if o.kind() == n.kind()
+                && o.name() == n.name()
+                && o.attributes() == n.attributes() =>
+        {
+            diff_containers(o, n, changes)
+        }
+        (DomNode::Text(o), DomNode::Text(n)) => {
+            if o.data() != n.data() {
+                changes.modified.push(n.handle());
+            }
+        }
+        (DomNode::LineBreak(_), DomNode::LineBreak(_)) => {}
+        (DomNode::Mention(_), DomNode::Mention(_))
+        | (DomNode::Image(_), DomNode::Image(_))
+            if old.to_html() == new.to_html() => {}
+        _ => {
+            changes.deleted.push(old.handle());
+            changes.created.push(new.handle());
+        }
+    }
+}
+
+/// Children that are the same at the start and end are matched up, and
+/// the ones in between are compared in order.
+fn diff_containers<S>(
+    old: &ContainerNode<S>,
+    new: &ContainerNode<S>,
+    changes: &mut DomChanges,
+) where
+    S: UnicodeString,
+{
+    let old_children = old.children();
+    let new_children = new.children();
+    let (same_before, same_after) = count_same_ends(old_children, new_children);
+
+    let old_changed =
+        &old_children[same_before..old_children.len() - same_after];
+    let new_changed =
+        &new_children[same_before..new_children.len() - same_after];
+    for (o, n) in old_changed.iter().zip(new_changed.iter()) {
+        diff_nodes(o, n, changes);
+    }
+    if old_changed.len() != new_changed.len() {
+        let paired = old_changed.len().min(new_changed.len());
+        changes
+            .deleted
+            .extend(old_changed[paired..].iter().map(|n| n.handle()));
+        changes
+            .created
+            .extend(new_changed[paired..].iter().map(|n| n.handle()));
+        changes.modified.push(new.handle());
+    }
+}
+
+/// Add the steps that turn `old` into `new` to `patches`, where `handle`
+/// is the handle `new` will have.
+fn patch_nodes<S>(
+    old: &DomNode<S>,
+    new: &DomNode<S>,
+    handle: &DomHandle,
+    patches: &mut Vec<DomPatch<S>>,
+) where
+    S: UnicodeString,
+{
+    match (old, new) {
+        (DomNode::Container(o), DomNode::Container(n))
+            if o.kind() == n.kind()
+                && o.name() == n.name()
+                && o.attributes() == n.attributes() =>
+        {
+            patch_containers(o, n, handle, patches)
+        }
+        (DomNode::Container(_), _) | (_, DomNode::Container(_)) => {
+            patches.push(DomPatch::Delete(handle.clone()));
+            patches.push(DomPatch::Insert(handle.clone(), new.clone()));
+        }
+        _ if same_content(old, new) => {}
+        _ => patches.push(DomPatch::Update(handle.clone(), new.clone())),
+    }
+}
+
+/// The children the same at the start and end of both containers are
+/// kept. Of the ones in between, those that stay the same are found as
+/// their longest common subsequence. Between those, children are compared
+/// in order, and any left over are deleted or inserted.
+fn patch_containers<S>(
+    old: &ContainerNode<S>,
+    new: &ContainerNode<S>,
+    handle: &DomHandle,
+    patches: &mut Vec<DomPatch<S>>,
+) where
+    S: UnicodeString,
+{
+    let (prefix, suffix) = count_same_ends(old.children(), new.children());
+    let old_children = &old.children()[prefix..old.children().len() - suffix];
+    let new_children = &new.children()[prefix..new.children().len() - suffix];
+
+    let same: Vec<Vec<bool>> = old_children
+        .iter()
+        .map(|o| new_children.iter().map(|n| same_content(o, n)).collect())
+        .collect();
+    // common[i][j] is the length of the longest common subsequence of
+    // old_children[i..] and new_children[j..]
+    let mut common =
+        vec![vec![0; new_children.len() + 1]; old_children.len() + 1];
+    for i in (0..old_children.len()).rev() {
+        for j in (0..new_children.len()).rev() {
+            common[i][j] = if same[i][j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    // The index each child will have once the steps so far are applied
+    let mut index = prefix;
+    let (mut i, mut j) = (0, 0);
+    loop {
+        // Find the next pair of children that stay the same, or the ends
+        let (mut next_i, mut next_j) = (i, j);
+        while next_i < old_children.len()
+            && next_j < new_children.len()
+            && !same[next_i][next_j]
+        {
+            if common[next_i + 1][next_j] >= common[next_i][next_j + 1] {
+                next_i += 1;
+            } else {
+                next_j += 1;
+            }
+        }
+        if next_i == old_children.len() || next_j == new_children.len() {
+            next_i = old_children.len();
+            next_j = new_children.len();
+        }
+
+        let old_changed = &old_children[i..next_i];
+        let new_changed = &new_children[j..next_j];
+        for (o, n) in old_changed.iter().zip(new_changed.iter()) {
+            patch_nodes(o, n, &handle.child_handle(index), patches);
+            index += 1;
+        }
+        for _ in new_changed.len()..old_changed.len() {
+            patches.push(DomPatch::Delete(handle.child_handle(index)));
+        }
+        for n in new_changed.iter().skip(old_changed.len()) {
+            patches
+                .push(DomPatch::Insert(handle.child_handle(index), n.clone()));
+            index += 1;
+        }
+
+        if next_i == old_children.len() {
+            break;
+        }
+        // Skip the pair that stays the same
+        index += 1;
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+}
+
+/// How many children are the same at the start, and then at the end, of
+/// `old` and `new`
+fn count_same_ends<S>(old: &[DomNode<S>], new: &[DomNode<S>]) -> (usize, usize)
+where
+    S: UnicodeString,
+{
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(o, n)| same_content(o, n))
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(o, n)| same_content(o, n))
+        .count();
+    (prefix, suffix)
+}
+
+/// Whether two nodes would be written out the same, wherever they are.
+/// Children shared by both containers aren't compared.
+fn same_content<S>(old: &DomNode<S>, new: &DomNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    match (old, new) {
+        (DomNode::Container(o), DomNode::Container(n)) => {
+            o.kind() == n.kind()
+                && o.name() == n.name()
+                && o.attributes() == n.attributes()
+                && (o.shares_children_with(n)
+                    || o.children().len() == n.children().len()
+                        && o.children()
+                            .iter()
+                            .zip(n.children().iter())
+                            .all(|(o, n)| same_content(o, n)))
+        }
+        (DomNode::Text(o), DomNode::Text(n)) => o.data() == n.data(),
+        (DomNode::LineBreak(_), DomNode::LineBreak(_)) => true,
+        (DomNode::Mention(_), DomNode::Mention(_))
+        | (DomNode::Image(_), DomNode::Image(_)) => {
+            old.to_html() == new.to_html()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use super::*;
+    use crate::dom::parser::parse;
+
+    fn changes(old: &str, new: &str) -> DomChanges {
+        let old: Dom<Utf16String> = parse(old).unwrap();
+        let new: Dom<Utf16String> = parse(new).unwrap();
+        new.changes_since(&old)
+    }
+
+    fn handles(paths: &[&[usize]]) -> Vec<DomHandle> {
+        paths
+            .iter()
+            .map(|p| DomHandle::from_raw(p.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn identical_documents_have_no_changes() {
+        assert!(changes("a<em>b</em>", "a<em>b</em>").is_empty());
+    }
+
+    #[test]
+    fn editing_text_modifies_its_node() {
+        let changes = changes("a<em>b</em>c", "a<em>bx</em>c");
+        assert_eq!(changes.modified, handles(&[&[1, 0]]));
+        assert!(changes.created.is_empty());
+        assert!(changes.deleted.is_empty());
+    }
+
+    #[test]
+    fn inserting_a_node_creates_it_and_modifies_its_parent() {
+        let changes = changes("a<em>b</em>", "a<u>x</u><em>b</em>");
+        assert_eq!(changes.created, handles(&[&[1]]));
+        assert_eq!(changes.modified, handles(&[&[]]));
+        assert!(changes.deleted.is_empty());
+    }
+
+    /// The patches that turn `old` into `new`, written out as text, after
+    /// checking that they do
+    fn patches(old: &str, new: &str) -> Vec<String> {
+        let old: Dom<Utf16String> = parse(old).unwrap();
+        let new: Dom<Utf16String> = parse(new).unwrap();
+        let patches = old.diff(&new);
+        let mut patched = old.clone();
+        patched.apply(&patches).unwrap();
+        assert_eq!(patched.to_html(), new.to_html());
+        patches
+            .iter()
+            .map(|patch| match patch {
+                DomPatch::Insert(h, n) => {
+                    format!("insert {:?} {}", h.raw(), n.to_html())
+                }
+                DomPatch::Delete(h) => format!("delete {:?}", h.raw()),
+                DomPatch::Update(h, n) => {
+                    format!("update {:?} {}", h.raw(), n.to_html())
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_documents_need_no_patches() {
+        assert!(patches("a<em>b</em>", "a<em>b</em>").is_empty());
+    }
+
+    #[test]
+    fn editing_text_updates_its_node() {
+        assert_eq!(
+            patches("a<em>b</em>c", "a<em>bx</em>c"),
+            vec!["update [1, 0] bx"]
+        );
+    }
+
+    #[test]
+    fn only_the_nodes_that_differ_are_inserted_and_deleted() {
+        assert_eq!(
+            patches("a<em>b</em><br />c", "<u>x</u>a<br />c<br />"),
+            vec!["insert [0] <u>x</u>", "delete [2]", "insert [4] <br />"]
+        );
+    }
+
+    #[test]
+    fn only_the_middle_of_long_documents_is_compared() {
+        let paragraphs = |r: std::ops::Range<usize>| -> String {
+            r.map(|i| format!("<p>{}</p>", i)).collect()
+        };
+        let original = paragraphs(0..200);
+        let edited =
+            format!("{}<p>new</p>{}", paragraphs(0..100), paragraphs(100..200));
+        assert_eq!(
+            patches(&original, &edited),
+            vec!["insert [100] <p>new</p>"]
+        );
+    }
+
+    #[test]
+    fn moved_nodes_with_the_same_content_are_kept() {
+        assert_eq!(
+            patches("<em>a</em>b<em>c</em>", "b<em>c</em>"),
+            vec!["delete [0]"]
+        );
+    }
+
+    #[test]
+    fn patches_turn_one_document_into_the_other() {
+        patches(
+            "<ol><li>a</li><li>b</li></ol>c<br />d",
+            "<ol><li>a</li><li>x<em>b</em></li><li>y</li></ol>c",
+        );
+        patches("<em>a</em>b<br />", "<strong>a</strong>b");
+        patches("a<br />b<br />c", "");
+        patches("", "a<br />b<br />c");
+    }
+
+    #[test]
+    fn applying_an_invalid_patch_leaves_the_document_unchanged() {
+        let mut dom: Dom<Utf16String> = parse("a<em>b</em>").unwrap();
+        let result = dom.apply(&[
+            DomPatch::Delete(DomHandle::from_raw(vec![0])),
+            DomPatch::Delete(DomHandle::from_raw(vec![3])),
+        ]);
+        assert_eq!(
+            result,
+            Err(DomError::InvalidHandle(DomHandle::from_raw(vec![3])))
+        );
+        assert_eq!(dom.to_html(), "a<em>b</em>");
+    }
+
+    #[test]
+    fn patches_cannot_delete_the_document_or_insert_into_text() {
+        let mut dom: Dom<Utf16String> = parse("a").unwrap();
+        assert!(dom
+            .apply(&[DomPatch::Delete(DomHandle::from_raw(vec![]))])
+            .is_err());
+        assert_eq!(
+            dom.apply(&[DomPatch::Insert(
+                DomHandle::from_raw(vec![0, 0]),
+                DomNode::new_line_break()
+            )]),
+            Err(DomError::NotAContainer(DomHandle::from_raw(vec![0])))
+        );
+    }
+
+    #[test]
+    fn inserting_updates_the_handles_of_later_nodes() {
+        let mut dom: Dom<Utf16String> = parse("a<em>b</em>").unwrap();
+        dom.apply(&[DomPatch::Insert(
+            DomHandle::from_raw(vec![0]),
+            DomNode::new_line_break(),
+        )])
+        .unwrap();
+        assert_eq!(dom.to_html(), "<br />a<em>b</em>");
+        let visited: Vec<DomHandle> =
+            dom.iter().map(|(handle, _)| handle).collect();
+        assert_eq!(visited, handles(&[&[], &[0], &[1], &[2], &[2, 0]]));
+    }
+
+    #[test]
+    fn replacing_formatting_deletes_and_creates_it() {
+        let changes = changes("<em>a</em>", "<strong>a</strong>");
+        assert_eq!(changes.deleted, handles(&[&[0]]));
+        assert_eq!(changes.created, handles(&[&[0]]));
+    }
+}
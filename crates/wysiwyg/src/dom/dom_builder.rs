@@ -0,0 +1,209 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Building a [Dom] in code, without assembling node vectors by hand.
+//!
+//! ```
+//! use widestring::Utf16String;
+//! use wysiwyg::{DomBuilder, ListType, ToHtml};
+//!
+//! let dom = DomBuilder::<Utf16String>::new()
+//!     .text("a")
+//!     .bold(|b| b.text("b").italic(|i| i.text("c")))
+//!     .list(ListType::Unordered, |list| list.item(|item| item.text("d")))
+//!     .build();
+//! assert_eq!(dom.to_html(), "a<strong>b<em>c</em></strong><ul><li>d</li></ul>");
+//! ```
+
+use crate::dom::nodes::DomNode;
+use crate::dom::{Dom, UnicodeString};
+use crate::{InlineFormatType, ListType};
+
+/// Collects nodes in order. Methods that take a closure give it a fresh
+/// builder for the children of the node they add.
+#[derive(Clone, Debug, Default)]
+pub struct DomBuilder<S>
+where
+    S: UnicodeString,
+{
+    nodes: Vec<DomNode<S>>,
+}
+
+impl<S> DomBuilder<S>
+where
+    S: UnicodeString,
+{
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// A document holding the nodes added so far, with handles set.
+    pub fn build(self) -> Dom<S> {
+        Dom::new(self.nodes)
+    }
+
+    /// The nodes added so far, e.g. to put inside another node. Their
+    /// handles are unset.
+    pub fn into_nodes(self) -> Vec<DomNode<S>> {
+        self.nodes
+    }
+
+    /// Add any node, e.g. one made with the DomNode::new_* functions.
+    pub fn node(mut self, node: DomNode<S>) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    pub fn text(self, text: &str) -> Self {
+        self.node(DomNode::new_text(S::from(text)))
+    }
+
+    pub fn line_break(self) -> Self {
+        self.node(DomNode::new_line_break())
+    }
+
+    pub fn mention(self, url: &str, display_text: &str) -> Self {
+        self.node(DomNode::new_mention(S::from(url), S::from(display_text)))
+    }
+
+    pub fn image(self, src: &str) -> Self {
+        self.node(DomNode::new_image(vec![(S::from("src"), S::from(src))]))
+    }
+
+    pub fn formatting(
+        self,
+        format: InlineFormatType,
+        children: impl FnOnce(Self) -> Self,
+    ) -> Self {
+        self.node(DomNode::new_formatting(format, build_children(children)))
+    }
+
+    pub fn bold(self, children: impl FnOnce(Self) -> Self) -> Self {
+        self.formatting(InlineFormatType::Bold, children)
+    }
+
+    pub fn italic(self, children: impl FnOnce(Self) -> Self) -> Self {
+        self.formatting(InlineFormatType::Italic, children)
+    }
+
+    pub fn strike_through(self, children: impl FnOnce(Self) -> Self) -> Self {
+        self.formatting(InlineFormatType::StrikeThrough, children)
+    }
+
+    pub fn underline(self, children: impl FnOnce(Self) -> Self) -> Self {
+        self.formatting(InlineFormatType::Underline, children)
+    }
+
+    pub fn inline_code(self, children: impl FnOnce(Self) -> Self) -> Self {
+        self.formatting(InlineFormatType::InlineCode, children)
+    }
+
+    pub fn link(self, url: &str, children: impl FnOnce(Self) -> Self) -> Self {
+        self.node(DomNode::new_link(S::from(url), build_children(children)))
+    }
+
+    /// Add a list. Its children should be added with [DomBuilder::item].
+    pub fn list(
+        self,
+        list_type: ListType,
+        items: impl FnOnce(Self) -> Self,
+    ) -> Self {
+        self.node(DomNode::new_list(list_type, build_children(items)))
+    }
+
+    pub fn item(self, children: impl FnOnce(Self) -> Self) -> Self {
+        self.node(DomNode::new_list_item(
+            S::from("li"),
+            build_children(children),
+        ))
+    }
+
+    /// Add a table. Its children should be added with [DomBuilder::row].
+    pub fn table(self, rows: impl FnOnce(Self) -> Self) -> Self {
+        self.node(DomNode::new_table(build_children(rows)))
+    }
+
+    /// Add a table row. Its children should be added with
+    /// [DomBuilder::cell].
+    pub fn row(self, cells: impl FnOnce(Self) -> Self) -> Self {
+        self.node(DomNode::new_table_row(build_children(cells)))
+    }
+
+    pub fn cell(self, children: impl FnOnce(Self) -> Self) -> Self {
+        self.node(DomNode::new_table_cell(
+            S::from("td"),
+            build_children(children),
+        ))
+    }
+}
+
+fn build_children<S: UnicodeString>(
+    children: impl FnOnce(DomBuilder<S>) -> DomBuilder<S>,
+) -> Vec<DomNode<S>> {
+    children(DomBuilder::new()).into_nodes()
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+    use crate::dom::DomHandle;
+    use crate::ToHtml;
+
+    use super::*;
+
+    fn builder() -> DomBuilder<Utf16String> {
+        DomBuilder::new()
+    }
+
+    #[test]
+    fn builds_the_same_tree_as_parsing() {
+        let dom = builder()
+            .text("a")
+            .bold(|b| b.text("b").underline(|u| u.text("c")))
+            .line_break()
+            .link("https://matrix.org", |a| a.text("d"))
+            .mention("https://matrix.to/#/@alice:matrix.org", "Alice")
+            .build();
+        assert_eq!(
+            dom.to_html(),
+            "a<strong>b<u>c</u></strong><br />\
+             <a href=\"https://matrix.org\">d</a>\
+             <a href=\"https://matrix.to/#/@alice:matrix.org\" \
+             contenteditable=\"false\">Alice</a>"
+        );
+        assert_eq!(
+            dom.lookup_node(&DomHandle::from_raw(vec![1, 1, 0]))
+                .handle(),
+            DomHandle::from_raw(vec![1, 1, 0])
+        );
+    }
+
+    #[test]
+    fn builds_lists_and_tables() {
+        let dom = builder()
+            .list(ListType::Ordered, |list| {
+                list.item(|i| i.text("a")).item(|i| i.text("b"))
+            })
+            .table(|t| t.row(|r| r.cell(|c| c.text("c")).cell(|c| c)))
+            .build();
+        let parsed = parse(
+            "<ol><li>a</li><li>b</li></ol>\
+             <table><tr><td>c</td><td></td></tr></table>",
+        )
+        .unwrap();
+        assert_eq!(dom, parsed);
+    }
+}
@@ -12,13 +12,88 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
+
 use super::{Dom, UnicodeString};
 
 #[derive(Debug)]
 pub struct DomCreationError<S>
+where
+    S: UnicodeString,
+{
+    /// The best the parser could make of the HTML. It is boxed to keep
+    /// results holding this error small.
+    pub dom: Box<Dom<S>>,
+    pub parse_errors: Vec<ParseError>,
+}
+
+/// What kind of problem a [ParseError] is
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// A tag is opened but never closed
+    UnclosedTag,
+    /// A closing tag doesn't match any open tag
+    UnexpectedEndTag,
+    /// A tag is inside one it isn't allowed in, e.g. a link inside a link,
+    /// or is closed while tags opened inside it are still open
+    DisallowedNesting,
+    /// Anything else the underlying HTML parser reported
+    Other,
+}
+
+/// A problem found while parsing HTML. The parser recovers from it the way
+/// a browser would, so this is for logging malformed HTML rather than
+/// refusing it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The offset in bytes of the tag at fault in the UTF-8 HTML, if known
+    pub position: Option<usize>,
+    pub description: String,
+}
+
+impl ParseError {
+    /// A problem reported by the underlying HTML parser, which doesn't
+    /// say where it is
+    #[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+    pub(crate) fn other(description: impl Into<String>) -> Self {
+        Self {
+            kind: ParseErrorKind::Other,
+            position: None,
+            description: description.into(),
+        }
+    }
+}
+
+/// A change made to malformed HTML to be able to parse it, see
+/// [crate::parse_lenient]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseCorrection {
+    /// The offset in bytes in the UTF-8 HTML where the change was made, if
+    /// known
+    pub position: Option<usize>,
+    pub description: String,
+}
+
+/// The result of parsing HTML leniently: the best the parser could make of
+/// it, and what had to be changed to get there
+#[derive(Debug)]
+pub struct LenientParse<S>
 where
     S: UnicodeString,
 {
     pub dom: Dom<S>,
-    pub parse_errors: Vec<String>,
+    /// Empty if the HTML was well-formed
+    pub corrections: Vec<ParseCorrection>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(position) => {
+                write!(formatter, "{} at byte {}", self.description, position)
+            }
+            None => write!(formatter, "{}", self.description),
+        }
+    }
 }
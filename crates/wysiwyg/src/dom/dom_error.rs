@@ -0,0 +1,57 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt};
+
+use super::DomHandle;
+
+/// Why a handle couldn't be used to find the node it was expected to
+/// point at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomError {
+    InvalidHandle(DomHandle),
+    NotAContainer(DomHandle),
+    NotATextNode(DomHandle),
+    /// The offset given is past the end of the node at the handle
+    OffsetOutOfRange(DomHandle, usize),
+}
+
+impl Error for DomError {}
+
+impl fmt::Display for DomError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHandle(handle) => write!(
+                formatter,
+                "Handle {:?} does not point at a node",
+                handle
+            ),
+            Self::NotAContainer(handle) => write!(
+                formatter,
+                "Node at {:?} was expected to be a container",
+                handle
+            ),
+            Self::NotATextNode(handle) => write!(
+                formatter,
+                "Node at {:?} was expected to be a text node",
+                handle
+            ),
+            Self::OffsetOutOfRange(handle, offset) => write!(
+                formatter,
+                "Offset {} is past the end of the node at {:?}",
+                offset, handle
+            ),
+        }
+    }
+}
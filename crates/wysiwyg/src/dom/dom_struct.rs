@@ -19,9 +19,10 @@ use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode};
 use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
 use crate::dom::unicode_string::UnicodeStrExt;
 use crate::dom::{
-    find_range, to_raw_text::ToRawText, DomHandle, Range, ToTree, UnicodeString,
+    find_range, to_raw_text::ToRawText, DomError, DomHandle, Range, ToTree,
+    UnicodeString,
 };
-use crate::ToHtml;
+use crate::{ComposerModel, ToHtml};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Dom<S>
@@ -73,7 +74,6 @@ where
         self.document().children()
     }
 
-    #[cfg(all(feature = "js", target_arch = "wasm32"))]
     pub(crate) fn take_children(self) -> Vec<DomNode<S>> {
         if let DomNode::Container(container) = self.document {
             container.take_children()
@@ -102,6 +102,22 @@ where
         parent.remove_child(index);
     }
 
+    /// Delete the text between the start and end code units, and any nodes
+    /// left empty, then join what remains either side as typing over a
+    /// selection would, e.g. two bold nodes become one and two list items
+    /// become one. `end` is clamped to the length of the document.
+    pub fn remove_range(&mut self, start: usize, end: usize) {
+        let end = end.min(self.text_len());
+        if start >= end {
+            return;
+        }
+        // The joining is done by the composer, so borrow one
+        let mut model = ComposerModel::new();
+        model.state.dom = std::mem::replace(self, Dom::new(Vec::new()));
+        model.remove_range_in_dom(start, end);
+        *self = model.state.dom;
+    }
+
     /// Removes node at given handle from the dom, and if it has children
     /// moves them to its parent container children.
     pub fn remove_and_keep_children(&mut self, node_handle: &DomHandle) {
@@ -122,6 +138,38 @@ where
         find_range::find_range(self, start, end)
     }
 
+    /// The plain text between the start and end code units. See
+    /// [Range::text].
+    pub fn text_in(&self, start: usize, end: usize) -> S {
+        self.find_range(start, end).text(self)
+    }
+
+    /// Where the node at `handle` starts, or an error if there is no node
+    /// there. Only the containers above it are looked at.
+    pub(crate) fn try_find_start(
+        &self,
+        node_handle: &DomHandle,
+    ) -> Result<usize, DomError> {
+        let invalid = || DomError::InvalidHandle(node_handle.clone());
+        if !node_handle.is_set() {
+            return Err(invalid());
+        }
+        let mut start = 0;
+        let mut node = &self.document;
+        for &idx in node_handle.raw() {
+            let container = match node {
+                DomNode::Container(container) => container,
+                _ => return Err(invalid()),
+            };
+            if idx > 0 {
+                start +=
+                    container.child_ends().get(idx - 1).ok_or_else(invalid)?;
+            }
+            node = container.children().get(idx).ok_or_else(invalid)?;
+        }
+        Ok(start)
+    }
+
     pub(crate) fn document_handle(&self) -> DomHandle {
         self.document.handle()
     }
@@ -143,6 +191,46 @@ where
         }
     }
 
+    pub fn find_parent_table_cell_or_self(
+        &self,
+        child_handle: &DomHandle,
+    ) -> Option<DomHandle> {
+        if self.lookup_node(child_handle).is_table_cell() {
+            return Some(child_handle.clone());
+        }
+
+        if child_handle.has_parent() {
+            self.find_parent_table_cell_or_self(&child_handle.parent_handle())
+        } else {
+            None
+        }
+    }
+
+    /// Find the block that the node at `handle` is laid out in: the
+    /// closest list item or table cell containing it, or the document.
+    pub(crate) fn find_block_ancestor_or_self(
+        &self,
+        handle: &DomHandle,
+    ) -> DomHandle {
+        let mut handle = handle.clone();
+        loop {
+            if let DomNode::Container(n) = self.lookup_node(&handle) {
+                if matches!(
+                    n.kind(),
+                    ContainerNodeKind::Generic
+                        | ContainerNodeKind::ListItem
+                        | ContainerNodeKind::TableCell
+                ) {
+                    return handle;
+                }
+            }
+            if !handle.has_parent() {
+                return handle;
+            }
+            handle = handle.parent_handle();
+        }
+    }
+
     pub(crate) fn find_closest_list_ancestor(
         &self,
         handle: &DomHandle,
@@ -160,85 +248,87 @@ where
     }
 
     /// Find the node based on its handle.
-    /// Panics if the handle is unset or invalid
+    /// Panics if the handle is unset or invalid. Use try_lookup_node() if
+    /// the handle may be stale.
     pub fn lookup_node(&self, node_handle: &DomHandle) -> &DomNode<S> {
-        fn nth_child<S>(element: &ContainerNode<S>, idx: usize) -> &DomNode<S>
-        where
-            S: UnicodeString,
-        {
-            element.children().get(idx).unwrap_or_else(|| {
-                panic!(
-                    "Handle is invalid: it refers to a child index ({}) which \
-                is too large for the number of children in this node ({:?}).",
-                    idx, element
-                )
-            })
-        }
+        self.try_lookup_node(node_handle)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        let mut node = &self.document;
+    /// Find the node based on its handle and returns a mutable reference.
+    /// Panics if the handle is invalid or unset. Use try_lookup_node_mut()
+    /// if the handle may be stale.
+    pub fn lookup_node_mut(
+        &mut self,
+        node_handle: &DomHandle,
+    ) -> &mut DomNode<S> {
+        self.try_lookup_node_mut(node_handle)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Find the node based on its handle, or return an error if the handle
+    /// is unset or doesn't point at a node in this Dom.
+    pub fn try_lookup_node(
+        &self,
+        node_handle: &DomHandle,
+    ) -> Result<&DomNode<S>, DomError> {
         if !node_handle.is_set() {
-            panic!(
-                "Attempting to lookup a node using an unset DomHandle ({:?})",
-                node_handle.raw()
-            );
+            return Err(DomError::InvalidHandle(node_handle.clone()));
         }
+        let mut node = &self.document;
         for idx in node_handle.raw() {
             node = match node {
-                DomNode::Container(n) => nth_child(n, *idx),
-                DomNode::LineBreak(_) => panic!(
-                    "Handle is invalid: refers to the child of a line break, \
-                    but line breaks cannot have children."
-                ),
-                DomNode::Text(_) => panic!(
-                    "Handle {:?} is invalid: refers to the child of a text node, \
-                    but text nodes cannot have children.", node_handle
-                ),
+                DomNode::Container(n) => n.children().get(*idx),
+                _ => None,
             }
+            .ok_or_else(|| DomError::InvalidHandle(node_handle.clone()))?;
         }
-        node
+        Ok(node)
     }
 
-    /// Find the node based on its handle and returns a mutable reference.
-    /// Panics if the handle is invalid or unset
-    pub fn lookup_node_mut(
+    /// Find the node based on its handle and return a mutable reference,
+    /// or return an error if the handle is unset or doesn't point at a node
+    /// in this Dom.
+    pub fn try_lookup_node_mut(
         &mut self,
         node_handle: &DomHandle,
-    ) -> &mut DomNode<S> {
-        fn nth_child<S>(
-            element: &mut ContainerNode<S>,
-            idx: usize,
-        ) -> &mut DomNode<S>
-        where
-            S: UnicodeString,
-        {
-            element.get_child_mut(idx).expect(
-                "Handle is invalid: it refers to a child index which is too \
-                large for the number of children in this node.",
-            )
-        }
-
-        let mut node = &mut self.document;
+    ) -> Result<&mut DomNode<S>, DomError> {
         if !node_handle.is_set() {
-            panic!(
-                "Attempting to lookup a node using an unset DomHandle ({:?})",
-                node_handle.raw()
-            );
+            return Err(DomError::InvalidHandle(node_handle.clone()));
         }
+        let mut node = &mut self.document;
         for idx in node_handle.raw() {
             node = match node {
-                DomNode::Container(n) => nth_child(n, *idx),
-                DomNode::LineBreak(_) => panic!(
-                    "Handle is invalid: refers to the child of a line break, \
-                    but line breaks cannot have children."
-                ),
-                DomNode::Text(_) => panic!(
-                    "Handle is invalid: refers to the child of a text node, \
-                    but text nodes cannot have children."
-                ),
+                DomNode::Container(n) => n.get_child_mut(*idx),
+                _ => None,
             }
+            .ok_or_else(|| DomError::InvalidHandle(node_handle.clone()))?;
         }
+        Ok(node)
+    }
 
-        node
+    /// Find the container node based on its handle, or return an error if
+    /// there is no node there or it isn't a container.
+    pub fn try_lookup_container(
+        &self,
+        node_handle: &DomHandle,
+    ) -> Result<&ContainerNode<S>, DomError> {
+        match self.try_lookup_node(node_handle)? {
+            DomNode::Container(container) => Ok(container),
+            _ => Err(DomError::NotAContainer(node_handle.clone())),
+        }
+    }
+
+    /// Find the container node based on its handle and return a mutable
+    /// reference, or return an error as try_lookup_container() does.
+    pub fn try_lookup_container_mut(
+        &mut self,
+        node_handle: &DomHandle,
+    ) -> Result<&mut ContainerNode<S>, DomError> {
+        match self.try_lookup_node_mut(node_handle)? {
+            DomNode::Container(container) => Ok(container),
+            _ => Err(DomError::NotAContainer(node_handle.clone())),
+        }
     }
 
     /// Return the number of code points in the string representation of this
@@ -258,6 +348,17 @@ where
         handle: &DomHandle,
         offset: usize,
         new_node: DomNode<S>,
+    ) {
+        self.insert_nodes_into_text(handle, offset, vec![new_node]);
+    }
+
+    /// Insert `new_nodes` one after another at `offset` in the leaf node at
+    /// `handle`, splitting it if needed.
+    pub fn insert_nodes_into_text(
+        &mut self,
+        handle: &DomHandle,
+        offset: usize,
+        new_nodes: Vec<DomNode<S>>,
     ) {
         enum Where {
             Before,
@@ -269,7 +370,7 @@ where
             DomNode::Container(_) => {
                 panic!("Can't insert into a non-text node!")
             }
-            DomNode::LineBreak(_) => {
+            DomNode::LineBreak(_) | DomNode::Mention(_) | DomNode::Image(_) => {
                 if offset == 0 {
                     Where::Before
                 } else if offset == 1 {
@@ -293,13 +394,10 @@ where
             }
         };
 
-        match wh {
-            Where::Before => {
-                self.parent_mut(handle)
-                    .insert_child(handle.index_in_parent(), new_node);
-            }
+        let index = match wh {
+            Where::Before => handle.index_in_parent(),
             Where::During => {
-                // Splice new_node in between this text node and a new one
+                // Splice new_nodes in between this text node and a new one
                 let old_node = self.lookup_node_mut(handle);
                 if let DomNode::Text(old_text_node) = old_node {
                     let data = old_text_node.data();
@@ -307,20 +405,21 @@ where
                     let after_text = data[offset..].to_owned();
                     old_text_node.set_data(before_text);
                     let new_text_node = DomNode::new_text(after_text);
-                    let parent = self.parent_mut(handle);
-                    parent.insert_child(handle.index_in_parent() + 1, new_node);
-                    parent.insert_child(
-                        handle.index_in_parent() + 2,
+                    self.parent_mut(handle).insert_child(
+                        handle.index_in_parent() + 1,
                         new_text_node,
                     );
                 } else {
                     panic!("Can't insert in the middle of non-text node!");
                 }
+                handle.index_in_parent() + 1
             }
-            Where::After => {
-                self.parent_mut(handle)
-                    .insert_child(handle.index_in_parent() + 1, new_node);
-            }
+            Where::After => handle.index_in_parent() + 1,
+        };
+
+        let parent = self.parent_mut(handle);
+        for (i, new_node) in new_nodes.into_iter().enumerate() {
+            parent.insert_child(index + i, new_node);
         }
     }
 
@@ -331,12 +430,8 @@ where
     /// * the parent is not a container node
     /// * the handle is invalid
     pub fn parent_mut(&mut self, handle: &DomHandle) -> &mut ContainerNode<S> {
-        let parent = self.lookup_node_mut(&handle.parent_handle());
-        if let DomNode::Container(parent) = parent {
-            parent
-        } else {
-            panic!("Parent node was not a container!");
-        }
+        self.try_parent_mut(handle)
+            .unwrap_or_else(|error| panic!("{}", error))
     }
 
     /// Look up the parent node of the node pointed to by this handle.
@@ -345,12 +440,32 @@ where
     /// * the parent is not a container node
     /// * the handle is invalid
     pub fn parent(&self, handle: &DomHandle) -> &ContainerNode<S> {
-        let parent = self.lookup_node(&handle.parent_handle());
-        if let DomNode::Container(parent) = parent {
-            parent
-        } else {
-            panic!("Parent node was not a container!");
+        self.try_parent(handle)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Look up the parent node of the node pointed to by this handle, or
+    /// return an error if it has none, e.g. because the handle is stale.
+    pub fn try_parent(
+        &self,
+        handle: &DomHandle,
+    ) -> Result<&ContainerNode<S>, DomError> {
+        if !handle.is_set() || !handle.has_parent() {
+            return Err(DomError::InvalidHandle(handle.clone()));
         }
+        self.try_lookup_container(&handle.parent_handle())
+    }
+
+    /// Look up the parent node of the node pointed to by this handle and
+    /// return a mutable reference, or return an error as try_parent() does.
+    pub fn try_parent_mut(
+        &mut self,
+        handle: &DomHandle,
+    ) -> Result<&mut ContainerNode<S>, DomError> {
+        if !handle.is_set() || !handle.has_parent() {
+            return Err(DomError::InvalidHandle(handle.clone()));
+        }
+        self.try_lookup_container_mut(&handle.parent_handle())
     }
 }
 
@@ -449,6 +564,67 @@ mod test {
         assert_eq!(dom.to_string(), "a<b>b</b>");
     }
 
+    #[test]
+    fn looking_up_an_invalid_handle_returns_an_error() {
+        let dom = dom(&[tn("a"), b(&[tn("b")])]);
+        let missing = DomHandle::from_raw(vec![2]);
+        let inside_text = DomHandle::from_raw(vec![0, 0]);
+        assert_eq!(
+            dom.try_lookup_node(&missing),
+            Err(DomError::InvalidHandle(missing))
+        );
+        assert_eq!(
+            dom.try_lookup_node(&inside_text),
+            Err(DomError::InvalidHandle(inside_text))
+        );
+        assert_eq!(
+            dom.try_lookup_node(&DomHandle::new_unset()),
+            Err(DomError::InvalidHandle(DomHandle::new_unset()))
+        );
+    }
+
+    #[test]
+    fn looking_up_a_container_checks_the_node_kind() {
+        let dom = dom(&[tn("a"), b(&[tn("b")])]);
+        let text = DomHandle::from_raw(vec![0]);
+        assert!(dom
+            .try_lookup_container(&DomHandle::from_raw(vec![1]))
+            .is_ok());
+        assert_eq!(
+            dom.try_lookup_container(&text),
+            Err(DomError::NotAContainer(text))
+        );
+    }
+
+    #[test]
+    fn mutable_and_parent_lookups_of_stale_handles_return_errors() {
+        let mut dom = dom(&[tn("a"), b(&[tn("b")])]);
+        let missing = DomHandle::from_raw(vec![1, 3]);
+        let text = DomHandle::from_raw(vec![0]);
+        assert_eq!(
+            dom.try_lookup_node_mut(&missing).err(),
+            Some(DomError::InvalidHandle(missing.clone()))
+        );
+        assert_eq!(
+            dom.try_lookup_container_mut(&text).err(),
+            Some(DomError::NotAContainer(text))
+        );
+        assert_eq!(
+            dom.try_parent(&DomHandle::from_raw(Vec::new())).err(),
+            Some(DomError::InvalidHandle(DomHandle::from_raw(Vec::new())))
+        );
+        assert_eq!(
+            dom.try_parent_mut(&DomHandle::from_raw(vec![0, 1])).err(),
+            Some(DomError::NotAContainer(DomHandle::from_raw(vec![0])))
+        );
+        assert_eq!(
+            dom.try_parent(&DomHandle::from_raw(vec![1, 0]))
+                .unwrap()
+                .handle(),
+            DomHandle::from_raw(vec![1])
+        );
+    }
+
     #[test]
     fn can_find_toplevel_nodes_via_handles() {
         // Create a simple DOM
@@ -515,6 +691,36 @@ mod test {
         assert_eq!(dom.to_string(), "<b>f<i>o</i>o</b>");
     }
 
+    #[test]
+    fn text_in_returns_the_plain_text_between_positions() {
+        let dom = cm("a<b>bc</b><br />d|").state.dom;
+        assert_eq!(dom.text_in(1, 5), utf16("bc\nd"));
+        assert_eq!(dom.text_in(0, 0), utf16(""));
+    }
+
+    #[test]
+    fn remove_range_joins_formatting_either_side() {
+        let mut dom = cm("<b>ab</b>cd<b>ef</b>|").state.dom;
+        dom.remove_range(1, 5);
+        assert_eq!(dom.to_html(), "<b>af</b>");
+    }
+
+    #[test]
+    fn remove_range_joins_list_items() {
+        let mut dom = cm("<ol><li>ab</li><li>cd</li></ol>|").state.dom;
+        dom.remove_range(1, 3);
+        assert_eq!(dom.to_html(), "<ol><li>ad</li></ol>");
+    }
+
+    #[test]
+    fn remove_range_clamps_to_the_document() {
+        let mut dom = cm("a<br />bc|").state.dom;
+        dom.remove_range(1, 10);
+        assert_eq!(dom.to_html(), "a");
+        dom.remove_range(1, 1);
+        assert_eq!(dom.to_html(), "a");
+    }
+
     #[test]
     fn can_remove_node_and_keep_children() {
         let mut dom = dom(&[b(&[tn("foo"), i(&[tn("bar")])])]);
@@ -666,7 +872,9 @@ mod test {
     fn kids(node: &DomNode<Utf16String>) -> &Vec<DomNode<Utf16String>> {
         match node {
             DomNode::Container(n) => n.children(),
-            DomNode::LineBreak(_) => NO_CHILDREN,
+            DomNode::LineBreak(_) | DomNode::Mention(_) | DomNode::Image(_) => {
+                NO_CHILDREN
+            }
             DomNode::Text(_) => {
                 panic!("We expected an Element, but found Text")
             }
@@ -12,12 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dom::nodes::{ContainerNode, DomNode, LineBreakNode, TextNode};
+use crate::dom::nodes::DomNode;
+#[cfg(test)]
+use crate::dom::nodes::{ContainerNode, LineBreakNode, TextNode};
 use crate::dom::range::DomLocation;
+#[cfg(test)]
 use crate::dom::unicode_string::UnicodeStrExt;
-use crate::dom::{Dom, DomHandle, FindResult, Range};
+#[cfg(test)]
+use crate::dom::FindResult;
+use crate::dom::{Dom, DomHandle, Range};
 use crate::UnicodeString;
+use once_cell::sync::OnceCell;
 use std::cmp::{max, min};
+use std::fmt;
 
 pub fn find_range<S>(dom: &Dom<S>, start: usize, end: usize) -> Range
 where
@@ -36,25 +43,170 @@ where
         (start, end)
     };
 
-    // TODO: is there really a difference between find_pos and find_range?
-    let result = find_pos(dom, &dom.document_handle(), s, e);
-    match result {
-        FindResult::Found(locations) => {
-            let locations: Vec<DomLocation> = if is_reversed {
-                locations
-                    .iter()
-                    .map(|location| location.reversed())
-                    .collect()
-            } else {
-                locations
-            };
-            Range::new(&locations)
+    let mut locations = Vec::new();
+    find_in_node(
+        dom.lookup_node(&dom.document_handle()),
+        0,
+        s,
+        e,
+        &mut locations,
+    );
+    let locations: Vec<DomLocation> = if is_reversed {
+        locations
+            .iter()
+            .map(|location| location.reversed())
+            .collect()
+    } else {
+        locations
+    };
+    Range::new(&locations)
+}
+
+/// Add the locations of `node`, which starts at `node_start`, and of the
+/// nodes inside it between `start` and `end` to `locations`, each node
+/// after its descendants. The children of a container that can touch the
+/// range are found by binary search over its [ChildPositions], so only the
+/// nodes along the edges of the range are visited besides those inside it.
+/// The ends are sorted, so searching them finds a child as quickly as a map
+/// from positions to nodes would, and unlike a map for the whole document
+/// they stay right for every container that an edit doesn't go through.
+fn find_in_node<S>(
+    node: &DomNode<S>,
+    node_start: usize,
+    start: usize,
+    end: usize,
+    locations: &mut Vec<DomLocation>,
+) where
+    S: UnicodeString,
+{
+    let handle = node.handle();
+    let location = match node {
+        DomNode::Container(container) => {
+            let ends = container.child_ends();
+            // Children that end before the range can't touch it
+            let first = ends.partition_point(|&e| node_start + e < start);
+            for (i, child) in
+                container.children().iter().enumerate().skip(first)
+            {
+                let child_start =
+                    node_start + if i == 0 { 0 } else { ends[i - 1] };
+                if child_start > end {
+                    break;
+                }
+                find_in_node(child, child_start, start, end, locations);
+            }
+            let node_end = node_start + ends.last().copied().unwrap_or(0);
+            container_location(handle, node_start, node_end, start, end)
+        }
+        DomNode::Text(_) | DomNode::LineBreak(_) => {
+            textlike_location(handle, node_start, node.text_len(), start, end)
         }
-        FindResult::NotFound => Range::new(Vec::new()),
+        DomNode::Mention(_) | DomNode::Image(_) => {
+            let len = node.text_len();
+            textlike_location(handle, node_start, len, start, end)
+                .map(|location| widen_atomic_location(location, len))
+        }
+    };
+    if let Some(location) = location {
+        locations.push(location);
     }
 }
 
-/// Find a particular character range in the DOM
+/// Where each child of a container ends, counted from the start of the
+/// container. The container tells it about every change to its children:
+/// when a child is inserted or removed the ends after it are moved along by
+/// its length, and when a child is handed out to be changed the ends before
+/// it are kept and only the rest are worked out again when next needed.
+/// Every change deep in a document goes through each container above it,
+/// so only the containers along the path of an edit have any work to do.
+#[derive(Clone, Default)]
+pub(crate) struct ChildPositions {
+    /// The end of every child, if known
+    ends: OnceCell<Vec<usize>>,
+    /// The ends of the children before the first one that may have changed,
+    /// used when `ends` is not known
+    kept: Vec<usize>,
+}
+
+impl ChildPositions {
+    pub(crate) fn ends<S>(&self, children: &[DomNode<S>]) -> &[usize]
+    where
+        S: UnicodeString,
+    {
+        self.ends.get_or_init(|| {
+            let mut ends = Vec::with_capacity(children.len());
+            ends.extend_from_slice(&self.kept);
+            let mut end = ends.last().copied().unwrap_or(0);
+            for child in &children[ends.len()..] {
+                end += child.text_len();
+                ends.push(end);
+            }
+            ends
+        })
+    }
+
+    /// `child` is about to be inserted at `index`
+    pub(crate) fn inserted<S>(&mut self, index: usize, child: &DomNode<S>)
+    where
+        S: UnicodeString,
+    {
+        if let Some(ends) = self.ends.get_mut() {
+            let len = child.text_len();
+            let start = if index == 0 { 0 } else { ends[index - 1] };
+            ends.insert(index, start);
+            for end in &mut ends[index..] {
+                *end += len;
+            }
+        } else {
+            self.changed_from(index);
+        }
+    }
+
+    /// `child`, at `index`, is about to be removed
+    pub(crate) fn removed<S>(&mut self, index: usize, child: &DomNode<S>)
+    where
+        S: UnicodeString,
+    {
+        if let Some(ends) = self.ends.get_mut() {
+            let len = child.text_len();
+            ends.remove(index);
+            for end in &mut ends[index..] {
+                *end -= len;
+            }
+        } else {
+            self.changed_from(index);
+        }
+    }
+
+    /// The child at `index`, and any after it, may change length
+    pub(crate) fn changed_from(&mut self, index: usize) {
+        let mut ends = self
+            .ends
+            .take()
+            .unwrap_or_else(|| std::mem::take(&mut self.kept));
+        ends.truncate(index);
+        self.kept = ends;
+    }
+}
+
+/// The positions don't affect what a container holds, so they are ignored
+/// when comparing containers.
+impl PartialEq for ChildPositions {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for ChildPositions {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ChildPositions")
+    }
+}
+
+/// Find a particular character range in the DOM by walking the whole tree.
+/// This is how [find_range] worked before containers kept their
+/// [ChildPositions], and is kept to check them against.
+#[cfg(test)]
 pub fn find_pos<S>(
     dom: &Dom<S>,
     node_handle: &DomHandle,
@@ -74,6 +226,7 @@ where
     }
 }
 
+#[cfg(test)]
 fn do_find_pos<S>(
     dom: &Dom<S>,
     node_handle: &DomHandle,
@@ -102,6 +255,13 @@ where
                 locations.push(location);
             }
         }
+        DomNode::Mention(_) | DomNode::Image(_) => {
+            if let Some(location) =
+                process_atomic_inline_node(node, start, end, offset)
+            {
+                locations.push(location);
+            }
+        }
         DomNode::Container(n) => {
             locations
                 .extend(process_container_node(dom, n, start, end, offset));
@@ -110,6 +270,7 @@ where
     locations
 }
 
+#[cfg(test)]
 fn process_container_node<S>(
     dom: &Dom<S>,
     node: &ContainerNode<S>,
@@ -131,27 +292,39 @@ where
         }
     }
     // If container node is completely selected, include it
-    let container_end = *offset;
-    let container_node_len = container_end - container_start;
-    // We never want to return the root node
-    if container_end >= start
-        && container_start <= end
-        && !node.handle().is_root()
+    if let Some(location) =
+        container_location(node.handle(), container_start, *offset, start, end)
     {
+        results.push(location);
+    }
+    results
+}
+
+fn container_location(
+    handle: DomHandle,
+    container_start: usize,
+    container_end: usize,
+    start: usize,
+    end: usize,
+) -> Option<DomLocation> {
+    // We never want to return the root node
+    if container_end >= start && container_start <= end && !handle.is_root() {
         let start_offset = max(start, container_start) - container_start;
         let end_offset = min(end, container_end) - container_start;
-        results.push(DomLocation {
-            node_handle: node.handle(),
+        Some(DomLocation {
+            node_handle: handle,
             position: container_start,
             start_offset,
             end_offset,
-            length: container_node_len,
+            length: container_end - container_start,
             is_leaf: false,
         })
+    } else {
+        None
     }
-    results
 }
 
+#[cfg(test)]
 fn process_text_node<S>(
     node: &TextNode<S>,
     start: usize,
@@ -164,6 +337,7 @@ where
     process_textlike_node(node.handle(), node.data().len(), start, end, offset)
 }
 
+#[cfg(test)]
 fn process_line_break_node<S>(
     node: &LineBreakNode<S>,
     start: usize,
@@ -177,6 +351,34 @@ where
     process_textlike_node(node.handle(), 1, start, end, offset)
 }
 
+#[cfg(test)]
+fn process_atomic_inline_node<S>(
+    node: &DomNode<S>,
+    start: usize,
+    end: usize,
+    offset: &mut usize,
+) -> Option<DomLocation>
+where
+    S: UnicodeString,
+{
+    // Atomic nodes are like text nodes that can never be split, so a range
+    // that reaches inside one is widened to cover the whole node.
+    let len = node.text_len();
+    process_textlike_node(node.handle(), len, start, end, offset)
+        .map(|location| widen_atomic_location(location, len))
+}
+
+fn widen_atomic_location(mut location: DomLocation, len: usize) -> DomLocation {
+    if location.start_offset < len {
+        location.start_offset = 0;
+    }
+    if location.end_offset > 0 {
+        location.end_offset = len;
+    }
+    location
+}
+
+#[cfg(test)]
 fn process_textlike_node(
     handle: DomHandle,
     node_len: usize,
@@ -185,11 +387,21 @@ fn process_textlike_node(
     offset: &mut usize,
 ) -> Option<DomLocation> {
     let node_start = *offset;
-    let node_end = node_start + node_len;
 
     // Increase offset to keep track of the current position
     *offset += node_len;
 
+    textlike_location(handle, node_start, node_len, start, end)
+}
+
+fn textlike_location(
+    handle: DomHandle,
+    node_start: usize,
+    node_len: usize,
+    start: usize,
+    end: usize,
+) -> Option<DomLocation> {
+    let node_end = node_start + node_len;
     let outside_selection_range = start > node_end || end < node_start;
     let is_cursor = start == end;
 
@@ -479,4 +691,117 @@ mod test {
         assert_eq!(utf16("<b>ing <i>a </i></b>"), html_of_ranges[2]);
         assert_eq!(utf16("new feature"), html_of_ranges[3]);
     }
+
+    #[test]
+    fn child_positions_find_the_same_nodes_as_walking_the_tree() {
+        let models = [
+            cm("|"),
+            cm("abc|"),
+            cm("a<b>b<i>c</i></b>d|"),
+            cm("<ol><li>a|</li><li><b>b</b>c</li></ol>"),
+            cm("a<br />b<br />|"),
+            cm("<blockquote><p>a|</p><p></p></blockquote><pre>b</pre>"),
+            cm("<a href=\"https://matrix.org\">link|</a> text"),
+            cm("a<a href=\"https://matrix.to/#/@alice:matrix.org\" \
+                contenteditable=\"false\">Alice</a>b|"),
+            cm("<img src=\"mxc://matrix.org/a\" />|"),
+        ];
+        for model in models {
+            let d = model.state.dom;
+            let len = d.text_len();
+            for start in 0..=len + 1 {
+                for end in start..=len + 1 {
+                    let expected =
+                        match find_pos(&d, &d.document_handle(), start, end) {
+                            FindResult::Found(locations) => locations,
+                            FindResult::NotFound => Vec::new(),
+                        };
+                    // Walking the tree stops once it passes the range, so
+                    // containers it leaves early are reported shorter than
+                    // they are. Their positions always give the full length.
+                    let expected: Vec<DomLocation> = expected
+                        .into_iter()
+                        .map(|mut location| {
+                            location.length =
+                                d.lookup_node(&location.node_handle).text_len();
+                            location
+                        })
+                        .collect();
+                    assert_eq!(
+                        d.find_range(start, end).locations,
+                        expected,
+                        "{} ({}, {})",
+                        d.to_html(),
+                        start,
+                        end
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn child_positions_are_updated_after_the_document_changes() {
+        let mut d = cm("abc|").state.dom;
+        assert_eq!(d.find_range(4, 4).locations, Vec::new());
+        d.append_child(DomNode::new_text(utf16("def")));
+        assert_eq!(ranges_to_html(&d, &d.find_range(4, 4)), [utf16("def")]);
+    }
+
+    #[test]
+    fn child_positions_are_updated_after_a_change_deep_in_the_document() {
+        let mut d = cm("<ol><li><b>abc|</b></li></ol>d").state.dom;
+        assert_eq!(ranges_to_html(&d, &d.find_range(4, 4)), [utf16("d")]);
+        let text = DomHandle::from_raw(vec![0, 0, 0, 0]);
+        if let DomNode::Text(text) = d.lookup_node_mut(&text) {
+            text.set_data(utf16("abcdef"));
+        }
+        assert_eq!(ranges_to_html(&d, &d.find_range(5, 5)).len(), 4);
+        assert_eq!(ranges_to_html(&d, &d.find_range(7, 7)), [utf16("d")]);
+    }
+
+    #[test]
+    fn child_positions_are_kept_in_step_with_each_kind_of_change() {
+        let mut d = cm("a<b>bc</b>def|").state.dom;
+        let doc = d.document_mut();
+        fn check(doc: &ContainerNode<Utf16String>) {
+            let mut end = 0;
+            let expected: Vec<usize> = doc
+                .children()
+                .iter()
+                .map(|child| {
+                    end += child.text_len();
+                    end
+                })
+                .collect();
+            assert_eq!(doc.child_ends(), expected);
+        }
+        check(doc);
+        doc.insert_child(1, DomNode::new_text(utf16("gh")));
+        check(doc);
+        doc.remove_child(0);
+        check(doc);
+        doc.replace_child(1, vec![tn("i"), tn("jklm")]);
+        check(doc);
+        doc.append_child(tn("n"));
+        check(doc);
+        if let Some(DomNode::Text(text)) = doc.get_child_mut(2) {
+            text.set_data(utf16(""));
+        }
+        check(doc);
+        doc.insert_child(0, tn("op"));
+        check(doc);
+        if let Some(DomNode::Text(text)) = doc.last_child_mut() {
+            text.set_data(utf16("qrs"));
+        }
+        check(doc);
+        doc.replace_children(vec![tn("t")]);
+        check(doc);
+    }
+
+    #[test]
+    fn doms_can_be_shared_between_threads() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Dom<Utf16String>>();
+    }
 }
@@ -0,0 +1,154 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The inline formatting that applies to a node, worked out from the
+//! containers around it.
+
+use std::collections::HashMap;
+
+use crate::dom::nodes::{ContainerNodeKind, DomNode};
+use crate::dom::{Dom, DomHandle, Range, UnicodeString};
+use crate::InlineFormatType;
+
+/// The inline formats and link that apply to a node: those of the
+/// containers it is inside, and its own if it is one of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormattingContext<S>
+where
+    S: UnicodeString,
+{
+    /// Outermost first. A format appears more than once if it is nested
+    /// inside itself.
+    pub formats: Vec<InlineFormatType>,
+    /// The URL of the innermost link around the node
+    pub link: Option<S>,
+}
+
+impl<S> FormattingContext<S>
+where
+    S: UnicodeString,
+{
+    pub fn has_format(&self, format: &InlineFormatType) -> bool {
+        self.formats.contains(format)
+    }
+
+    /// This context, plus whatever `node` adds to it
+    fn inside(&self, node: &DomNode<S>) -> Self {
+        let mut context = self.clone();
+        if let DomNode::Container(container) = node {
+            match container.kind() {
+                ContainerNodeKind::Formatting(format) => {
+                    context.formats.push(format.clone())
+                }
+                ContainerNodeKind::Link(url) => {
+                    context.link = Some(url.clone())
+                }
+                _ => {}
+            }
+        }
+        context
+    }
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// The formatting that applies to the node at `handle`
+    pub fn formatting_context(
+        &self,
+        handle: &DomHandle,
+    ) -> FormattingContext<S> {
+        let parent_context = if handle.has_parent() {
+            self.formatting_context(&handle.parent_handle())
+        } else {
+            FormattingContext::default()
+        };
+        parent_context.inside(self.lookup_node(handle))
+    }
+
+    /// The formatting of every node in `range`, by handle. Each node's
+    /// context is built from its parent's, so the containers around the
+    /// range are only looked at once, however many locations are in it.
+    pub fn formatting_contexts(
+        &self,
+        range: &Range,
+    ) -> HashMap<DomHandle, FormattingContext<S>> {
+        let mut handles: Vec<&DomHandle> =
+            range.locations.iter().map(|loc| &loc.node_handle).collect();
+        handles.sort_by_key(|handle| handle.raw().len());
+
+        let mut contexts: HashMap<DomHandle, FormattingContext<S>> =
+            HashMap::new();
+        for handle in handles {
+            let context = if handle.has_parent() {
+                let parent = handle.parent_handle();
+                match contexts.get(&parent) {
+                    Some(parent_context) => {
+                        parent_context.inside(self.lookup_node(handle))
+                    }
+                    None => self.formatting_context(handle),
+                }
+            } else {
+                FormattingContext::default().inside(self.lookup_node(handle))
+            };
+            contexts.insert(handle.clone(), context);
+        }
+        contexts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::tests::testutils_composer_model::cm;
+    use crate::tests::testutils_conversion::utf16;
+
+    use super::*;
+
+    #[test]
+    fn context_lists_formats_outermost_first_and_the_link() {
+        let model = cm("<em>a<a href=\"https://x.org\"><b>b|</b></a></em>");
+        let context: FormattingContext<Utf16String> = model
+            .state
+            .dom
+            .formatting_context(&DomHandle::from_raw(vec![0, 1, 0, 0]));
+        assert_eq!(
+            context.formats,
+            vec![InlineFormatType::Italic, InlineFormatType::Bold]
+        );
+        assert_eq!(context.link, Some(utf16("https://x.org")));
+        assert!(context.has_format(&InlineFormatType::Bold));
+        assert!(!context.has_format(&InlineFormatType::Underline));
+    }
+
+    #[test]
+    fn contexts_for_a_range_match_looking_each_one_up() {
+        let model = cm("a{b<b>c<i>d</i></b><u>e}|</u>");
+        let dom = &model.state.dom;
+        let range = dom.find_range(1, 5);
+        let contexts = dom.formatting_contexts(&range);
+        assert_eq!(contexts.len(), range.locations.len());
+        for location in range.locations.iter() {
+            let handle = &location.node_handle;
+            assert_eq!(contexts[handle], dom.formatting_context(handle));
+        }
+        let d = DomHandle::from_raw(vec![1, 1, 0]);
+        assert_eq!(
+            contexts[&d].formats,
+            vec![InlineFormatType::Bold, InlineFormatType::Italic]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::{Dom, UnicodeString};
+use crate::{GraphemeLocation, Location};
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// How many user-perceived characters come before `location`. A
+    /// location inside a character counts from its start.
+    pub fn grapheme_location(&self, location: Location) -> GraphemeLocation {
+        let target = usize::from(location);
+        let mut graphemes = 0;
+        for (start, len) in self.grapheme_spans() {
+            if start + len > target {
+                break;
+            }
+            graphemes += 1;
+        }
+        GraphemeLocation::from(graphemes)
+    }
+
+    /// The code unit location of the start of the user-perceived character
+    /// at `location`, or of the end of the content if it is past the end.
+    pub fn location_of_grapheme(&self, location: GraphemeLocation) -> Location {
+        let target = usize::from(location);
+        self.grapheme_spans()
+            .nth(target)
+            .map(|(start, _)| Location::from(start))
+            .unwrap_or_else(|| Location::from(self.text_len()))
+    }
+
+    /// The start and length in code units of each user-perceived character
+    /// of the content, in order
+    fn grapheme_spans(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut position = 0;
+        self.iter()
+            .filter(|(_, node)| !matches!(node, DomNode::Container(_)))
+            .flat_map(|(_, node)| match node {
+                DomNode::Text(text) => text
+                    .data()
+                    .to_string()
+                    .graphemes(true)
+                    .map(|g| S::from(g).len())
+                    .collect::<Vec<_>>(),
+                _ => vec![node.text_len()],
+            })
+            .map(move |len| {
+                let start = position;
+                position += len;
+                (start, len)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+    use crate::dom::Dom;
+    use crate::{GraphemeLocation, Location};
+
+    fn dom(html: &str) -> Dom<Utf16String> {
+        parse(html).unwrap()
+    }
+
+    fn graphemes(dom: &Dom<Utf16String>, location: usize) -> usize {
+        dom.grapheme_location(Location::from(location)).into()
+    }
+
+    fn code_units(dom: &Dom<Utf16String>, location: usize) -> usize {
+        dom.location_of_grapheme(GraphemeLocation::from(location))
+            .into()
+    }
+
+    #[test]
+    fn plain_text_counts_the_same() {
+        let d = dom("abc");
+        assert_eq!(graphemes(&d, 2), 2);
+        assert_eq!(code_units(&d, 2), 2);
+    }
+
+    #[test]
+    fn surrogate_pairs_and_combined_emoji_are_one_character() {
+        // 😀 is 2 code units, 👍🏽 is 4 and e followed by a combining
+        // accent is 2
+        let d = dom("a😀b👍🏽ce\u{301}d");
+        assert_eq!(graphemes(&d, 3), 2);
+        assert_eq!(graphemes(&d, 8), 4);
+        assert_eq!(graphemes(&d, 11), 6);
+        assert_eq!(code_units(&d, 2), 3);
+        assert_eq!(code_units(&d, 4), 8);
+        assert_eq!(code_units(&d, 6), 11);
+    }
+
+    #[test]
+    fn locations_inside_a_character_count_from_its_start() {
+        let d = dom("a😀b");
+        assert_eq!(graphemes(&d, 2), 1);
+    }
+
+    #[test]
+    fn line_breaks_mentions_and_formatting_are_counted_by_their_leaves() {
+        let d = dom("<b>😀</b><br /><a href=\"https://matrix.to/#/@u:x.org\" \
+            contenteditable=\"false\">user</a>😀");
+        assert_eq!(graphemes(&d, 6), 4);
+        assert_eq!(code_units(&d, 3), 4);
+    }
+
+    #[test]
+    fn locations_past_the_end_are_clamped() {
+        let d = dom("a😀");
+        assert_eq!(graphemes(&d, 10), 2);
+        assert_eq!(code_units(&d, 10), 3);
+    }
+}
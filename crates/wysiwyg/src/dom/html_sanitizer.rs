@@ -0,0 +1,601 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Restricting a [Dom] to the HTML a [SanitizePolicy] allows, by default
+//! what the Matrix client-server spec allows in the `formatted_body` of a
+//! message, see
+//! <https://spec.matrix.org/latest/client-server-api/#mroommessage-msgtypes>.
+
+use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode, ImageNode};
+use crate::dom::unicode_string::UnicodeStringExt;
+use crate::dom::{Dom, UnicodeString};
+
+/// The tags the Matrix spec allows
+const MATRIX_TAGS: [&str; 38] = [
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "code",
+    "del",
+    "details",
+    "div",
+    "em",
+    "font",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "span",
+    "strike",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "u",
+];
+
+/// The tags and attributes the Matrix spec allows on them
+const MATRIX_ATTRIBUTES: [(&str, &str); 18] = [
+    ("a", "href"),
+    ("a", "name"),
+    ("a", "target"),
+    ("code", "class"),
+    ("div", "data-mx-maths"),
+    ("font", "color"),
+    ("font", "data-mx-bg-color"),
+    ("font", "data-mx-color"),
+    ("img", "alt"),
+    ("img", "height"),
+    ("img", "src"),
+    ("img", "title"),
+    ("img", "width"),
+    ("ol", "start"),
+    ("span", "data-mx-bg-color"),
+    ("span", "data-mx-color"),
+    ("span", "data-mx-maths"),
+    ("span", "data-mx-spoiler"),
+];
+
+/// The URL schemes the Matrix spec allows links to point at
+const MATRIX_LINK_SCHEMES: [&str; 5] =
+    ["ftp", "http", "https", "magnet", "mailto"];
+
+/// The URL schemes the Matrix spec allows images to be loaded from
+const MATRIX_IMAGE_SCHEMES: [&str; 1] = ["mxc"];
+
+/// How deeply the Matrix spec allows tags to be nested
+pub const MATRIX_MAX_DEPTH: usize = 100;
+
+/// What to do with an element whose tag a [SanitizePolicy] doesn't allow
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnknownTagStrategy {
+    /// Replace the element by its content, which is sanitized in turn
+    KeepContent,
+    /// Drop the element along with its content
+    Drop,
+}
+
+/// An attribute a [SanitizePolicy] allows on elements with a given tag
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowedAttribute {
+    pub tag: String,
+    pub name: String,
+    /// If set, the attribute is only allowed if its value starts with this,
+    /// e.g. `language-` for the class of a code block
+    pub value_prefix: Option<String>,
+}
+
+impl AllowedAttribute {
+    pub fn new(tag: &str, name: &str) -> Self {
+        Self {
+            tag: tag.to_owned(),
+            name: name.to_owned(),
+            value_prefix: None,
+        }
+    }
+}
+
+/// Which HTML may be kept when it is loaded or pasted into the editor, see
+/// [Dom::sanitize]. Line breaks, text and the editor's own attributes of
+/// mentions are always kept.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SanitizePolicy {
+    /// The lowercase tags that may be kept
+    pub allowed_tags: Vec<String>,
+    /// The attributes that may be kept. Any others are removed.
+    pub allowed_attributes: Vec<AllowedAttribute>,
+    /// The URL schemes links may point at. Links pointing elsewhere are
+    /// replaced by their text.
+    pub link_schemes: Vec<String>,
+    /// The URL schemes images may be loaded from. Other images are dropped.
+    pub image_schemes: Vec<String>,
+    /// How many tags deep elements may be nested, if limited. Anything
+    /// deeper is dropped.
+    pub max_depth: Option<usize>,
+    /// What to do with elements whose tag isn't allowed
+    pub unknown_tags: UnknownTagStrategy,
+}
+
+impl SanitizePolicy {
+    /// The HTML the Matrix spec allows in a message: its tags, attributes
+    /// and URL schemes, nested at most [MATRIX_MAX_DEPTH] deep. Code may
+    /// only be given the class of the language it is written in.
+    pub fn matrix() -> Self {
+        let allowed_attributes = MATRIX_ATTRIBUTES
+            .iter()
+            .map(|(tag, name)| {
+                let mut attribute = AllowedAttribute::new(tag, name);
+                if *tag == "code" && *name == "class" {
+                    attribute.value_prefix = Some("language-".to_owned());
+                }
+                attribute
+            })
+            .collect();
+        Self {
+            allowed_tags: to_strings(&MATRIX_TAGS),
+            allowed_attributes,
+            link_schemes: to_strings(&MATRIX_LINK_SCHEMES),
+            image_schemes: to_strings(&MATRIX_IMAGE_SCHEMES),
+            max_depth: Some(MATRIX_MAX_DEPTH),
+            unknown_tags: UnknownTagStrategy::KeepContent,
+        }
+    }
+
+    fn allows_tag(&self, tag: &str) -> bool {
+        self.allowed_tags.iter().any(|allowed| allowed == tag)
+    }
+
+    fn allows_attribute(&self, tag: &str, name: &str, value: &str) -> bool {
+        self.allowed_attributes.iter().any(|allowed| {
+            allowed.tag == tag
+                && allowed.name == name
+                && allowed
+                    .value_prefix
+                    .as_ref()
+                    .map_or(true, |prefix| value.starts_with(prefix.as_str()))
+        })
+    }
+
+    fn is_too_deep(&self, depth: usize) -> bool {
+        self.max_depth.map_or(false, |max_depth| depth > max_depth)
+    }
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        Self::matrix()
+    }
+}
+
+fn to_strings(strs: &[&str]) -> Vec<String> {
+    strs.iter().map(|s| (*s).to_owned()).collect()
+}
+
+/// Whether `url` uses one of `schemes`
+fn has_scheme(url: &str, schemes: &[String]) -> bool {
+    match url.trim_start().split_once(':') {
+        Some((scheme, _)) => schemes
+            .iter()
+            .any(|allowed| scheme.eq_ignore_ascii_case(allowed)),
+        None => false,
+    }
+}
+
+/// An attribute removed from an element
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RemovedAttribute {
+    pub tag: String,
+    pub name: String,
+}
+
+/// What had to be removed to make a [Dom] follow a [SanitizePolicy], in
+/// document order. Nothing was removed if it is empty.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SanitizeReport {
+    /// Tags that aren't allowed, and images loaded from URLs that aren't
+    pub removed_tags: Vec<String>,
+    /// Attributes that aren't allowed on their element, including links to
+    /// URLs that aren't allowed, whose text is kept.
+    pub removed_attributes: Vec<RemovedAttribute>,
+    /// How many elements were dropped, along with their content, for being
+    /// nested too deeply.
+    pub removed_too_deep: usize,
+}
+
+impl SanitizeReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed_tags.is_empty()
+            && self.removed_attributes.is_empty()
+            && self.removed_too_deep == 0
+    }
+
+    fn remove_attribute(&mut self, tag: &str, name: &str) {
+        self.removed_attributes.push(RemovedAttribute {
+            tag: tag.to_owned(),
+            name: name.to_owned(),
+        });
+    }
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Remove the tags, attributes and URLs that `policy` doesn't allow,
+    /// and anything nested too deeply, and report what was removed.
+    pub fn sanitize(&mut self, policy: &SanitizePolicy) -> SanitizeReport {
+        let mut report = SanitizeReport::default();
+        sanitize_children(self.document_mut(), 1, policy, &mut report);
+        report
+    }
+
+    /// Remove what the Matrix spec doesn't allow in a message, see
+    /// [SanitizePolicy::matrix].
+    pub fn sanitize_for_matrix(&mut self) -> SanitizeReport {
+        self.sanitize(&SanitizePolicy::matrix())
+    }
+}
+
+/// Sanitize the children of `node`, which are `depth` tags deep
+fn sanitize_children<S>(
+    node: &mut ContainerNode<S>,
+    depth: usize,
+    policy: &SanitizePolicy,
+    report: &mut SanitizeReport,
+) where
+    S: UnicodeString,
+{
+    let mut new_children = Vec::new();
+    for child in node.replace_children(Vec::new()) {
+        for new_child in sanitize_node(child, depth, policy, report) {
+            push_joining_text(&mut new_children, new_child);
+        }
+    }
+    node.replace_children(new_children);
+}
+
+/// The nodes to put in place of `node`, which is `depth` tags deep
+fn sanitize_node<S>(
+    node: DomNode<S>,
+    depth: usize,
+    policy: &SanitizePolicy,
+    report: &mut SanitizeReport,
+) -> Vec<DomNode<S>>
+where
+    S: UnicodeString,
+{
+    let is_tag = !matches!(node, DomNode::Text(_));
+    if is_tag && policy.is_too_deep(depth) {
+        report.removed_too_deep += 1;
+        return Vec::new();
+    }
+    match node {
+        DomNode::Container(mut container) => {
+            let tag = container.name().to_string();
+            if !policy.allows_tag(&tag) {
+                report.removed_tags.push(tag);
+                return match policy.unknown_tags {
+                    UnknownTagStrategy::KeepContent => {
+                        sanitize_children(
+                            &mut container,
+                            depth,
+                            policy,
+                            report,
+                        );
+                        container.take_children()
+                    }
+                    UnknownTagStrategy::Drop => Vec::new(),
+                };
+            }
+            if let ContainerNodeKind::Link(url) = container.kind() {
+                if !has_scheme(&url.to_string(), &policy.link_schemes) {
+                    report.remove_attribute(&tag, "href");
+                    sanitize_children(&mut container, depth, policy, report);
+                    return container.take_children();
+                }
+            }
+            let removed: Vec<String> = container
+                .attributes()
+                .into_iter()
+                .flatten()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .filter(|(name, value)| {
+                    !policy.allows_attribute(&tag, name, value)
+                })
+                .map(|(name, _)| name)
+                .collect();
+            for name in removed {
+                container.remove_attribute(&name);
+                report.remove_attribute(&tag, &name);
+            }
+            sanitize_children(&mut container, depth + 1, policy, report);
+            vec![DomNode::Container(container)]
+        }
+        DomNode::Image(image) => {
+            let src = image.src().to_string();
+            if !policy.allows_tag("img")
+                || !has_scheme(&src, &policy.image_schemes)
+            {
+                report.removed_tags.push("img".to_owned());
+                return Vec::new();
+            }
+            let (kept, removed): (Vec<_>, Vec<_>) =
+                image.attributes().iter().cloned().partition(|(n, v)| {
+                    policy.allows_attribute(
+                        "img",
+                        &n.to_string(),
+                        &v.to_string(),
+                    )
+                });
+            if removed.is_empty() {
+                return vec![DomNode::Image(image)];
+            }
+            for (name, _) in removed {
+                report.remove_attribute("img", &name.to_string());
+            }
+            vec![DomNode::Image(ImageNode::new(kept))]
+        }
+        DomNode::Mention(mention) => {
+            // A mention's href and contenteditable belong to the editor, but
+            // the attributes it was given when parsed are checked like any
+            // link's
+            if mention.attributes().is_empty() {
+                return vec![DomNode::Mention(mention)];
+            }
+            let (kept, removed): (Vec<_>, Vec<_>) =
+                mention.attributes().iter().cloned().partition(|(n, v)| {
+                    policy.allows_attribute("a", &n.to_string(), &v.to_string())
+                });
+            for (name, _) in removed {
+                report.remove_attribute("a", &name.to_string());
+            }
+            vec![DomNode::new_mention_with_attributes(
+                mention.url().clone(),
+                mention.display_text().clone(),
+                kept,
+            )]
+        }
+        DomNode::Text(_) | DomNode::LineBreak(_) => vec![node],
+    }
+}
+
+/// Add `node` to the end of `nodes`, joining it to the text before it if
+/// both are text, as they may be once a tag between them is removed
+fn push_joining_text<S>(nodes: &mut Vec<DomNode<S>>, node: DomNode<S>)
+where
+    S: UnicodeString,
+{
+    if let (Some(DomNode::Text(previous)), DomNode::Text(text)) =
+        (nodes.last_mut(), &node)
+    {
+        let mut data = previous.data().to_owned();
+        data.push(text.data());
+        previous.set_data(data);
+    } else {
+        nodes.push(node);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::html_sanitizer::{
+        AllowedAttribute, RemovedAttribute, SanitizePolicy, SanitizeReport,
+        UnknownTagStrategy, MATRIX_MAX_DEPTH,
+    };
+    use crate::dom::parser::parse;
+    use crate::ToHtml;
+
+    fn sanitized(html: &str) -> (String, SanitizeReport) {
+        sanitized_by(html, &SanitizePolicy::matrix())
+    }
+
+    fn sanitized_by(
+        html: &str,
+        policy: &SanitizePolicy,
+    ) -> (String, SanitizeReport) {
+        let mut dom = parse::<Utf16String>(html).unwrap();
+        let report = dom.sanitize(policy);
+        (dom.to_html().to_string(), report)
+    }
+
+    fn removed(tag: &str, name: &str) -> RemovedAttribute {
+        RemovedAttribute {
+            tag: tag.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+
+    #[test]
+    fn allowed_html_is_left_as_it_is() {
+        let html = "<p>a <strong>b</strong> <a href=\"https://matrix.org\" \
+            target=\"_blank\">c</a></p><ol start=\"3\"><li>d</li></ol>\
+            <span data-mx-spoiler=\"\">e</span>";
+        let (output, report) = sanitized(html);
+        assert_eq!(output, html);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn unknown_tags_are_replaced_by_their_content() {
+        let (output, report) = sanitized("a<section>b<q>c</q></section>d");
+        assert_eq!(output, "abcd");
+        assert_eq!(report.removed_tags, vec!["section", "q"]);
+    }
+
+    #[test]
+    fn attributes_that_are_not_allowed_are_removed() {
+        let (output, report) = sanitized(
+            "<span class=\"x\" data-mx-color=\"#ff0000\">a</span>\
+            <code class=\"language-rust\">b</code><code class=\"x\">c</code>",
+        );
+        assert_eq!(
+            output,
+            "<span data-mx-color=\"#ff0000\">a</span>\
+            <code class=\"language-rust\">b</code><code>c</code>"
+        );
+        assert_eq!(
+            report.removed_attributes,
+            vec![removed("span", "class"), removed("code", "class")]
+        );
+    }
+
+    #[test]
+    fn links_to_other_schemes_are_replaced_by_their_text() {
+        let (output, report) =
+            sanitized("<a href=\"ftp://x.org\">a</a><a href=\"tel:123\">b</a>");
+        assert_eq!(output, "<a href=\"ftp://x.org\">a</a>b");
+        assert_eq!(report.removed_attributes, vec![removed("a", "href")]);
+    }
+
+    #[test]
+    fn images_must_be_loaded_from_the_media_repository() {
+        let (output, report) = sanitized(
+            "<img src=\"mxc://x.org/a\" alt=\"a\" class=\"emoji\" />\
+            <img src=\"https://x.org/b\" alt=\"b\" />",
+        );
+        assert_eq!(output, "<img src=\"mxc://x.org/a\" alt=\"a\" />");
+        assert_eq!(report.removed_tags, vec!["img"]);
+        assert_eq!(report.removed_attributes, vec![removed("img", "class")]);
+    }
+
+    #[test]
+    fn attributes_of_mentions_are_removed() {
+        let (output, report) = sanitized(
+            "<a href=\"https://matrix.to/#/@u:x.org\" contenteditable=\"false\" \
+            data-mention-type=\"user\">u</a>",
+        );
+        assert_eq!(
+            output,
+            "<a href=\"https://matrix.to/#/@u:x.org\" \
+            contenteditable=\"false\">u</a>"
+        );
+        assert_eq!(
+            report.removed_attributes,
+            vec![removed("a", "data-mention-type")]
+        );
+    }
+
+    #[test]
+    fn tags_nested_too_deeply_are_dropped() {
+        let depth = MATRIX_MAX_DEPTH + 2;
+        let html = format!(
+            "{}a{}",
+            "<blockquote>".repeat(depth),
+            "</blockquote>".repeat(depth)
+        );
+        let (output, report) = sanitized(&html);
+        assert_eq!(
+            output,
+            format!(
+                "{}{}",
+                "<blockquote>".repeat(MATRIX_MAX_DEPTH),
+                "</blockquote>".repeat(MATRIX_MAX_DEPTH)
+            )
+        );
+        assert_eq!(report.removed_too_deep, 1);
+    }
+
+    #[test]
+    fn a_policy_can_allow_other_tags_and_attributes() {
+        let mut policy = SanitizePolicy::matrix();
+        policy.allowed_tags.push("section".to_owned());
+        policy
+            .allowed_attributes
+            .push(AllowedAttribute::new("span", "class"));
+        let (output, report) = sanitized_by(
+            "<section><span class=\"x\">a</span></section>",
+            &policy,
+        );
+        assert_eq!(output, "<section><span class=\"x\">a</span></section>");
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn a_policy_can_allow_attributes_with_some_values() {
+        let policy = SanitizePolicy {
+            allowed_tags: vec!["span".to_owned()],
+            allowed_attributes: vec![AllowedAttribute {
+                tag: "span".to_owned(),
+                name: "class".to_owned(),
+                value_prefix: Some("mx-".to_owned()),
+            }],
+            ..SanitizePolicy::matrix()
+        };
+        let (output, _) = sanitized_by(
+            "<span class=\"mx-a\">a</span><span class=\"b\">b</span>",
+            &policy,
+        );
+        assert_eq!(output, "<span class=\"mx-a\">a</span><span>b</span>");
+    }
+
+    #[test]
+    fn a_policy_can_drop_unknown_tags_with_their_content() {
+        let policy = SanitizePolicy {
+            unknown_tags: UnknownTagStrategy::Drop,
+            ..SanitizePolicy::matrix()
+        };
+        let (output, report) =
+            sanitized_by("a<section>b<q>c</q></section>d", &policy);
+        assert_eq!(output, "ad");
+        assert_eq!(report.removed_tags, vec!["section"]);
+    }
+
+    #[test]
+    fn a_policy_can_allow_other_url_schemes() {
+        let mut policy = SanitizePolicy::matrix();
+        policy.link_schemes.push("tel".to_owned());
+        policy.image_schemes.push("https".to_owned());
+        let html = "<a href=\"tel:123\">a</a><img src=\"https://x.org/b\" />";
+        let (output, report) = sanitized_by(html, &policy);
+        assert_eq!(output, html);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn a_policy_need_not_limit_depth() {
+        let policy = SanitizePolicy {
+            max_depth: None,
+            ..SanitizePolicy::matrix()
+        };
+        let depth = MATRIX_MAX_DEPTH + 2;
+        let html = format!(
+            "{}a{}",
+            "<blockquote>".repeat(depth),
+            "</blockquote>".repeat(depth)
+        );
+        let (output, report) = sanitized_by(&html, &policy);
+        assert_eq!(output, html);
+        assert!(report.is_empty());
+    }
+}
@@ -0,0 +1,399 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structural rules a well-formed [Dom] obeys. Checking them returns what
+//! is wrong rather than panicking, so callers can decide whether to log,
+//! repair or abort.
+//!
+//! With the `assert-invariants` feature, the composer checks its document
+//! before every update it sends, against the rules that hold between edits
+//! (see [Invariant::holds_between_edits]). Violations go to the sink set by
+//! [set_invariant_sink], or cause a panic if there is none.
+
+#[cfg(feature = "assert-invariants")]
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::dom::nodes::{ContainerNode, DomNode};
+use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
+use crate::dom::{Dom, DomHandle, ToTree, UnicodeString};
+
+/// The rule an [InvariantViolation] breaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invariant {
+    /// Two text nodes are next to each other and should have been joined.
+    NoAdjacentTextNodes,
+    /// A text node holds no text and should have been removed.
+    NoEmptyTextNodes,
+    /// A node's stored handle doesn't match where it sits in the tree.
+    HandlesMatchPositions,
+}
+
+impl Invariant {
+    /// Whether the composer keeps to this rule between edits. It leaves
+    /// empty and adjacent text nodes behind while editing, e.g. an empty
+    /// text node holds the cursor in front of a list, and formatting part
+    /// of a text node splits it, so those rules only hold once the document
+    /// has been tidied up, e.g. by [Dom::validate_and_fix].
+    pub fn holds_between_edits(&self) -> bool {
+        matches!(self, Invariant::HandlesMatchPositions)
+    }
+}
+
+/// One broken rule, and the node that breaks it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    /// Where the offending node actually is. For adjacent text nodes this
+    /// is the second of the pair.
+    pub handle: DomHandle,
+    pub invariant: Invariant,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rule = match self.invariant {
+            Invariant::NoAdjacentTextNodes => "follows another text node",
+            Invariant::NoEmptyTextNodes => "is an empty text node",
+            Invariant::HandlesMatchPositions => {
+                "has a handle that does not match its position"
+            }
+        };
+        write!(formatter, "Node at {:?} {}", self.handle.raw(), rule)
+    }
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// List every way this document breaks the [Invariant]s, in document
+    /// order. An empty list means the document is well-formed.
+    pub fn check_invariants(&self) -> Vec<InvariantViolation> {
+        let mut violations = Vec::new();
+        let root = self.document();
+        let root_handle = self.document_handle();
+        if root.handle() != root_handle {
+            violations.push(InvariantViolation {
+                handle: root_handle.clone(),
+                invariant: Invariant::HandlesMatchPositions,
+            });
+        }
+        check_children(root, &root_handle, &mut violations);
+        violations
+    }
+
+    /// Repair every broken [Invariant]: give each node the handle of where
+    /// it is, drop empty text nodes and join adjacent ones. Returns what was
+    /// wrong beforehand, as [Dom::check_invariants] would have reported it,
+    /// e.g. to log after parsing HTML from elsewhere.
+    pub fn validate_and_fix(&mut self) -> Vec<InvariantViolation> {
+        let violations = self.check_invariants();
+        if !violations.is_empty() {
+            let root_handle = self.document_handle();
+            let root = self.document_mut();
+            root.set_handle(root_handle);
+            fix_children(root);
+        }
+        violations
+    }
+
+    /// Panic with a description of every broken [Invariant], if there are
+    /// any.
+    pub fn assert_invariants(&self) {
+        let violations = self.check_invariants();
+        if !violations.is_empty() {
+            panic_with_violations(&violations, &self.to_tree().to_string());
+        }
+    }
+}
+
+fn panic_with_violations(violations: &[InvariantViolation], tree: &str) -> ! {
+    let descriptions: Vec<String> =
+        violations.iter().map(|v| v.to_string()).collect();
+    panic!(
+        "Dom invariants violated:\n{}\n{}",
+        descriptions.join("\n"),
+        tree
+    );
+}
+
+/// What is passed to the sink set by [set_invariant_sink] when a document
+/// breaks its invariants.
+#[cfg(feature = "assert-invariants")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantReport {
+    pub violations: Vec<InvariantViolation>,
+    /// The document as printed by [ToTree::to_tree]
+    pub tree: String,
+}
+
+#[cfg(feature = "assert-invariants")]
+type InvariantSink = Box<dyn Fn(InvariantReport)>;
+
+#[cfg(feature = "assert-invariants")]
+thread_local! {
+    static INVARIANT_SINK: RefCell<Option<InvariantSink>> = RefCell::new(None);
+}
+
+/// Send the violations found by the composer's checks to `sink` instead of
+/// panicking. Pass None to go back to panicking. The sink is used by the
+/// composers on the current thread only.
+#[cfg(feature = "assert-invariants")]
+pub fn set_invariant_sink(sink: Option<InvariantSink>) {
+    INVARIANT_SINK.with(|current| *current.borrow_mut() = sink);
+}
+
+/// Check `dom` against the rules that hold between edits, and pass what is
+/// wrong with it to the sink, if anything.
+#[cfg(feature = "assert-invariants")]
+pub(crate) fn report_violations<S: UnicodeString>(dom: &Dom<S>) {
+    let violations: Vec<InvariantViolation> = dom
+        .check_invariants()
+        .into_iter()
+        .filter(|violation| violation.invariant.holds_between_edits())
+        .collect();
+    if violations.is_empty() {
+        return;
+    }
+    let report = InvariantReport {
+        violations,
+        tree: dom.to_tree().to_string(),
+    };
+    INVARIANT_SINK.with(|sink| match sink.borrow().as_ref() {
+        Some(sink) => sink(report),
+        None => panic_with_violations(&report.violations, &report.tree),
+    });
+}
+
+/// Remove empty text nodes and join adjacent ones in `parent` and below.
+/// Handles must already be correct.
+fn fix_children<S: UnicodeString>(parent: &mut ContainerNode<S>) {
+    let mut index = 0;
+    while index < parent.children().len() {
+        let follows_text = index > 0
+            && matches!(parent.children()[index - 1], DomNode::Text(_));
+        match parent.get_child_mut(index) {
+            Some(DomNode::Container(container)) => fix_children(container),
+            Some(DomNode::Text(text)) if text.data().len() == 0 => {
+                parent.remove_child(index);
+                continue;
+            }
+            Some(DomNode::Text(_)) if follows_text => {
+                if let DomNode::Text(text) = parent.remove_child(index) {
+                    if let Some(DomNode::Text(previous)) =
+                        parent.get_child_mut(index - 1)
+                    {
+                        let mut data = previous.data().to_owned();
+                        data.push(text.data().to_owned());
+                        previous.set_data(data);
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+}
+
+fn check_children<S: UnicodeString>(
+    parent: &ContainerNode<S>,
+    parent_handle: &DomHandle,
+    violations: &mut Vec<InvariantViolation>,
+) {
+    let mut previous_was_text = false;
+    for (index, child) in parent.children().iter().enumerate() {
+        let handle = parent_handle.child_handle(index);
+        if !child.handle().is_set() || child.handle() != handle {
+            violations.push(InvariantViolation {
+                handle: handle.clone(),
+                invariant: Invariant::HandlesMatchPositions,
+            });
+        }
+        match child {
+            DomNode::Text(text) => {
+                if previous_was_text {
+                    violations.push(InvariantViolation {
+                        handle: handle.clone(),
+                        invariant: Invariant::NoAdjacentTextNodes,
+                    });
+                }
+                if text.data().len() == 0 {
+                    violations.push(InvariantViolation {
+                        handle: handle.clone(),
+                        invariant: Invariant::NoEmptyTextNodes,
+                    });
+                }
+            }
+            DomNode::Container(container) => {
+                check_children(container, &handle, violations);
+            }
+            _ => {}
+        }
+        previous_was_text = matches!(child, DomNode::Text(_));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dom::nodes::DomNode;
+    use crate::tests::testutils_composer_model::cm;
+    use crate::tests::testutils_dom::{b, dom, tn};
+    use crate::ToHtml;
+
+    use super::*;
+
+    #[test]
+    fn well_formed_documents_have_no_violations() {
+        let model = cm("<b>abc</b>def<br />gh|i");
+        assert_eq!(model.state.dom.check_invariants(), Vec::new());
+        model.state.dom.assert_invariants();
+    }
+
+    #[test]
+    fn adjacent_and_empty_text_nodes_are_reported() {
+        let dom = dom(&[tn("a"), b(&[tn("b"), tn("")]), tn("c"), tn("d")]);
+        assert_eq!(
+            dom.check_invariants(),
+            vec![
+                InvariantViolation {
+                    handle: DomHandle::from_raw(vec![1, 1]),
+                    invariant: Invariant::NoAdjacentTextNodes,
+                },
+                InvariantViolation {
+                    handle: DomHandle::from_raw(vec![1, 1]),
+                    invariant: Invariant::NoEmptyTextNodes,
+                },
+                InvariantViolation {
+                    handle: DomHandle::from_raw(vec![3]),
+                    invariant: Invariant::NoAdjacentTextNodes,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_and_fix_joins_and_removes_text_nodes() {
+        let mut dom = dom(&[
+            tn(""),
+            tn("a"),
+            b(&[tn("b"), tn(""), tn("c")]),
+            tn("d"),
+            tn(""),
+            tn("e"),
+        ]);
+        let fixed = dom.validate_and_fix();
+        assert_eq!(fixed.len(), 8);
+        assert_eq!(dom.to_html(), "a<b>bc</b>de");
+        assert_eq!(dom.check_invariants(), Vec::new());
+        assert_eq!(
+            dom.lookup_node(&DomHandle::from_raw(vec![2])).handle(),
+            DomHandle::from_raw(vec![2])
+        );
+    }
+
+    #[test]
+    fn validate_and_fix_corrects_stale_handles() {
+        let mut dom = dom(&[tn("a"), b(&[tn("b")])]);
+        dom.lookup_node_mut(&DomHandle::from_raw(vec![1, 0]))
+            .set_handle(DomHandle::from_raw(vec![0]));
+        assert_eq!(dom.validate_and_fix().len(), 1);
+        assert_eq!(dom.check_invariants(), Vec::new());
+        assert_eq!(dom.validate_and_fix(), Vec::new());
+    }
+
+    #[test]
+    fn stale_handles_are_reported() {
+        let mut dom = dom(&[tn("a"), b(&[tn("b")])]);
+        dom.lookup_node_mut(&DomHandle::from_raw(vec![1, 0]))
+            .set_handle(DomHandle::from_raw(vec![0]));
+        let violations = dom.check_invariants();
+        assert_eq!(
+            violations,
+            vec![InvariantViolation {
+                handle: DomHandle::from_raw(vec![1, 0]),
+                invariant: Invariant::HandlesMatchPositions,
+            }]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "Node at [1, 0] has a handle that does not match its position"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Node at [1] is an empty text node")]
+    fn assert_invariants_panics_with_the_violations() {
+        dom(&[DomNode::new_line_break(), tn("")]).assert_invariants();
+    }
+
+    #[cfg(feature = "assert-invariants")]
+    #[test]
+    fn composer_updates_report_violations_to_the_sink() {
+        use std::rc::Rc;
+
+        use crate::tests::testutils_conversion::utf16;
+
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let collected = Rc::clone(&reports);
+        set_invariant_sink(Some(Box::new(move |report| {
+            collected.borrow_mut().push(report)
+        })));
+
+        let mut model = cm("abc|");
+        model.replace_text(utf16("d"));
+        // Text nodes left behind while editing are not reported
+        model.state.dom.append_child(tn(""));
+        model.create_update_replace_all();
+        assert!(reports.borrow().is_empty());
+
+        model
+            .state
+            .dom
+            .lookup_node_mut(&DomHandle::from_raw(vec![1]))
+            .set_handle(DomHandle::from_raw(vec![0]));
+        model.create_update_replace_all();
+        set_invariant_sink(None);
+
+        let reports = reports.borrow();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            reports[0].violations,
+            vec![InvariantViolation {
+                handle: DomHandle::from_raw(vec![1]),
+                invariant: Invariant::HandlesMatchPositions,
+            }]
+        );
+        assert!(reports[0].tree.contains("text \"abcd\""));
+    }
+
+    #[cfg(feature = "assert-invariants")]
+    #[test]
+    fn the_sink_is_only_used_on_the_thread_that_set_it() {
+        set_invariant_sink(Some(Box::new(|_| {})));
+        let panicked = std::thread::spawn(|| {
+            let mut model = cm("abc|");
+            model
+                .state
+                .dom
+                .lookup_node_mut(&DomHandle::from_raw(vec![0]))
+                .set_handle(DomHandle::from_raw(vec![1]));
+            model.create_update_replace_all();
+        })
+        .join()
+        .is_err();
+        set_invariant_sink(None);
+        assert!(panicked);
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Walking over the nodes of a [Dom] in document order, forwards or
+//! backwards.
+
+use crate::dom::nodes::{DomNode, TextNode};
+use crate::dom::{Dom, DomHandle, UnicodeString};
+
+/// Iterates depth-first over a node and its descendants, in document
+/// order, yielding each node with its handle
+pub struct DomIterator<'a, S>
+where
+    S: UnicodeString,
+{
+    /// The nodes still to visit, with the next one last
+    stack: Vec<&'a DomNode<S>>,
+}
+
+impl<'a, S> DomIterator<'a, S>
+where
+    S: UnicodeString,
+{
+    fn new(node: &'a DomNode<S>) -> Self {
+        Self { stack: vec![node] }
+    }
+}
+
+impl<'a, S> Iterator for DomIterator<'a, S>
+where
+    S: UnicodeString,
+{
+    type Item = (DomHandle, &'a DomNode<S>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if let DomNode::Container(container) = node {
+            self.stack.extend(container.children().iter().rev());
+        }
+        Some((node.handle(), node))
+    }
+}
+
+/// Iterates backwards in document order from a node, yielding each node
+/// with its handle: the node itself, then the nodes before it, each one
+/// after its descendants, ending with the document.
+pub struct DomReverseIterator<'a, S>
+where
+    S: UnicodeString,
+{
+    dom: &'a Dom<S>,
+    /// The handle of the next node to visit
+    next: Option<DomHandle>,
+}
+
+impl<'a, S> Iterator for DomReverseIterator<'a, S>
+where
+    S: UnicodeString,
+{
+    type Item = (DomHandle, &'a DomNode<S>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.next.take()?;
+        self.next = if !handle.has_parent() {
+            None
+        } else if handle.index_in_parent() > 0 {
+            Some(self.last_descendant_or_self(handle.prev_sibling()))
+        } else {
+            Some(handle.parent_handle())
+        };
+        Some((handle.clone(), self.dom.lookup_node(&handle)))
+    }
+}
+
+impl<'a, S> DomReverseIterator<'a, S>
+where
+    S: UnicodeString,
+{
+    /// The node that comes last in document order out of the one at
+    /// `handle` and its descendants
+    fn last_descendant_or_self(&self, mut handle: DomHandle) -> DomHandle {
+        while let DomNode::Container(container) = self.dom.lookup_node(&handle)
+        {
+            match container.children().len() {
+                0 => break,
+                len => handle = handle.child_handle(len - 1),
+            }
+        }
+        handle
+    }
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// All the nodes of this document, starting with the document itself
+    pub fn iter(&self) -> DomIterator<'_, S> {
+        self.iter_from(&self.document_handle())
+    }
+
+    /// The node at `handle` and all its descendants
+    ///
+    /// Panics if `handle` is not in this document.
+    pub fn iter_from(&self, handle: &DomHandle) -> DomIterator<'_, S> {
+        DomIterator::new(self.lookup_node(handle))
+    }
+
+    /// The node at `handle` and all the nodes before it, walking
+    /// backwards in document order
+    ///
+    /// Panics if `handle` is not in this document.
+    pub fn iter_backwards_from(
+        &self,
+        handle: &DomHandle,
+    ) -> DomReverseIterator<'_, S> {
+        // Look the node up now so a bad handle fails here, not later
+        self.lookup_node(handle);
+        DomReverseIterator {
+            dom: self,
+            next: Some(handle.clone()),
+        }
+    }
+
+    /// All the text nodes of this document
+    pub fn iter_text(&self) -> impl Iterator<Item = (DomHandle, &TextNode<S>)> {
+        self.iter().filter_map(|(handle, node)| match node {
+            DomNode::Text(text) => Some((handle, text)),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+    use crate::dom::DomHandle;
+    use crate::ToHtml;
+
+    fn handles(paths: &[&[usize]]) -> Vec<DomHandle> {
+        paths
+            .iter()
+            .map(|p| DomHandle::from_raw(p.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn iterating_visits_every_node_in_document_order() {
+        let dom = parse::<Utf16String>("a<em>b<u>c</u></em>d").unwrap();
+        let visited: Vec<DomHandle> =
+            dom.iter().map(|(handle, _)| handle).collect();
+        assert_eq!(
+            visited,
+            handles(&[&[], &[0], &[1], &[1, 0], &[1, 1], &[1, 1, 0], &[2]])
+        );
+    }
+
+    #[test]
+    fn iterating_from_a_handle_visits_its_subtree() {
+        let dom = parse::<Utf16String>("a<em>b<u>c</u></em>d").unwrap();
+        let html: Vec<String> = dom
+            .iter_from(&DomHandle::from_raw(vec![1, 1]))
+            .map(|(_, node)| node.to_html().to_string())
+            .collect();
+        assert_eq!(html, vec!["<u>c</u>", "c"]);
+    }
+
+    #[test]
+    fn iterating_backwards_visits_earlier_nodes_in_reverse_order() {
+        let dom = parse::<Utf16String>("a<em>b<u>c</u></em>d").unwrap();
+        let visited: Vec<DomHandle> = dom
+            .iter_backwards_from(&DomHandle::from_raw(vec![2]))
+            .map(|(handle, _)| handle)
+            .collect();
+        assert_eq!(
+            visited,
+            handles(&[&[2], &[1, 1, 0], &[1, 1], &[1, 0], &[1], &[0], &[]])
+        );
+    }
+
+    #[test]
+    fn iterating_backwards_is_the_reverse_of_iterating_forwards() {
+        let dom = parse::<Utf16String>(
+            "<ol><li>a<br />b</li><li><em></em></li></ol>c",
+        )
+        .unwrap();
+        let (last, _) = dom.iter().last().unwrap();
+        let mut backwards: Vec<DomHandle> = dom
+            .iter_backwards_from(&last)
+            .map(|(handle, _)| handle)
+            .collect();
+        backwards.reverse();
+        let forwards: Vec<DomHandle> =
+            dom.iter().map(|(handle, _)| handle).collect();
+        assert_eq!(backwards, forwards);
+    }
+
+    #[test]
+    fn iterating_text_skips_other_nodes() {
+        let dom = parse::<Utf16String>("a<em>b<br />c</em>").unwrap();
+        let text: Vec<(DomHandle, String)> = dom
+            .iter_text()
+            .map(|(handle, node)| (handle, node.data().to_string()))
+            .collect();
+        assert_eq!(
+            text,
+            vec![
+                (DomHandle::from_raw(vec![0]), "a".to_owned()),
+                (DomHandle::from_raw(vec![1, 0]), "b".to_owned()),
+                (DomHandle::from_raw(vec![1, 2]), "c".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ids that identify nodes for as long as they are in a [Dom], unlike
+//! handles, which change whenever the nodes before them change.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::dom::nodes::DomNode;
+use crate::dom::{Dom, DomHandle, UnicodeString};
+
+/// The last id given to a node. Ids are never reused, so a node that was
+/// removed can't be confused with one created later.
+static LAST_NODE_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u64);
+
+impl NodeId {
+    fn next() -> Self {
+        Self(LAST_NODE_ID.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Give an id to each node that doesn't have one yet, i.e. the ones
+    /// created since this was last called. When a node has been copied,
+    /// e.g. by splitting a text node in two, the first copy keeps its id
+    /// and the others get new ones.
+    pub fn assign_node_ids(&mut self) {
+        let mut seen = HashSet::new();
+        let document = self.document_handle();
+        assign_ids(self.lookup_node_mut(&document), &mut seen);
+    }
+
+    /// The handle of every node that has an id, by id
+    pub fn node_handles_by_id(&self) -> HashMap<NodeId, DomHandle> {
+        self.iter()
+            .filter_map(|(handle, node)| node.id().map(|id| (id, handle)))
+            .collect()
+    }
+
+    /// The handle of the node with the id given, if it is in this document
+    pub fn find_node_by_id(&self, id: NodeId) -> Option<DomHandle> {
+        self.iter()
+            .find(|(_, node)| node.id() == Some(id))
+            .map(|(handle, _)| handle)
+    }
+}
+
+fn assign_ids<S>(node: &mut DomNode<S>, seen: &mut HashSet<NodeId>)
+where
+    S: UnicodeString,
+{
+    match node.id() {
+        Some(id) if seen.insert(id) => {}
+        _ => {
+            let id = NodeId::next();
+            seen.insert(id);
+            node.set_id(Some(id));
+        }
+    }
+    if let DomNode::Container(container) = node {
+        for i in 0..container.children().len() {
+            assign_ids(container.get_child_mut(i).unwrap(), seen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use super::*;
+    use crate::dom::parser::parse;
+
+    fn ids(dom: &Dom<Utf16String>) -> Vec<Option<NodeId>> {
+        dom.iter().map(|(_, node)| node.id()).collect()
+    }
+
+    #[test]
+    fn nodes_have_no_ids_until_they_are_assigned() {
+        let mut dom: Dom<Utf16String> = parse("a<em>b</em>").unwrap();
+        assert!(ids(&dom).iter().all(Option::is_none));
+        dom.assign_node_ids();
+        assert!(ids(&dom).iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn ids_are_kept_when_nodes_move() {
+        let mut dom: Dom<Utf16String> = parse("a<em>b</em>").unwrap();
+        dom.assign_node_ids();
+        let em = dom.lookup_node(&DomHandle::from_raw(vec![1])).id().unwrap();
+
+        dom.document_mut()
+            .insert_child(0, DomNode::new_line_break());
+        dom.assign_node_ids();
+
+        assert_eq!(dom.find_node_by_id(em), Some(DomHandle::from_raw(vec![2])));
+        let new_id = dom.lookup_node(&DomHandle::from_raw(vec![0])).id();
+        assert!(new_id.unwrap() > em);
+    }
+
+    #[test]
+    fn copies_of_a_node_get_new_ids() {
+        let mut dom: Dom<Utf16String> = parse("a").unwrap();
+        dom.assign_node_ids();
+        let copy = dom.children()[0].clone();
+        dom.append_child(copy);
+        dom.assign_node_ids();
+
+        let by_id = dom.node_handles_by_id();
+        assert_eq!(by_id.len(), 3);
+        assert_ne!(dom.children()[0].id(), dom.children()[1].id());
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key/value pairs that platforms can attach to nodes, e.g. rendering
+//! hints or their own tracking ids. The editor never reads them and never
+//! writes them to HTML or markdown, but they move with their node while it
+//! is edited, and are copied when a node is copied, e.g. by splitting it.
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeMetadata {
+    entries: BTreeMap<String, String>,
+}
+
+impl NodeMetadata {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Set `key` to `value`, returning the value it had before, if any
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Option<String> {
+        self.entries.insert(key.into(), value.into())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.entries.remove(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// All the entries, ordered by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dom::DomHandle;
+    use crate::tests::testutils_composer_model::{cm, tx};
+    use crate::tests::testutils_conversion::utf16;
+
+    use super::*;
+
+    #[test]
+    fn entries_can_be_set_replaced_and_removed() {
+        let mut metadata = NodeMetadata::default();
+        assert!(metadata.is_empty());
+        assert_eq!(metadata.insert("b", "1"), None);
+        assert_eq!(metadata.insert("a", "2"), None);
+        assert_eq!(metadata.insert("b", "3"), Some(String::from("1")));
+        assert_eq!(metadata.get("b"), Some("3"));
+        assert_eq!(
+            metadata.iter().collect::<Vec<_>>(),
+            [("a", "2"), ("b", "3")]
+        );
+
+        assert_eq!(metadata.remove("a"), Some(String::from("2")));
+        assert_eq!(metadata.remove("b"), Some(String::from("3")));
+        assert_eq!(metadata.remove("b"), None);
+        assert!(metadata.is_empty());
+        assert_eq!(metadata, NodeMetadata::default());
+    }
+
+    #[test]
+    fn metadata_moves_with_its_node_and_is_not_written_out() {
+        let mut model = cm("a<em>bc|</em>");
+        let em = DomHandle::from_raw(vec![1]);
+        model
+            .state
+            .dom
+            .lookup_node_mut(&em)
+            .metadata_mut()
+            .insert("hint", "shiny");
+
+        model.select(0.into(), 0.into());
+        model.replace_text(utf16("x"));
+        model.select(4.into(), 4.into());
+        model.replace_text(utf16("d"));
+
+        assert_eq!(tx(&model), "xa<em>bcd|</em>");
+        assert_eq!(model.get_content_as_html(), "xa<em>bcd</em>");
+        let moved = model.state.dom.lookup_node(&em);
+        assert_eq!(moved.metadata().get("hint"), Some("shiny"));
+    }
+}
@@ -14,11 +14,22 @@
 
 pub mod container_node;
 pub mod dom_node;
+pub mod image_node;
 pub mod line_break_node;
+pub mod mention_node;
 pub mod text_node;
 
 pub use container_node::ContainerNode;
 pub use container_node::ContainerNodeKind;
 pub use dom_node::DomNode;
+pub use image_node::ImageNode;
 pub use line_break_node::LineBreakNode;
+pub use mention_node::MentionNode;
 pub use text_node::TextNode;
+
+/// Whether an attribute is kept as it is on a mention or image, as well as
+/// the ones they manage themselves. Matrix clients use `class` and `data-*`
+/// attributes for e.g. custom emoji and colours.
+pub(crate) fn is_extra_attribute(name: &str) -> bool {
+    name == "class" || name.starts_with("data-")
+}
@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::composer_model::counts::{CachedCounts, LineCounts};
 use crate::composer_model::example_format::SelectionWriter;
 use crate::dom::dom_handle::DomHandle;
+use crate::dom::find_range::ChildPositions;
+use crate::dom::node_id::NodeId;
+use crate::dom::node_metadata::NodeMetadata;
 use crate::dom::nodes::dom_node::DomNode;
 use crate::dom::to_html::ToHtml;
 use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
@@ -21,30 +25,92 @@ use crate::dom::to_raw_text::ToRawText;
 use crate::dom::to_tree::ToTree;
 use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
 use crate::dom::UnicodeString;
-use crate::{InlineFormatType, ListType};
+use crate::{InlineFormatType, ListNumberingType, ListType};
+use std::sync::Arc;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct ContainerNode<S>
 where
     S: UnicodeString,
 {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::dom::serialization::string")
+    )]
     name: S,
     kind: ContainerNodeKind<S>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::dom::serialization::optional_attributes")
+    )]
     attrs: Option<Vec<(S, S)>>,
-    children: Vec<DomNode<S>>,
+    /// Shared with the copies of this node until one of them changes its
+    /// children, so that copies of a document, e.g. in the undo history,
+    /// only hold the parts that differ
+    children: Arc<Vec<DomNode<S>>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip, default = "DomHandle::new_unset")
+    )]
     handle: DomHandle,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id: Option<NodeId>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "NodeMetadata::is_empty")
+    )]
+    metadata: NodeMetadata,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    positions: ChildPositions,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    counts: CachedCounts,
+}
+
+/// Children shared with `other` are known to be equal without comparing
+/// them, so comparing a document with an earlier copy of itself only looks
+/// at the parts that were changed.
+impl<S> PartialEq for ContainerNode<S>
+where
+    S: UnicodeString,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.kind == other.kind
+            && self.attrs == other.attrs
+            && self.handle == other.handle
+            && self.id == other.id
+            && self.metadata == other.metadata
+            && (self.shares_children_with(other)
+                || self.children == other.children)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub enum ContainerNodeKind<S>
 where
     S: UnicodeString,
 {
     Generic, // E.g. the root node (the containing div)
     Formatting(InlineFormatType),
-    Link(S),
+    Link(
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::dom::serialization::string")
+        )]
+        S,
+    ),
     List,
     ListItem,
+    Table,
+    TableRow,
+    TableCell,
+    /// An element the editor doesn't understand, kept as it is so that it
+    /// isn't lost. Its children can still be edited.
+    Unknown,
 }
 
 impl<S> ContainerNode<S>
@@ -65,8 +131,12 @@ where
             name,
             kind,
             attrs,
-            children,
+            children: Arc::new(children),
             handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
         }
     }
 
@@ -79,8 +149,12 @@ where
                 name: format,
                 kind: ContainerNodeKind::Formatting(f),
                 attrs: None,
-                children,
+                children: Arc::new(children),
                 handle: DomHandle::new_unset(),
+                id: None,
+                metadata: NodeMetadata::default(),
+                positions: ChildPositions::default(),
+                counts: CachedCounts::default(),
             })
             .ok()
     }
@@ -93,8 +167,12 @@ where
             name: format.tag().into(),
             kind: ContainerNodeKind::Formatting(format),
             attrs: None,
-            children,
+            children: Arc::new(children),
             handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
         }
     }
 
@@ -103,8 +181,12 @@ where
             name: list_type.tag().into(),
             kind: ContainerNodeKind::List,
             attrs: None,
-            children,
+            children: Arc::new(children),
             handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
         }
     }
 
@@ -113,8 +195,70 @@ where
             name: item_name,
             kind: ContainerNodeKind::ListItem,
             attrs: None,
-            children,
+            children: Arc::new(children),
+            handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
+        }
+    }
+
+    pub fn new_table(children: Vec<DomNode<S>>) -> Self {
+        Self {
+            name: "table".into(),
+            kind: ContainerNodeKind::Table,
+            attrs: None,
+            children: Arc::new(children),
+            handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
+        }
+    }
+
+    pub fn new_table_row(children: Vec<DomNode<S>>) -> Self {
+        Self {
+            name: "tr".into(),
+            kind: ContainerNodeKind::TableRow,
+            attrs: None,
+            children: Arc::new(children),
+            handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
+        }
+    }
+
+    pub fn new_table_cell(cell_name: S, children: Vec<DomNode<S>>) -> Self {
+        Self {
+            name: cell_name,
+            kind: ContainerNodeKind::TableCell,
+            attrs: None,
+            children: Arc::new(children),
             handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
+        }
+    }
+
+    /// Create a node for an element with a `tag` the editor doesn't
+    /// understand
+    pub fn new_unknown(tag: S, children: Vec<DomNode<S>>) -> Self {
+        Self {
+            name: tag,
+            kind: ContainerNodeKind::Unknown,
+            attrs: None,
+            children: Arc::new(children),
+            handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
         }
     }
 
@@ -124,7 +268,9 @@ where
         let child_index = self.children.len();
         let child_handle = self.handle.child_handle(child_index);
         child.set_handle(child_handle.clone());
-        self.children.push(child);
+        self.positions.inserted(child_index, &child);
+        self.counts.clear();
+        Arc::make_mut(&mut self.children).push(child);
         child_handle
     }
 
@@ -132,11 +278,14 @@ where
         assert!(self.handle.is_set());
         assert!(index < self.children().len());
 
-        let ret = self.children.remove(index);
+        self.positions.removed(index, &self.children[index]);
+        self.counts.clear();
+        let children = Arc::make_mut(&mut self.children);
+        let ret = children.remove(index);
 
-        for child_index in index..self.children.len() {
-            let new_handle = self.handle.child_handle(child_index);
-            self.children[child_index].set_handle(new_handle);
+        for (child_index, child) in children.iter_mut().enumerate().skip(index)
+        {
+            child.set_handle(self.handle.child_handle(child_index));
         }
 
         ret
@@ -152,40 +301,54 @@ where
 
         let mut handles = Vec::new();
 
-        self.children.remove(index);
+        self.positions.removed(index, &self.children[index]);
+        self.counts.clear();
+        let children = Arc::make_mut(&mut self.children);
+        children.remove(index);
         let mut current_index = index;
         for mut node in nodes {
             let child_handle = self.handle.child_handle(current_index);
             node.set_handle(child_handle);
-            self.children.insert(current_index, node);
+            self.positions.inserted(current_index, &node);
+            children.insert(current_index, node);
             current_index += 1;
         }
 
-        for child_index in current_index..self.children.len() {
+        for (child_index, child) in
+            children.iter_mut().enumerate().skip(current_index)
+        {
             let new_handle = self.handle.child_handle(child_index);
-            self.children[child_index].set_handle(new_handle.clone());
+            child.set_handle(new_handle.clone());
             handles.push(new_handle);
         }
         handles
     }
 
     pub fn get_child_mut(&mut self, idx: usize) -> Option<&mut DomNode<S>> {
-        self.children.get_mut(idx)
+        // The child may change length or text
+        self.positions.changed_from(idx);
+        self.counts.clear();
+        Arc::make_mut(&mut self.children).get_mut(idx)
     }
 
     pub fn last_child_mut(&mut self) -> Option<&mut DomNode<S>> {
-        self.children.last_mut()
+        self.positions
+            .changed_from(self.children.len().saturating_sub(1));
+        self.counts.clear();
+        Arc::make_mut(&mut self.children).last_mut()
     }
 
     pub fn insert_child(&mut self, index: usize, node: DomNode<S>) {
         assert!(self.handle.is_set());
         assert!(index <= self.children().len());
 
-        self.children.insert(index, node);
+        self.positions.inserted(index, &node);
+        self.counts.clear();
+        let children = Arc::make_mut(&mut self.children);
+        children.insert(index, node);
 
-        for i in index..self.children.len() {
-            let new_handle = self.handle.child_handle(i);
-            self.children[i].set_handle(new_handle);
+        for (i, child) in children.iter_mut().enumerate().skip(index) {
+            child.set_handle(self.handle.child_handle(i));
         }
     }
 
@@ -195,11 +358,41 @@ where
 
     pub fn set_handle(&mut self, handle: DomHandle) {
         self.handle = handle;
-        for (i, child) in self.children.iter_mut().enumerate() {
-            child.set_handle(self.handle.child_handle(i))
+        // Children that already have the right handles are left alone, so
+        // that they stay shared with copies of this node
+        for i in 0..self.children.len() {
+            let child_handle = self.handle.child_handle(i);
+            if !self.children[i].has_handles_from(&child_handle) {
+                Arc::make_mut(&mut self.children)[i].set_handle(child_handle);
+            }
         }
     }
 
+    /// Whether this node and everything in it already have the handles
+    /// that [Self::set_handle] would give them
+    pub(crate) fn has_handles_from(&self, handle: &DomHandle) -> bool {
+        self.handle == *handle
+            && self.children.iter().enumerate().all(|(i, child)| {
+                child.has_handles_from(&handle.child_handle(i))
+            })
+    }
+
+    pub fn id(&self) -> Option<NodeId> {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: Option<NodeId>) {
+        self.id = id;
+    }
+
+    pub fn metadata(&self) -> &NodeMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut NodeMetadata {
+        &mut self.metadata
+    }
+
     pub fn name(&self) -> &S::Str {
         &self.name
     }
@@ -208,23 +401,125 @@ where
         self.attrs.as_ref()
     }
 
+    /// Look up the value of the attribute called `name`, if it is set
+    pub fn get_attribute(&self, name: &str) -> Option<&S> {
+        self.attrs
+            .as_ref()?
+            .iter()
+            .find(|(n, _)| **n == *name)
+            .map(|(_, v)| v)
+    }
+
+    /// Set the attribute called `name` to `value`, replacing its old value
+    /// if it was already set
+    pub fn set_attribute(&mut self, name: S, value: S) {
+        let attrs = self.attrs.get_or_insert_with(Vec::new);
+        match attrs.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => attrs.push((name, value)),
+        }
+    }
+
+    /// Remove the attribute called `name`, if it is set
+    pub fn remove_attribute(&mut self, name: &str) {
+        if let Some(attrs) = &mut self.attrs {
+            attrs.retain(|(n, _)| **n != *name);
+            if attrs.is_empty() {
+                self.attrs = None;
+            }
+        }
+    }
+
     pub fn children(&self) -> &Vec<DomNode<S>> {
         &self.children
     }
 
-    #[cfg(all(feature = "js", target_arch = "wasm32"))]
+    /// A copy of this node holding `children` instead of its own, with an
+    /// unset handle and no id
+    pub(crate) fn copy_with_new_children(
+        &self,
+        children: Vec<DomNode<S>>,
+    ) -> Self {
+        Self {
+            name: self.name.clone(),
+            kind: self.kind.clone(),
+            attrs: self.attrs.clone(),
+            children: Arc::new(children),
+            handle: DomHandle::new_unset(),
+            id: None,
+            metadata: self.metadata.clone(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
+        }
+    }
+
     pub(crate) fn take_children(self) -> Vec<DomNode<S>> {
-        self.children
+        Arc::try_unwrap(self.children)
+            .unwrap_or_else(|children| children.as_ref().clone())
+    }
+
+    /// Whether this node holds the very same children as `other`, rather
+    /// than a copy of them
+    pub(crate) fn shares_children_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.children, &other.children)
+    }
+
+    /// Give this node `children` in place of its own, returning the old
+    /// ones. The new children are given handles if this node has one.
+    pub(crate) fn replace_children(
+        &mut self,
+        children: Vec<DomNode<S>>,
+    ) -> Vec<DomNode<S>> {
+        self.positions.changed_from(0);
+        self.counts.clear();
+        let old_children =
+            std::mem::replace(&mut self.children, Arc::new(children));
+        if self.handle.is_set() {
+            self.set_handle(self.handle.clone());
+        }
+        Arc::try_unwrap(old_children)
+            .unwrap_or_else(|children| children.as_ref().clone())
     }
 
     pub fn kind(&self) -> &ContainerNodeKind<S> {
         &self.kind
     }
 
+    /// What kind of container this is, in words, for debug output
+    pub(crate) fn kind_name(&self) -> &'static str {
+        match self.kind {
+            ContainerNodeKind::Generic => "generic",
+            ContainerNodeKind::Formatting(_) => "formatting",
+            ContainerNodeKind::Link(_) => "link",
+            ContainerNodeKind::List => "list",
+            ContainerNodeKind::ListItem => "list item",
+            ContainerNodeKind::Table => "table",
+            ContainerNodeKind::TableRow => "table row",
+            ContainerNodeKind::TableCell => "table cell",
+            ContainerNodeKind::Unknown => "unknown",
+        }
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self.kind, ContainerNodeKind::List)
+    }
+
     pub fn is_list_item(&self) -> bool {
         matches!(self.kind, ContainerNodeKind::ListItem)
     }
 
+    pub fn is_link(&self) -> bool {
+        matches!(self.kind, ContainerNodeKind::Link(_))
+    }
+
+    pub fn is_table(&self) -> bool {
+        matches!(self.kind, ContainerNodeKind::Table)
+    }
+
+    pub fn is_table_cell(&self) -> bool {
+        matches!(self.kind, ContainerNodeKind::TableCell)
+    }
+
     pub(crate) fn is_list_of_type(&self, list_type: ListType) -> bool {
         match self.kind {
             ContainerNodeKind::List => {
@@ -255,20 +550,97 @@ where
     pub(crate) fn is_block_node(&self) -> bool {
         use ContainerNodeKind::*;
 
-        matches!(self.kind, Generic | List)
+        matches!(self.kind, Generic | List | Table)
     }
 
     pub fn text_len(&self) -> usize {
-        self.children.iter().map(|child| child.text_len()).sum()
+        self.child_ends().last().copied().unwrap_or(0)
+    }
+
+    /// Where each child ends, counted from the start of this node
+    pub(crate) fn child_ends(&self) -> &[usize] {
+        self.positions.ends(&self.children)
+    }
+
+    /// The counts of the text of this block, see [LineCounts]
+    pub(crate) fn line_counts(&self) -> LineCounts {
+        self.counts.get_or_count(self)
     }
 
     pub fn new_link(url: S, children: Vec<DomNode<S>>) -> Self {
+        Self::new_link_with_attributes(url, Vec::new(), children)
+    }
+
+    /// Create a link carrying extra attributes (e.g. `rel` or `title`),
+    /// which are written out after its `href`.
+    pub fn new_link_with_attributes(
+        url: S,
+        attributes: Vec<(S, S)>,
+        children: Vec<DomNode<S>>,
+    ) -> Self {
+        let mut attrs = vec![("href".into(), url.clone())];
+        attrs.extend(attributes.into_iter().filter(|(n, _)| **n != *"href"));
         Self {
             name: "a".into(),
-            kind: ContainerNodeKind::Link(url.clone()),
-            attrs: Some(vec![("href".into(), url)]),
-            children,
+            kind: ContainerNodeKind::Link(url),
+            attrs: Some(attrs),
+            children: Arc::new(children),
             handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+            positions: ChildPositions::default(),
+            counts: CachedCounts::default(),
+        }
+    }
+
+    /// The number the first item of this ordered list is counted from, if
+    /// it was set.
+    pub fn list_start(&self) -> Option<usize> {
+        self.get_attribute("start")?.to_string().parse().ok()
+    }
+
+    /// How the items of this ordered list are numbered, if it was set.
+    pub fn list_numbering_type(&self) -> Option<ListNumberingType> {
+        ListNumberingType::from_attribute_value(
+            &self.get_attribute("type")?.to_string(),
+        )
+    }
+
+    pub(crate) fn set_list_start(&mut self, start: Option<usize>) {
+        let numbering_type = self.list_numbering_type();
+        self.set_list_attributes(start, numbering_type);
+    }
+
+    pub(crate) fn set_list_numbering_type(
+        &mut self,
+        numbering_type: Option<ListNumberingType>,
+    ) {
+        let start = self.list_start();
+        self.set_list_attributes(start, numbering_type);
+    }
+
+    fn set_list_attributes(
+        &mut self,
+        start: Option<usize>,
+        numbering_type: Option<ListNumberingType>,
+    ) {
+        assert!(
+            self.is_list_of_type(ListType::Ordered),
+            "Only ordered lists can be numbered"
+        );
+        self.remove_attribute("start");
+        self.remove_attribute("type");
+        if let Some(start) = start {
+            self.set_attribute(
+                "start".into(),
+                start.to_string().as_str().into(),
+            );
+        }
+        if let Some(numbering_type) = numbering_type {
+            self.set_attribute(
+                "type".into(),
+                numbering_type.attribute_value().into(),
+            );
         }
     }
 
@@ -286,6 +658,11 @@ where
         match self.kind {
             ContainerNodeKind::List => {
                 self.name = list_type.tag().into();
+                // Numbering only means something for ordered lists
+                if list_type == ListType::Unordered {
+                    self.remove_attribute("start");
+                    self.remove_attribute("type");
+                }
             }
             _ => panic!(
                 "Setting list type to a non-list container is not allowed"
@@ -315,7 +692,11 @@ where
                     formatter.push(&**attr_name);
                     formatter.push('=');
                     formatter.push('"');
-                    formatter.push(&**value);
+                    formatter.push(
+                        &*html_escape::encode_double_quoted_attribute(
+                            &value.to_string(),
+                        ),
+                    );
                     formatter.push('"');
                 }
             }
@@ -327,6 +708,15 @@ where
                 let is_last = self.children().len() == i + 1;
                 child.fmt_html(formatter, Some(w), is_last);
             }
+        } else if self.is_table_cell() {
+            // The placeholder a cell is given when it is created only holds
+            // the cursor, so it is not part of the content
+            let mut cell = S::default();
+            for (i, child) in self.children.iter().enumerate() {
+                let is_last = self.children().len() == i + 1;
+                child.fmt_html(&mut cell, None, is_last);
+            }
+            formatter.push(cell.to_string().replace('\u{200b}', "").as_str());
         } else {
             for (i, child) in self.children.iter().enumerate() {
                 let is_last = self.children().len() == i + 1;
@@ -349,7 +739,7 @@ where
 {
     fn to_raw_text(&self) -> S {
         let mut text = S::default();
-        for child in &self.children {
+        for child in self.children.iter() {
             text.push(child.to_raw_text());
         }
         text
@@ -362,11 +752,16 @@ where
 {
     fn to_tree_display(&self, continuous_positions: Vec<usize>) -> S {
         let mut description = self.name.clone();
+        if !description.is_empty() {
+            description.push(" ");
+        }
+        description.push(self.kind_name());
         if let ContainerNodeKind::Link(url) = self.kind() {
             description.push(" \"");
             description.push(url.clone());
             description.push("\"");
         }
+        description.push(self.tree_node_details(&self.handle, self.text_len()));
 
         let mut tree_part = self.tree_line(
             description,
@@ -400,7 +795,7 @@ where
         let mut options = *options;
 
         match self.kind() {
-            Generic => {
+            Generic | Unknown => {
                 fmt_children(self, buffer, &options)?;
             }
 
@@ -437,6 +832,18 @@ where
             ListItem => {
                 fmt_list_item(self, buffer, &options)?;
             }
+
+            Table => {
+                fmt_table(self, buffer, &options)?;
+            }
+
+            TableRow => {
+                fmt_table_row(self, buffer, &options)?;
+            }
+
+            TableCell => {
+                fmt_table_cell(self, buffer, &options)?;
+            }
         };
 
         return Ok(());
@@ -633,7 +1040,9 @@ where
             let ordered_list_name = "ol";
             let expected_list_item_name = &S::from("li");
             let number_of_children = this.children.len();
-            let mut ordered_list_counter = 0i32;
+            // Markdown can't express the numbering type, but it can start
+            // counting from any number.
+            let mut ordered_list_counter = this.list_start().unwrap_or(1);
 
             for (nth, child) in this.children.iter().enumerate() {
                 // Verify the list item is correct.
@@ -668,6 +1077,18 @@ where
                         )))
                     }
 
+                    DomNode::Mention(mention) => {
+                        return Err(MarkdownError::InvalidListItem(Some(
+                            mention.name(),
+                        )))
+                    }
+
+                    DomNode::Image(image) => {
+                        return Err(MarkdownError::InvalidListItem(Some(
+                            image.name(),
+                        )))
+                    }
+
                     DomNode::Text(_) => {
                         return Err(MarkdownError::InvalidListItem(None))
                     }
@@ -678,13 +1099,13 @@ where
 
                 // It's an ordered list.
                 if list_type == ordered_list_name {
-                    // Update the counter.
-                    ordered_list_counter += 1;
-
                     // Generate something like `1.` (arabic numbers only,
                     // as requested by the specification).
                     let counter = ordered_list_counter.to_string();
 
+                    // Update the counter.
+                    ordered_list_counter += 1;
+
                     buffer.push(counter.as_str());
                     buffer.push('.');
 
@@ -756,6 +1177,85 @@ where
 
             Ok(())
         }
+
+        // Tables are written in the GitHub Flavored Markdown style, where
+        // the first row is the header row.
+        #[inline(always)]
+        fn fmt_table<S>(
+            this: &ContainerNode<S>,
+            buffer: &mut S,
+            options: &MarkdownOptions,
+        ) -> Result<(), MarkdownError<S>>
+        where
+            S: UnicodeString,
+        {
+            for (nth, row) in this.children.iter().enumerate() {
+                if nth > 0 {
+                    buffer.push('\n');
+                }
+
+                row.fmt_markdown(buffer, options)?;
+
+                if nth == 0 {
+                    let number_of_columns = match row {
+                        DomNode::Container(row) => row.children().len(),
+                        _ => 1,
+                    };
+                    buffer.push('\n');
+                    buffer.push("|");
+                    buffer.push(" --- |".repeat(number_of_columns).as_str());
+                }
+            }
+
+            Ok(())
+        }
+
+        #[inline(always)]
+        fn fmt_table_row<S>(
+            this: &ContainerNode<S>,
+            buffer: &mut S,
+            options: &MarkdownOptions,
+        ) -> Result<(), MarkdownError<S>>
+        where
+            S: UnicodeString,
+        {
+            buffer.push('|');
+
+            for cell in this.children.iter() {
+                buffer.push(' ');
+                cell.fmt_markdown(buffer, options)?;
+                buffer.push(" |");
+            }
+
+            Ok(())
+        }
+
+        #[inline(always)]
+        fn fmt_table_cell<S>(
+            this: &ContainerNode<S>,
+            buffer: &mut S,
+            options: &MarkdownOptions,
+        ) -> Result<(), MarkdownError<S>>
+        where
+            S: UnicodeString,
+        {
+            // A cell must fit on one line, and its content can't contain
+            // an unescaped pipe.
+            let mut options = *options;
+            options.insert(MarkdownOptions::IGNORE_LINE_BREAK);
+
+            let mut cell_buffer = S::default();
+            fmt_children(this, &mut cell_buffer, &options)?;
+            buffer.push(
+                cell_buffer
+                    .to_string()
+                    .replace('\u{200b}', "")
+                    .replace('|', "\\|")
+                    .as_str(),
+            );
+
+            Ok(())
+        }
     }
 }
 
@@ -853,6 +1353,38 @@ mod test {
         assert_eq!(text_node2.handle().raw(), &[4, 5, 4, 4]);
     }
 
+    #[test]
+    fn copies_share_children_until_they_are_changed() {
+        let mut node = container_with_handle(&[0]);
+        node.append_child(text_node("a"));
+        node.append_child(DomNode::Container(container_with_handle(&[0])));
+        let copy = node.clone();
+        assert!(node.shares_children_with(&copy));
+
+        node.append_child(text_node("b"));
+        assert!(!node.shares_children_with(&copy));
+        assert_eq!(copy.children().len(), 2);
+        // Only the list of children was copied, not the children in it
+        match (&node.children[1], &copy.children[1]) {
+            (DomNode::Container(inner), DomNode::Container(inner_copy)) => {
+                assert!(inner.shares_children_with(inner_copy))
+            }
+            _ => panic!("Expected containers"),
+        }
+    }
+
+    #[test]
+    fn setting_the_same_handle_keeps_children_shared() {
+        let mut node = container_with_handle(&[0]);
+        node.append_child(text_node("a"));
+        let copy = node.clone();
+        node.set_handle(DomHandle::from_raw(vec![0]));
+        assert!(node.shares_children_with(&copy));
+        node.set_handle(DomHandle::from_raw(vec![1]));
+        assert!(!node.shares_children_with(&copy));
+        assert_eq!(copy.children[0].handle().raw(), &[0, 0]);
+    }
+
     fn container_with_handle<'a>(
         raw_handle: impl IntoIterator<Item = &'a usize>,
     ) -> ContainerNode<Utf16String> {
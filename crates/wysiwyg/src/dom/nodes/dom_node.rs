@@ -14,7 +14,11 @@
 
 use crate::composer_model::example_format::SelectionWriter;
 use crate::dom::dom_handle::DomHandle;
-use crate::dom::nodes::{ContainerNode, LineBreakNode, TextNode};
+use crate::dom::node_id::NodeId;
+use crate::dom::node_metadata::NodeMetadata;
+use crate::dom::nodes::{
+    ContainerNode, ImageNode, LineBreakNode, MentionNode, TextNode,
+};
 use crate::dom::to_html::ToHtml;
 use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
 use crate::dom::to_raw_text::ToRawText;
@@ -24,6 +28,8 @@ use crate::dom::UnicodeString;
 use crate::{InlineFormatType, ListType};
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub enum DomNode<S>
 where
     S: UnicodeString,
@@ -31,6 +37,8 @@ where
     Container(ContainerNode<S>), // E.g. html, div
     Text(TextNode<S>),
     LineBreak(LineBreakNode<S>),
+    Mention(MentionNode<S>),
+    Image(ImageNode<S>),
 }
 
 impl<S> DomNode<S>
@@ -49,6 +57,26 @@ where
         DomNode::LineBreak(LineBreakNode::new())
     }
 
+    pub fn new_mention(url: S, display_text: S) -> DomNode<S> {
+        DomNode::Mention(MentionNode::new(url, display_text))
+    }
+
+    pub fn new_mention_with_attributes(
+        url: S,
+        display_text: S,
+        attrs: Vec<(S, S)>,
+    ) -> DomNode<S> {
+        DomNode::Mention(MentionNode::new_with_attributes(
+            url,
+            display_text,
+            attrs,
+        ))
+    }
+
+    pub fn new_image(attrs: Vec<(S, S)>) -> DomNode<S> {
+        DomNode::Image(ImageNode::new(attrs))
+    }
+
     pub fn new_formatting(
         format: InlineFormatType,
         children: Vec<DomNode<S>>,
@@ -80,26 +108,104 @@ where
         DomNode::Container(ContainerNode::new_list_item(item_name, children))
     }
 
+    pub fn new_table(rows: Vec<DomNode<S>>) -> DomNode<S> {
+        DomNode::Container(ContainerNode::new_table(rows))
+    }
+
+    pub fn new_table_row(cells: Vec<DomNode<S>>) -> DomNode<S> {
+        DomNode::Container(ContainerNode::new_table_row(cells))
+    }
+
+    pub fn new_table_cell(
+        cell_name: S,
+        children: Vec<DomNode<S>>,
+    ) -> DomNode<S> {
+        DomNode::Container(ContainerNode::new_table_cell(cell_name, children))
+    }
+
+    pub fn new_unknown(tag: S, children: Vec<DomNode<S>>) -> DomNode<S> {
+        DomNode::Container(ContainerNode::new_unknown(tag, children))
+    }
+
     pub fn handle(&self) -> DomHandle {
         match self {
             DomNode::Container(n) => n.handle(),
             DomNode::LineBreak(n) => n.handle(),
+            DomNode::Mention(n) => n.handle(),
+            DomNode::Image(n) => n.handle(),
             DomNode::Text(n) => n.handle(),
         }
     }
 
+    /// Whether this node and everything in it already have the handles
+    /// that [Self::set_handle] would give them
+    pub(crate) fn has_handles_from(&self, handle: &DomHandle) -> bool {
+        match self {
+            DomNode::Container(n) => n.has_handles_from(handle),
+            _ => self.handle() == *handle,
+        }
+    }
+
     pub fn set_handle(&mut self, handle: DomHandle) {
         match self {
             DomNode::Container(n) => n.set_handle(handle),
             DomNode::LineBreak(n) => n.set_handle(handle),
+            DomNode::Mention(n) => n.set_handle(handle),
+            DomNode::Image(n) => n.set_handle(handle),
             DomNode::Text(n) => n.set_handle(handle),
         }
     }
 
+    /// The stable id of this node, if it was given one by
+    /// [Dom::assign_node_ids](crate::dom::Dom::assign_node_ids). Unlike
+    /// its handle, it stays the same when the nodes around it change.
+    pub fn id(&self) -> Option<NodeId> {
+        match self {
+            DomNode::Container(n) => n.id(),
+            DomNode::LineBreak(n) => n.id(),
+            DomNode::Mention(n) => n.id(),
+            DomNode::Image(n) => n.id(),
+            DomNode::Text(n) => n.id(),
+        }
+    }
+
+    pub(crate) fn set_id(&mut self, id: Option<NodeId>) {
+        match self {
+            DomNode::Container(n) => n.set_id(id),
+            DomNode::LineBreak(n) => n.set_id(id),
+            DomNode::Mention(n) => n.set_id(id),
+            DomNode::Image(n) => n.set_id(id),
+            DomNode::Text(n) => n.set_id(id),
+        }
+    }
+
+    /// Data kept with this node for the platform, see [NodeMetadata]
+    pub fn metadata(&self) -> &NodeMetadata {
+        match self {
+            DomNode::Container(n) => n.metadata(),
+            DomNode::LineBreak(n) => n.metadata(),
+            DomNode::Mention(n) => n.metadata(),
+            DomNode::Image(n) => n.metadata(),
+            DomNode::Text(n) => n.metadata(),
+        }
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut NodeMetadata {
+        match self {
+            DomNode::Container(n) => n.metadata_mut(),
+            DomNode::LineBreak(n) => n.metadata_mut(),
+            DomNode::Mention(n) => n.metadata_mut(),
+            DomNode::Image(n) => n.metadata_mut(),
+            DomNode::Text(n) => n.metadata_mut(),
+        }
+    }
+
     pub fn text_len(&self) -> usize {
         match self {
             DomNode::Text(n) => n.data().len(),
             DomNode::LineBreak(n) => n.text_len(),
+            DomNode::Mention(n) => n.text_len(),
+            DomNode::Image(n) => n.text_len(),
             DomNode::Container(n) => n.text_len(),
         }
     }
@@ -108,6 +214,16 @@ where
         DomNode::Container(ContainerNode::new_link(url, children))
     }
 
+    pub fn new_link_with_attributes(
+        url: S,
+        attributes: Vec<(S, S)>,
+        children: Vec<DomNode<S>>,
+    ) -> DomNode<S> {
+        DomNode::Container(ContainerNode::new_link_with_attributes(
+            url, attributes, children,
+        ))
+    }
+
     pub fn is_container_node(&self) -> bool {
         matches!(self, DomNode::Container(_))
     }
@@ -120,6 +236,25 @@ where
         matches!(self, DomNode::Container(n) if n.is_structure_node())
     }
 
+    pub fn is_link(&self) -> bool {
+        matches!(self, DomNode::Container(n) if n.is_link())
+    }
+
+    pub fn is_table_cell(&self) -> bool {
+        matches!(self, DomNode::Container(n) if n.is_table_cell())
+    }
+
+    pub fn is_mention(&self) -> bool {
+        matches!(self, DomNode::Mention(_) | DomNode::Image(_))
+    }
+
+    /// Atomic inline nodes (e.g. mentions and images) are leaves that are edited as a
+    /// single unit: the cursor can't be placed inside them, and deleting or
+    /// selecting any part of one affects all of it.
+    pub fn is_atomic_inline(&self) -> bool {
+        matches!(self, DomNode::Mention(_) | DomNode::Image(_))
+    }
+
     pub fn is_formatting_node(&self) -> bool {
         matches!(self, DomNode::Container(n) if n.is_formatting_node())
     }
@@ -132,7 +267,7 @@ where
     }
 
     pub(crate) fn is_placeholder_text_node(&self) -> bool {
-        matches!(self, DomNode::Text(n) if n.data().len() == 1 && n.data() == "\u{200b}")
+        matches!(self, DomNode::Text(n) if n.data() == "\u{200b}")
     }
 
     pub(crate) fn has_only_placeholder_text_child(&self) -> bool {
@@ -167,6 +302,12 @@ where
             DomNode::LineBreak(s) => {
                 s.fmt_html(buf, selection_writer, is_last_node_in_parent)
             }
+            DomNode::Mention(s) => {
+                s.fmt_html(buf, selection_writer, is_last_node_in_parent)
+            }
+            DomNode::Image(s) => {
+                s.fmt_html(buf, selection_writer, is_last_node_in_parent)
+            }
             DomNode::Text(s) => {
                 s.fmt_html(buf, selection_writer, is_last_node_in_parent)
             }
@@ -182,6 +323,8 @@ where
         match self {
             DomNode::Container(n) => n.to_raw_text(),
             DomNode::LineBreak(n) => n.to_raw_text(),
+            DomNode::Mention(n) => n.to_raw_text(),
+            DomNode::Image(n) => n.to_raw_text(),
             DomNode::Text(n) => n.to_raw_text(),
         }
     }
@@ -195,6 +338,8 @@ where
         match self {
             DomNode::Container(n) => n.to_tree_display(continuous_positions),
             DomNode::LineBreak(n) => n.to_tree_display(continuous_positions),
+            DomNode::Mention(n) => n.to_tree_display(continuous_positions),
+            DomNode::Image(n) => n.to_tree_display(continuous_positions),
             DomNode::Text(n) => n.to_tree_display(continuous_positions),
         }
     }
@@ -215,6 +360,8 @@ where
             }
             DomNode::Text(text) => text.fmt_markdown(buffer, options),
             DomNode::LineBreak(node) => node.fmt_markdown(buffer, options),
+            DomNode::Mention(node) => node.fmt_markdown(buffer, options),
+            DomNode::Image(node) => node.fmt_markdown(buffer, options),
         }
     }
 }
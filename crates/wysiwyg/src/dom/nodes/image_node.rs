@@ -0,0 +1,239 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_model::example_format::SelectionWriter;
+use crate::dom::dom_handle::DomHandle;
+use crate::dom::node_id::NodeId;
+use crate::dom::node_metadata::NodeMetadata;
+use crate::dom::nodes::is_extra_attribute;
+use crate::dom::to_html::ToHtml;
+use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
+use crate::dom::to_raw_text::ToRawText;
+use crate::dom::to_tree::ToTree;
+use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
+use crate::dom::UnicodeString;
+
+/// The attributes an image may carry, in the order they are written out
+pub const IMAGE_ATTRIBUTES: [&str; 5] =
+    ["src", "alt", "width", "height", "data-mx-emoticon"];
+
+/// An inline image, e.g. a custom emoji. Like a mention, the cursor treats it
+/// as a single character. Images with a `data-mx-emoticon` attribute are
+/// emoticons, see [ImageNode::is_emoticon].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct ImageNode<S>
+where
+    S: UnicodeString,
+{
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::dom::serialization::attributes")
+    )]
+    attrs: Vec<(S, S)>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip, default = "DomHandle::new_unset")
+    )]
+    handle: DomHandle,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id: Option<NodeId>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "NodeMetadata::is_empty")
+    )]
+    metadata: NodeMetadata,
+}
+
+impl<S> ImageNode<S>
+where
+    S: UnicodeString,
+{
+    /// Create a new ImageNode. Attributes not listed in [IMAGE_ATTRIBUTES]
+    /// are dropped, except `class` and `data-*` attributes, which are
+    /// written out after them.
+    ///
+    /// NOTE: Its handle() will be unset until you call set_handle() or
+    /// append() it to another node.
+    pub fn new(attrs: Vec<(S, S)>) -> Self {
+        let attrs = IMAGE_ATTRIBUTES
+            .iter()
+            .filter_map(|name| {
+                attrs
+                    .iter()
+                    .find(|(n, _)| **n == **name)
+                    .map(|(_, v)| ((*name).into(), v.clone()))
+            })
+            .chain(
+                attrs
+                    .iter()
+                    .filter(|(n, _)| {
+                        let name = n.to_string();
+                        !IMAGE_ATTRIBUTES.contains(&name.as_str())
+                            && is_extra_attribute(&name)
+                    })
+                    .cloned(),
+            )
+            .collect();
+        Self {
+            attrs,
+            handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+        }
+    }
+
+    pub fn name(&self) -> S {
+        "img".into()
+    }
+
+    pub fn attributes(&self) -> &Vec<(S, S)> {
+        &self.attrs
+    }
+
+    /// Look up the value of the attribute called `name`, if it is set
+    pub fn get_attribute(&self, name: &str) -> Option<&S> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| **n == *name)
+            .map(|(_, v)| v)
+    }
+
+    pub fn src(&self) -> S {
+        self.get_attribute("src").cloned().unwrap_or_default()
+    }
+
+    pub fn alt(&self) -> S {
+        self.get_attribute("alt").cloned().unwrap_or_default()
+    }
+
+    /// Whether this image is a custom emoticon, i.e. it has a
+    /// `data-mx-emoticon` attribute, whatever its value
+    pub fn is_emoticon(&self) -> bool {
+        self.get_attribute("data-mx-emoticon").is_some()
+    }
+
+    pub fn handle(&self) -> DomHandle {
+        self.handle.clone()
+    }
+
+    pub fn set_handle(&mut self, handle: DomHandle) {
+        self.handle = handle;
+    }
+
+    pub fn id(&self) -> Option<NodeId> {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: Option<NodeId>) {
+        self.id = id;
+    }
+
+    pub fn metadata(&self) -> &NodeMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut NodeMetadata {
+        &mut self.metadata
+    }
+
+    // An image is always treated as 1 character, so this always returns 1
+    pub fn text_len(&self) -> usize {
+        1
+    }
+}
+
+impl<S> ToHtml<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn fmt_html(
+        &self,
+        buf: &mut S,
+        selection_writer: Option<&mut SelectionWriter>,
+        _: bool,
+    ) {
+        let cur_pos = buf.len();
+        buf.push('<');
+        buf.push(self.name());
+        for (name, value) in &self.attrs {
+            buf.push(' ');
+            buf.push(name.clone());
+            buf.push("=\"");
+            buf.push(&*html_escape::encode_double_quoted_attribute(
+                &value.to_string(),
+            ));
+            buf.push('"');
+        }
+        buf.push(" />");
+        if let Some(sel_writer) = selection_writer {
+            sel_writer.write_selection_atomic_inline_node(
+                buf,
+                cur_pos,
+                &self.handle,
+                self.text_len(),
+            );
+        }
+    }
+}
+
+impl<S> ToRawText<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn to_raw_text(&self) -> S {
+        self.alt()
+    }
+}
+
+impl<S> ToTree<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn to_tree_display(&self, continuous_positions: Vec<usize>) -> S {
+        let mut description = self.name();
+        if self.is_emoticon() {
+            description.push(" emoticon");
+        }
+        description.push(" \"");
+        description.push(self.src());
+        description.push("\"");
+        description.push(self.tree_node_details(&self.handle, self.text_len()));
+        self.tree_line(
+            description,
+            self.handle.raw().len(),
+            continuous_positions,
+        )
+    }
+}
+
+impl<S> ToMarkdown<S> for ImageNode<S>
+where
+    S: UnicodeString,
+{
+    fn fmt_markdown(
+        &self,
+        buffer: &mut S,
+        _options: &MarkdownOptions,
+    ) -> Result<(), MarkdownError<S>> {
+        buffer.push("![");
+        buffer.push(self.alt());
+        buffer.push("](<");
+        buffer.push(self.src());
+        buffer.push(">)");
+
+        Ok(())
+    }
+}
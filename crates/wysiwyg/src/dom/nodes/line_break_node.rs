@@ -14,6 +14,8 @@
 
 use crate::composer_model::example_format::SelectionWriter;
 use crate::dom::dom_handle::DomHandle;
+use crate::dom::node_id::NodeId;
+use crate::dom::node_metadata::NodeMetadata;
 use crate::dom::to_html::ToHtml;
 use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
 use crate::dom::to_raw_text::ToRawText;
@@ -23,12 +25,26 @@ use crate::dom::UnicodeString;
 use std::marker::PhantomData;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct LineBreakNode<S>
 where
     S: UnicodeString,
 {
+    #[cfg_attr(feature = "serde", serde(skip))]
     _phantom_data: PhantomData<S>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip, default = "DomHandle::new_unset")
+    )]
     handle: DomHandle,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id: Option<NodeId>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "NodeMetadata::is_empty")
+    )]
+    metadata: NodeMetadata,
 }
 
 impl<S> LineBreakNode<S>
@@ -43,6 +59,8 @@ where
         Self {
             _phantom_data: PhantomData {},
             handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
         }
     }
 
@@ -58,6 +76,22 @@ where
         self.handle = handle;
     }
 
+    pub fn id(&self) -> Option<NodeId> {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: Option<NodeId>) {
+        self.id = id;
+    }
+
+    pub fn metadata(&self) -> &NodeMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut NodeMetadata {
+        &mut self.metadata
+    }
+
     // A br tag is always treated as 1 character, so this always returns 1
     pub fn text_len(&self) -> usize {
         1
@@ -100,8 +134,10 @@ where
     S: UnicodeString,
 {
     fn to_tree_display(&self, continuous_positions: Vec<usize>) -> S {
+        let mut description = self.name();
+        description.push(self.tree_node_details(&self.handle, self.text_len()));
         self.tree_line(
-            self.name(),
+            description,
             self.handle.raw().len(),
             continuous_positions,
         )
@@ -0,0 +1,239 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_model::example_format::SelectionWriter;
+use crate::dom::dom_handle::DomHandle;
+use crate::dom::node_id::NodeId;
+use crate::dom::node_metadata::NodeMetadata;
+use crate::dom::nodes::is_extra_attribute;
+use crate::dom::to_html::ToHtml;
+use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
+use crate::dom::to_raw_text::ToRawText;
+use crate::dom::to_tree::{text_preview, ToTree};
+use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
+use crate::dom::UnicodeString;
+
+/// A "pill" pointing at a user, e.g. `@alice:matrix.org`. It is rendered as
+/// a non-editable link, and the cursor treats it as a single character.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct MentionNode<S>
+where
+    S: UnicodeString,
+{
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::dom::serialization::string")
+    )]
+    url: S,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::dom::serialization::string")
+    )]
+    display_text: S,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Vec::is_empty",
+            with = "crate::dom::serialization::attributes"
+        )
+    )]
+    attrs: Vec<(S, S)>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip, default = "DomHandle::new_unset")
+    )]
+    handle: DomHandle,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id: Option<NodeId>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "NodeMetadata::is_empty")
+    )]
+    metadata: NodeMetadata,
+}
+
+impl<S> MentionNode<S>
+where
+    S: UnicodeString,
+{
+    /// Create a new MentionNode
+    ///
+    /// NOTE: Its handle() will be unset until you call set_handle() or
+    /// append() it to another node.
+    pub fn new(url: S, display_text: S) -> Self {
+        Self::new_with_attributes(url, display_text, Vec::new())
+    }
+
+    /// Create a new MentionNode carrying extra `class` and `data-*`
+    /// attributes, which are written out after its `href`. Other
+    /// attributes are dropped.
+    pub fn new_with_attributes(
+        url: S,
+        display_text: S,
+        attrs: Vec<(S, S)>,
+    ) -> Self {
+        Self {
+            url,
+            display_text,
+            attrs: attrs
+                .into_iter()
+                .filter(|(n, _)| is_extra_attribute(&n.to_string()))
+                .collect(),
+            handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
+        }
+    }
+
+    pub fn name(&self) -> S {
+        "a".into()
+    }
+
+    pub fn url(&self) -> &S {
+        &self.url
+    }
+
+    pub fn display_text(&self) -> &S {
+        &self.display_text
+    }
+
+    /// The extra attributes of the mention, see
+    /// [MentionNode::new_with_attributes]
+    pub fn attributes(&self) -> &Vec<(S, S)> {
+        &self.attrs
+    }
+
+    pub fn handle(&self) -> DomHandle {
+        self.handle.clone()
+    }
+
+    pub fn set_handle(&mut self, handle: DomHandle) {
+        self.handle = handle;
+    }
+
+    pub fn id(&self) -> Option<NodeId> {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: Option<NodeId>) {
+        self.id = id;
+    }
+
+    pub fn metadata(&self) -> &NodeMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut NodeMetadata {
+        &mut self.metadata
+    }
+
+    // A mention is always treated as 1 character, so this always returns 1
+    pub fn text_len(&self) -> usize {
+        1
+    }
+}
+
+impl<S> ToHtml<S> for MentionNode<S>
+where
+    S: UnicodeString,
+{
+    fn fmt_html(
+        &self,
+        buf: &mut S,
+        selection_writer: Option<&mut SelectionWriter>,
+        _: bool,
+    ) {
+        let cur_pos = buf.len();
+        buf.push('<');
+        buf.push(self.name());
+        buf.push(" href=\"");
+        buf.push(&*html_escape::encode_double_quoted_attribute(
+            &self.url.to_string(),
+        ));
+        buf.push("\" contenteditable=\"false\"");
+        for (name, value) in &self.attrs {
+            buf.push(' ');
+            buf.push(name.clone());
+            buf.push("=\"");
+            buf.push(&*html_escape::encode_double_quoted_attribute(
+                &value.to_string(),
+            ));
+            buf.push('"');
+        }
+        buf.push('>');
+        buf.push(&*html_escape::encode_text(&self.display_text.to_string()));
+        buf.push("</");
+        buf.push(self.name());
+        buf.push('>');
+        if let Some(sel_writer) = selection_writer {
+            sel_writer.write_selection_atomic_inline_node(
+                buf,
+                cur_pos,
+                &self.handle,
+                self.text_len(),
+            );
+        }
+    }
+}
+
+impl<S> ToRawText<S> for MentionNode<S>
+where
+    S: UnicodeString,
+{
+    fn to_raw_text(&self) -> S {
+        self.display_text.clone()
+    }
+}
+
+impl<S> ToTree<S> for MentionNode<S>
+where
+    S: UnicodeString,
+{
+    fn to_tree_display(&self, continuous_positions: Vec<usize>) -> S {
+        let mut description = S::from("mention ");
+        description.push(text_preview(&self.display_text.to_string()).as_str());
+        description.push(" ");
+        description.push(self.url.clone());
+        description.push(self.tree_node_details(&self.handle, self.text_len()));
+        self.tree_line(
+            description,
+            self.handle.raw().len(),
+            continuous_positions,
+        )
+    }
+}
+
+impl<S> ToMarkdown<S> for MentionNode<S>
+where
+    S: UnicodeString,
+{
+    fn fmt_markdown(
+        &self,
+        buffer: &mut S,
+        _options: &MarkdownOptions,
+    ) -> Result<(), MarkdownError<S>> {
+        // Mentions are written as ordinary Markdown links, which clients
+        // turn back into pills when they see a matrix.to URL.
+        buffer.push('[');
+        buffer.push(self.display_text.clone());
+        buffer.push("](<");
+        buffer.push(self.url.clone());
+        buffer.push(">)");
+
+        Ok(())
+    }
+}
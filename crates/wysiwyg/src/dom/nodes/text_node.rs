@@ -14,21 +14,40 @@
 
 use crate::composer_model::example_format::SelectionWriter;
 use crate::dom::dom_handle::DomHandle;
+use crate::dom::node_id::NodeId;
+use crate::dom::node_metadata::NodeMetadata;
 use crate::dom::to_html::ToHtml;
 use crate::dom::to_markdown::{MarkdownError, MarkdownOptions, ToMarkdown};
 use crate::dom::to_raw_text::ToRawText;
-use crate::dom::to_tree::ToTree;
+use crate::dom::to_tree::{text_preview, ToTree};
 use crate::dom::unicode_string::{UnicodeStr, UnicodeStrExt, UnicodeStringExt};
 use crate::dom::UnicodeString;
 use html_escape;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct TextNode<S>
 where
     S: UnicodeString,
 {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::dom::serialization::string")
+    )]
     data: S,
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip, default = "DomHandle::new_unset")
+    )]
     handle: DomHandle,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    id: Option<NodeId>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "NodeMetadata::is_empty")
+    )]
+    metadata: NodeMetadata,
 }
 
 impl<S> TextNode<S>
@@ -43,6 +62,8 @@ where
         Self {
             data,
             handle: DomHandle::new_unset(),
+            id: None,
+            metadata: NodeMetadata::default(),
         }
     }
 
@@ -62,6 +83,22 @@ where
         self.handle = handle;
     }
 
+    pub fn id(&self) -> Option<NodeId> {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: Option<NodeId>) {
+        self.id = id;
+    }
+
+    pub fn metadata(&self) -> &NodeMetadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut NodeMetadata {
+        &mut self.metadata
+    }
+
     pub fn is_blank(&self) -> bool {
         self.data
             .chars()
@@ -116,10 +153,9 @@ where
     S: UnicodeString,
 {
     fn to_tree_display(&self, continuous_positions: Vec<usize>) -> S {
-        let mut description = S::from("\"");
-        let text = &self.data.to_string().replace('\u{200b}', "~");
-        description.push(text.as_str());
-        description.push('"');
+        let mut description = S::from("text ");
+        description.push(text_preview(&self.data.to_string()).as_str());
+        description.push(self.tree_node_details(&self.handle, self.data.len()));
         return self.tree_line(
             description,
             self.handle.raw().len(),
@@ -0,0 +1,192 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::UnicodeStr;
+use crate::dom::{Dom, UnicodeString};
+use crate::{GraphemeLocation, Location};
+
+/// What a platform counts text offsets in, e.g. Android and Web count
+/// UTF-16 code units and iOS counts user-perceived characters.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OffsetUnit {
+    Utf8Bytes,
+    Utf16CodeUnits,
+    Graphemes,
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// The offset of `location` counted in `unit`. A line break, mention or
+    /// image counts as one unit. A location inside a character counts from
+    /// its start, and a location past the end is clamped to the end.
+    pub fn offset_in(&self, location: Location, unit: OffsetUnit) -> usize {
+        let lengths = match unit {
+            OffsetUnit::Graphemes => {
+                return self.grapheme_location(location).into()
+            }
+            OffsetUnit::Utf8Bytes => self.char_lengths(char::len_utf8),
+            OffsetUnit::Utf16CodeUnits => self.char_lengths(char::len_utf16),
+        };
+        let target = usize::from(location);
+        let mut position = 0;
+        let mut offset = 0;
+        for (len, unit_len) in lengths {
+            if position + len > target {
+                break;
+            }
+            position += len;
+            offset += unit_len;
+        }
+        offset
+    }
+
+    /// The location of `offset` counted in `unit`, the inverse of
+    /// [Dom::offset_in].
+    pub fn location_of_offset(
+        &self,
+        offset: usize,
+        unit: OffsetUnit,
+    ) -> Location {
+        let lengths = match unit {
+            OffsetUnit::Graphemes => {
+                return self
+                    .location_of_grapheme(GraphemeLocation::from(offset))
+            }
+            OffsetUnit::Utf8Bytes => self.char_lengths(char::len_utf8),
+            OffsetUnit::Utf16CodeUnits => self.char_lengths(char::len_utf16),
+        };
+        let mut position = 0;
+        let mut unit_offset = 0;
+        for (len, unit_len) in lengths {
+            if unit_offset + unit_len > offset {
+                break;
+            }
+            position += len;
+            unit_offset += unit_len;
+        }
+        Location::from(position)
+    }
+
+    /// Convert an offset counted in `from` to one counted in `to`, e.g. a
+    /// selection from an iOS text view to the UTF-16 offsets the model
+    /// bindings take.
+    pub fn convert_offset(
+        &self,
+        offset: usize,
+        from: OffsetUnit,
+        to: OffsetUnit,
+    ) -> usize {
+        self.offset_in(self.location_of_offset(offset, from), to)
+    }
+
+    /// The length of each character of the content in code units of the
+    /// model and as measured by `unit_len`, in order
+    fn char_lengths(
+        &self,
+        unit_len: fn(char) -> usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.iter()
+            .filter(|(_, node)| !matches!(node, DomNode::Container(_)))
+            .flat_map(move |(_, node)| match node {
+                DomNode::Text(text) => text
+                    .data()
+                    .chars()
+                    .map(|c| (text.data().char_len(&c), unit_len(c)))
+                    .collect::<Vec<_>>(),
+                _ => vec![(node.text_len(), 1)],
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+    use crate::dom::Dom;
+    use crate::{Location, OffsetUnit};
+
+    fn dom(html: &str) -> Dom<Utf16String> {
+        parse(html).unwrap()
+    }
+
+    #[test]
+    fn offsets_of_plain_ascii_are_the_same_in_every_unit() {
+        let d = dom("abc");
+        for unit in [
+            OffsetUnit::Utf8Bytes,
+            OffsetUnit::Utf16CodeUnits,
+            OffsetUnit::Graphemes,
+        ] {
+            assert_eq!(d.offset_in(Location::from(2), unit), 2);
+            assert_eq!(d.location_of_offset(2, unit), 2);
+        }
+    }
+
+    #[test]
+    fn offsets_count_each_unit_of_a_character() {
+        // é is 2 bytes and 1 code unit, 😀 is 4 bytes and 2 code units
+        let d = dom("é😀b");
+        assert_eq!(d.offset_in(Location::from(3), OffsetUnit::Utf8Bytes), 6);
+        assert_eq!(
+            d.offset_in(Location::from(3), OffsetUnit::Utf16CodeUnits),
+            3
+        );
+        assert_eq!(d.offset_in(Location::from(3), OffsetUnit::Graphemes), 2);
+        assert_eq!(d.location_of_offset(6, OffsetUnit::Utf8Bytes), 3);
+        assert_eq!(d.location_of_offset(2, OffsetUnit::Graphemes), 3);
+    }
+
+    #[test]
+    fn offsets_inside_a_character_count_from_its_start() {
+        let d = dom("a😀b");
+        assert_eq!(d.location_of_offset(3, OffsetUnit::Utf8Bytes), 1);
+        assert_eq!(d.offset_in(Location::from(2), OffsetUnit::Utf8Bytes), 1);
+    }
+
+    #[test]
+    fn line_breaks_and_mentions_are_one_unit() {
+        let d = dom("é<br /><a href=\"https://matrix.to/#/@u:x.org\" \
+            contenteditable=\"false\">user</a>é");
+        assert_eq!(d.offset_in(Location::from(4), OffsetUnit::Utf8Bytes), 6);
+        assert_eq!(d.location_of_offset(6, OffsetUnit::Utf8Bytes), 4);
+    }
+
+    #[test]
+    fn offsets_convert_between_units() {
+        let d = dom("👍🏽 x");
+        assert_eq!(
+            d.convert_offset(2, OffsetUnit::Graphemes, OffsetUnit::Utf8Bytes),
+            9
+        );
+        assert_eq!(
+            d.convert_offset(
+                9,
+                OffsetUnit::Utf8Bytes,
+                OffsetUnit::Utf16CodeUnits
+            ),
+            5
+        );
+    }
+
+    #[test]
+    fn offsets_past_the_end_are_clamped() {
+        let d = dom("é");
+        assert_eq!(d.offset_in(Location::from(9), OffsetUnit::Utf8Bytes), 2);
+        assert_eq!(d.location_of_offset(9, OffsetUnit::Utf8Bytes), 1);
+    }
+}
@@ -36,6 +36,10 @@ mod panode_text;
 #[cfg(feature = "sys")]
 mod paqual_name;
 mod parse;
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+mod sanitize;
+mod tag_structure;
+mod whitespace;
 
 // Group all re-exports for `feature = "sys"`.
 #[cfg(feature = "sys")]
@@ -56,3 +60,7 @@ mod sys {
 use sys::*;
 
 pub use parse::parse;
+pub(crate) use parse::parse_content;
+pub(crate) use parse::parse_content_lenient;
+pub(crate) use parse::parse_external;
+pub use parse::parse_lenient;
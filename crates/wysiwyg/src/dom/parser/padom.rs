@@ -64,6 +64,36 @@ impl PaDom {
         &self.document_handle
     }
 
+    /// The children of the node with this handle, or None if it is text
+    pub(crate) fn get_mut_children(
+        &mut self,
+        handle: &PaDomHandle,
+    ) -> Option<&mut Vec<PaDomHandle>> {
+        match self.get_mut_node(handle) {
+            PaDomNode::Container(n) | PaDomNode::Document(n) => {
+                Some(&mut n.children)
+            }
+            PaDomNode::Text(_) | PaDomNode::Ignored => None,
+        }
+    }
+
+    /// Find the node that has the node with this handle as a child, if it
+    /// has been added to one
+    pub(crate) fn find_parent(
+        &self,
+        handle: &PaDomHandle,
+    ) -> Option<PaDomHandle> {
+        self.nodes
+            .iter()
+            .position(|node| match node {
+                PaDomNode::Container(n) | PaDomNode::Document(n) => {
+                    n.children.contains(handle)
+                }
+                PaDomNode::Text(_) | PaDomNode::Ignored => false,
+            })
+            .map(PaDomHandle)
+    }
+
     pub(crate) fn add_node(&mut self, node: PaDomNode) -> PaDomHandle {
         let handle = PaDomHandle(self.nodes.len());
         self.nodes.push(node);
@@ -40,6 +40,12 @@ impl PaDomCreator {
     }
 }
 
+impl PaDomCreator {
+    fn is_ignored(&self, handle: &PaDomHandle) -> bool {
+        matches!(self.state.dom.get_node(handle), PaDomNode::Ignored)
+    }
+}
+
 impl Default for PaDomCreator {
     fn default() -> Self {
         Self {
@@ -82,7 +88,7 @@ impl TreeSink for PaDomCreator {
     }
 
     fn create_comment(&mut self, _text: StrTendril) -> Self::Handle {
-        todo!("Comments not yet supported")
+        self.state.dom.add_node(PaDomNode::Ignored)
     }
 
     fn create_pi(
@@ -90,7 +96,7 @@ impl TreeSink for PaDomCreator {
         _target: StrTendril,
         _data: StrTendril,
     ) -> Self::Handle {
-        todo!("create_pi not yet supported")
+        self.state.dom.add_node(PaDomNode::Ignored)
     }
 
     fn append(
@@ -99,18 +105,19 @@ impl TreeSink for PaDomCreator {
         child: NodeOrText<Self::Handle>,
     ) {
         match child {
+            NodeOrText::AppendNode(child) if self.is_ignored(&child) => {}
             NodeOrText::AppendNode(child) => {
                 match self.state.dom.get_mut_node(parent) {
                     PaDomNode::Container(p) => p.children.push(child),
                     PaDomNode::Document(p) => p.children.push(child),
-                    PaDomNode::Text(_) => {
+                    PaDomNode::Text(_) | PaDomNode::Ignored => {
                         panic!("Appending node to text! {:?}", parent)
                     }
                 }
             }
             NodeOrText::AppendText(tendril) => {
                 let text_handle = match self.state.dom.get_node(parent) {
-                    PaDomNode::Document(_) => None,
+                    PaDomNode::Document(_) | PaDomNode::Ignored => None,
                     PaDomNode::Text(_) => Some(parent.clone()),
                     PaDomNode::Container(PaNodeContainer {
                         children, ..
@@ -144,7 +151,7 @@ impl TreeSink for PaDomCreator {
                     match self.state.dom.get_mut_node(parent) {
                         PaDomNode::Container(p) => p.children.push(new_handle),
                         PaDomNode::Document(p) => p.children.push(new_handle),
-                        PaDomNode::Text(_) => {
+                        PaDomNode::Text(_) | PaDomNode::Ignored => {
                             panic!("parent changed from container to text!")
                         }
                     }
@@ -155,11 +162,17 @@ impl TreeSink for PaDomCreator {
 
     fn append_based_on_parent_node(
         &mut self,
-        _element: &Self::Handle,
-        _prev_element: &Self::Handle,
-        _child: NodeOrText<Self::Handle>,
+        element: &Self::Handle,
+        prev_element: &Self::Handle,
+        child: NodeOrText<Self::Handle>,
     ) {
-        todo!("append_based_on_parent_node not yet supported")
+        // Content that isn't allowed where it was found, e.g. text directly
+        // inside a table, is moved to just before the table
+        if self.state.dom.find_parent(element).is_some() {
+            self.append_before_sibling(element, child);
+        } else {
+            self.append(prev_element, child);
+        }
     }
 
     fn append_doctype_to_document(
@@ -168,14 +181,14 @@ impl TreeSink for PaDomCreator {
         _public_id: StrTendril,
         _system_id: StrTendril,
     ) {
-        todo!("append_doctype_to_document not yet supported")
+        // A doctype has no content, so there is nothing to keep
     }
 
-    fn get_template_contents(
-        &mut self,
-        _target: &Self::Handle,
-    ) -> Self::Handle {
-        todo!("get_template_contents not yet supported")
+    fn get_template_contents(&mut self, target: &Self::Handle) -> Self::Handle {
+        // There is no separate fragment for the contents of a template, so
+        // they are added to the template itself, which is dropped later
+        // along with them
+        target.clone()
     }
 
     fn same_node(&self, x: &Self::Handle, y: &Self::Handle) -> bool {
@@ -188,10 +201,47 @@ impl TreeSink for PaDomCreator {
 
     fn append_before_sibling(
         &mut self,
-        _sibling: &Self::Handle,
-        _new_node: NodeOrText<Self::Handle>,
+        sibling: &Self::Handle,
+        new_node: NodeOrText<Self::Handle>,
     ) {
-        todo!("append_before_sibling not yet supported")
+        let parent =
+            self.state.dom.find_parent(sibling).expect(
+                "append_before_sibling called on a node with no parent",
+            );
+        let new_node = match new_node {
+            NodeOrText::AppendNode(node) if self.is_ignored(&node) => return,
+            NodeOrText::AppendNode(node) => {
+                self.remove_from_parent(&node);
+                node
+            }
+            NodeOrText::AppendText(tendril) => {
+                let previous =
+                    self.state.dom.get_mut_children(&parent).and_then(|c| {
+                        let index = c.iter().position(|h| h == sibling)?;
+                        index.checked_sub(1).map(|i| c[i].clone())
+                    });
+                let previous =
+                    previous.map(|h| self.state.dom.get_mut_node(&h));
+                if let Some(PaDomNode::Text(text)) = previous {
+                    // Join with the text already there
+                    text.content += tendril.as_ref();
+                    return;
+                }
+                self.state.dom.add_node(PaDomNode::Text(PaNodeText {
+                    content: tendril.as_ref().to_owned(),
+                }))
+            }
+        };
+        let children = self
+            .state
+            .dom
+            .get_mut_children(&parent)
+            .expect("Parent of a node must be a container");
+        let index = children
+            .iter()
+            .position(|h| h == sibling)
+            .expect("Sibling must be a child of its parent");
+        children.insert(index, new_node);
     }
 
     fn add_attrs_if_missing(
@@ -221,16 +271,30 @@ impl TreeSink for PaDomCreator {
         }
     }
 
-    fn remove_from_parent(&mut self, _target: &Self::Handle) {
-        todo!("remove_from_parent not yet supported")
+    fn remove_from_parent(&mut self, target: &Self::Handle) {
+        if let Some(parent) = self.state.dom.find_parent(target) {
+            if let Some(children) = self.state.dom.get_mut_children(&parent) {
+                children.retain(|h| h != target);
+            }
+        }
     }
 
     fn reparent_children(
         &mut self,
-        _node: &Self::Handle,
-        _new_parent: &Self::Handle,
+        node: &Self::Handle,
+        new_parent: &Self::Handle,
     ) {
-        todo!("reparent_children not yet supported")
+        let children = self
+            .state
+            .dom
+            .get_mut_children(node)
+            .map(std::mem::take)
+            .unwrap_or_default();
+        self.state
+            .dom
+            .get_mut_children(new_parent)
+            .expect("Can't move children into a text node")
+            .extend(children);
     }
 }
 
@@ -265,7 +329,7 @@ mod test {
                     PaDomNode::Document(p) => {
                         children.extend(p.children.iter().cloned());
                     }
-                    PaDomNode::Text(_) => {}
+                    PaDomNode::Text(_) | PaDomNode::Ignored => {}
                 }
                 for ch in children {
                     find_used(dom_container, deleted_indices, &ch)
@@ -318,7 +382,7 @@ mod test {
                             *c = remap_handle(&deleted_indices, c);
                         }
                     }
-                    PaDomNode::Text(_) => {}
+                    PaDomNode::Text(_) | PaDomNode::Ignored => {}
                 }
             }
 
@@ -354,7 +418,9 @@ mod test {
                 PaDomNode::Document(p) => {
                     p.children.push(child.clone());
                 }
-                PaDomNode::Text(_) => panic!("Parent can't be a text node"),
+                PaDomNode::Text(_) | PaDomNode::Ignored => {
+                    panic!("Parent can't be a text node")
+                }
             }
 
             for ch in test_node.children {
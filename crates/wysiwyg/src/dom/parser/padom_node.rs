@@ -24,6 +24,9 @@ pub(crate) enum PaDomNode {
     Container(PaNodeContainer),
     Document(PaNodeContainer),
     Text(PaNodeText),
+    /// A comment or processing instruction. These are never added to a
+    /// parent, so they are dropped from the document.
+    Ignored,
 }
 
 impl PaDomNode {
@@ -31,7 +34,7 @@ impl PaDomNode {
         match self {
             PaDomNode::Container(n) => &n.name,
             PaDomNode::Document(n) => &n.name,
-            PaDomNode::Text(_) => q(&TEXT, ""),
+            PaDomNode::Text(_) | PaDomNode::Ignored => q(&TEXT, ""),
         }
     }
 }
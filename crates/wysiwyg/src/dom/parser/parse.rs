@@ -12,18 +12,216 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dom::{Dom, DomCreationError, UnicodeString};
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+use super::sanitize::{
+    apply_style_formatting, is_cancelled_formatting, is_dropped_tag,
+    is_safe_attribute, is_safe_url, is_source_formatting,
+    sanitize_unknown_element,
+};
+use super::tag_structure::{check_tag_structure, find_tag_structure_errors};
+use super::whitespace::normalize_whitespace;
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+use crate::dom::nodes::{ContainerNode, DomNode};
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+use crate::dom::ParseError;
+use crate::dom::{
+    Dom, DomCreationError, LenientParse, ParseCorrection, UnicodeString,
+};
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+use crate::{ListNumberingType, ListType};
+
+/// Attributes that a node of the kind created for `tag` manages itself, so
+/// they aren't copied over from the HTML as they are
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+fn managed_attributes(tag: &str) -> &'static [&'static str] {
+    match tag {
+        "a" => &["href", "contenteditable"],
+        "ol" => &["start", "type", "reversed"],
+        _ => &[],
+    }
+}
+
+/// Copy the `attributes` of an element with the lowercase `tag` onto the
+/// node created for it, except those the node manages itself and those
+/// that aren't safe. Inline styles of HTML from outside the editor have
+/// already been turned into formatting, so they are dropped too.
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+fn copy_attributes<'a, S>(
+    tag: &str,
+    attributes: impl IntoIterator<Item = (&'a str, &'a str)>,
+    node: &mut ContainerNode<S>,
+    sanitize: bool,
+) where
+    S: UnicodeString,
+{
+    let managed = managed_attributes(tag);
+    for (name, value) in attributes {
+        let is_dropped_style = sanitize && name == "style";
+        if !managed.contains(&name)
+            && !is_dropped_style
+            && is_safe_attribute(name, value)
+        {
+            node.set_attribute(name.into(), value.into());
+        }
+    }
+}
+
+/// Links that can't be edited are mentions, which we keep as a single unit
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+fn is_mention(contenteditable: Option<&str>) -> bool {
+    contenteditable == Some("false")
+}
+
+/// Create an ordered list, keeping its `start` and `type` attributes if
+/// they are valid
+#[cfg(any(feature = "sys", all(feature = "js", target_arch = "wasm32")))]
+fn new_ordered_list<S>(
+    start: Option<&str>,
+    numbering_type: Option<&str>,
+    children: Vec<DomNode<S>>,
+) -> DomNode<S>
+where
+    S: UnicodeString,
+{
+    let mut list = ContainerNode::new_list(ListType::Ordered, children);
+    list.set_list_start(start.and_then(|s| s.trim().parse().ok()));
+    list.set_list_numbering_type(
+        numbering_type.and_then(ListNumberingType::from_attribute_value),
+    );
+    DomNode::Container(list)
+}
 
 pub fn parse<S>(html: &str) -> Result<Dom<S>, DomCreationError<S>>
+where
+    S: UnicodeString,
+{
+    parse_with(html, false)
+}
+
+/// Parse HTML that is loaded as the content of the editor, e.g. the
+/// `formatted_body` of a message. Whitespace is collapsed as a browser
+/// would show it, so the indentation and line breaks of the HTML source
+/// don't become spaces, except inside `<pre>` where it is kept as it is.
+pub(crate) fn parse_content<S>(
+    html: &str,
+) -> Result<Dom<S>, DomCreationError<S>>
+where
+    S: UnicodeString,
+{
+    match parse_with(html, false) {
+        Ok(mut dom) => {
+            normalize_whitespace(&mut dom);
+            Ok(dom)
+        }
+        Err(mut e) => {
+            normalize_whitespace(&mut e.dom);
+            Err(e)
+        }
+    }
+}
+
+/// Parse HTML that may be malformed, e.g. from an incoming event, without
+/// failing. Problems with how its tags are opened, closed and nested are
+/// corrected by these rules, the same whichever parser is used:
+/// - a closing tag with no matching open tag is dropped
+/// - a closing tag first closes the tags opened inside its element that are
+///   still open
+/// - a link inside a link, or a block like `<div>` inside a paragraph,
+///   first closes the outer link or paragraph, and anything open inside it
+/// - tags still open at the end are closed there, innermost first
+///
+/// Tags whose closing tag may be left out, like `<li>`, are closed as a
+/// browser would without it being counted as a correction. Anything else
+/// the parser has to recover from is listed among the corrections too,
+/// without a position.
+pub fn parse_lenient<S>(html: &str) -> LenientParse<S>
+where
+    S: UnicodeString,
+{
+    let structure = check_tag_structure(html);
+    let mut corrections: Vec<ParseCorrection> = structure
+        .problems
+        .into_iter()
+        .map(|(_, correction)| correction)
+        .collect();
+    let dom = match parse_with_backend(&structure.corrected_html, false) {
+        Ok(dom) => dom,
+        Err(e) => {
+            corrections.extend(e.parse_errors.into_iter().map(|error| {
+                ParseCorrection {
+                    position: None,
+                    description: format!("Recovered from: {}", error),
+                }
+            }));
+            *e.dom
+        }
+    };
+    LenientParse { dom, corrections }
+}
+
+/// Like [parse_lenient], collapsing whitespace like [parse_content]
+pub(crate) fn parse_content_lenient<S>(html: &str) -> LenientParse<S>
+where
+    S: UnicodeString,
+{
+    let mut parsed = parse_lenient(html);
+    normalize_whitespace(&mut parsed.dom);
+    parsed
+}
+
+/// Parse HTML that didn't come from the editor, e.g. pasted from another
+/// app. Scripts and other invisible content are dropped, formatting from
+/// inline styles is kept, and other unknown tags are replaced by their
+/// content instead of being ignored.
+pub(crate) fn parse_external<S>(
+    html: &str,
+) -> Result<Dom<S>, DomCreationError<S>>
+where
+    S: UnicodeString,
+{
+    parse_with(html, true)
+}
+
+/// Parse `html` with whichever parser is enabled. If the HTML is malformed
+/// the error holds the Dom the parser recovered, and the problems found:
+/// first those with the structure of its tags, which say where they are,
+/// then anything else the parser reported.
+fn parse_with<S>(
+    html: &str,
+    sanitize: bool,
+) -> Result<Dom<S>, DomCreationError<S>>
+where
+    S: UnicodeString,
+{
+    let mut parse_errors = find_tag_structure_errors(html);
+    match parse_with_backend(html, sanitize) {
+        Ok(dom) if parse_errors.is_empty() => Ok(dom),
+        Ok(dom) => Err(DomCreationError {
+            dom: Box::new(dom),
+            parse_errors,
+        }),
+        Err(mut e) => {
+            parse_errors.append(&mut e.parse_errors);
+            e.parse_errors = parse_errors;
+            Err(e)
+        }
+    }
+}
+
+fn parse_with_backend<S>(
+    html: &str,
+    sanitize: bool,
+) -> Result<Dom<S>, DomCreationError<S>>
 where
     S: UnicodeString,
 {
     cfg_if::cfg_if! {
         if #[cfg(feature = "sys")] {
-            sys::parse(html)
+            sys::parse(html, sanitize)
         } else if #[cfg(all(feature = "js", target_arch = "wasm32"))] {
-            js::parse(html)
+            js::parse(html, sanitize)
         } else {
+            let _ = (html, sanitize);
             unreachable!("The `sys` or `js` are mutually exclusive, and one of them must be enabled.")
         }
     }
@@ -35,16 +233,21 @@ mod sys {
     use super::super::PaNodeContainer;
     use super::super::{PaDom, PaDomCreationError, PaDomCreator};
     use super::*;
-    use crate::dom::nodes::{ContainerNode, DomNode};
+    use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode};
     use crate::ListType;
 
-    pub(super) fn parse<S>(html: &str) -> Result<Dom<S>, DomCreationError<S>>
+    pub(super) fn parse<S>(
+        html: &str,
+        sanitize: bool,
+    ) -> Result<Dom<S>, DomCreationError<S>>
     where
         S: UnicodeString,
     {
         PaDomCreator::parse(html)
-            .map(padom_to_dom)
-            .map_err(padom_creation_error_to_dom_creation_error)
+            .map(|padom| padom_to_dom(padom, sanitize))
+            .map_err(|e| {
+                padom_creation_error_to_dom_creation_error(e, sanitize)
+            })
     }
 
     /// Convert a [PaDom] into a [Dom].
@@ -59,20 +262,33 @@ mod sys {
     ///
     /// [Dom] is for general use. Parent nodes own their children, and Dom may be
     /// cloned, compared, and converted into an HTML string.
-    fn padom_to_dom<S>(padom: PaDom) -> Dom<S>
+    fn padom_to_dom<S>(padom: PaDom, sanitize: bool) -> Dom<S>
     where
         S: UnicodeString,
     {
-        /// Recurse into panode's children and convert them too
+        /// Copy panode's attributes onto the node created for it, then
+        /// recurse into its children and convert them too. The formatting
+        /// asked for by the inline style of HTML from outside the editor is
+        /// applied to them.
         fn convert_children<S>(
             padom: &PaDom,
             child: &PaNodeContainer,
             new_node: Option<&mut DomNode<S>>,
+            sanitize: bool,
         ) where
             S: UnicodeString,
         {
             if let DomNode::Container(new_node) = new_node.unwrap() {
-                convert(padom, child, new_node);
+                copy_attributes(
+                    child.name.local.as_ref(),
+                    child.attrs.iter().map(|(n, v)| (n.as_str(), v.as_str())),
+                    new_node,
+                    sanitize,
+                );
+                convert(padom, child, new_node, sanitize);
+                if sanitize {
+                    apply_style_formatting(new_node, child.get_attr("style"));
+                }
             } else {
                 panic!("Container became non-container!");
             }
@@ -102,10 +318,59 @@ mod sys {
         where
             S: UnicodeString,
         {
-            DomNode::Container(ContainerNode::new_link(
+            DomNode::new_link(
                 child.get_attr("href").unwrap_or("").into(),
                 Vec::new(),
-            ))
+            )
+        }
+
+        /// Create a mention node, using the text inside the tag as its
+        /// display text and keeping its `class` and `data-*` attributes
+        fn new_mention<S>(padom: &PaDom, child: &PaNodeContainer) -> DomNode<S>
+        where
+            S: UnicodeString,
+        {
+            DomNode::new_mention_with_attributes(
+                child.get_attr("href").unwrap_or("").into(),
+                text_content(padom, child).into(),
+                child
+                    .attrs
+                    .iter()
+                    .map(|(n, v)| (n.as_str().into(), v.as_str().into()))
+                    .collect(),
+            )
+        }
+
+        /// Collect all the text inside panode and its descendants
+        fn text_content(padom: &PaDom, panode: &PaNodeContainer) -> String {
+            let mut text = String::new();
+            for child_handle in &panode.children {
+                match padom.get_node(child_handle) {
+                    PaDomNode::Container(child) => {
+                        text.push_str(&text_content(padom, child));
+                    }
+                    PaDomNode::Document(_) => {
+                        panic!("Found a document inside a document!")
+                    }
+                    PaDomNode::Text(t) => text.push_str(&t.content),
+                    PaDomNode::Ignored => {}
+                }
+            }
+            text
+        }
+
+        /// Create an image node
+        fn new_image<S>(child: &PaNodeContainer) -> DomNode<S>
+        where
+            S: UnicodeString,
+        {
+            DomNode::new_image(
+                child
+                    .attrs
+                    .iter()
+                    .map(|(n, v)| (n.as_str().into(), v.as_str().into()))
+                    .collect(),
+            )
         }
 
         /// Create a list node
@@ -130,43 +395,232 @@ mod sys {
             ))
         }
 
+        /// Create a table node
+        fn new_table<S>() -> DomNode<S>
+        where
+            S: UnicodeString,
+        {
+            DomNode::new_table(Vec::new())
+        }
+
+        /// Create a table row node
+        fn new_table_row<S>() -> DomNode<S>
+        where
+            S: UnicodeString,
+        {
+            DomNode::new_table_row(Vec::new())
+        }
+
+        /// Create a table cell node
+        fn new_table_cell<S>(tag: &str) -> DomNode<S>
+        where
+            S: UnicodeString,
+        {
+            DomNode::new_table_cell(tag.into(), Vec::new())
+        }
+
+        /// The children of panode that are elements called `tag`
+        fn child_elements<'a>(
+            padom: &'a PaDom,
+            panode: &'a PaNodeContainer,
+            tag: &'a str,
+        ) -> impl Iterator<Item = &'a PaNodeContainer> {
+            panode.children.iter().filter_map(move |handle| {
+                match padom.get_node(handle) {
+                    PaDomNode::Container(c) if c.name.local.as_ref() == tag => {
+                        Some(c)
+                    }
+                    _ => None,
+                }
+            })
+        }
+
+        /// Add the content of a table's caption to node as a paragraph,
+        /// because there is no caption node to keep it in
+        fn convert_caption<S>(
+            padom: &PaDom,
+            caption: &PaNodeContainer,
+            node: &mut ContainerNode<S>,
+            sanitize: bool,
+        ) where
+            S: UnicodeString,
+        {
+            let mut content = Dom::new(Vec::new());
+            convert(padom, caption, content.document_mut(), sanitize);
+            if sanitize {
+                for new_node in
+                    sanitize_unknown_element("p", None, content.take_children())
+                {
+                    node.append_child(new_node);
+                }
+            } else {
+                node.append_child(DomNode::new_unknown(
+                    "p".into(),
+                    content.take_children(),
+                ));
+            }
+        }
+
         /// Copy all panode's information into node (now we know it's a container).
         fn convert_container<S>(
             padom: &PaDom,
             child: &PaNodeContainer,
             node: &mut ContainerNode<S>,
+            sanitize: bool,
         ) where
             S: UnicodeString,
         {
             let tag = child.name.local.as_ref();
             match tag {
+                "b" | "code" | "del" | "em" | "i" | "strong" | "u"
+                    if sanitize
+                        && is_cancelled_formatting(
+                            tag,
+                            child.get_attr("style"),
+                        ) =>
+                {
+                    let mut content = Dom::new(Vec::new());
+                    convert(padom, child, content.document_mut(), sanitize);
+                    for new_node in sanitize_unknown_element(
+                        "span",
+                        child.get_attr("style"),
+                        content.take_children(),
+                    ) {
+                        node.append_child(new_node);
+                    }
+                }
                 "b" | "code" | "del" | "em" | "i" | "strong" | "u" => {
                     node.append_child(new_formatting(tag));
-                    convert_children(padom, child, node.last_child_mut());
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
                 }
                 "br" => {
                     node.append_child(new_line_break());
                 }
-                "ol" | "ul" => {
+                "img"
+                    if sanitize
+                        && !is_safe_url(
+                            child.get_attr("src").unwrap_or(""),
+                        ) => {}
+                "img" => {
+                    node.append_child(new_image(child));
+                }
+                "ol" => {
+                    node.append_child(new_ordered_list(
+                        child.get_attr("start"),
+                        child.get_attr("type"),
+                        Vec::new(),
+                    ));
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
+                }
+                "ul" => {
                     node.append_child(new_list(tag));
-                    convert_children(padom, child, node.last_child_mut());
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
                 }
                 "li" => {
                     node.append_child(new_list_item(tag));
-                    convert_children(padom, child, node.last_child_mut());
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
+                }
+                "a" if is_mention(child.get_attr("contenteditable")) => {
+                    node.append_child(new_mention(padom, child));
+                }
+                "a" if sanitize
+                    && !is_safe_url(child.get_attr("href").unwrap_or("")) =>
+                {
+                    // Keep the text of a link we can't trust
+                    convert(padom, child, node, sanitize);
                 }
                 "a" => {
                     node.append_child(new_link(child));
-                    convert_children(padom, child, node.last_child_mut());
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
+                }
+                "table" => {
+                    for caption in child_elements(padom, child, "caption") {
+                        convert_caption(padom, caption, node, sanitize);
+                    }
+                    node.append_child(new_table());
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
                 }
-                "html" => {
-                    // Skip the html tag - add its children to the
-                    // current node directly.
-                    convert(padom, child, node);
+                "tr" => {
+                    node.append_child(new_table_row());
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
                 }
+                "td" | "th" => {
+                    node.append_child(new_table_cell(tag));
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
+                }
+                "caption" | "colgroup" => {
+                    // A caption was moved before its table, and the columns
+                    // of a table have no node of their own
+                }
+                "html" | "tbody" | "thead" | "tfoot" => {
+                    // Skip the html tag, and the implied sections of a table
+                    // - add their children to the current node directly.
+                    convert(padom, child, node, sanitize);
+                }
+                _ if sanitize => {
+                    let mut content = Dom::new(Vec::new());
+                    convert(padom, child, content.document_mut(), sanitize);
+                    for new_node in sanitize_unknown_element(
+                        tag,
+                        child.get_attr("style"),
+                        content.take_children(),
+                    ) {
+                        node.append_child(new_node);
+                    }
+                }
+                _ if is_dropped_tag(tag) => {}
                 _ => {
-                    // Ignore tags we don't recognise
-                    // We should log - see internal task PSU-741
+                    // Keep tags we don't recognise as they are
+                    node.append_child(DomNode::new_unknown(
+                        tag.into(),
+                        Vec::new(),
+                    ));
+                    convert_children(
+                        padom,
+                        child,
+                        node.last_child_mut(),
+                        sanitize,
+                    );
                 }
             };
         }
@@ -176,6 +630,7 @@ mod sys {
             padom: &PaDom,
             panode: &PaNodeContainer,
             node: &mut ContainerNode<S>,
+            sanitize: bool,
         ) where
             S: UnicodeString,
         {
@@ -183,16 +638,30 @@ mod sys {
                 let child = padom.get_node(child_handle);
                 match child {
                     PaDomNode::Container(child) => {
-                        convert_container(padom, child, node);
+                        convert_container(padom, child, node, sanitize);
                     }
                     PaDomNode::Document(_) => {
                         panic!("Found a document inside a document!")
                     }
                     PaDomNode::Text(text) => {
-                        node.append_child(DomNode::new_text(
-                            text.content.as_str().into(),
-                        ));
+                        // Whitespace between the tags of a table is not
+                        // content, so we drop it.
+                        let in_table_structure = matches!(
+                            node.kind(),
+                            ContainerNodeKind::Table
+                                | ContainerNodeKind::TableRow
+                        );
+                        let is_formatting = (in_table_structure
+                            && text.content.trim().is_empty())
+                            || (sanitize
+                                && is_source_formatting(&text.content));
+                        if !is_formatting {
+                            node.append_child(DomNode::new_text(
+                                text.content.as_str().into(),
+                            ));
+                        }
                     }
+                    PaDomNode::Ignored => {}
                 }
             }
         }
@@ -201,7 +670,7 @@ mod sys {
         let doc = ret.document_mut();
 
         if let PaDomNode::Document(padoc) = padom.get_document() {
-            convert(&padom, padoc, doc)
+            convert(&padom, padoc, doc, sanitize)
         } else {
             panic!("Document was not a document!");
         }
@@ -211,13 +680,18 @@ mod sys {
 
     fn padom_creation_error_to_dom_creation_error<S>(
         e: PaDomCreationError,
+        sanitize: bool,
     ) -> DomCreationError<S>
     where
         S: UnicodeString,
     {
         DomCreationError {
-            dom: padom_to_dom(e.dom),
-            parse_errors: e.parse_errors,
+            dom: Box::new(padom_to_dom(e.dom, sanitize)),
+            parse_errors: e
+                .parse_errors
+                .into_iter()
+                .map(ParseError::other)
+                .collect(),
         }
     }
 
@@ -227,9 +701,9 @@ mod sys {
         use widestring::Utf16String;
 
         use crate::tests::testutils_composer_model::restore_whitespace;
-        use crate::ToHtml;
+        use crate::{ParseErrorKind, ToHtml};
 
-        use super::parse;
+        use super::super::{parse, parse_external};
 
         trait Roundtrips<T> {
             fn roundtrips(&self);
@@ -281,10 +755,247 @@ mod sys {
                 .roundtrips();
         }
 
+        #[test]
+        fn parse_link_with_extra_attributes() {
+            assert_that!(
+                r#"<a href="https://matrix.org" target="_blank" rel="noopener" title="Matrix">Matrix</a>"#
+            )
+            .roundtrips();
+        }
+
+        #[test]
+        fn parse_mention() {
+            assert_that!(
+                r#"Hi <a href="https://matrix.to/#/@alice:matrix.org" contenteditable="false">Alice</a>!"#
+            )
+            .roundtrips();
+        }
+
+        #[test]
+        fn parse_image() {
+            assert_that!(
+                r#"a<img src="mxc://matrix.org/abc" alt="blob" width="32" height="32" />b"#
+            )
+            .roundtrips();
+        }
+
+        #[test]
+        fn parse_ordered_list_numbering() {
+            assert_that!(r#"<ol start="3" type="i"><li>a</li></ol>"#)
+                .roundtrips();
+        }
+
+        #[test]
+        fn parse_ordered_list_drops_invalid_numbering() {
+            let dom = parse::<Utf16String>(
+                r#"<ol start="x" type="q" reversed=""><li>a</li></ol>"#,
+            )
+            .unwrap();
+            assert_eq!(dom.to_html().to_string(), "<ol><li>a</li></ol>");
+        }
+
+        #[test]
+        fn parse_table() {
+            assert_that!(
+                "<table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>2</td></tr></table>"
+            )
+            .roundtrips();
+        }
+
+        #[test]
+        fn parse_table_drops_sections_and_whitespace() {
+            let dom = parse::<Utf16String>(
+                "<table>\n<tbody>\n<tr>\n<td>a</td>\n</tr>\n</tbody>\n</table>",
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                "<table><tr><td>a</td></tr></table>"
+            );
+        }
+
+        #[test]
+        fn parse_table_flattens_header_and_body_sections() {
+            let dom = parse::<Utf16String>(
+                "<table><thead><tr><th>a</th></tr></thead>\
+                <tbody><tr><td>1</td></tr></tbody>\
+                <tfoot><tr><td>2</td></tr></tfoot></table>",
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                "<table><tr><th>a</th></tr><tr><td>1</td></tr>\
+                <tr><td>2</td></tr></table>"
+            );
+        }
+
+        #[test]
+        fn parse_table_moves_its_caption_before_it() {
+            let dom = parse::<Utf16String>(
+                "<table><caption>Totals</caption><colgroup><col /></colgroup>\
+                <tr><td>a</td></tr></table>",
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                "<p>Totals</p><table><tr><td>a</td></tr></table>"
+            );
+        }
+
+        #[test]
+        fn parse_table_moves_content_outside_its_cells_before_it() {
+            let dom = parse::<Utf16String>(
+                "<table>a<tr><td>1</td>b</tr><b>c</b></table>",
+            )
+            .unwrap_or_else(|e| *e.dom);
+            assert_eq!(
+                dom.to_html().to_string(),
+                "ab<b>c</b><table><tr><td>1</td></tr></table>"
+            );
+        }
+
+        #[test]
+        fn parse_misnested_formatting_does_not_panic() {
+            let dom = parse::<Utf16String>("<b>a<i>b</b>c</i>")
+                .unwrap_or_else(|e| *e.dom);
+            assert_eq!(dom.to_html().to_string(), "<b>a<i>b</i></b><i>c</i>");
+        }
+
+        #[test]
+        fn parse_external_moves_a_table_caption_before_it() {
+            let dom = parse_external::<Utf16String>(
+                "<table><caption><em>Totals</em></caption>\
+                <tr><td>a</td></tr></table>",
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                "<em>Totals</em><br /><table><tr><td>a</td></tr></table>"
+            );
+        }
+
+        #[test]
+        fn parse_link_drops_unknown_attributes() {
+            let dom = parse::<Utf16String>(
+                r#"<a href="https://matrix.org" onclick="x()">Matrix</a>"#,
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                r#"<a href="https://matrix.org">Matrix</a>"#
+            );
+        }
+
+        #[test]
+        fn parse_keeps_extra_attributes_of_containers() {
+            assert_that!(
+                r#"<code class="language-rust">fn</code> <ul class="x"><li data-mx-id="1">a</li></ul>"#
+            )
+            .roundtrips();
+            assert_that!(
+                r#"<ol start="3" data-mx-x="y"><li>a</li></ol><table class="t"><tr><td colspan="2">a</td></tr></table>"#
+            )
+            .roundtrips();
+        }
+
+        #[test]
+        fn parse_keeps_class_and_data_attributes_of_spoilers_and_colours() {
+            assert_that!(
+                r##"<span data-mx-spoiler="plot" class="s">a</span><font data-mx-color="#ff0000">b</font>"##
+            )
+            .roundtrips();
+        }
+
+        #[test]
+        fn parse_keeps_class_and_data_attributes_of_mentions() {
+            assert_that!(
+                r#"<a href="https://matrix.to/#/@alice:matrix.org" contenteditable="false" class="pill" data-mention-type="user">Alice</a>"#
+            )
+            .roundtrips();
+        }
+
+        #[test]
+        fn parse_keeps_class_and_data_attributes_of_images() {
+            let dom = parse::<Utf16String>(
+                r#"<img data-mx-emoticon="" class="e" src="mxc://a/b" alt=":b:" title="x" />"#,
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                r#"<img src="mxc://a/b" alt=":b:" data-mx-emoticon="" class="e" />"#
+            );
+        }
+
+        #[test]
+        fn parse_escapes_attribute_values() {
+            assert_that!(r#"<a href="https://matrix.org/?a=1&amp;b=2" title="&quot;M&quot;">M</a>"#)
+                .roundtrips();
+        }
+
+        #[test]
+        fn parse_external_drops_styles_kept_as_formatting() {
+            let dom = parse_external::<Utf16String>(
+                r#"<code class="x" style="color: red" onclick="y()">a</code>"#,
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                r#"<code class="x">a</code>"#
+            );
+        }
+
+        #[test]
+        fn parse_keeps_unknown_tags() {
+            assert_that!(r#"<details open=""><summary>a</summary>b</details>"#)
+                .roundtrips();
+        }
+
         #[test]
         fn parse_br_tag() {
             assert_that!("<br />").roundtrips();
         }
+
+        #[test]
+        fn parse_errors_say_where_the_problem_is() {
+            let e = parse::<Utf16String>("a<b>b<i>c</b>").unwrap_err();
+            assert_eq!(e.dom.to_html().to_string(), "a<b>b<i>c</i></b>");
+            assert_eq!(
+                e.parse_errors[0].kind,
+                ParseErrorKind::DisallowedNesting
+            );
+            assert_eq!(e.parse_errors[0].position, Some(9));
+            assert_eq!(
+                e.parse_errors[0].to_string(),
+                "</b> closes <b> while <i>, opened at byte 5, is still open \
+                at byte 9"
+            );
+            // Followed by what html5ever found, which doesn't say where
+            let last = e.parse_errors.last().unwrap();
+            assert_eq!(last.kind, ParseErrorKind::Other);
+            assert_eq!(last.position, None);
+        }
+
+        #[test]
+        fn parse_external_replaces_unknown_tags_with_their_content() {
+            let dom = parse_external::<Utf16String>(
+                "<p><span>a</span><script>b</script></p>",
+            )
+            .unwrap();
+            assert_eq!(dom.to_html().to_string(), "a<br />");
+        }
+
+        #[test]
+        fn parse_external_drops_doctypes_and_templates() {
+            // A doctype out of place is reported, but doesn't stop the
+            // rest of the content being parsed
+            let e = parse_external::<Utf16String>(
+                "<!DOCTYPE html><template><b>x</b></template>a",
+            )
+            .unwrap_err();
+            assert_eq!(e.dom.to_html().to_string(), "a");
+            let dom = parse::<Utf16String>("<template>x</template>a").unwrap();
+            assert_eq!(dom.to_html().to_string(), "a");
+        }
     }
 }
 
@@ -297,9 +1008,14 @@ mod js {
     };
     use std::fmt;
     use wasm_bindgen::JsCast;
-    use web_sys::{Document, DomParser, Element, NodeList, SupportedType};
+    use web_sys::{
+        Document, DomParser, Element, Node, NodeList, SupportedType,
+    };
 
-    pub(super) fn parse<S>(html: &str) -> Result<Dom<S>, DomCreationError<S>>
+    pub(super) fn parse<S>(
+        html: &str,
+        sanitize: bool,
+    ) -> Result<Dom<S>, DomCreationError<S>>
     where
         S: UnicodeString,
     {
@@ -317,16 +1033,19 @@ mod js {
                 )
             })?;
 
-        webdom_to_dom(document).map_err(to_dom_creation_error)
+        webdom_to_dom(document, sanitize).map_err(to_dom_creation_error)
     }
 
-    fn webdom_to_dom<S>(webdoc: Document) -> Result<Dom<S>, Error>
+    fn webdom_to_dom<S>(
+        webdoc: Document,
+        sanitize: bool,
+    ) -> Result<Dom<S>, Error>
     where
         S: UnicodeString,
     {
         let body = webdoc.body().ok_or_else(|| Error::NoBody)?;
 
-        fn convert<S>(nodes: NodeList) -> Result<Dom<S>, Error>
+        fn convert<S>(nodes: NodeList, sanitize: bool) -> Result<Dom<S>, Error>
         where
             S: UnicodeString,
         {
@@ -334,14 +1053,97 @@ mod js {
             let mut dom = Dom::new(Vec::with_capacity(number_of_nodes));
             let dom_document = dom.document_mut();
 
-            convert_container(nodes, dom_document)?;
+            convert_container(nodes, dom_document, sanitize)?;
 
             Ok(dom)
         }
 
+        /// The names and values of all the attributes of `element`
+        fn attributes_of<S>(element: &Element) -> Vec<(S, S)>
+        where
+            S: UnicodeString,
+        {
+            let attributes = element.attributes();
+            (0..attributes.length())
+                .filter_map(|i| attributes.item(i))
+                .map(|attr| {
+                    (attr.name().as_str().into(), attr.value().as_str().into())
+                })
+                .collect()
+        }
+
+        /// Copy the attributes of `element` onto the node created for it,
+        /// and apply the formatting its inline style asks for if it is from
+        /// outside the editor
+        fn with_attributes<S>(
+            mut node: DomNode<S>,
+            element: &Element,
+            sanitize: bool,
+        ) -> DomNode<S>
+        where
+            S: UnicodeString,
+        {
+            if let DomNode::Container(container) = &mut node {
+                let attributes = element.attributes();
+                let attributes: Vec<(String, String)> = (0..attributes
+                    .length())
+                    .filter_map(|i| attributes.item(i))
+                    .map(|attr| (attr.name(), attr.value()))
+                    .collect();
+                copy_attributes(
+                    &element.tag_name().to_lowercase(),
+                    attributes.iter().map(|(n, v)| (n.as_str(), v.as_str())),
+                    container,
+                    sanitize,
+                );
+                if sanitize {
+                    apply_style_formatting(
+                        container,
+                        element.get_attribute("style").as_deref(),
+                    );
+                }
+            }
+            node
+        }
+
+        fn without_blank_text<S>(nodes: Vec<DomNode<S>>) -> Vec<DomNode<S>>
+        where
+            S: UnicodeString,
+        {
+            nodes
+                .into_iter()
+                .filter(
+                    |node| !matches!(node, DomNode::Text(t) if t.is_blank()),
+                )
+                .collect()
+        }
+
+        /// Add the content of a table's caption to dom as a paragraph,
+        /// because there is no caption node to keep it in
+        fn convert_caption<S>(
+            caption: Node,
+            dom: &mut ContainerNode<S>,
+            sanitize: bool,
+        ) -> Result<(), Error>
+        where
+            S: UnicodeString,
+        {
+            let children =
+                convert(caption.child_nodes(), sanitize)?.take_children();
+            if sanitize {
+                for new_node in sanitize_unknown_element("p", None, children) {
+                    dom.append_child(new_node);
+                }
+            } else {
+                dom.append_child(DomNode::new_unknown("p".into(), children));
+            }
+            Ok(())
+        }
+
         fn convert_container<S>(
             nodes: NodeList,
             dom: &mut ContainerNode<S>,
+            sanitize: bool,
         ) -> Result<(), Error>
         where
             S: UnicodeString,
@@ -356,6 +1158,27 @@ mod js {
                         dom.append_child(DomNode::new_line_break());
                     }
 
+                    "IMG"
+                        if sanitize
+                            && !is_safe_url(
+                                &node
+                                    .unchecked_ref::<Element>()
+                                    .get_attribute("src")
+                                    .unwrap_or_default(),
+                            ) => {}
+
+                    "IMG" => {
+                        dom.append_child(DomNode::new_image(attributes_of(
+                            node.unchecked_ref::<Element>(),
+                        )));
+                    }
+
+                    "#text"
+                        if sanitize
+                            && is_source_formatting(
+                                &node.node_value().unwrap_or_default(),
+                            ) => {}
+
                     "#text" => {
                         dom.append_child(match node.node_value() {
                             Some(value) => {
@@ -365,64 +1188,218 @@ mod js {
                         });
                     }
 
-                    "A" => {
-                        dom.append_child(DomNode::new_link(
-                            node.unchecked_ref::<Element>()
+                    "A" if is_mention(
+                        node.unchecked_ref::<Element>()
+                            .get_attribute("contenteditable")
+                            .as_deref(),
+                    ) =>
+                    {
+                        let element = node.unchecked_ref::<Element>();
+                        dom.append_child(DomNode::new_mention_with_attributes(
+                            element
                                 .get_attribute("href")
                                 .unwrap_or_default()
                                 .into(),
-                            convert(node.child_nodes())?.take_children(),
+                            node.text_content().unwrap_or_default().into(),
+                            attributes_of(element),
+                        ));
+                    }
+
+                    "A" if sanitize
+                        && !is_safe_url(
+                            &node
+                                .unchecked_ref::<Element>()
+                                .get_attribute("href")
+                                .unwrap_or_default(),
+                        ) =>
+                    {
+                        // Keep the text of a link we can't trust
+                        for child in convert(node.child_nodes(), sanitize)?
+                            .take_children()
+                        {
+                            dom.append_child(child);
+                        }
+                    }
+
+                    "A" => {
+                        let element = node.unchecked_ref::<Element>();
+                        dom.append_child(with_attributes(
+                            DomNode::new_link(
+                                element
+                                    .get_attribute("href")
+                                    .unwrap_or_default()
+                                    .into(),
+                                convert(node.child_nodes(), sanitize)?
+                                    .take_children(),
+                            ),
+                            element,
+                            sanitize,
                         ));
                     }
 
                     "OL" => {
-                        dom.append_child(DomNode::Container(
-                            ContainerNode::new_list(
-                                ListType::Ordered,
-                                convert(node.child_nodes())?.take_children(),
+                        let element = node.unchecked_ref::<Element>();
+                        dom.append_child(with_attributes(
+                            new_ordered_list(
+                                element.get_attribute("start").as_deref(),
+                                element.get_attribute("type").as_deref(),
+                                convert(node.child_nodes(), sanitize)?
+                                    .take_children(),
                             ),
+                            element,
+                            sanitize,
                         ));
                     }
 
                     "UL" => {
-                        dom.append_child(DomNode::Container(
-                            ContainerNode::new_list(
+                        dom.append_child(with_attributes(
+                            DomNode::Container(ContainerNode::new_list(
                                 ListType::Unordered,
-                                convert(node.child_nodes())?.take_children(),
+                                convert(node.child_nodes(), sanitize)?
+                                    .take_children(),
+                            )),
+                            node.unchecked_ref::<Element>(),
+                            sanitize,
+                        ));
+                    }
+
+                    "TABLE" => {
+                        let children = node.child_nodes();
+                        for i in 0..children.length() {
+                            let child = children.get(i).unwrap();
+                            if child.node_name() == "CAPTION" {
+                                convert_caption(child, dom, sanitize)?;
+                            }
+                        }
+                        dom.append_child(with_attributes(
+                            DomNode::new_table(without_blank_text(
+                                convert(node.child_nodes(), sanitize)?
+                                    .take_children(),
+                            )),
+                            node.unchecked_ref::<Element>(),
+                            sanitize,
+                        ));
+                    }
+
+                    "CAPTION" | "COLGROUP" => {
+                        // A caption was moved before its table, and the
+                        // columns of a table have no node of their own
+                    }
+
+                    "TBODY" | "THEAD" | "TFOOT" => {
+                        for child in without_blank_text(
+                            convert(node.child_nodes(), sanitize)?
+                                .take_children(),
+                        ) {
+                            dom.append_child(child);
+                        }
+                    }
+
+                    "TR" => {
+                        dom.append_child(with_attributes(
+                            DomNode::new_table_row(without_blank_text(
+                                convert(node.child_nodes(), sanitize)?
+                                    .take_children(),
+                            )),
+                            node.unchecked_ref::<Element>(),
+                            sanitize,
+                        ));
+                    }
+
+                    "TD" | "TH" => {
+                        dom.append_child(with_attributes(
+                            DomNode::new_table_cell(
+                                node.node_name().to_lowercase().as_str().into(),
+                                convert(node.child_nodes(), sanitize)?
+                                    .take_children(),
                             ),
+                            node.unchecked_ref::<Element>(),
+                            sanitize,
                         ));
                     }
 
                     "LI" => {
-                        dom.append_child(DomNode::Container(
-                            ContainerNode::new_list_item(
+                        dom.append_child(with_attributes(
+                            DomNode::Container(ContainerNode::new_list_item(
                                 "li".into(),
-                                convert(node.child_nodes())?.take_children(),
-                            ),
+                                convert(node.child_nodes(), sanitize)?
+                                    .take_children(),
+                            )),
+                            node.unchecked_ref::<Element>(),
+                            sanitize,
                         ));
                     }
 
                     node_name => {
                         let children_nodes =
-                            convert(node.child_nodes())?.take_children();
-
-                        dom.append_child(DomNode::Container(
-                            ContainerNode::new_formatting(
-                                match node_name {
-                                    "STRONG" | "B" => InlineFormatType::Bold,
-                                    "EM" | "I" => InlineFormatType::Italic,
-                                    "DEL" => InlineFormatType::StrikeThrough,
-                                    "U" => InlineFormatType::Underline,
-                                    "CODE" => InlineFormatType::InlineCode,
-                                    _ => {
-                                        return Err(Error::UnknownNode(
-                                            node_name.to_owned(),
-                                        ))
-                                    }
-                                },
-                                children_nodes,
-                            ),
-                        ));
+                            convert(node.child_nodes(), sanitize)?
+                                .take_children();
+
+                        let format = match node_name {
+                            "STRONG" | "B" => Some(InlineFormatType::Bold),
+                            "EM" | "I" => Some(InlineFormatType::Italic),
+                            "DEL" => Some(InlineFormatType::StrikeThrough),
+                            "U" => Some(InlineFormatType::Underline),
+                            "CODE" => Some(InlineFormatType::InlineCode),
+                            _ => None,
+                        };
+                        let style = node
+                            .dyn_ref::<Element>()
+                            .and_then(|e| e.get_attribute("style"));
+                        let tag = node_name.to_lowercase();
+                        match format {
+                            Some(_)
+                                if sanitize
+                                    && is_cancelled_formatting(
+                                        &tag,
+                                        style.as_deref(),
+                                    ) =>
+                            {
+                                for new_node in sanitize_unknown_element(
+                                    "span",
+                                    style.as_deref(),
+                                    children_nodes,
+                                ) {
+                                    dom.append_child(new_node);
+                                }
+                            }
+                            Some(format) => {
+                                dom.append_child(with_attributes(
+                                    DomNode::new_formatting(
+                                        format,
+                                        children_nodes,
+                                    ),
+                                    node.unchecked_ref::<Element>(),
+                                    sanitize,
+                                ));
+                            }
+                            None if sanitize => {
+                                for new_node in sanitize_unknown_element(
+                                    &tag,
+                                    style.as_deref(),
+                                    children_nodes,
+                                ) {
+                                    dom.append_child(new_node);
+                                }
+                            }
+                            None if is_dropped_tag(&tag) => {}
+                            None => {
+                                // Keep elements we don't recognise as they
+                                // are. Other nodes, e.g. comments, are
+                                // dropped.
+                                if let Some(element) = node.dyn_ref::<Element>()
+                                {
+                                    dom.append_child(with_attributes(
+                                        DomNode::new_unknown(
+                                            tag.as_str().into(),
+                                            children_nodes,
+                                        ),
+                                        element,
+                                        sanitize,
+                                    ));
+                                }
+                            }
+                        }
                     }
                 }
             }
@@ -430,7 +1407,7 @@ mod js {
             Ok(())
         }
 
-        convert(body.child_nodes())
+        convert(body.child_nodes(), sanitize)
     }
 
     fn to_dom_creation_error<S, E>(error: E) -> DomCreationError<S>
@@ -439,14 +1416,13 @@ mod js {
         E: ToString,
     {
         DomCreationError {
-            dom: Dom::new(vec![]),
-            parse_errors: vec![error.to_string()],
+            dom: Box::new(Dom::new(vec![])),
+            parse_errors: vec![ParseError::other(error.to_string())],
         }
     }
 
     enum Error {
         NoBody,
-        UnknownNode(String),
     }
 
     impl fmt::Display for Error {
@@ -458,10 +1434,6 @@ mod js {
                         "The `Document` does not have a `<body>` element"
                     )
                 }
-
-                Self::UnknownNode(node_name) => {
-                    write!(formatter, "Node `{node_name}` is not supported")
-                }
             }
         }
     }
@@ -478,7 +1450,7 @@ mod js {
         wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
 
         fn roundtrip(html: &str) {
-            let parse = parse::<Utf16String>(html);
+            let parse = parse::<Utf16String>(html, false);
 
             assert!(
                 parse.is_ok(),
@@ -509,6 +1481,51 @@ mod js {
         fn a() {
             roundtrip(r#"foo <a href="url">bar</a> baz"#);
             roundtrip(r#"foo <a href="">bar</a> baz"#);
+            roundtrip(
+                r#"foo <a href="url" target="_blank" rel="noopener" title="t">bar</a> baz"#,
+            );
+        }
+
+        #[wasm_bindgen_test]
+        fn img() {
+            roundtrip(r#"foo <img src="url" alt="text" /> bar"#);
+        }
+
+        #[wasm_bindgen_test]
+        fn ol_numbering() {
+            roundtrip(r#"<ol start="3" type="i"><li>a</li></ol>"#);
+        }
+
+        #[wasm_bindgen_test]
+        fn extra_attributes() {
+            roundtrip(r#"<code class="language-rust">fn</code>"#);
+            roundtrip(r#"<ul class="x"><li data-mx-id="1">a</li></ul>"#);
+        }
+
+        #[wasm_bindgen_test]
+        fn table() {
+            roundtrip("<table><tr><td>a</td><td>b</td></tr></table>");
+        }
+
+        #[wasm_bindgen_test]
+        fn table_caption() {
+            let dom = parse::<Utf16String>(
+                "<table><caption>c</caption><tbody><tr><td>a</td></tr>\
+                </tbody></table>",
+                false,
+            )
+            .unwrap();
+            assert_eq!(
+                dom.to_html().to_string(),
+                "<p>c</p><table><tr><td>a</td></tr></table>"
+            );
+        }
+
+        #[wasm_bindgen_test]
+        fn mention() {
+            roundtrip(
+                r#"Hi <a href="https://matrix.to/#/@alice:matrix.org" contenteditable="false">Alice</a>!"#,
+            );
         }
 
         #[wasm_bindgen_test]
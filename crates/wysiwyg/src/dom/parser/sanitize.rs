@@ -0,0 +1,363 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Turning HTML from outside the editor, e.g. from the clipboard, into
+//! nodes the editor understands. Both parser backends use these for the
+//! elements they don't have nodes of their own for.
+
+use super::whitespace::is_block_tag;
+use crate::dom::nodes::{ContainerNode, DomNode};
+use crate::dom::UnicodeString;
+use crate::InlineFormatType;
+
+/// Tags whose content is never displayed, so is dropped along with them.
+/// Word marks empty paragraphs with `<o:p>&nbsp;</o:p>`.
+const DROPPED_TAGS: [&str; 13] = [
+    "embed", "head", "iframe", "link", "meta", "noscript", "o:p", "object",
+    "script", "style", "svg", "template", "title",
+];
+
+/// Fonts that show code, as named in the `font-family` of text pasted from
+/// word processors
+const MONOSPACE_FONTS: [&str; 4] =
+    ["consolas", "courier", "monaco", "monospace"];
+
+/// Whether a link or image pointing at `url` is safe to keep
+pub(super) fn is_safe_url(url: &str) -> bool {
+    let scheme = url.trim_start().to_ascii_lowercase();
+    !(scheme.starts_with("javascript:") || scheme.starts_with("vbscript:"))
+}
+
+/// Whether an attribute can be kept as it is. Event handlers are dropped,
+/// as are URLs that would run a script.
+pub(super) fn is_safe_attribute(name: &str, value: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    if name.starts_with("on") {
+        return false;
+    }
+    match name.as_str() {
+        "action" | "formaction" | "href" | "src" | "xlink:href" => {
+            is_safe_url(value)
+        }
+        _ => true,
+    }
+}
+
+/// Whether the content of an element with this lowercase `tag` is never
+/// displayed, so it should be dropped along with it
+pub(super) fn is_dropped_tag(tag: &str) -> bool {
+    DROPPED_TAGS.contains(&tag)
+}
+
+/// Whitespace-only text containing a line break is just the formatting
+/// of the HTML source, e.g. between two paragraphs.
+pub(super) fn is_source_formatting(text: &str) -> bool {
+    text.trim().is_empty() && text.contains('\n')
+}
+
+/// Convert an element with no node of its own into nodes the editor
+/// understands, given its lowercase `tag`, its `style` attribute and its
+/// already converted children.
+pub(super) fn sanitize_unknown_element<S>(
+    tag: &str,
+    style: Option<&str>,
+    children: Vec<DomNode<S>>,
+) -> Vec<DomNode<S>>
+where
+    S: UnicodeString,
+{
+    if is_dropped_tag(tag) || style.map_or(false, is_list_marker) {
+        return Vec::new();
+    }
+
+    let mut formats = style.map(formats_from_style).unwrap_or_default();
+    match tag {
+        "s" | "strike" => formats.push(InlineFormatType::StrikeThrough),
+        "ins" => formats.push(InlineFormatType::Underline),
+        "kbd" | "samp" | "tt" => formats.push(InlineFormatType::InlineCode),
+        _ => {}
+    }
+
+    let mut nodes = wrap_in_formats(&formats, children);
+    if is_block_tag(tag) && !matches!(nodes.last(), Some(DomNode::LineBreak(_)))
+    {
+        nodes.push(DomNode::new_line_break());
+    }
+    nodes
+}
+
+/// Wrap the inline content of a `node` the editor has a kind of its own
+/// for, e.g. a link or list item, in the formatting its element's inline
+/// `style` asks for. Formatting the node already applies is left out, as
+/// is any nested block like a list.
+pub(super) fn apply_style_formatting<S>(
+    node: &mut ContainerNode<S>,
+    style: Option<&str>,
+) where
+    S: UnicodeString,
+{
+    let formats: Vec<InlineFormatType> = style
+        .map(formats_from_style)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|format| !node.is_formatting_node_of_type(format))
+        .collect();
+    if formats.is_empty() {
+        return;
+    }
+
+    let mut new_children = Vec::new();
+    let mut inline_run = Vec::new();
+    for child in node.replace_children(Vec::new()) {
+        if is_inline(&child) {
+            inline_run.push(child);
+        } else {
+            new_children.extend(wrap_in_formats(
+                &formats,
+                std::mem::take(&mut inline_run),
+            ));
+            new_children.push(child);
+        }
+    }
+    new_children.extend(wrap_in_formats(&formats, inline_run));
+    node.replace_children(new_children);
+}
+
+/// Whether `node` can go inside formatting
+fn is_inline<S>(node: &DomNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    match node {
+        DomNode::Container(container) => {
+            container.is_formatting_node() || container.is_link()
+        }
+        _ => true,
+    }
+}
+
+/// Wrap `nodes` in nested formatting nodes, the first of `formats`
+/// outermost
+fn wrap_in_formats<S>(
+    formats: &[InlineFormatType],
+    nodes: Vec<DomNode<S>>,
+) -> Vec<DomNode<S>>
+where
+    S: UnicodeString,
+{
+    let mut nodes = nodes;
+    for format in formats.iter().rev() {
+        if !nodes.is_empty() {
+            nodes = vec![DomNode::new_formatting(format.clone(), nodes)];
+        }
+    }
+    nodes
+}
+
+/// Whether a formatting `tag` has an inline `style` that undoes its own
+/// formatting, like the `<b style="font-weight:normal">` that Google Docs
+/// wraps everything it copies in. These should be treated as a `<span>`.
+pub(super) fn is_cancelled_formatting(tag: &str, style: Option<&str>) -> bool {
+    let style = match style {
+        Some(style) => style,
+        None => return false,
+    };
+    declarations(style).any(|(property, value)| match tag {
+        "b" | "strong" => property == "font-weight" && !is_bold_weight(&value),
+        "em" | "i" => property == "font-style" && value == "normal",
+        "del" | "u" => {
+            (property == "text-decoration"
+                || property == "text-decoration-line")
+                && value == "none"
+        }
+        _ => false,
+    })
+}
+
+/// Word turns list items into paragraphs, with the bullet or number as
+/// text in a `<span style="mso-list:Ignore">`
+fn is_list_marker(style: &str) -> bool {
+    declarations(style)
+        .any(|(property, value)| property == "mso-list" && value == "ignore")
+}
+
+/// The formatting that an inline `style` attribute asks for
+fn formats_from_style(style: &str) -> Vec<InlineFormatType> {
+    let mut formats = Vec::new();
+    for (property, value) in declarations(style) {
+        let declared = match property.as_str() {
+            "font-weight" if is_bold_weight(&value) => {
+                vec![InlineFormatType::Bold]
+            }
+            "font-style" if value == "italic" || value == "oblique" => {
+                vec![InlineFormatType::Italic]
+            }
+            // One declaration may ask for both, e.g.
+            // `text-decoration: underline line-through`
+            "text-decoration" | "text-decoration-line" => {
+                let mut decorations = Vec::new();
+                if value.contains("underline") {
+                    decorations.push(InlineFormatType::Underline);
+                }
+                if value.contains("line-through") {
+                    decorations.push(InlineFormatType::StrikeThrough);
+                }
+                decorations
+            }
+            "font-family"
+                if MONOSPACE_FONTS.iter().any(|font| value.contains(font)) =>
+            {
+                vec![InlineFormatType::InlineCode]
+            }
+            _ => continue,
+        };
+        for format in declared {
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+    }
+    formats
+}
+
+/// The lowercase properties and values of the declarations in a `style`
+/// attribute
+fn declarations(style: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    style.split(';').filter_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        Some((
+            property.trim().to_ascii_lowercase(),
+            value.trim().to_ascii_lowercase(),
+        ))
+    })
+}
+
+fn is_bold_weight(value: &str) -> bool {
+    match value {
+        "bold" | "bolder" => true,
+        _ => value.parse::<u32>().map_or(false, |weight| weight >= 600),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use super::*;
+    use crate::ToHtml;
+
+    fn sanitized(
+        tag: &str,
+        style: Option<&str>,
+        children: Vec<DomNode<Utf16String>>,
+    ) -> String {
+        sanitize_unknown_element(tag, style, children)
+            .iter()
+            .map(|node| node.to_html().to_string())
+            .collect()
+    }
+
+    fn text(s: &str) -> Vec<DomNode<Utf16String>> {
+        vec![DomNode::new_text(s.into())]
+    }
+
+    #[test]
+    fn scripts_and_styles_are_dropped() {
+        assert_eq!(sanitized("script", None, text("alert(1)")), "");
+        assert_eq!(sanitized("style", None, text("b {}")), "");
+    }
+
+    #[test]
+    fn unknown_inline_tags_are_unwrapped() {
+        assert_eq!(sanitized("span", None, text("a")), "a");
+        assert_eq!(sanitized("sup", None, text("a")), "a");
+    }
+
+    #[test]
+    fn block_tags_end_with_a_line_break() {
+        assert_eq!(sanitized("p", None, text("a")), "a<br />");
+        assert_eq!(sanitized("h1", None, text("a")), "a<br />");
+    }
+
+    #[test]
+    fn styles_become_formatting() {
+        assert_eq!(
+            sanitized(
+                "span",
+                Some("font-weight: bold; font-style:italic"),
+                text("a")
+            ),
+            "<strong><em>a</em></strong>"
+        );
+        assert_eq!(
+            sanitized("span", Some("text-decoration: underline"), text("a")),
+            "<u>a</u>"
+        );
+        assert_eq!(sanitized("span", Some("font-weight: 400"), text("a")), "a");
+    }
+
+    #[test]
+    fn one_text_decoration_may_ask_for_two_formats() {
+        assert_eq!(
+            sanitized(
+                "span",
+                Some("text-decoration: underline line-through"),
+                text("a")
+            ),
+            "<u><del>a</del></u>"
+        );
+    }
+
+    #[test]
+    fn tags_with_equivalent_formatting_are_mapped() {
+        assert_eq!(sanitized("s", None, text("a")), "<del>a</del>");
+        assert_eq!(sanitized("strike", None, text("a")), "<del>a</del>");
+    }
+
+    #[test]
+    fn monospace_fonts_become_inline_code() {
+        assert_eq!(
+            sanitized("span", Some("font-family:'Courier New'"), text("a")),
+            "<code>a</code>"
+        );
+    }
+
+    #[test]
+    fn word_list_markers_are_dropped() {
+        assert_eq!(sanitized("span", Some("mso-list:Ignore"), text("·")), "");
+        assert_eq!(sanitized("o:p", None, text("\u{a0}")), "");
+    }
+
+    #[test]
+    fn formatting_undone_by_its_style_is_cancelled() {
+        assert!(is_cancelled_formatting("b", Some("font-weight:normal;")));
+        assert!(is_cancelled_formatting("em", Some("font-style: normal")));
+        assert!(!is_cancelled_formatting("b", Some("font-weight:700")));
+        assert!(!is_cancelled_formatting("b", None));
+    }
+
+    #[test]
+    fn script_urls_are_unsafe() {
+        assert!(is_safe_url("https://matrix.org"));
+        assert!(!is_safe_url(" JavaScript:alert(1)"));
+    }
+
+    #[test]
+    fn event_handlers_and_script_urls_are_unsafe_attributes() {
+        assert!(is_safe_attribute("class", "language-rust"));
+        assert!(is_safe_attribute("data-mx-color", "#ff0000"));
+        assert!(!is_safe_attribute("onClick", "x()"));
+        assert!(!is_safe_attribute("src", "javascript:x()"));
+    }
+}
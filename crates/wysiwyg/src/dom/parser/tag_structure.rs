@@ -0,0 +1,508 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checking how the tags of HTML are opened, closed and nested, so that
+//! malformed HTML can be reported with where the problem is, and corrected
+//! by rules of our own. The parsers themselves recover from these problems
+//! without saying where they are or what they did.
+
+use crate::dom::{ParseCorrection, ParseError, ParseErrorKind};
+
+/// Tags that never have content or a closing tag
+const VOID_TAGS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "source", "track", "wbr",
+];
+
+/// Tags whose closing tag may be left out, as the next tag of the same
+/// kind or the end of their parent closes them
+const OPTIONAL_END_TAGS: [&str; 12] = [
+    "caption", "colgroup", "dd", "dt", "li", "optgroup", "option", "p",
+    "tbody", "td", "tfoot", "th",
+];
+
+/// Tags whose content is text, even if it looks like tags
+const RAW_TEXT_TAGS: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// Tags that can't be inside a paragraph, and close it instead
+const PARAGRAPH_CLOSING_TAGS: [&str; 23] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "div",
+    "dl",
+    "fieldset",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "ol",
+    "section",
+    "ul",
+];
+
+/// For a tag whose opening closes an earlier tag whose closing tag may be
+/// left out, e.g. `<li>` closing the list item before it, the tags it
+/// closes and the tags they must be inside for that to happen
+fn implied_closes(tag: &str) -> Option<(&[&str], &[&str])> {
+    match tag {
+        "li" => Some((&["li"], &["ol", "ul"])),
+        "dd" | "dt" => Some((&["dd", "dt"], &["dl"])),
+        "td" | "th" => Some((&["td", "th"], &["table", "tr"])),
+        "tr" => {
+            Some((&["td", "th", "tr"], &["table", "tbody", "tfoot", "thead"]))
+        }
+        "tbody" | "tfoot" | "thead" => {
+            Some((&["tbody", "td", "tfoot", "th", "thead", "tr"], &["table"]))
+        }
+        "option" => Some((&["option"], &["optgroup", "select"])),
+        "p" => Some((&["p"], &[])),
+        _ => None,
+    }
+}
+
+/// A tag that is open, and the byte offset of its opening tag
+struct OpenTag {
+    name: String,
+    position: usize,
+}
+
+/// The problems with how the tags of some HTML are opened, closed and
+/// nested, and the same HTML with them corrected
+pub(super) struct TagStructure {
+    /// The HTML with every tag closed, in the right order
+    pub(super) corrected_html: String,
+    /// Each problem, and how it was corrected, in the order found
+    pub(super) problems: Vec<(ParseError, ParseCorrection)>,
+}
+
+/// Find the problems with how the tags of `html` are opened, closed and
+/// nested, in the order they are found
+pub(super) fn find_tag_structure_errors(html: &str) -> Vec<ParseError> {
+    check_tag_structure(html)
+        .problems
+        .into_iter()
+        .map(|(error, _)| error)
+        .collect()
+}
+
+/// Check how the tags of `html` are opened, closed and nested, and correct
+/// them by these rules:
+/// - a closing tag with no matching open tag is dropped
+/// - a closing tag first closes the tags opened inside its element that are
+///   still open
+/// - a link inside a link, or a block like `<div>` inside a paragraph,
+///   first closes the outer link or paragraph, and anything open inside it
+/// - tags still open at the end are closed there, innermost first
+///
+/// Tags whose closing tag may be left out are closed without it being
+/// counted as a problem, by those rules or by the next tag of their kind,
+/// e.g. a `<li>` closes the list item before it in the same list.
+pub(super) fn check_tag_structure(html: &str) -> TagStructure {
+    let mut checker = TagChecker {
+        html,
+        corrected_html: String::with_capacity(html.len()),
+        copied_to: 0,
+        open: Vec::new(),
+        problems: Vec::new(),
+    };
+    checker.check();
+    TagStructure {
+        corrected_html: checker.corrected_html,
+        problems: checker.problems,
+    }
+}
+
+struct TagChecker<'a> {
+    html: &'a str,
+    corrected_html: String,
+    /// How much of `html` has been copied to `corrected_html`
+    copied_to: usize,
+    open: Vec<OpenTag>,
+    problems: Vec<(ParseError, ParseCorrection)>,
+}
+
+impl<'a> TagChecker<'a> {
+    fn check(&mut self) {
+        let html = self.html;
+        let bytes = html.as_bytes();
+        let mut pos = 0;
+        while let Some(offset) = html[pos..].find('<') {
+            let start = pos + offset;
+            let rest = &html[start..];
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                pos = comment
+                    .find("-->")
+                    .map_or(html.len(), |end| start + 4 + end + 3);
+                continue;
+            }
+            if rest.starts_with("<!") || rest.starts_with("<?") {
+                pos = skip_past_tag(html, start);
+                continue;
+            }
+            let is_end_tag = rest.starts_with("</");
+            let name_start = start + if is_end_tag { 2 } else { 1 };
+            if !bytes.get(name_start).map_or(false, u8::is_ascii_alphabetic) {
+                // A `<` that doesn't start a tag is just text
+                pos = start + 1;
+                continue;
+            }
+            let name_end = html[name_start..]
+                .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+                .map_or(html.len(), |end| name_start + end);
+            let name = html[name_start..name_end].to_ascii_lowercase();
+            pos = skip_past_tag(html, name_end);
+
+            if is_end_tag {
+                self.close_tag(&name, start, pos);
+                continue;
+            }
+            self.open_tag(&name, start);
+            let is_self_closing = html[..pos].ends_with("/>");
+            if RAW_TEXT_TAGS.contains(&name.as_str()) {
+                let closing = format!("</{}", name);
+                pos = html[pos..]
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .map_or(html.len(), |end| pos + end);
+            } else if VOID_TAGS.contains(&name.as_str()) || is_self_closing {
+                self.open.pop();
+            }
+        }
+
+        self.copy_to(html.len());
+        let open = std::mem::take(&mut self.open);
+        for tag in &open {
+            if !OPTIONAL_END_TAGS.contains(&tag.name.as_str()) {
+                self.problems.push((
+                    ParseError {
+                        kind: ParseErrorKind::UnclosedTag,
+                        position: Some(tag.position),
+                        description: format!("<{}> is never closed", tag.name),
+                    },
+                    ParseCorrection {
+                        position: Some(html.len()),
+                        description: format!(
+                            "Closed <{}> at the end",
+                            tag.name
+                        ),
+                    },
+                ));
+            }
+        }
+        for tag in open.iter().rev() {
+            self.write_closing_tag(&tag.name);
+        }
+    }
+
+    /// Record that `name` is opened at `position`, first closing the tag it
+    /// isn't allowed inside, if any
+    fn open_tag(&mut self, name: &str, position: usize) {
+        if let Some((closes, boundaries)) = implied_closes(name) {
+            let index = self
+                .open
+                .iter()
+                .rposition(|t| boundaries.contains(&t.name.as_str()))
+                .map_or(0, |boundary| boundary + 1);
+            if let Some(found) = self.open[index..]
+                .iter()
+                .position(|t| closes.contains(&t.name.as_str()))
+            {
+                // Not a problem, as these closing tags may be left out
+                self.close_from(index + found, position);
+            }
+        }
+        if name == "a" {
+            if let Some(index) = self.open.iter().rposition(|t| t.name == "a") {
+                let link = self.close_from(index, position);
+                self.problems.push((
+                    ParseError {
+                        kind: ParseErrorKind::DisallowedNesting,
+                        position: Some(position),
+                        description: format!(
+                            "<a> is inside another <a>, opened at byte {}",
+                            link.position
+                        ),
+                    },
+                    ParseCorrection {
+                        position: Some(position),
+                        description: "Closed the outer <a> before the <a> \
+                            inside it"
+                            .to_owned(),
+                    },
+                ));
+            }
+        }
+        if PARAGRAPH_CLOSING_TAGS.contains(&name) {
+            if let Some(index) = self.open.iter().rposition(|t| t.name == "p") {
+                let paragraph = self.close_from(index, position);
+                self.problems.push((
+                    ParseError {
+                        kind: ParseErrorKind::DisallowedNesting,
+                        position: Some(position),
+                        description: format!(
+                            "<{}> is not allowed inside <p>, opened at byte {}",
+                            name, paragraph.position
+                        ),
+                    },
+                    ParseCorrection {
+                        position: Some(position),
+                        description: format!("Closed <p> before <{}>", name),
+                    },
+                ));
+            }
+        }
+        self.open.push(OpenTag {
+            name: name.to_owned(),
+            position,
+        });
+    }
+
+    /// Record that `name` is closed by the tag from `position` to `end`,
+    /// first closing the tags still open inside it, or dropping the closing
+    /// tag if it isn't open
+    fn close_tag(&mut self, name: &str, position: usize, end: usize) {
+        let index = match self.open.iter().rposition(|tag| tag.name == name) {
+            Some(index) => index,
+            None => {
+                self.copy_to(position);
+                self.copied_to = end;
+                self.problems.push((
+                    ParseError {
+                        kind: ParseErrorKind::UnexpectedEndTag,
+                        position: Some(position),
+                        description: format!(
+                            "</{}> has no matching <{}>",
+                            name, name
+                        ),
+                    },
+                    ParseCorrection {
+                        position: Some(position),
+                        description: format!("Dropped </{}>", name),
+                    },
+                ));
+                return;
+            }
+        };
+        self.copy_to(position);
+        while self.open.len() > index + 1 {
+            let inner = self.open.pop().unwrap();
+            self.write_closing_tag(&inner.name);
+            if !OPTIONAL_END_TAGS.contains(&inner.name.as_str()) {
+                self.problems.push((
+                    ParseError {
+                        kind: ParseErrorKind::DisallowedNesting,
+                        position: Some(position),
+                        description: format!(
+                            "</{}> closes <{}> while <{}>, opened at byte {}, \
+                            is still open",
+                            name, name, inner.name, inner.position
+                        ),
+                    },
+                    ParseCorrection {
+                        position: Some(position),
+                        description: format!(
+                            "Closed <{}> before </{}>",
+                            inner.name, name
+                        ),
+                    },
+                ));
+            }
+        }
+        self.open.pop();
+    }
+
+    /// Close the open tag at `index`, and everything opened inside it, just
+    /// before `position`, returning the tag
+    fn close_from(&mut self, index: usize, position: usize) -> OpenTag {
+        self.copy_to(position);
+        let closed: Vec<OpenTag> = self.open.drain(index..).collect();
+        for tag in closed.iter().skip(1).rev() {
+            self.write_closing_tag(&tag.name);
+        }
+        let outer = closed.into_iter().next().unwrap();
+        self.write_closing_tag(&outer.name);
+        outer
+    }
+
+    /// Copy the HTML up to `position` to the corrected HTML as it is
+    fn copy_to(&mut self, position: usize) {
+        if position > self.copied_to {
+            self.corrected_html
+                .push_str(&self.html[self.copied_to..position]);
+            self.copied_to = position;
+        }
+    }
+
+    fn write_closing_tag(&mut self, name: &str) {
+        self.corrected_html.push_str("</");
+        self.corrected_html.push_str(name);
+        self.corrected_html.push('>');
+    }
+}
+
+/// The position just after the `>` ending the tag that `pos` is inside,
+/// skipping over any quoted attribute values
+fn skip_past_tag(html: &str, pos: usize) -> usize {
+    let mut quote = None;
+    for (i, c) in html[pos..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return pos + i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_tag_structure, find_tag_structure_errors};
+    use crate::dom::{ParseError, ParseErrorKind};
+
+    fn errors(html: &str) -> Vec<(ParseErrorKind, Option<usize>)> {
+        find_tag_structure_errors(html)
+            .into_iter()
+            .map(|ParseError { kind, position, .. }| (kind, position))
+            .collect()
+    }
+
+    #[test]
+    fn well_formed_html_has_no_errors() {
+        assert!(errors(
+            "<p>a <b>b</b><br>c<img src=\"x>y\" /></p><!-- <i> -->\
+            <ul><li>d<li>e</ul><p>f"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn tags_never_closed_are_reported_where_they_open() {
+        assert_eq!(
+            errors("a<b>b<i>c</i>"),
+            vec![(ParseErrorKind::UnclosedTag, Some(1))]
+        );
+    }
+
+    #[test]
+    fn closing_tags_that_close_nothing_are_reported() {
+        assert_eq!(
+            errors("a</b>"),
+            vec![(ParseErrorKind::UnexpectedEndTag, Some(1))]
+        );
+    }
+
+    #[test]
+    fn tags_closed_out_of_order_are_reported() {
+        let found = find_tag_structure_errors("<b><i>a</b></i>");
+        assert_eq!(
+            found[0],
+            ParseError {
+                kind: ParseErrorKind::DisallowedNesting,
+                position: Some(7),
+                description: "</b> closes <b> while <i>, opened at byte 3, \
+                    is still open"
+                    .to_owned(),
+            }
+        );
+        assert_eq!(found[1].kind, ParseErrorKind::UnexpectedEndTag);
+        assert_eq!(found[1].position, Some(11));
+    }
+
+    #[test]
+    fn links_inside_links_are_reported() {
+        assert_eq!(
+            errors("<a href=\"x\">a<a href=\"y\">b</a></a>"),
+            vec![
+                (ParseErrorKind::DisallowedNesting, Some(13)),
+                (ParseErrorKind::UnexpectedEndTag, Some(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_inside_paragraphs_are_reported() {
+        assert_eq!(
+            errors("<p><div>a</div></p>"),
+            vec![
+                (ParseErrorKind::DisallowedNesting, Some(3)),
+                (ParseErrorKind::UnexpectedEndTag, Some(15)),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_content_of_scripts_is_not_checked() {
+        assert!(
+            errors("<script>if (a < b) { x = '</b>'; }</script>").is_empty()
+        );
+    }
+
+    #[test]
+    fn positions_count_bytes() {
+        assert_eq!(
+            errors("é</b>"),
+            vec![(ParseErrorKind::UnexpectedEndTag, Some(2))]
+        );
+    }
+
+    fn corrected(html: &str) -> String {
+        check_tag_structure(html).corrected_html
+    }
+
+    #[test]
+    fn well_formed_html_is_not_corrected() {
+        let html = "<p>a <b>b</b><br>c</p><ul><li>d<li>e</ul><p>f";
+        assert_eq!(
+            corrected(html),
+            "<p>a <b>b</b><br>c</p><ul><li>d</li>\
+            <li>e</li></ul><p>f</p>"
+        );
+        assert!(check_tag_structure(html).problems.is_empty());
+    }
+
+    #[test]
+    fn tags_closed_out_of_order_are_closed_first() {
+        assert_eq!(corrected("<b><i>a</b>b</i>"), "<b><i>a</i></b>b");
+    }
+
+    #[test]
+    fn links_inside_links_close_the_outer_link() {
+        assert_eq!(
+            corrected("<a href=\"x\"><b>a<a href=\"y\">b</a>"),
+            "<a href=\"x\"><b>a</b></a><a href=\"y\">b</a>"
+        );
+    }
+
+    #[test]
+    fn blocks_inside_paragraphs_close_the_paragraph() {
+        assert_eq!(corrected("<p>a<div>b</div></p>"), "<p>a</p><div>b</div>");
+    }
+
+    #[test]
+    fn tags_left_open_are_closed_at_the_end() {
+        assert_eq!(corrected("<ol><li><b>a"), "<ol><li><b>a</b></li></ol>");
+    }
+}
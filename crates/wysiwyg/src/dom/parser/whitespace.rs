@@ -0,0 +1,240 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Collapsing the whitespace of parsed HTML the way a browser shows it, so
+//! that the line breaks and indentation of the HTML source don't become
+//! spaces in the content.
+
+use crate::dom::nodes::{ContainerNode, ContainerNodeKind, DomNode};
+use crate::dom::{Dom, UnicodeString};
+
+/// Block tags without a node of their own. Their content is kept on a
+/// line of its own.
+const BLOCK_TAGS: [&str; 20] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dt",
+    "figcaption",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "main",
+    "p",
+    "pre",
+    "section",
+];
+
+/// Whether `tag` is one of the block tags without a node of their own,
+/// e.g. `p` or `div`
+pub(super) fn is_block_tag(tag: &str) -> bool {
+    BLOCK_TAGS.contains(&tag)
+}
+
+/// Turn each run of whitespace into a single space, and drop spaces at the
+/// start and end of a line, except inside `<pre>`. Non-breaking spaces are
+/// content and are kept as they are.
+pub(super) fn normalize_whitespace<S>(dom: &mut Dom<S>)
+where
+    S: UnicodeString,
+{
+    let document = dom.document_mut();
+    collapse_spaces(document, &mut true);
+    trim_line_ends(document, &mut true);
+    remove_empty_text(document);
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c')
+}
+
+fn is_pre<S>(node: &ContainerNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    node.name().to_string().eq_ignore_ascii_case("pre")
+}
+
+/// Whether the content of this container starts on a new line and the
+/// content after it does too
+fn is_line_boundary<S>(node: &ContainerNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    match node.kind() {
+        ContainerNodeKind::Formatting(_) | ContainerNodeKind::Link(_) => false,
+        ContainerNodeKind::Unknown => {
+            is_block_tag(&node.name().to_string().to_ascii_lowercase())
+        }
+        _ => true,
+    }
+}
+
+/// Replace each run of whitespace with a space, leaving out the space if
+/// the text before it on the same line already ends with one or there is
+/// none. `after_space` is whether that is the case before `node`.
+fn collapse_spaces<S>(node: &mut ContainerNode<S>, after_space: &mut bool)
+where
+    S: UnicodeString,
+{
+    if is_pre(node) {
+        *after_space = true;
+        return;
+    }
+    let boundary = is_line_boundary(node);
+    if boundary {
+        *after_space = true;
+    }
+    for i in 0..node.children().len() {
+        match node.get_child_mut(i).unwrap() {
+            DomNode::Container(child) => collapse_spaces(child, after_space),
+            DomNode::Text(text) => {
+                let mut collapsed = String::new();
+                for c in text.data().to_string().chars() {
+                    if !is_whitespace(c) {
+                        collapsed.push(c);
+                        *after_space = false;
+                    } else if !*after_space {
+                        collapsed.push(' ');
+                        *after_space = true;
+                    }
+                }
+                text.set_data(S::from(collapsed.as_str()));
+            }
+            DomNode::LineBreak(_) => *after_space = true,
+            DomNode::Mention(_) | DomNode::Image(_) => *after_space = false,
+        }
+    }
+    if boundary {
+        *after_space = true;
+    }
+}
+
+/// Drop the space at the end of each line, walking backwards through the
+/// collapsed text. `at_line_end` is whether nothing but spaces follows
+/// `node` on its line.
+fn trim_line_ends<S>(node: &mut ContainerNode<S>, at_line_end: &mut bool)
+where
+    S: UnicodeString,
+{
+    if is_pre(node) {
+        *at_line_end = true;
+        return;
+    }
+    let boundary = is_line_boundary(node);
+    if boundary {
+        *at_line_end = true;
+    }
+    for i in (0..node.children().len()).rev() {
+        match node.get_child_mut(i).unwrap() {
+            DomNode::Container(child) => trim_line_ends(child, at_line_end),
+            DomNode::Text(text) => {
+                if *at_line_end {
+                    let data = text.data().to_string();
+                    text.set_data(S::from(data.trim_end_matches(' ')));
+                }
+                if !text.data().to_string().is_empty() {
+                    *at_line_end = false;
+                }
+            }
+            DomNode::LineBreak(_) => *at_line_end = true,
+            DomNode::Mention(_) | DomNode::Image(_) => *at_line_end = false,
+        }
+    }
+    if boundary {
+        *at_line_end = true;
+    }
+}
+
+/// Remove the text nodes that were nothing but whitespace
+fn remove_empty_text<S>(node: &mut ContainerNode<S>)
+where
+    S: UnicodeString,
+{
+    if is_pre(node) {
+        return;
+    }
+    for i in (0..node.children().len()).rev() {
+        match node.get_child_mut(i).unwrap() {
+            DomNode::Container(child) => remove_empty_text(child),
+            DomNode::Text(text) if text.data().to_string().is_empty() => {
+                node.remove_child(i);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use super::*;
+    use crate::dom::parser::parse;
+    use crate::{ToHtml, ToRawText};
+
+    fn normalized(html: &str) -> String {
+        let mut dom: Dom<Utf16String> = parse(html).unwrap();
+        normalize_whitespace(&mut dom);
+        dom.to_html().to_string()
+    }
+
+    #[test]
+    fn runs_of_whitespace_become_one_space() {
+        assert_eq!(normalized("a \n\t b"), "a b");
+        assert_eq!(normalized("a <b> b</b> c"), "a <b>b</b> c");
+    }
+
+    #[test]
+    fn whitespace_at_the_ends_of_lines_is_dropped() {
+        assert_eq!(normalized("\n  a <br />  b\n"), "a<br />b");
+        assert_eq!(
+            normalized("<ul>\n  <li> a </li>\n  <li>b\n</li>\n</ul>"),
+            "<ul><li>a</li><li>b</li></ul>"
+        );
+    }
+
+    #[test]
+    fn non_breaking_spaces_are_kept() {
+        assert_eq!(normalized("a\u{A0} b"), "a\u{A0} b");
+    }
+
+    #[test]
+    fn whitespace_around_mentions_is_kept() {
+        assert_eq!(
+            normalized(
+                "a <a href=\"https://matrix.to/#/@u:x.org\" \
+                contenteditable=\"false\">u</a> b"
+            ),
+            "a <a href=\"https://matrix.to/#/@u:x.org\" \
+            contenteditable=\"false\">u</a> b"
+        );
+    }
+
+    #[test]
+    fn whitespace_in_pre_is_kept() {
+        let mut dom: Dom<Utf16String> =
+            parse("<pre>  a\n    b </pre>\n c").unwrap();
+        normalize_whitespace(&mut dom);
+        assert_eq!(dom.to_raw_text(), "  a\n    b c");
+    }
+}
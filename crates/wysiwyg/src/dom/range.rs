@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use crate::dom::dom_handle::DomHandle;
+use crate::dom::nodes::DomNode;
+use crate::dom::{Dom, ToRawText, UnicodeString};
 use std::cmp::Ordering;
 
 /// Represents a part of a Range.
@@ -139,6 +141,17 @@ impl Ord for DomLocation {
     }
 }
 
+/// The part of a [Range] inside one block, i.e. one list item, table cell,
+/// or the document itself for text outside those. See [Range::blocks].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockSlice {
+    pub block_handle: DomHandle,
+    /// Where the range starts, measured from the start of the block
+    pub start: usize,
+    /// Where the range ends, measured from the start of the block
+    pub end: usize,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Range {
     pub locations: Vec<DomLocation>,
@@ -192,6 +205,71 @@ impl Range {
         self.locations.iter().filter(|loc| loc.is_leaf)
     }
 
+    /// The blocks this range touches, in document order, with the part of
+    /// the range inside each. Text belongs to the closest block containing
+    /// it, so a nested list item is separate from the item around it.
+    pub fn blocks<S>(&self, dom: &Dom<S>) -> impl Iterator<Item = BlockSlice>
+    where
+        S: UnicodeString,
+    {
+        let mut slices: Vec<BlockSlice> = Vec::new();
+        for leaf in self.leaves() {
+            let block_handle =
+                dom.find_block_ancestor_or_self(&leaf.node_handle);
+            let block_start = self
+                .locations
+                .iter()
+                .find(|loc| loc.node_handle == block_handle)
+                .map_or(0, |loc| loc.position);
+            let start = leaf.position + leaf.start_offset - block_start;
+            let end = leaf.position + leaf.end_offset - block_start;
+            match slices.last_mut() {
+                Some(slice) if slice.block_handle == block_handle => {
+                    slice.end = end;
+                }
+                _ => slices.push(BlockSlice {
+                    block_handle,
+                    start,
+                    end,
+                }),
+            }
+        }
+        slices.into_iter()
+    }
+
+    /// The plain text this range covers in `dom`, e.g. to copy it. Line
+    /// breaks and the boundaries between blocks become newlines, mentions
+    /// and images are included whole if the range covers them, and
+    /// zero-width placeholders are left out.
+    pub fn text<S>(&self, dom: &Dom<S>) -> S
+    where
+        S: UnicodeString,
+    {
+        let mut text = String::new();
+        let mut previous_block = None;
+        for leaf in self.leaves() {
+            if leaf.start_offset == leaf.end_offset {
+                continue;
+            }
+            let block = dom.find_block_ancestor_or_self(&leaf.node_handle);
+            if previous_block.map_or(false, |previous| previous != block) {
+                text.push('\n');
+            }
+            match dom.lookup_node(&leaf.node_handle) {
+                DomNode::Text(node) => {
+                    let data = node.data()[leaf.start_offset..leaf.end_offset]
+                        .to_owned()
+                        .to_string();
+                    text.push_str(&data.replace('\u{200b}', ""));
+                }
+                DomNode::LineBreak(_) => text.push('\n'),
+                node => text.push_str(&node.to_raw_text().to_string()),
+            }
+            previous_block = Some(block);
+        }
+        S::from(text.as_str())
+    }
+
     // TODO: remove all uses of this when we guarantee that Dom is never empty
     pub fn is_empty(&self) -> bool {
         self.locations.is_empty()
@@ -213,7 +291,7 @@ mod test {
         dom::DomLocation, tests::testutils_composer_model::cm, DomHandle,
     };
 
-    use super::Range;
+    use super::{BlockSlice, Range};
 
     #[test]
     fn range_start_and_end_for_cursor_at_beginning() {
@@ -342,4 +420,85 @@ mod test {
         let (s, e) = model.safe_selection();
         model.state.dom.find_range(s, e)
     }
+
+    fn blocks_of(model: &str) -> Vec<BlockSlice> {
+        let model = cm(model);
+        let (s, e) = model.safe_selection();
+        let range = model.state.dom.find_range(s, e);
+        range.blocks(&model.state.dom).collect()
+    }
+
+    fn slice(path: Vec<usize>, start: usize, end: usize) -> BlockSlice {
+        BlockSlice {
+            block_handle: DomHandle::from_raw(path),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn range_blocks_of_plain_text_is_the_document() {
+        assert_eq!(blocks_of("a{b<b>c}|d</b>"), vec![slice(Vec::new(), 1, 3)]);
+    }
+
+    #[test]
+    fn range_blocks_are_split_by_list_item() {
+        assert_eq!(
+            blocks_of("<ol><li>a{b</li><li><b>cd</b></li><li>e}|f</li></ol>"),
+            vec![
+                slice(vec![0, 0], 1, 2),
+                slice(vec![0, 1], 0, 2),
+                slice(vec![0, 2], 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn range_blocks_separate_nested_list_items() {
+        assert_eq!(
+            blocks_of("<ul><li>a{b<ul><li>c}|d</li></ul></li></ul>"),
+            vec![slice(vec![0, 0], 1, 2), slice(vec![0, 0, 1, 0], 0, 1)]
+        );
+    }
+
+    fn text_of(model: &str) -> String {
+        let model = cm(model);
+        let (s, e) = model.safe_selection();
+        model
+            .state
+            .dom
+            .find_range(s, e)
+            .text(&model.state.dom)
+            .to_string()
+    }
+
+    #[test]
+    fn range_text_includes_partial_text_nodes_across_formatting() {
+        assert_eq!(text_of("a{b<b>cd</b><i>e}|f</i>"), "bcde");
+        assert_eq!(text_of("ab|c"), "");
+    }
+
+    #[test]
+    fn range_text_turns_line_breaks_and_list_items_into_newlines() {
+        assert_eq!(text_of("a{b<br />c}|d"), "b\nc");
+        assert_eq!(
+            text_of("<ol><li>a{b</li><li>c</li><li>d}|e</li></ol>"),
+            "b\nc\nd"
+        );
+    }
+
+    #[test]
+    fn range_text_includes_mentions_and_skips_placeholders() {
+        assert_eq!(
+            text_of(
+                "{hi <a href=\"https://matrix.to/#/@alice:matrix.org\">\
+                 Alice</a>}|"
+            ),
+            "hi Alice"
+        );
+        assert_eq!(
+            text_of("<ul><li>{a</li><li>\u{200b}</li><li>b}|</li></ul>"),
+            "a\n\nb"
+        );
+    }
 }
@@ -0,0 +1,140 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparing documents by what they say rather than how their nodes
+//! happen to be arranged.
+
+use crate::dom::nodes::DomNode;
+use crate::dom::unicode_string::{UnicodeStrExt, UnicodeStringExt};
+use crate::dom::{Dom, ToHtml, UnicodeString};
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// True if both documents have the same content. Unlike `==`, this
+    /// ignores handles, node ids and metadata, and treats text split over
+    /// several adjacent text nodes, or with empty text nodes among it, the
+    /// same as one text node holding all of it.
+    pub fn semantically_equals(&self, other: &Dom<S>) -> bool {
+        children_equal(self.children(), other.children())
+    }
+}
+
+/// A child of a container, with adjacent text nodes run together
+enum Item<'a, S>
+where
+    S: UnicodeString,
+{
+    Text(S),
+    Node(&'a DomNode<S>),
+}
+
+fn items<S: UnicodeString>(children: &[DomNode<S>]) -> Vec<Item<'_, S>> {
+    let mut items = Vec::new();
+    let mut text: Option<S> = None;
+    for child in children {
+        if let DomNode::Text(node) = child {
+            text.get_or_insert_with(S::default)
+                .push(node.data().to_owned());
+            continue;
+        }
+        if let Some(text) = text.take() {
+            items.push(Item::Text(text));
+        }
+        items.push(Item::Node(child));
+    }
+    items.extend(text.map(Item::Text));
+    items.retain(|item| !matches!(item, Item::Text(text) if text.is_empty()));
+    items
+}
+
+fn children_equal<S: UnicodeString>(
+    a: &[DomNode<S>],
+    b: &[DomNode<S>],
+) -> bool {
+    let (a, b) = (items(a), items(b));
+    a.len() == b.len()
+        && a.iter().zip(b.iter()).all(|pair| match pair {
+            (Item::Text(a), Item::Text(b)) => a == b,
+            (Item::Node(a), Item::Node(b)) => nodes_equal(a, b),
+            _ => false,
+        })
+}
+
+fn nodes_equal<S: UnicodeString>(a: &DomNode<S>, b: &DomNode<S>) -> bool {
+    match (a, b) {
+        (DomNode::Container(a), DomNode::Container(b)) => {
+            a.name() == b.name()
+                && a.kind() == b.kind()
+                && a.attributes() == b.attributes()
+                && children_equal(a.children(), b.children())
+        }
+        (DomNode::LineBreak(_), DomNode::LineBreak(_)) => true,
+        // These have no children, so all there is to them is their HTML
+        (DomNode::Mention(_), DomNode::Mention(_))
+        | (DomNode::Image(_), DomNode::Image(_)) => a.to_html() == b.to_html(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+    use crate::dom::DomHandle;
+    use crate::tests::testutils_dom::{b, dom, i, tn};
+
+    use super::*;
+
+    fn parsed(html: &str) -> Dom<Utf16String> {
+        parse(html).unwrap()
+    }
+
+    #[test]
+    fn split_and_empty_text_nodes_are_the_same_as_joined_ones() {
+        let split = dom(&[tn("a"), tn(""), tn("b"), b(&[tn("c"), tn("d")])]);
+        assert!(split.semantically_equals(&parsed("ab<b>cd</b>")));
+        assert!(parsed("ab<b>cd</b>").semantically_equals(&split));
+        assert_ne!(split, parsed("ab<b>cd</b>"));
+    }
+
+    #[test]
+    fn handles_ids_and_metadata_are_ignored() {
+        let mut a = parsed("a<b>b</b>");
+        let other = a.clone();
+        a.assign_node_ids();
+        a.lookup_node_mut(&DomHandle::from_raw(vec![1]))
+            .metadata_mut()
+            .insert("hint", "x");
+        a.lookup_node_mut(&DomHandle::from_raw(vec![1, 0]))
+            .set_handle(DomHandle::new_unset());
+        assert!(a.semantically_equals(&other));
+    }
+
+    #[test]
+    fn different_content_is_not_equal() {
+        let doc = parsed("ab<b>c</b>");
+        assert!(!doc.semantically_equals(&parsed("ab<b>d</b>")));
+        assert!(!doc.semantically_equals(&parsed("ab<i>c</i>")));
+        assert!(!doc.semantically_equals(&parsed("a<b>bc</b>")));
+        assert!(!doc.semantically_equals(&parsed("ab<b>c</b><br />")));
+        assert!(!doc.semantically_equals(&parsed(
+            "ab<a href=\"https://matrix.org\">c</a>"
+        )));
+        assert!(!parsed("<b></b>").semantically_equals(&parsed("")));
+        assert!(!doc.semantically_equals(&dom(&[tn("ab"), i(&[tn("c")])])));
+    }
+}
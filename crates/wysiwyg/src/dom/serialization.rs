@@ -0,0 +1,210 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serde support, behind the `serde` feature. Strings are written as
+//! plain strings whatever [UnicodeString] the model uses, so a document
+//! saved by one platform can be loaded by another. Handles and node ids
+//! are not saved: handles are worked out again when a [Dom] is loaded, and
+//! ids are only meaningful while the process that assigned them runs.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::dom::nodes::DomNode;
+use crate::dom::{Dom, UnicodeString};
+
+/// A [Dom] is saved as the list of its top level nodes
+impl<S> Serialize for Dom<S>
+where
+    S: UnicodeString,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        serializer.collect_seq(self.children())
+    }
+}
+
+impl<'de, S> Deserialize<'de> for Dom<S>
+where
+    S: UnicodeString,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Dom::new(Vec::<DomNode<S>>::deserialize(deserializer)?))
+    }
+}
+
+pub(crate) mod string {
+    use super::*;
+
+    pub(crate) fn serialize<S, Ser>(
+        value: &S,
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error>
+    where
+        S: UnicodeString,
+        Ser: Serializer,
+    {
+        serializer.collect_str(value)
+    }
+
+    pub(crate) fn deserialize<'de, S, D>(deserializer: D) -> Result<S, D::Error>
+    where
+        S: UnicodeString,
+        D: Deserializer<'de>,
+    {
+        Ok(S::from(String::deserialize(deserializer)?))
+    }
+}
+
+/// Attributes are saved as a list of `[name, value]` pairs, so that their
+/// order is kept
+pub(crate) mod attributes {
+    use super::*;
+
+    pub(crate) fn serialize<S, Ser>(
+        attrs: &[(S, S)],
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error>
+    where
+        S: UnicodeString,
+        Ser: Serializer,
+    {
+        serializer.collect_seq(
+            attrs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        )
+    }
+
+    pub(crate) fn deserialize<'de, S, D>(
+        deserializer: D,
+    ) -> Result<Vec<(S, S)>, D::Error>
+    where
+        S: UnicodeString,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(String, String)>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| (S::from(name), S::from(value)))
+            .collect())
+    }
+}
+
+pub(crate) mod optional_attributes {
+    use super::*;
+
+    pub(crate) fn serialize<S, Ser>(
+        attrs: &Option<Vec<(S, S)>>,
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error>
+    where
+        S: UnicodeString,
+        Ser: Serializer,
+    {
+        match attrs {
+            Some(attrs) => {
+                serializer.serialize_some(&Attributes(attrs.as_slice()))
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, S, D>(
+        deserializer: D,
+    ) -> Result<Option<Vec<(S, S)>>, D::Error>
+    where
+        S: UnicodeString,
+        D: Deserializer<'de>,
+    {
+        Ok(
+            Option::<Vec<(String, String)>>::deserialize(deserializer)?.map(
+                |attrs| {
+                    attrs
+                        .into_iter()
+                        .map(|(name, value)| (S::from(name), S::from(value)))
+                        .collect()
+                },
+            ),
+        )
+    }
+
+    struct Attributes<'a, S>(&'a [(S, S)]);
+
+    impl<'a, S> Serialize for Attributes<'a, S>
+    where
+        S: UnicodeString,
+    {
+        fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+        where
+            Ser: Serializer,
+        {
+            super::attributes::serialize(self.0, serializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::Dom;
+    use crate::tests::testutils_composer_model::{cm, tx};
+    use crate::{ComposerModel, ComposerState, DomHandle, ToHtml};
+
+    fn round_trip(
+        state: &ComposerState<Utf16String>,
+    ) -> ComposerState<Utf16String> {
+        let json = serde_json::to_string(state).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn composer_state_is_restored_exactly() {
+        let model = cm("<ol><li>a<b>{bc</b></li><li><em>d}|</em></li></ol>");
+        let restored = round_trip(&model.state);
+        assert_eq!(restored, model.state);
+        let restored_model = ComposerModel::from_state(restored);
+        assert_eq!(tx(&restored_model), tx(&model));
+    }
+
+    #[test]
+    fn attributes_mentions_and_images_are_restored() {
+        let model = cm("<a href=\"https://matrix.org\" title=\"M\">a</a>\
+            <a href=\"https://matrix.to/#/@alice:matrix.org\" \
+            contenteditable=\"false\">Alice</a>\
+            <img src=\"mxc://matrix.org/a\" alt=\"pic\" />|");
+        let restored = round_trip(&model.state);
+        assert_eq!(restored.dom.to_html(), model.state.dom.to_html());
+        assert_eq!(restored, model.state);
+    }
+
+    #[test]
+    fn strings_are_saved_as_plain_text() {
+        let model = cm("<code>a\"b</code>|");
+        let json = serde_json::to_string(&model.state.dom).unwrap();
+        assert!(json.contains(r#""data":"a\"b""#), "{}", json);
+        assert!(!json.contains("handle"), "{}", json);
+    }
+
+    #[test]
+    fn handles_are_worked_out_when_loading() {
+        let dom: Dom<Utf16String> =
+            serde_json::from_str(r#"[{"Text":{"data":"a"}}]"#).unwrap();
+        assert_eq!(dom.children()[0].handle(), DomHandle::from_raw(vec![0]));
+    }
+}
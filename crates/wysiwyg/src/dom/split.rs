@@ -0,0 +1,168 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Splitting a container in two at a position, e.g. to end a block where
+//! the cursor is and start a new one of the same kind.
+
+use crate::dom::nodes::{ContainerNode, DomNode};
+use crate::dom::{Dom, DomError, DomHandle, UnicodeString};
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Split the container at `handle` in two, `offset` code units from its
+    /// start. Everything from the offset onwards moves into a copy of the
+    /// container, inserted as its next sibling, whose handle is returned.
+    /// The copy has the same kind and attributes, and the containers in
+    /// between are copied the same way.
+    ///
+    /// Nodes that end at or before the offset stay where they are, nodes
+    /// that start at or after it move, and text nodes that span it are cut
+    /// in two. Splitting at 0 or at the end of the container leaves one of
+    /// the two halves empty, which is kept: it is up to the caller to
+    /// remove it or fill it in.
+    pub fn split_sub_tree(
+        &mut self,
+        handle: &DomHandle,
+        offset: usize,
+    ) -> Result<DomHandle, DomError> {
+        let container = self.try_lookup_container_mut(handle)?;
+        if offset > container.text_len() {
+            return Err(DomError::OffsetOutOfRange(handle.clone(), offset));
+        }
+        if !handle.has_parent() {
+            // The document itself can't have a sibling
+            return Err(DomError::InvalidHandle(handle.clone()));
+        }
+        let after = split_container(container, offset);
+        let parent = self.try_parent_mut(handle)?;
+        let index = handle.index_in_parent() + 1;
+        parent.insert_child(index, DomNode::Container(after));
+        Ok(handle.next_sibling())
+    }
+}
+
+/// Move everything in `container` from `offset` onwards into a new copy of
+/// it, and return the copy. The copy's handles are unset.
+fn split_container<S: UnicodeString>(
+    container: &mut ContainerNode<S>,
+    offset: usize,
+) -> ContainerNode<S> {
+    let mut moved = Vec::new();
+    let mut position = 0;
+    let mut index = 0;
+    while index < container.children().len() {
+        let len = container.children()[index].text_len();
+        if position >= offset {
+            moved.push(container.remove_child(index));
+            continue;
+        }
+        if position + len > offset {
+            let split_at = offset - position;
+            match container.get_child_mut(index) {
+                Some(DomNode::Text(text)) => {
+                    let data = text.data();
+                    let second = data[split_at..].to_owned();
+                    let first = data[..split_at].to_owned();
+                    text.set_data(first);
+                    moved.push(DomNode::new_text(second));
+                }
+                Some(DomNode::Container(child)) => {
+                    let child_after = split_container(child, split_at);
+                    moved.push(DomNode::Container(child_after));
+                }
+                // Other nodes have length 1, so an offset can't be inside
+                // them
+                _ => {}
+            }
+        }
+        position += len;
+        index += 1;
+    }
+    container.copy_with_new_children(moved)
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+    use crate::ToHtml;
+
+    use super::*;
+
+    fn dom(html: &str) -> Dom<Utf16String> {
+        parse(html).unwrap()
+    }
+
+    #[test]
+    fn splitting_cuts_text_and_copies_containers_in_between() {
+        let mut dom = dom("<ol><li>a<b>bc</b>d</li></ol>");
+        let li = DomHandle::from_raw(vec![0, 0]);
+        let new_li = dom.split_sub_tree(&li, 2).unwrap();
+        assert_eq!(new_li, DomHandle::from_raw(vec![0, 1]));
+        assert_eq!(
+            dom.to_html(),
+            "<ol><li>a<b>b</b></li><li><b>c</b>d</li></ol>"
+        );
+        assert_eq!(
+            dom.lookup_node(&DomHandle::from_raw(vec![0, 1, 0, 0]))
+                .handle(),
+            DomHandle::from_raw(vec![0, 1, 0, 0])
+        );
+    }
+
+    #[test]
+    fn nodes_ending_at_the_offset_stay_and_starting_at_it_move() {
+        let mut dom = dom("<ul><li><b>ab</b><i>cd</i></li></ul>");
+        dom.split_sub_tree(&DomHandle::from_raw(vec![0, 0]), 2)
+            .unwrap();
+        assert_eq!(
+            dom.to_html(),
+            "<ul><li><b>ab</b></li><li><i>cd</i></li></ul>"
+        );
+    }
+
+    #[test]
+    fn splitting_at_either_end_leaves_an_empty_half() {
+        let mut dom = dom("<b>ab</b>");
+        let bold = DomHandle::from_raw(vec![0]);
+        dom.split_sub_tree(&bold, 0).unwrap();
+        assert_eq!(dom.to_html(), "<b></b><b>ab</b>");
+        dom.split_sub_tree(&bold.next_sibling(), 2).unwrap();
+        assert_eq!(dom.to_html(), "<b></b><b>ab</b><b></b>");
+    }
+
+    #[test]
+    fn splitting_checks_its_arguments() {
+        let mut dom = dom("a<b>bc</b>");
+        let text = DomHandle::from_raw(vec![0]);
+        let bold = DomHandle::from_raw(vec![1]);
+        let root = DomHandle::from_raw(Vec::new());
+        assert_eq!(
+            dom.split_sub_tree(&text, 0),
+            Err(DomError::NotAContainer(text))
+        );
+        assert_eq!(
+            dom.split_sub_tree(&bold, 3),
+            Err(DomError::OffsetOutOfRange(bold.clone(), 3))
+        );
+        assert_eq!(
+            dom.split_sub_tree(&root, 1),
+            Err(DomError::InvalidHandle(root))
+        );
+        assert_eq!(dom.to_html(), "a<b>bc</b>");
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Copying part of a [Dom] into a document of its own, and translating
+//! handles and positions between the two.
+
+use crate::dom::nodes::DomNode;
+use crate::dom::{Dom, DomError, DomHandle, DomLocation, UnicodeString};
+
+/// How the handles and positions of a sub-tree copied out by
+/// [Dom::extract_sub_tree] relate to those in the document it came from.
+/// In the extracted document the sub-tree's root is the first child of the
+/// document, at `[0]`, and starts at position 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubTreeMapping {
+    /// Where the sub-tree's root is in the original document
+    root: DomHandle,
+    /// The original position of the start of the sub-tree
+    start: usize,
+    /// How many code units the sub-tree covers
+    length: usize,
+}
+
+impl SubTreeMapping {
+    fn extracted_root() -> DomHandle {
+        DomHandle::from_raw(vec![0])
+    }
+
+    /// The handle in the original document of the sub-tree's root
+    pub fn original_root(&self) -> &DomHandle {
+        &self.root
+    }
+
+    /// Where the node at `handle` in the original document is in the
+    /// extracted one, or None if it isn't part of the sub-tree.
+    pub fn to_extracted(&self, handle: &DomHandle) -> Option<DomHandle> {
+        replace_prefix(handle, &self.root, &Self::extracted_root())
+    }
+
+    /// Where the node at `handle` in the extracted document is in the
+    /// original one, or None if it is the extracted document itself.
+    pub fn to_original(&self, handle: &DomHandle) -> Option<DomHandle> {
+        replace_prefix(handle, &Self::extracted_root(), &self.root)
+    }
+
+    /// The extracted position for a position in the original document, or
+    /// None if it is outside the sub-tree. Both ends of the sub-tree count
+    /// as inside it.
+    pub fn position_to_extracted(&self, position: usize) -> Option<usize> {
+        if position < self.start || position > self.start + self.length {
+            None
+        } else {
+            Some(position - self.start)
+        }
+    }
+
+    /// The original position for a position in the extracted document
+    pub fn position_to_original(&self, position: usize) -> usize {
+        position + self.start
+    }
+
+    /// A location in the original document, moved to the extracted one,
+    /// or None if its node isn't part of the sub-tree.
+    pub fn location_to_extracted(
+        &self,
+        location: &DomLocation,
+    ) -> Option<DomLocation> {
+        let node_handle = self.to_extracted(&location.node_handle)?;
+        let position = self.position_to_extracted(location.position)?;
+        Some(DomLocation {
+            node_handle,
+            position,
+            ..location.clone()
+        })
+    }
+
+    /// A location in the extracted document, moved to the original one,
+    /// or None if it is the location of the extracted document itself.
+    pub fn location_to_original(
+        &self,
+        location: &DomLocation,
+    ) -> Option<DomLocation> {
+        Some(DomLocation {
+            node_handle: self.to_original(&location.node_handle)?,
+            position: self.position_to_original(location.position),
+            ..location.clone()
+        })
+    }
+}
+
+/// `handle` with `from` at the start of its path swapped for `to`, if it
+/// starts with `from`.
+fn replace_prefix(
+    handle: &DomHandle,
+    from: &DomHandle,
+    to: &DomHandle,
+) -> Option<DomHandle> {
+    if !handle.is_set() || !handle.raw().starts_with(from.raw()) {
+        return None;
+    }
+    let mut new_handle = handle.clone();
+    new_handle.replace_ancestor(from.clone(), to.clone());
+    Some(new_handle)
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Copy the node at `handle` and everything inside it into a new
+    /// document, along with a [SubTreeMapping] to find the copied nodes
+    /// and positions in it.
+    pub fn extract_sub_tree(
+        &self,
+        handle: &DomHandle,
+    ) -> Result<(Dom<S>, SubTreeMapping), DomError> {
+        let node = self.try_lookup_node(handle)?;
+        if !handle.has_parent() {
+            // The whole document would become a child of a new one
+            return Err(DomError::InvalidHandle(handle.clone()));
+        }
+        let start = self.try_find_start(handle)?;
+        let mapping = SubTreeMapping {
+            root: handle.clone(),
+            start,
+            length: node.text_len(),
+        };
+        Ok((Dom::new(vec![node.clone()]), mapping))
+    }
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Copy the content between the start and end code units into a new
+    /// document. Text nodes are cut at the ends of the range, containers
+    /// are kept around whatever is left inside them, and containers with
+    /// nothing left are dropped.
+    pub fn fragment(&self, start: usize, end: usize) -> Dom<S> {
+        let mut position = 0;
+        Dom::new(slice_children(self.children(), &mut position, start, end))
+    }
+}
+
+/// Copies of the parts of `children` between `start` and `end`, where the
+/// first child starts at `position`, which is moved past them all.
+fn slice_children<S: UnicodeString>(
+    children: &[DomNode<S>],
+    position: &mut usize,
+    start: usize,
+    end: usize,
+) -> Vec<DomNode<S>> {
+    let mut sliced = Vec::new();
+    for child in children {
+        let child_start = *position;
+        let child_end = child_start + child.text_len();
+        if child_end <= start || child_start >= end {
+            *position = child_end;
+            continue;
+        }
+        match child {
+            DomNode::Text(text) => {
+                let from = start.saturating_sub(child_start);
+                let to = end.min(child_end) - child_start;
+                if from < to {
+                    let data = text.data()[from..to].to_owned();
+                    sliced.push(DomNode::new_text(data));
+                }
+                *position = child_end;
+            }
+            DomNode::Container(container) => {
+                let inside =
+                    slice_children(container.children(), position, start, end);
+                if !inside.is_empty() {
+                    sliced.push(DomNode::Container(
+                        container.copy_with_new_children(inside),
+                    ));
+                }
+            }
+            _ => {
+                sliced.push(child.clone());
+                *position = child_end;
+            }
+        }
+    }
+    sliced
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+    use crate::ToHtml;
+
+    use super::*;
+
+    fn extract(
+        html: &str,
+        path: Vec<usize>,
+    ) -> (Dom<Utf16String>, Dom<Utf16String>, SubTreeMapping) {
+        let dom: Dom<Utf16String> = parse(html).unwrap();
+        let (extracted, mapping) =
+            dom.extract_sub_tree(&DomHandle::from_raw(path)).unwrap();
+        (dom, extracted, mapping)
+    }
+
+    #[test]
+    fn handles_map_into_the_extracted_tree_and_back() {
+        let (dom, extracted, mapping) =
+            extract("ab<ol><li>c</li><li>d<b>ef</b></li></ol>", vec![1, 1]);
+        assert_eq!(extracted.to_html(), "<li>d<b>ef</b></li>");
+
+        let original = DomHandle::from_raw(vec![1, 1, 1, 0]);
+        let moved = mapping.to_extracted(&original).unwrap();
+        assert_eq!(moved, DomHandle::from_raw(vec![0, 1, 0]));
+        assert_eq!(
+            extracted.lookup_node(&moved).to_html(),
+            dom.lookup_node(&original).to_html()
+        );
+        assert_eq!(mapping.to_original(&moved), Some(original));
+
+        assert_eq!(
+            mapping.to_extracted(&DomHandle::from_raw(vec![1, 0])),
+            None
+        );
+        assert_eq!(mapping.to_original(&DomHandle::from_raw(Vec::new())), None);
+    }
+
+    #[test]
+    fn positions_and_locations_are_shifted_by_the_start_of_the_tree() {
+        let (dom, extracted, mapping) =
+            extract("ab<ol><li>c</li><li>d<b>ef</b></li></ol>", vec![1, 1]);
+        assert_eq!(mapping.position_to_extracted(2), None);
+        assert_eq!(mapping.position_to_extracted(3), Some(0));
+        assert_eq!(mapping.position_to_extracted(6), Some(3));
+        assert_eq!(mapping.position_to_extracted(7), None);
+        assert_eq!(mapping.position_to_original(1), 4);
+
+        let original = dom.find_range(5, 6);
+        let in_extracted = extracted.find_range(2, 3);
+        let moved: Vec<DomLocation> = original
+            .locations
+            .iter()
+            .filter_map(|location| mapping.location_to_extracted(location))
+            .collect();
+        // The list item, the bold node and its text
+        assert_eq!(moved.len(), 3);
+        for location in moved {
+            assert!(in_extracted.locations.contains(&location));
+            let back = mapping.location_to_original(&location).unwrap();
+            assert!(original.locations.contains(&back));
+        }
+    }
+
+    #[test]
+    fn the_whole_document_cannot_be_extracted() {
+        let dom: Dom<Utf16String> = parse("a").unwrap();
+        let root = DomHandle::from_raw(Vec::new());
+        assert_eq!(
+            dom.extract_sub_tree(&root),
+            Err(DomError::InvalidHandle(root))
+        );
+    }
+
+    #[test]
+    fn fragment_cuts_text_and_keeps_the_containers_around_it() {
+        let dom: Dom<Utf16String> =
+            parse("a<b>bc<i>de</i></b>f<br />g").unwrap();
+        assert_eq!(dom.fragment(2, 5).to_html(), "<b>c<i>de</i></b>");
+        assert_eq!(dom.fragment(4, 8).to_html(), "<b><i>e</i></b>f<br />g");
+        assert_eq!(dom.fragment(3, 3).to_html(), "");
+    }
+
+    #[test]
+    fn fragment_drops_containers_left_empty() {
+        let dom: Dom<Utf16String> =
+            parse("<ol><li>ab</li><li>cd</li><li>ef</li></ol>").unwrap();
+        assert_eq!(
+            dom.fragment(1, 4).to_html(),
+            "<ol><li>b</li><li>cd</li></ol>"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Graphviz output of a [Dom], to see the shape of a document that breaks
+//! an invariant, e.g. with `dot -Tsvg`.
+
+use std::fmt::Write;
+
+use crate::dom::nodes::{
+    ContainerNode, ImageNode, LineBreakNode, MentionNode, TextNode,
+};
+use crate::dom::to_tree::text_preview;
+use crate::dom::unicode_string::UnicodeStrExt;
+use crate::dom::visitor::DomVisitor;
+use crate::dom::{Dom, DomHandle, UnicodeString};
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Describe this document as a graphviz digraph. Each node is labelled
+    /// with what it is, its handle and the positions it covers.
+    pub fn to_dot(&self) -> S {
+        let mut writer = DotWriter {
+            dot: String::from("digraph dom {\n  node [shape=box];\n"),
+            offset: 0,
+        };
+        self.accept(&mut writer);
+        writer.dot.push_str("}\n");
+        S::from(writer.dot.as_str())
+    }
+}
+
+struct DotWriter {
+    dot: String,
+    /// The position where the next node starts
+    offset: usize,
+}
+
+impl DotWriter {
+    fn add_node(&mut self, handle: &DomHandle, description: &str, len: usize) {
+        let id = node_id(handle);
+        let label = format!(
+            "{}\n{:?} {}..{}",
+            description,
+            handle.raw(),
+            self.offset,
+            self.offset + len
+        );
+        writeln!(self.dot, "  {} [label=\"{}\"];", id, escape(&label)).unwrap();
+        if handle.has_parent() {
+            writeln!(
+                self.dot,
+                "  {} -> {};",
+                node_id(&handle.parent_handle()),
+                id
+            )
+            .unwrap();
+        }
+    }
+
+    fn add_leaf(&mut self, handle: &DomHandle, description: &str, len: usize) {
+        self.add_node(handle, description, len);
+        self.offset += len;
+    }
+}
+
+impl<S> DomVisitor<S> for DotWriter
+where
+    S: UnicodeString,
+{
+    fn enter_container(
+        &mut self,
+        handle: &DomHandle,
+        container: &ContainerNode<S>,
+    ) {
+        let name = container.name().to_string();
+        let description = if name.is_empty() {
+            container.kind_name().to_owned()
+        } else {
+            format!("{} {}", name, container.kind_name())
+        };
+        self.add_node(handle, &description, container.text_len());
+    }
+
+    fn visit_text(&mut self, handle: &DomHandle, text: &TextNode<S>) {
+        let description = text_preview(&text.data().to_string());
+        self.add_leaf(handle, &description, text.data().len());
+    }
+
+    fn visit_line_break(
+        &mut self,
+        handle: &DomHandle,
+        line_break: &LineBreakNode<S>,
+    ) {
+        self.add_leaf(handle, "br", line_break.text_len());
+    }
+
+    fn visit_mention(&mut self, handle: &DomHandle, mention: &MentionNode<S>) {
+        let description = format!(
+            "mention {}",
+            text_preview(&mention.display_text().to_string())
+        );
+        self.add_leaf(handle, &description, mention.text_len());
+    }
+
+    fn visit_image(&mut self, handle: &DomHandle, image: &ImageNode<S>) {
+        let description = format!("img \"{}\"", image.src());
+        self.add_leaf(handle, &description, image.text_len());
+    }
+}
+
+/// A graphviz identifier for the node at `handle`, e.g. `n_0_1`
+fn node_id(handle: &DomHandle) -> String {
+    let mut id = String::from("n");
+    for index in handle.raw() {
+        write!(id, "_{}", index).unwrap();
+    }
+    id
+}
+
+/// Escape `label` so it can go between the quotes of a graphviz string
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use crate::dom::parser::parse;
+
+    #[test]
+    fn nodes_are_labelled_with_handles_and_positions() {
+        let dom = parse::<Utf16String>("a<b>bc</b><br />").unwrap();
+        assert_eq!(
+            dom.to_dot().to_string(),
+            "digraph dom {
+  node [shape=box];
+  n [label=\"generic\\n[] 0..4\"];
+  n_0 [label=\"\\\"a\\\"\\n[0] 0..1\"];
+  n -> n_0;
+  n_1 [label=\"b formatting\\n[1] 1..3\"];
+  n -> n_1;
+  n_1_0 [label=\"\\\"bc\\\"\\n[1, 0] 1..3\"];
+  n_1 -> n_1_0;
+  n_2 [label=\"br\\n[2] 3..4\"];
+  n -> n_2;
+}
+"
+        );
+    }
+
+    #[test]
+    fn quotes_in_text_are_escaped() {
+        let dom = parse::<Utf16String>("say \"hi\"").unwrap();
+        assert!(dom
+            .to_dot()
+            .to_string()
+            .contains("label=\"\\\"say \\\"hi\\\"\\\"\\n[0] 0..8\""));
+    }
+}
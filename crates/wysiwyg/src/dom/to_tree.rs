@@ -13,12 +13,14 @@
 // limitations under the License.
 
 use super::unicode_string::UnicodeStringExt;
-use super::UnicodeString;
+use super::{DomHandle, UnicodeString};
 
 const DOUBLE_WHITESPACE: &str = "\u{0020}\u{0020}";
 const UP_RIGHT_AND_GT: &str = "\u{2514}\u{003E}";
 const VERTICAL_RIGHT_AND_GT: &str = "\u{251C}\u{003E}";
 const VERTICAL_AND_WHITESPACE: &str = "\u{2502}\u{0020}";
+/// Text longer than this is cut short in the tree
+const PREVIEW_CHARS: usize = 20;
 
 pub trait ToTree<S>
 where
@@ -33,6 +35,12 @@ where
     /// given vector of ancestors that have extra children.
     fn to_tree_display(&self, continuous_positions: Vec<usize>) -> S;
 
+    /// Output the handle of a node and its length in the model, for the end
+    /// of its tree line.
+    fn tree_node_details(&self, handle: &DomHandle, length: usize) -> S {
+        S::from(format!(" {:?} len {}", handle.raw(), length).as_str())
+    }
+
     /// Output content of a tree line with given description
     /// with current depth in the tree as well as positions
     /// of ancestor that have extra children.
@@ -61,3 +69,18 @@ where
         tree_part
     }
 }
+
+/// Quote `text` for a tree line, showing zero width spaces as `~` and
+/// cutting long text short.
+pub(crate) fn text_preview(text: &str) -> String {
+    let text = text.replace('\u{200b}', "~");
+    let mut preview = String::from("\"");
+    if text.chars().count() > PREVIEW_CHARS {
+        preview.extend(text.chars().take(PREVIEW_CHARS));
+        preview.push_str("\"\u{2026}");
+    } else {
+        preview.push_str(&text);
+        preview.push('"');
+    }
+    preview
+}
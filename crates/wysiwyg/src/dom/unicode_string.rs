@@ -157,6 +157,9 @@ pub trait UnicodeStrExt: UnicodeStr {
         &self,
         index: usize,
     ) -> (Option<Self::StringType>, Option<Self::StringType>);
+    fn grapheme_boundaries(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+    fn word_boundaries(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+    fn sentence_boundaries(&self) -> Box<dyn Iterator<Item = usize> + '_>;
     fn u8_map_index(&self, pos: usize) -> usize;
 }
 
@@ -192,6 +195,30 @@ impl<S: UnicodeStr + ?Sized> UnicodeStrExt for S {
         (prev, next)
     }
 
+    /// Iterates over the indexes of the boundaries between graphemes,
+    /// including the start and end of the string, in ascending order.
+    fn grapheme_boundaries(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        boundaries_of(self, self.to_string().graphemes(true))
+    }
+
+    /// Iterates over the indexes of the Unicode word boundaries (UAX #29),
+    /// including the start and end of the string, in ascending order.
+    /// Whitespace and punctuation between words are segments of their own.
+    /// With the `icu-segmentation` feature, Thai, Lao, Khmer, Burmese,
+    /// Chinese and Japanese text is also split into words by dictionary.
+    /// That feature needs Rust 1.81 or newer.
+    fn word_boundaries(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        let text = self.to_string();
+        boundaries_of(self, split_words(&text).into_iter())
+    }
+
+    /// Iterates over the indexes of the Unicode sentence boundaries
+    /// (UAX #29), including the start and end of the string, in ascending
+    /// order. The whitespace after a sentence belongs to it.
+    fn sentence_boundaries(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        boundaries_of(self, self.to_string().split_sentence_bounds())
+    }
+
     /// Translates indexes from any [UnicodeString] implementation to UTF-8.
     fn u8_map_index(&self, pos: usize) -> usize {
         let mut offset_u8: usize = 0;
@@ -217,6 +244,46 @@ impl<S: UnicodeStr + ?Sized> UnicodeStrExt for S {
     }
 }
 
+/// Splits `text` into the segments between word boundaries
+#[cfg(not(feature = "icu-segmentation"))]
+fn split_words(text: &str) -> Vec<&str> {
+    text.split_word_bounds().collect()
+}
+
+/// Splits `text` into the segments between word boundaries, using the
+/// dictionaries of ICU4X for scripts written without spaces
+#[cfg(feature = "icu-segmentation")]
+fn split_words(text: &str) -> Vec<&str> {
+    let segmenter = icu_segmenter::WordSegmenter::new_dictionary();
+    let boundaries: Vec<usize> = segmenter.segment_str(text).collect();
+    boundaries
+        .windows(2)
+        .map(|bounds| &text[bounds[0]..bounds[1]])
+        .collect()
+}
+
+/// The indexes in `s` of the boundaries between `segments`, which together
+/// make up `s`, including its start and end. The segments borrow a UTF-8
+/// copy of `s`, so only their lengths are kept.
+fn boundaries_of<'a, 's, S>(
+    s: &'s S,
+    segments: impl Iterator<Item = &'a str>,
+) -> Box<dyn Iterator<Item = usize> + 's>
+where
+    S: UnicodeStr + ?Sized,
+{
+    let lengths: Vec<usize> = segments
+        .map(|segment| segment.chars().map(|c| s.char_len(&c)).sum())
+        .collect();
+    Box::new(iter::once(0).chain(lengths.into_iter().scan(
+        0,
+        |index, length| {
+            *index += length;
+            Some(*index)
+        },
+    )))
+}
+
 #[cfg(test)]
 mod test {
     use crate::dom::unicode_string::UnicodeStrExt;
@@ -298,4 +365,67 @@ mod test {
         let str = Utf32String::from_str("😮‍💨");
         str.find_graphemes_at(100);
     }
+
+    #[test]
+    fn test_grapheme_boundaries() {
+        let str = Utf16String::from_str("a😮‍💨e\u{301}");
+        assert_eq!(
+            str.grapheme_boundaries().collect::<Vec<_>>(),
+            vec![0, 1, 6, 8]
+        );
+        assert_eq!(
+            "a😮‍💨".grapheme_boundaries().collect::<Vec<_>>(),
+            vec![0, 1, 12]
+        );
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        let str = Utf16String::from_str("Hi 😀, can't");
+        assert_eq!(
+            str.word_boundaries().collect::<Vec<_>>(),
+            vec![0, 2, 3, 5, 6, 7, 12]
+        );
+        assert_eq!("".word_boundaries().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    #[cfg(feature = "icu-segmentation")]
+    fn test_word_boundaries_of_thai_use_a_dictionary() {
+        // "I eat rice" with no spaces between the words
+        let str = Utf16String::from_str("ฉันกินข้าว");
+        assert_eq!(
+            str.word_boundaries().collect::<Vec<_>>(),
+            vec![0, 3, 6, 10]
+        );
+    }
+
+    #[test]
+    fn test_grapheme_boundaries_of_emoji() {
+        assert_eq!("😄".grapheme_boundaries().collect::<Vec<_>>(), vec![0, 4]);
+        assert_eq!(
+            "Test 😮‍💨".grapheme_boundaries().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5, 16]
+        );
+        let str = Utf16String::from_str("Test 😮‍💨");
+        assert_eq!(
+            str.grapheme_boundaries().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5, 10]
+        );
+        let str = Utf32String::from_str("Test 😮‍💨");
+        assert_eq!(
+            str.grapheme_boundaries().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5, 8]
+        );
+    }
+
+    #[test]
+    fn test_sentence_boundaries() {
+        let str = Utf16String::from_str("Hi. Is it 😀? Yes");
+        assert_eq!(
+            str.sentence_boundaries().collect::<Vec<_>>(),
+            vec![0, 4, 14, 17]
+        );
+        assert_eq!("".sentence_boundaries().collect::<Vec<_>>(), vec![0]);
+    }
 }
@@ -0,0 +1,172 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Visiting every node of a [Dom], so that passes over the whole document
+//! don't each need their own traversal.
+
+use crate::dom::nodes::{
+    ContainerNode, DomNode, ImageNode, LineBreakNode, MentionNode, TextNode,
+};
+use crate::dom::{Dom, DomHandle, UnicodeString};
+
+/// Called for each node of a document by [Dom::accept], in document order.
+/// Containers are entered before their children are visited and left
+/// after. Every method does nothing by default, so a visitor only needs to
+/// implement the ones for the nodes it is interested in.
+pub trait DomVisitor<S>
+where
+    S: UnicodeString,
+{
+    fn enter_container(
+        &mut self,
+        _handle: &DomHandle,
+        _container: &ContainerNode<S>,
+    ) {
+    }
+
+    fn leave_container(
+        &mut self,
+        _handle: &DomHandle,
+        _container: &ContainerNode<S>,
+    ) {
+    }
+
+    fn visit_text(&mut self, _handle: &DomHandle, _text: &TextNode<S>) {}
+
+    fn visit_line_break(
+        &mut self,
+        _handle: &DomHandle,
+        _line_break: &LineBreakNode<S>,
+    ) {
+    }
+
+    fn visit_mention(
+        &mut self,
+        _handle: &DomHandle,
+        _mention: &MentionNode<S>,
+    ) {
+    }
+
+    fn visit_image(&mut self, _handle: &DomHandle, _image: &ImageNode<S>) {}
+}
+
+impl<S> Dom<S>
+where
+    S: UnicodeString,
+{
+    /// Pass every node of this document to `visitor`, starting with the
+    /// document itself
+    pub fn accept<V>(&self, visitor: &mut V)
+    where
+        V: DomVisitor<S>,
+    {
+        accept_node(self.lookup_node(&self.document_handle()), visitor);
+    }
+}
+
+fn accept_node<S, V>(node: &DomNode<S>, visitor: &mut V)
+where
+    S: UnicodeString,
+    V: DomVisitor<S>,
+{
+    let handle = node.handle();
+    match node {
+        DomNode::Container(container) => {
+            visitor.enter_container(&handle, container);
+            for child in container.children() {
+                accept_node(child, visitor);
+            }
+            visitor.leave_container(&handle, container);
+        }
+        DomNode::Text(text) => visitor.visit_text(&handle, text),
+        DomNode::LineBreak(line_break) => {
+            visitor.visit_line_break(&handle, line_break)
+        }
+        DomNode::Mention(mention) => visitor.visit_mention(&handle, mention),
+        DomNode::Image(image) => visitor.visit_image(&handle, image),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use super::*;
+    use crate::dom::parser::parse;
+
+    /// Writes out the tags of containers as they are entered and left, and
+    /// the contents of the other nodes
+    #[derive(Default)]
+    struct Outline(String);
+
+    impl DomVisitor<Utf16String> for Outline {
+        fn enter_container(
+            &mut self,
+            _: &DomHandle,
+            container: &ContainerNode<Utf16String>,
+        ) {
+            self.0 += &format!("<{}>", container.name());
+        }
+
+        fn leave_container(
+            &mut self,
+            _: &DomHandle,
+            container: &ContainerNode<Utf16String>,
+        ) {
+            self.0 += &format!("</{}>", container.name());
+        }
+
+        fn visit_text(&mut self, _: &DomHandle, text: &TextNode<Utf16String>) {
+            self.0 += &text.data().to_string();
+        }
+
+        fn visit_line_break(
+            &mut self,
+            _: &DomHandle,
+            _: &LineBreakNode<Utf16String>,
+        ) {
+            self.0 += "|";
+        }
+    }
+
+    #[test]
+    fn containers_are_entered_before_and_left_after_their_children() {
+        let dom = parse("a<em>b<u>c</u></em><br />d").unwrap();
+        let mut outline = Outline::default();
+        dom.accept(&mut outline);
+        assert_eq!(outline.0, "<>a<em>b<u>c</u></em>|d</>");
+    }
+
+    #[test]
+    fn nodes_are_visited_with_their_handles() {
+        struct Mentions(Vec<DomHandle>);
+        impl DomVisitor<Utf16String> for Mentions {
+            fn visit_mention(
+                &mut self,
+                handle: &DomHandle,
+                _: &MentionNode<Utf16String>,
+            ) {
+                self.0.push(handle.clone());
+            }
+        }
+
+        let dom = parse(
+            r#"<em>Hi <a href="https://matrix.to/#/@a:b.c" contenteditable="false">A</a></em>"#,
+        )
+        .unwrap();
+        let mut mentions = Mentions(Vec::new());
+        dom.accept(&mut mentions);
+        assert_eq!(mentions.0, vec![DomHandle::from_raw(vec![0, 1])]);
+    }
+}
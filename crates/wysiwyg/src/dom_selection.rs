@@ -0,0 +1,35 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::DomHandle;
+
+/// The selection as positions in nodes rather than in the text of the
+/// whole document, so that platforms can map it onto their own views of
+/// the document without counting characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomSelection {
+    /// Where the selection starts
+    pub anchor: DomPoint,
+    /// Where the selection ends, i.e. where the cursor is
+    pub focus: DomPoint,
+}
+
+/// A position inside a node: `offset` code units into a text node, or 0
+/// (before) or 1 (after) for a line break or other single-character node.
+/// In an empty document this is the root node, at offset 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomPoint {
+    pub handle: DomHandle,
+    pub offset: usize,
+}
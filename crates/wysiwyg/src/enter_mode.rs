@@ -0,0 +1,29 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// What pressing Enter does in the composer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EnterMode {
+    /// Enter starts a new line, list item etc., as in a document editor.
+    Document,
+    /// Enter asks for the message to be sent, and Shift+Enter starts a new
+    /// line instead, as in a chat app.
+    Message,
+}
+
+impl Default for EnterMode {
+    fn default() -> Self {
+        Self::Document
+    }
+}
@@ -15,6 +15,7 @@
 use crate::{ComposerAction, UnicodeString};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InlineFormatType {
     Bold,
     Italic,
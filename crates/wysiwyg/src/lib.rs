@@ -12,37 +12,95 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod block_type;
+mod clipboard_content;
 mod composer_action;
+mod composer_error;
+mod composer_history;
 mod composer_model;
 mod composer_state;
 mod composer_update;
+mod content_counts;
+mod cursor_movement;
 mod dom;
+mod dom_selection;
+mod enter_mode;
 mod format_type;
 mod list_type;
 mod location;
 mod markdown_html_parser;
+mod menu_action;
 mod menu_state;
+mod shortcut;
+mod state_encoding;
+mod state_stack;
+mod suggestion_trigger;
 mod tests;
+mod text_case;
 mod text_update;
 
+pub use crate::block_type::BlockType;
+pub use crate::clipboard_content::ClipboardContent;
 pub use crate::composer_action::ComposerAction;
+pub use crate::composer_error::ComposerError;
+pub use crate::composer_history::ComposerHistory;
+pub use crate::composer_history::HistoryEntry;
 pub use crate::composer_model::action_state::ActionState;
+pub use crate::composer_model::action_state::DisabledReason;
 pub use crate::composer_model::ComposerModel;
 pub use crate::composer_state::ComposerState;
 pub use crate::composer_update::ComposerUpdate;
+pub use crate::content_counts::ContentCounts;
+pub use crate::cursor_movement::MoveDirection;
+pub use crate::cursor_movement::MoveGranularity;
+pub use crate::dom::diff::DomChanges;
+pub use crate::dom::diff::DomPatch;
 pub use crate::dom::nodes::DomNode;
-pub use crate::dom::parser::parse;
+pub use crate::dom::parser::{parse, parse_lenient};
+pub use crate::dom::BlockSlice;
+pub use crate::dom::DomBuilder;
+pub use crate::dom::DomCreationError;
+pub use crate::dom::DomError;
 pub use crate::dom::DomHandle;
+pub use crate::dom::FormattingContext;
+pub use crate::dom::NodeId;
+pub use crate::dom::NodeMetadata;
+pub use crate::dom::OffsetUnit;
+pub use crate::dom::SubTreeMapping;
 pub use crate::dom::ToHtml;
 pub use crate::dom::ToRawText;
 pub use crate::dom::ToTree;
-pub use crate::dom::UnicodeString;
+#[cfg(feature = "assert-invariants")]
+pub use crate::dom::{set_invariant_sink, InvariantReport};
+pub use crate::dom::{
+    AllowedAttribute, RemovedAttribute, SanitizePolicy, SanitizeReport,
+    UnknownTagStrategy,
+};
+pub use crate::dom::{Invariant, InvariantViolation};
+pub use crate::dom::{
+    LenientParse, ParseCorrection, ParseError, ParseErrorKind,
+};
 pub use crate::dom::{MarkdownError, ToMarkdown};
+pub use crate::dom::{UnicodeStr, UnicodeStrExt, UnicodeString};
+pub use crate::dom_selection::DomPoint;
+pub use crate::dom_selection::DomSelection;
+pub use crate::enter_mode::EnterMode;
 pub use crate::format_type::InlineFormatType;
+pub use crate::list_type::ListNumberingType;
 pub use crate::list_type::ListType;
+pub use crate::location::GraphemeLocation;
 pub use crate::location::Location;
+pub use crate::menu_action::MenuAction;
+pub use crate::menu_action::PatternKey;
+pub use crate::menu_action::SuggestionPattern;
 pub use crate::menu_state::MenuState;
 pub use crate::menu_state::MenuStateUpdate;
+pub use crate::shortcut::Shortcut;
+pub use crate::state_encoding::DecodeError;
+pub use crate::suggestion_trigger::SuggestionTrigger;
+pub use crate::suggestion_trigger::TriggerBoundary;
+pub use crate::text_case::TextCase;
 pub use crate::text_update::ReplaceAll;
+pub use crate::text_update::ReplaceRange;
 pub use crate::text_update::Selection;
 pub use crate::text_update::TextUpdate;
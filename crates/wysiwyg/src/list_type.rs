@@ -40,3 +40,37 @@ impl<S: UnicodeString> From<S> for ListType {
         }
     }
 }
+
+/// How the items of an ordered list are numbered, as set by the `type`
+/// attribute of an `<ol>` tag.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ListNumberingType {
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl ListNumberingType {
+    pub(crate) fn attribute_value(&self) -> &'static str {
+        match self {
+            ListNumberingType::Decimal => "1",
+            ListNumberingType::LowerAlpha => "a",
+            ListNumberingType::UpperAlpha => "A",
+            ListNumberingType::LowerRoman => "i",
+            ListNumberingType::UpperRoman => "I",
+        }
+    }
+
+    pub(crate) fn from_attribute_value(value: &str) -> Option<Self> {
+        match value {
+            "1" => Some(ListNumberingType::Decimal),
+            "a" => Some(ListNumberingType::LowerAlpha),
+            "A" => Some(ListNumberingType::UpperAlpha),
+            "i" => Some(ListNumberingType::LowerRoman),
+            "I" => Some(ListNumberingType::UpperRoman),
+            _ => None,
+        }
+    }
+}
@@ -15,6 +15,7 @@
 use std::ops;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location(usize);
 
 impl From<usize> for Location {
@@ -59,3 +60,30 @@ impl ops::SubAssign<isize> for Location {
         *self += -rhs
     }
 }
+
+/// A position in the content counted in user-perceived characters
+/// (extended grapheme clusters) rather than code units, as the selection of
+/// some platforms' text views is. A line break, mention or image counts as
+/// one. See [crate::Dom::grapheme_location] and
+/// [crate::Dom::location_of_grapheme] to convert to and from [Location].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphemeLocation(usize);
+
+impl From<usize> for GraphemeLocation {
+    fn from(value: usize) -> Self {
+        Self(value)
+    }
+}
+
+impl From<GraphemeLocation> for usize {
+    fn from(val: GraphemeLocation) -> Self {
+        val.0
+    }
+}
+
+impl PartialEq<usize> for GraphemeLocation {
+    fn eq(&self, other: &usize) -> bool {
+        self.0 == *other
+    }
+}
@@ -54,6 +54,8 @@ impl MarkdownHTMLParser {
             .replace("<ul>\n", "<ul>")
             .replace("</ul>\n", "</ul>")
             .replace("<ol>\n", "<ol>")
+            // Ordered lists not counting from 1 look like `<ol start="3">`.
+            .replace("\">\n<li>", "\"><li>")
             .replace("</ol>\n", "</ol>")
             .replace("</li>\n", "</li>")
             .replace("<br />\n", "<br />");
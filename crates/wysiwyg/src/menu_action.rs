@@ -0,0 +1,65 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// What the platform should do with its suggestions menu, e.g. to offer
+/// users to mention or commands while they type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuAction {
+    /// Leave the menu as it is
+    Keep,
+    /// Hide the menu, if shown
+    None,
+    /// Show suggestions for the pattern at the cursor
+    Suggestion(SuggestionPattern),
+}
+
+/// A pattern the user has typed that suggestions can be offered for, e.g.
+/// `@ali` when mentioning a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestionPattern {
+    pub key: PatternKey,
+    /// The text typed after the key so far
+    pub text: String,
+    /// The position of the key
+    pub start: usize,
+    /// The end of the pattern
+    pub end: usize,
+}
+
+/// The prefix that starts a [SuggestionPattern]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PatternKey {
+    /// `@`, for mentioning users
+    At,
+    /// `#`, for mentioning rooms
+    Hash,
+    /// `/`, for commands
+    Slash,
+    /// `:`, for emoji shortcodes like `:smile`
+    Colon,
+    /// The prefix of a trigger registered by the host
+    Custom(String),
+}
+
+impl PatternKey {
+    pub(crate) fn from_prefix(prefix: &str) -> Self {
+        match prefix {
+            "@" => Self::At,
+            "#" => Self::Hash,
+            "/" => Self::Slash,
+            ":" => Self::Colon,
+            _ => Self::Custom(prefix.to_owned()),
+        }
+    }
+}
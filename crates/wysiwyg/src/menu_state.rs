@@ -12,16 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{composer_model::action_state::ActionState, ComposerAction};
+use crate::{
+    composer_model::action_state::ActionState, BlockType, ComposerAction,
+};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MenuState {
     Keep,
     Update(MenuStateUpdate),
+    /// Only the states of some actions changed since the last update.
+    /// Holds just those, to be merged into the states already known.
+    PartialUpdate(HashMap<ComposerAction, ActionState>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MenuStateUpdate {
     pub action_states: HashMap<ComposerAction, ActionState>,
+    pub block_type: BlockType,
+    /// The URL of the link the selection is inside, if it is all inside
+    /// one, e.g. to prefill an "edit link" dialog
+    pub link_url: Option<String>,
+    /// Whether the selection covers parts of more than one link
+    pub spans_multiple_links: bool,
 }
@@ -0,0 +1,88 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ComposerAction;
+
+/// A key combination that performs a [ComposerAction], so that every
+/// platform offers the same ones. Displays as e.g. `Mod+Shift+Z`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    /// The key pressed, e.g. `B` or `Tab`
+    pub key: String,
+    /// Whether Cmd is held on Apple platforms, or Ctrl elsewhere
+    pub mod_key: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Shortcut {
+    pub fn new(key: &str, mod_key: bool, shift: bool, alt: bool) -> Self {
+        Self {
+            key: key.to_owned(),
+            mod_key,
+            shift,
+            alt,
+        }
+    }
+
+    fn with_mod(key: &str) -> Self {
+        Self::new(key, true, false, false)
+    }
+
+    fn with_mod_shift(key: &str) -> Self {
+        Self::new(key, true, true, false)
+    }
+
+    /// The shortcuts each action has unless the host overrides them
+    pub fn defaults() -> HashMap<ComposerAction, Shortcut> {
+        HashMap::from([
+            (ComposerAction::Bold, Self::with_mod("B")),
+            (ComposerAction::Italic, Self::with_mod("I")),
+            (ComposerAction::Underline, Self::with_mod("U")),
+            (ComposerAction::StrikeThrough, Self::with_mod_shift("X")),
+            (ComposerAction::InlineCode, Self::with_mod("E")),
+            (ComposerAction::Link, Self::with_mod("K")),
+            (ComposerAction::Undo, Self::with_mod("Z")),
+            (ComposerAction::Redo, Self::with_mod_shift("Z")),
+            (ComposerAction::OrderedList, Self::with_mod_shift("7")),
+            (ComposerAction::UnorderedList, Self::with_mod_shift("8")),
+            (
+                ComposerAction::Indent,
+                Self::new("Tab", false, false, false),
+            ),
+            (
+                ComposerAction::UnIndent,
+                Self::new("Tab", false, true, false),
+            ),
+        ])
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.mod_key {
+            f.write_str("Mod+")?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        if self.alt {
+            f.write_str("Alt+")?;
+        }
+        f.write_str(&self.key)
+    }
+}
@@ -0,0 +1,425 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A compact binary form of composer states, so that a platform can save
+//! the model when its view is destroyed and restore it exactly later. See
+//! [crate::ComposerModel::to_bytes].
+
+use std::{error::Error, fmt};
+
+use crate::composer_state::ComposerState;
+use crate::dom::nodes::container_node::ContainerNodeKind;
+use crate::dom::nodes::{ContainerNode, DomNode, ImageNode, MentionNode};
+use crate::dom::{Dom, UnicodeString};
+use crate::{InlineFormatType, Location};
+
+/// Written first, so that a later version of the format can still read
+/// what older versions saved. Bump it whenever the format changes.
+pub(crate) const FORMAT_VERSION: u8 = 2;
+
+const CONTAINER: u8 = 0;
+const TEXT: u8 = 1;
+const LINE_BREAK: u8 = 2;
+const MENTION: u8 = 3;
+const IMAGE: u8 = 4;
+
+/// Why [crate::ComposerModel::from_bytes] couldn't restore a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes were saved in a format this version doesn't know, e.g. by
+    /// a newer version
+    UnsupportedVersion(u8),
+    /// The bytes stop part way through the model
+    UnexpectedEnd,
+    /// The bytes don't describe a model. The message says what was wrong.
+    Invalid(&'static str),
+}
+
+impl Error for DecodeError {}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(
+                formatter,
+                "Saved state has unsupported format version {}",
+                version
+            ),
+            Self::UnexpectedEnd => {
+                formatter.write_str("Saved state ends unexpectedly")
+            }
+            Self::Invalid(message) => {
+                write!(formatter, "Saved state is invalid: {}", message)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub(crate) fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub(crate) fn write_bool(&mut self, value: bool) {
+        self.write_u8(u8::from(value));
+    }
+
+    /// Written as a LEB128 varint, so that small numbers take one byte
+    pub(crate) fn write_usize(&mut self, value: usize) {
+        let mut value = value as u64;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.write_u8(byte);
+                return;
+            }
+            self.write_u8(byte | 0x80);
+        }
+    }
+
+    /// Written as UTF-8, whatever kind of string the model uses
+    pub(crate) fn write_str<S>(&mut self, value: &S)
+    where
+        S: UnicodeString,
+    {
+        let value = value.to_string();
+        self.write_usize(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    pub(crate) fn write_state<S>(&mut self, state: &ComposerState<S>)
+    where
+        S: UnicodeString,
+    {
+        self.write_nodes(state.dom.children());
+        self.write_selection(
+            state.start,
+            state.end,
+            &state.toggled_format_types,
+        );
+    }
+
+    pub(crate) fn write_selection(
+        &mut self,
+        start: Location,
+        end: Location,
+        toggled_format_types: &[InlineFormatType],
+    ) {
+        self.write_usize(start.into());
+        self.write_usize(end.into());
+        self.write_usize(toggled_format_types.len());
+        for format in toggled_format_types {
+            self.write_u8(format_code(format));
+        }
+    }
+
+    pub(crate) fn write_nodes<S>(&mut self, nodes: &[DomNode<S>])
+    where
+        S: UnicodeString,
+    {
+        self.write_usize(nodes.len());
+        for node in nodes {
+            self.write_node(node);
+        }
+    }
+
+    fn write_node<S>(&mut self, node: &DomNode<S>)
+    where
+        S: UnicodeString,
+    {
+        match node {
+            DomNode::Container(container) => {
+                self.write_u8(CONTAINER);
+                self.write_str(&S::from(container.name().to_string()));
+                self.write_kind(container.kind());
+                match container.attributes() {
+                    Some(attrs) => {
+                        self.write_bool(true);
+                        self.write_attributes(attrs);
+                    }
+                    None => self.write_bool(false),
+                }
+                self.write_nodes(container.children());
+            }
+            DomNode::Text(text) => {
+                self.write_u8(TEXT);
+                self.write_str(&S::from(text.data().to_string()));
+            }
+            DomNode::LineBreak(_) => self.write_u8(LINE_BREAK),
+            DomNode::Mention(mention) => {
+                self.write_u8(MENTION);
+                self.write_str(mention.url());
+                self.write_str(mention.display_text());
+            }
+            DomNode::Image(image) => {
+                self.write_u8(IMAGE);
+                self.write_attributes(image.attributes());
+            }
+        }
+    }
+
+    fn write_kind<S>(&mut self, kind: &ContainerNodeKind<S>)
+    where
+        S: UnicodeString,
+    {
+        match kind {
+            ContainerNodeKind::Generic => self.write_u8(0),
+            ContainerNodeKind::Formatting(format) => {
+                self.write_u8(1);
+                self.write_u8(format_code(format));
+            }
+            ContainerNodeKind::Link(url) => {
+                self.write_u8(2);
+                self.write_str(url);
+            }
+            ContainerNodeKind::List => self.write_u8(3),
+            ContainerNodeKind::ListItem => self.write_u8(4),
+            ContainerNodeKind::Table => self.write_u8(5),
+            ContainerNodeKind::TableRow => self.write_u8(6),
+            ContainerNodeKind::TableCell => self.write_u8(7),
+            ContainerNodeKind::Unknown => self.write_u8(8),
+        }
+    }
+
+    fn write_attributes<S>(&mut self, attrs: &[(S, S)])
+    where
+        S: UnicodeString,
+    {
+        self.write_usize(attrs.len());
+        for (name, value) in attrs {
+            self.write_str(name);
+            self.write_str(value);
+        }
+    }
+}
+
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Fail unless every byte has been read
+    pub(crate) fn finish(&self) -> Result<(), DecodeError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::Invalid("unexpected bytes after the model"))
+        }
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let (first, rest) =
+            self.bytes.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(*first)
+    }
+
+    pub(crate) fn read_bool(&mut self) -> Result<bool, DecodeError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Invalid("bad flag")),
+        }
+    }
+
+    pub(crate) fn read_usize(&mut self) -> Result<usize, DecodeError> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 64 {
+                return Err(DecodeError::Invalid("number too large"));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        usize::try_from(value)
+            .map_err(|_| DecodeError::Invalid("number too large"))
+    }
+
+    /// Read a count of items that each take at least one byte, so that a
+    /// corrupt count can't make us allocate more than the input could hold
+    fn read_count(&mut self) -> Result<usize, DecodeError> {
+        let count = self.read_usize()?;
+        if count > self.bytes.len() {
+            Err(DecodeError::UnexpectedEnd)
+        } else {
+            Ok(count)
+        }
+    }
+
+    pub(crate) fn read_str<S>(&mut self) -> Result<S, DecodeError>
+    where
+        S: UnicodeString,
+    {
+        let len = self.read_usize()?;
+        if len > self.bytes.len() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (text, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        let text = std::str::from_utf8(text)
+            .map_err(|_| DecodeError::Invalid("text is not UTF-8"))?;
+        Ok(S::from(text))
+    }
+
+    pub(crate) fn read_state<S>(
+        &mut self,
+    ) -> Result<ComposerState<S>, DecodeError>
+    where
+        S: UnicodeString,
+    {
+        let dom = Dom::new(self.read_nodes()?);
+        let (start, end, toggled_format_types) = self.read_selection()?;
+        let len = dom.text_len();
+        if usize::from(start) > len || usize::from(end) > len {
+            return Err(DecodeError::Invalid("selection outside the content"));
+        }
+        Ok(ComposerState {
+            dom,
+            start,
+            end,
+            toggled_format_types,
+        })
+    }
+
+    pub(crate) fn read_selection(
+        &mut self,
+    ) -> Result<(Location, Location, Vec<InlineFormatType>), DecodeError> {
+        let start = Location::from(self.read_usize()?);
+        let end = Location::from(self.read_usize()?);
+        let count = self.read_count()?;
+        let mut formats = Vec::with_capacity(count);
+        for _ in 0..count {
+            formats.push(format_from_code(self.read_u8()?)?);
+        }
+        Ok((start, end, formats))
+    }
+
+    pub(crate) fn read_nodes<S>(
+        &mut self,
+    ) -> Result<Vec<DomNode<S>>, DecodeError>
+    where
+        S: UnicodeString,
+    {
+        let count = self.read_count()?;
+        let mut nodes = Vec::with_capacity(count);
+        for _ in 0..count {
+            nodes.push(self.read_node()?);
+        }
+        Ok(nodes)
+    }
+
+    fn read_node<S>(&mut self) -> Result<DomNode<S>, DecodeError>
+    where
+        S: UnicodeString,
+    {
+        match self.read_u8()? {
+            CONTAINER => {
+                let name = self.read_str()?;
+                let kind = self.read_kind()?;
+                let attrs = if self.read_bool()? {
+                    Some(self.read_attributes()?)
+                } else {
+                    None
+                };
+                let children = self.read_nodes()?;
+                Ok(DomNode::Container(ContainerNode::new(
+                    name, kind, attrs, children,
+                )))
+            }
+            TEXT => Ok(DomNode::new_text(self.read_str()?)),
+            LINE_BREAK => Ok(DomNode::new_line_break()),
+            MENTION => {
+                let url = self.read_str()?;
+                let display_text = self.read_str()?;
+                Ok(DomNode::Mention(MentionNode::new(url, display_text)))
+            }
+            IMAGE => {
+                Ok(DomNode::Image(ImageNode::new(self.read_attributes()?)))
+            }
+            _ => Err(DecodeError::Invalid("unknown kind of node")),
+        }
+    }
+
+    fn read_kind<S>(&mut self) -> Result<ContainerNodeKind<S>, DecodeError>
+    where
+        S: UnicodeString,
+    {
+        Ok(match self.read_u8()? {
+            0 => ContainerNodeKind::Generic,
+            1 => ContainerNodeKind::Formatting(format_from_code(
+                self.read_u8()?,
+            )?),
+            2 => ContainerNodeKind::Link(self.read_str()?),
+            3 => ContainerNodeKind::List,
+            4 => ContainerNodeKind::ListItem,
+            5 => ContainerNodeKind::Table,
+            6 => ContainerNodeKind::TableRow,
+            7 => ContainerNodeKind::TableCell,
+            8 => ContainerNodeKind::Unknown,
+            _ => return Err(DecodeError::Invalid("unknown kind of container")),
+        })
+    }
+
+    fn read_attributes<S>(&mut self) -> Result<Vec<(S, S)>, DecodeError>
+    where
+        S: UnicodeString,
+    {
+        let count = self.read_count()?;
+        let mut attrs = Vec::with_capacity(count);
+        for _ in 0..count {
+            attrs.push((self.read_str()?, self.read_str()?));
+        }
+        Ok(attrs)
+    }
+}
+
+fn format_code(format: &InlineFormatType) -> u8 {
+    match format {
+        InlineFormatType::Bold => 0,
+        InlineFormatType::Italic => 1,
+        InlineFormatType::StrikeThrough => 2,
+        InlineFormatType::Underline => 3,
+        InlineFormatType::InlineCode => 4,
+    }
+}
+
+fn format_from_code(code: u8) -> Result<InlineFormatType, DecodeError> {
+    match code {
+        0 => Ok(InlineFormatType::Bold),
+        1 => Ok(InlineFormatType::Italic),
+        2 => Ok(InlineFormatType::StrikeThrough),
+        3 => Ok(InlineFormatType::Underline),
+        4 => Ok(InlineFormatType::InlineCode),
+        _ => Err(DecodeError::Invalid("unknown format")),
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::composer_state::ComposerState;
+use crate::dom::nodes::{ContainerNode, DomNode};
+use crate::dom::{DomHandle, UnicodeString};
+use crate::state_encoding::{DecodeError, Decoder, Encoder};
+use crate::{InlineFormatType, Location};
+
+/// A stack of composer states for undo or redo. Only the state on top is
+/// stored in full. Each one below it is stored as the difference from the
+/// state above, so a long history of small edits to a large document
+/// doesn't keep a copy of the whole document per edit.
+#[derive(Clone, Debug)]
+pub(crate) struct StateStack<S>
+where
+    S: UnicodeString,
+{
+    top: Option<ComposerState<S>>,
+    /// The states below the top, bottom first
+    deltas: Vec<StateDelta<S>>,
+}
+
+/// How to turn a state into the one below it in a [StateStack]: the
+/// children of one container, the deepest one holding all the changes, are
+/// spliced, and the selection and toggled formats are restored.
+#[derive(Clone, Debug)]
+struct StateDelta<S>
+where
+    S: UnicodeString,
+{
+    /// The index of each container from the document down to the one
+    /// whose children changed
+    path: Vec<usize>,
+    first_changed: usize,
+    removed: usize,
+    inserted: Vec<DomNode<S>>,
+    start: Location,
+    end: Location,
+    toggled_format_types: Vec<InlineFormatType>,
+}
+
+impl<S> StateStack<S>
+where
+    S: UnicodeString,
+{
+    pub fn new() -> Self {
+        Self {
+            top: None,
+            deltas: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deltas.len() + usize::from(self.top.is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.top.is_none()
+    }
+
+    pub fn last(&self) -> Option<&ComposerState<S>> {
+        self.top.as_ref()
+    }
+
+    pub fn push(&mut self, state: ComposerState<S>) {
+        if let Some(below) = self.top.replace(state) {
+            let top = self.top.as_ref().unwrap();
+            self.deltas.push(StateDelta::between(top, below));
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<ComposerState<S>> {
+        let top = self.top.take()?;
+        self.top = self.deltas.pop().and_then(|delta| delta.apply_to(&top));
+        Some(top)
+    }
+
+    pub fn clear(&mut self) {
+        self.top = None;
+        self.deltas.clear();
+    }
+
+    /// Forget the oldest states so that at most `len` remain
+    pub fn truncate_oldest(&mut self, len: usize) {
+        if len == 0 {
+            self.clear();
+        } else {
+            let excess = self.deltas.len().saturating_sub(len - 1);
+            self.deltas.drain(..excess);
+        }
+    }
+
+    /// Write the stack as it is stored: the top state in full, then the
+    /// differences below it
+    pub fn encode(&self, encoder: &mut Encoder) {
+        match &self.top {
+            Some(top) => {
+                encoder.write_bool(true);
+                encoder.write_state(top);
+            }
+            None => encoder.write_bool(false),
+        }
+        encoder.write_usize(self.deltas.len());
+        for delta in &self.deltas {
+            encoder.write_usize(delta.path.len());
+            for index in &delta.path {
+                encoder.write_usize(*index);
+            }
+            encoder.write_usize(delta.first_changed);
+            encoder.write_usize(delta.removed);
+            encoder.write_nodes(&delta.inserted);
+            encoder.write_selection(
+                delta.start,
+                delta.end,
+                &delta.toggled_format_types,
+            );
+        }
+    }
+
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+        let top: Option<ComposerState<S>> = if decoder.read_bool()? {
+            Some(decoder.read_state()?)
+        } else {
+            None
+        };
+        let count = decoder.read_usize()?;
+        if top.is_none() && count > 0 {
+            return Err(DecodeError::Invalid("history without a top state"));
+        }
+        let mut deltas = Vec::new();
+        for _ in 0..count {
+            let depth = decoder.read_usize()?;
+            let mut path = Vec::new();
+            for _ in 0..depth {
+                path.push(decoder.read_usize()?);
+            }
+            let first_changed = decoder.read_usize()?;
+            let removed = decoder.read_usize()?;
+            let inserted = decoder.read_nodes()?;
+            let (start, end, toggled_format_types) =
+                decoder.read_selection()?;
+            deltas.push(StateDelta {
+                path,
+                first_changed,
+                removed,
+                inserted,
+                start,
+                end,
+                toggled_format_types,
+            });
+        }
+
+        // Each difference must fit the state above it
+        if let Some(top) = &top {
+            let mut above = top.clone();
+            for delta in deltas.iter().rev() {
+                above = delta
+                    .apply_to(&above)
+                    .ok_or(DecodeError::Invalid("history is corrupt"))?;
+            }
+        }
+        Ok(Self { top, deltas })
+    }
+
+    /// All the states, bottom first
+    pub fn states(&self) -> Vec<ComposerState<S>> {
+        let mut states: Vec<ComposerState<S>> = Vec::new();
+        if let Some(top) = &self.top {
+            states.push(top.clone());
+            for delta in self.deltas.iter().rev() {
+                match states.last().and_then(|above| delta.apply_to(above)) {
+                    Some(state) => states.push(state),
+                    None => break,
+                }
+            }
+        }
+        states.reverse();
+        states
+    }
+}
+
+impl<S> FromIterator<ComposerState<S>> for StateStack<S>
+where
+    S: UnicodeString,
+{
+    fn from_iter<T: IntoIterator<Item = ComposerState<S>>>(iter: T) -> Self {
+        let mut stack = Self::new();
+        for state in iter {
+            stack.push(state);
+        }
+        stack
+    }
+}
+
+impl<S> StateDelta<S>
+where
+    S: UnicodeString,
+{
+    /// The difference that turns `above` into `below`
+    fn between(above: &ComposerState<S>, below: ComposerState<S>) -> Self {
+        let mut path = Vec::new();
+        let mut above_children: &[DomNode<S>] = above.dom.children();
+        let mut below_children = below.dom.take_children();
+        loop {
+            let first_changed = above_children
+                .iter()
+                .zip(below_children.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let unchanged_after = count_unchanged_after(
+                &above_children[first_changed..],
+                &mut below_children[first_changed..],
+            );
+            let removed =
+                above_children.len() - first_changed - unchanged_after;
+            let inserted_len =
+                below_children.len() - first_changed - unchanged_after;
+
+            // A single container changed inside, so look for the change
+            // in its children instead
+            if removed == 1 && inserted_len == 1 {
+                if let DomNode::Container(above_child) =
+                    &above_children[first_changed]
+                {
+                    if matches!(
+                        &below_children[first_changed],
+                        DomNode::Container(below_child)
+                            if is_same_container(above_child, below_child)
+                    ) {
+                        if let DomNode::Container(below_child) =
+                            below_children.swap_remove(first_changed)
+                        {
+                            path.push(first_changed);
+                            above_children = above_child.children();
+                            below_children = below_child.take_children();
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            let inserted = below_children
+                .drain(first_changed..first_changed + inserted_len)
+                .collect();
+            return Self {
+                path,
+                first_changed,
+                removed,
+                inserted,
+                start: below.start,
+                end: below.end,
+                toggled_format_types: below.toggled_format_types,
+            };
+        }
+    }
+
+    /// The state below `above`, or None if this difference doesn't fit it
+    fn apply_to(&self, above: &ComposerState<S>) -> Option<ComposerState<S>> {
+        let mut dom = above.dom.clone();
+        let handle = DomHandle::from_raw(self.path.clone());
+        let container = match dom.try_lookup_node_mut(&handle).ok()? {
+            DomNode::Container(container) => container,
+            _ => return None,
+        };
+        let changed_end = self.first_changed.checked_add(self.removed)?;
+        if changed_end > container.children().len() {
+            return None;
+        }
+        let mut children = container.children().clone();
+        children.splice(
+            self.first_changed..changed_end,
+            self.inserted.iter().cloned(),
+        );
+        container.replace_children(children);
+        Some(ComposerState {
+            dom,
+            start: self.start,
+            end: self.end,
+            toggled_format_types: self.toggled_format_types.clone(),
+        })
+    }
+}
+
+/// How many nodes at the end of `above` and `below` are the same. Nodes
+/// after a change may have moved, so the handles of those in `below` are
+/// updated before comparing them.
+fn count_unchanged_after<S>(
+    above: &[DomNode<S>],
+    below: &mut [DomNode<S>],
+) -> usize
+where
+    S: UnicodeString,
+{
+    let shortest = above.len().min(below.len());
+    let mut unchanged_after = 0;
+    while unchanged_after < shortest {
+        let above_node = &above[above.len() - 1 - unchanged_after];
+        let below_node = &mut below[below.len() - 1 - unchanged_after];
+        below_node.set_handle(above_node.handle());
+        if below_node != above_node {
+            break;
+        }
+        unchanged_after += 1;
+    }
+    unchanged_after
+}
+
+/// Whether two containers are the same apart from their children
+fn is_same_container<S>(a: &ContainerNode<S>, b: &ContainerNode<S>) -> bool
+where
+    S: UnicodeString,
+{
+    a.handle() == b.handle()
+        && a.id() == b.id()
+        && a.copy_with_new_children(Vec::new())
+            == b.copy_with_new_children(Vec::new())
+}
+
+#[cfg(test)]
+mod test {
+    use widestring::Utf16String;
+
+    use super::*;
+    use crate::dom::parser::parse;
+    use crate::ToHtml;
+
+    fn state(html: &str) -> ComposerState<Utf16String> {
+        ComposerState {
+            dom: parse(html).unwrap(),
+            start: Location::from(0),
+            end: Location::from(0),
+            toggled_format_types: Vec::new(),
+        }
+    }
+
+    fn html(state: &ComposerState<Utf16String>) -> String {
+        state.dom.to_html().to_string()
+    }
+
+    #[test]
+    fn popping_restores_the_states_pushed() {
+        let states = [
+            "<em>a</em><u>b</u><del>c</del>",
+            "<em>a</em><u>bx</u><del>c</del>",
+            "<em>a</em><u>bx</u><code>y</code><del>c</del>",
+            "<del>c</del>",
+            "",
+        ];
+        let mut stack: StateStack<Utf16String> =
+            states.iter().map(|s| state(s)).collect();
+        assert_eq!(stack.len(), 5);
+        for expected in states.iter().rev() {
+            let popped = stack.pop().unwrap();
+            assert_eq!(html(&popped), *expected);
+            assert_eq!(popped, state(expected));
+        }
+        assert!(stack.pop().is_none());
+    }
+
+    #[test]
+    fn only_the_changed_top_level_nodes_are_stored() {
+        let mut stack = StateStack::new();
+        stack.push(state("<em>a</em><u>b</u><del>c</del>"));
+        stack.push(state("<em>a</em><u>b</u><code>x</code><del>c</del>"));
+        let delta = stack.deltas.last().unwrap();
+        assert!(delta.path.is_empty());
+        assert_eq!(delta.first_changed, 2);
+        assert_eq!(delta.removed, 1);
+        assert!(delta.inserted.is_empty());
+    }
+
+    #[test]
+    fn changes_inside_a_node_store_only_the_changed_children() {
+        let mut stack = StateStack::new();
+        let below = "<ol><li>a</li><li><b>b</b>c</li><li>d</li></ol>";
+        let above = "<ol><li>a</li><li><b>b</b>cx</li><li>d</li></ol>";
+        stack.push(state(below));
+        stack.push(state(above));
+        let delta = stack.deltas.last().unwrap();
+        assert_eq!(delta.path, vec![0, 1]);
+        assert_eq!(delta.first_changed, 1);
+        assert_eq!(delta.removed, 1);
+        assert_eq!(delta.inserted.len(), 1);
+        assert_eq!(delta.inserted[0].to_html(), "c");
+
+        assert_eq!(html(&stack.pop().unwrap()), above);
+        assert_eq!(stack.pop().unwrap(), state(below));
+    }
+
+    #[test]
+    fn a_change_to_a_container_itself_is_stored_at_its_parent() {
+        let mut stack = StateStack::new();
+        stack.push(state("a<b>b</b>"));
+        stack.push(state("a<i>b</i>"));
+        let delta = stack.deltas.last().unwrap();
+        assert!(delta.path.is_empty());
+        assert_eq!(delta.first_changed, 1);
+        assert_eq!(stack.pop().map(|s| html(&s)).unwrap(), "a<i>b</i>");
+        assert_eq!(stack.pop().map(|s| html(&s)).unwrap(), "a<b>b</b>");
+    }
+
+    #[test]
+    fn truncating_keeps_the_newest_states() {
+        let mut stack: StateStack<Utf16String> =
+            ["a", "b", "c"].iter().map(|s| state(s)).collect();
+        stack.truncate_oldest(2);
+        let states: Vec<String> = stack.states().iter().map(html).collect();
+        assert_eq!(states, vec!["b", "c"]);
+    }
+}
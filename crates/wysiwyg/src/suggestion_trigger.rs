@@ -0,0 +1,63 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// A prefix that starts a [crate::SuggestionPattern] when typed, e.g. `@`
+/// to mention a user or `:` to pick an emoji.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestionTrigger {
+    pub prefix: String,
+    /// How many characters must follow the prefix before suggestions are
+    /// offered
+    pub min_length: usize,
+    /// Where the prefix may appear
+    pub boundary: TriggerBoundary,
+}
+
+/// Where a [SuggestionTrigger] prefix has to be for it to start a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriggerBoundary {
+    /// Only at the start of the message, like slash commands
+    StartOfMessage,
+    /// At the start of any word
+    StartOfWord,
+    /// Anywhere, even in the middle of a word
+    Anywhere,
+}
+
+impl SuggestionTrigger {
+    pub fn new(
+        prefix: &str,
+        min_length: usize,
+        boundary: TriggerBoundary,
+    ) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            min_length,
+            boundary,
+        }
+    }
+
+    /// `@` and `#` for mentions at the start of any word, `/` for
+    /// commands at the start of the message and `:` for emoji shortcodes.
+    /// Shortcodes need two characters so that smileys like `:)` and `:P`
+    /// don't trigger them.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("@", 0, TriggerBoundary::StartOfWord),
+            Self::new("#", 0, TriggerBoundary::StartOfWord),
+            Self::new("/", 0, TriggerBoundary::StartOfMessage),
+            Self::new(":", 2, TriggerBoundary::StartOfWord),
+        ]
+    }
+}
@@ -15,18 +15,32 @@
 #![cfg(test)]
 
 pub mod test_characters;
+pub mod test_clipboard;
+pub mod test_composition;
+pub mod test_counts;
 pub mod test_deleting;
+pub mod test_dom_changes;
+pub mod test_find_replace;
 pub mod test_formatting;
+pub mod test_images;
 pub mod test_links;
 pub mod test_lists;
+pub mod test_mentions;
 pub mod test_menu_state;
 pub mod test_paragraphs;
+pub mod test_paste;
+pub mod test_saved_state;
 pub mod test_selection;
 pub mod test_set_content;
+pub mod test_shortcuts;
+pub mod test_suggestions;
+pub mod test_tables;
+pub mod test_text_case;
 pub mod test_to_markdown;
 pub mod test_to_raw_text;
 pub mod test_to_tree;
 pub mod test_undo_redo;
+pub mod test_utf8;
 pub mod testutils_composer_model;
 pub mod testutils_conversion;
 pub mod testutils_dom;
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{ComposerModel, Location};
+use crate::{ComposerModel, Location, ReplaceRange, TextUpdate};
 use widestring::Utf16String;
 
 use crate::tests::testutils_composer_model::{cm, restore_whitespace, tx};
@@ -311,3 +311,99 @@ fn inserting_a_new_line_and_text_before_a_new_line_works() {
 fn replace_text(model: &mut ComposerModel<Utf16String>, new_text: &str) {
     model.replace_text(utf16(new_text));
 }
+
+#[test]
+fn typing_into_a_text_node_only_replaces_its_range() {
+    let mut model = cm("<b>ab|c</b> d");
+    let update = model.replace_text(utf16("<x>"));
+    assert_eq!(
+        update.text_update,
+        TextUpdate::ReplaceRange(ReplaceRange {
+            start: Location::from(2),
+            end: Location::from(2),
+            replacement_html: utf16("&lt;x&gt;"),
+            selection_start: Location::from(5),
+            selection_end: Location::from(5),
+        })
+    );
+}
+
+#[test]
+fn replacing_a_selection_within_a_text_node_only_replaces_its_range() {
+    let mut model = cm("a{bc}|d");
+    let update = model.replace_text(utf16("x"));
+    assert!(matches!(
+        update.text_update,
+        TextUpdate::ReplaceRange(ReplaceRange { start, end, .. })
+            if start == Location::from(1) && end == Location::from(3)
+    ));
+    assert_eq!(tx(&model), "ax|d");
+}
+
+#[test]
+fn replacing_text_across_nodes_replaces_all() {
+    let mut model = cm("a{b<b>c}|d</b>");
+    let update = model.replace_text(utf16("x"));
+    assert!(matches!(update.text_update, TextUpdate::ReplaceAll(_)));
+}
+
+#[test]
+fn typing_at_the_end_of_formatting_only_replaces_its_range() {
+    let mut model = cm("a <b>bc|</b>");
+    let update = model.replace_text(utf16("x"));
+    assert!(matches!(
+        update.text_update,
+        TextUpdate::ReplaceRange(ReplaceRange { start, end, .. })
+            if start == Location::from(4) && end == Location::from(4)
+    ));
+    assert_eq!(tx(&model), "a <b>bcx|</b>");
+}
+
+#[test]
+fn typing_at_the_start_of_a_text_node_replaces_all() {
+    let mut model = cm("|ab");
+    let update = model.replace_text(utf16("x"));
+    assert!(matches!(update.text_update, TextUpdate::ReplaceAll(_)));
+    assert_eq!(tx(&model), "x|ab");
+}
+
+#[test]
+fn typing_with_a_pending_format_replaces_all() {
+    let mut model = cm("ab|");
+    model.bold();
+    let update = model.replace_text(utf16("x"));
+    assert!(matches!(update.text_update, TextUpdate::ReplaceAll(_)));
+}
+
+#[test]
+fn decomposed_text_is_kept_as_typed_by_default() {
+    let mut model = cm("|");
+    replace_text(&mut model, "e\u{301}");
+    assert_eq!(tx(&model), "e\u{301}|");
+}
+
+#[test]
+fn decomposed_text_is_composed_with_nfc_normalization() {
+    let mut model = cm("a|");
+    model.set_nfc_normalization(true);
+    replace_text(&mut model, "e\u{301}");
+    assert_eq!(tx(&model), "a\u{e9}|");
+}
+
+#[test]
+fn combining_mark_typed_alone_composes_with_the_previous_character() {
+    let mut model = cm("<b>ae|</b>b");
+    model.set_nfc_normalization(true);
+    replace_text(&mut model, "\u{301}");
+    assert_eq!(tx(&model), "<b>a\u{e9}|</b>b");
+    model.undo();
+    assert_eq!(tx(&model), "<b>ae|</b>b");
+}
+
+#[test]
+fn combining_mark_without_a_composed_form_is_inserted_as_is() {
+    let mut model = cm("x|");
+    model.set_nfc_normalization(true);
+    replace_text(&mut model, "\u{301}");
+    assert_eq!(tx(&model), "x\u{301}|");
+}
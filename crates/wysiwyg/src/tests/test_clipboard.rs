@@ -0,0 +1,76 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+
+#[test]
+fn selected_html_closes_formatting_cut_by_the_selection() {
+    let model = cm("a<b>b{c<i>d}|e</i></b>f");
+    assert_eq!(model.get_selected_html(), utf16("<b>c<i>d</i></b>"));
+}
+
+#[test]
+fn selected_html_keeps_links_and_list_items() {
+    let model = cm(
+        "<ol><li><a href=\"https://matrix.org\">a{b</a></li><li>c}|d</li></ol>",
+    );
+    assert_eq!(
+        model.get_selected_html(),
+        utf16(
+            "<ol><li><a href=\"https://matrix.org\">b</a></li><li>c</li></ol>"
+        )
+    );
+}
+
+#[test]
+fn selected_html_is_empty_for_a_cursor() {
+    let model = cm("a<b>b|c</b>");
+    assert_eq!(model.get_selected_html(), utf16(""));
+}
+
+#[test]
+fn copy_gives_html_and_text_of_the_selection() {
+    let model = cm("a<b>b{c</b><br />d}|e");
+    let content = model.copy();
+    assert_eq!(content.html, utf16("<b>c</b><br />d"));
+    assert_eq!(content.text, utf16("c\nd"));
+    assert_eq!(tx(&model), "a<b>b{c</b><br />d}|e");
+}
+
+#[test]
+fn cut_removes_the_selection_and_returns_it() {
+    let mut model = cm("a<b>b{c</b>d}|e");
+    let update = model.cut();
+    let content = update.clipboard.unwrap();
+    assert_eq!(content.html, utf16("<b>c</b>d"));
+    assert_eq!(content.text, utf16("cd"));
+    assert_eq!(tx(&model), "a<b>b|</b>e");
+}
+
+#[test]
+fn cut_with_a_cursor_does_nothing() {
+    let mut model = cm("a<b>b|c</b>");
+    let update = model.cut();
+    assert_eq!(update.clipboard, None);
+    assert_eq!(tx(&model), "a<b>b|c</b>");
+}
+
+#[test]
+fn cut_can_be_undone() {
+    let mut model = cm("ab{cd}|e");
+    model.cut();
+    model.undo();
+    assert_eq!(tx(&model), "ab{cd}|e");
+}
@@ -0,0 +1,96 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::Location;
+
+#[test]
+fn set_composition_shows_provisional_text() {
+    let mut model = cm("a|b");
+    model.set_composition(utf16("に"), 1, 1);
+    assert_eq!(tx(&model), "aに|b");
+    assert_eq!(
+        model.get_composition(),
+        Some((Location::from(1), Location::from(2)))
+    );
+}
+
+#[test]
+fn set_composition_replaces_the_previous_provisional_text() {
+    let mut model = cm("a|b");
+    model.set_composition(utf16("n"), 1, 1);
+    model.set_composition(utf16("に"), 1, 1);
+    model.set_composition(utf16("日本"), 0, 2);
+    assert_eq!(tx(&model), "a{日本}|b");
+    assert_eq!(
+        model.get_composition(),
+        Some((Location::from(1), Location::from(3)))
+    );
+}
+
+#[test]
+fn set_composition_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.set_composition(utf16("x"), 1, 1);
+    assert_eq!(tx(&model), "ax|d");
+}
+
+#[test]
+fn commit_composition_keeps_the_text_and_moves_the_cursor_after_it() {
+    let mut model = cm("a|b");
+    model.set_composition(utf16("日本"), 0, 0);
+    model.commit_composition();
+    assert_eq!(tx(&model), "a日本|b");
+    assert_eq!(model.get_composition(), None);
+}
+
+#[test]
+fn a_composition_is_undone_in_one_step() {
+    let mut model = cm("a|");
+    model.set_composition(utf16("n"), 1, 1);
+    model.set_composition(utf16("に"), 1, 1);
+    model.set_composition(utf16("日"), 1, 1);
+    model.commit_composition();
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn a_composition_that_changes_nothing_leaves_no_undo_step() {
+    let mut model = cm("a|");
+    model.set_composition(utf16("n"), 1, 1);
+    model.set_composition(utf16(""), 0, 0);
+    model.commit_composition();
+    assert_eq!(tx(&model), "a|");
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn other_changes_end_the_composition() {
+    let mut model = cm("a|");
+    model.set_composition(utf16("n"), 1, 1);
+    model.replace_text(utf16("b"));
+    assert_eq!(model.get_composition(), None);
+    model.set_composition(utf16("c"), 1, 1);
+    assert_eq!(tx(&model), "anbc|");
+}
+
+#[test]
+fn commit_composition_without_a_composition_does_nothing() {
+    let mut model = cm("a|");
+    model.commit_composition();
+    assert_eq!(tx(&model), "a|");
+}
@@ -0,0 +1,139 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::cm;
+use crate::tests::testutils_conversion::utf16;
+use crate::ContentCounts;
+
+#[test]
+fn counts_of_an_empty_model_are_zero() {
+    assert_eq!(cm("|").counts(), ContentCounts::default());
+}
+
+#[test]
+fn counts_ignore_formatting() {
+    assert_eq!(
+        cm("Hello <strong>big</strong> <em>world</em>|").counts(),
+        ContentCounts {
+            characters: 15,
+            words: 3,
+            code_units: 15,
+        }
+    );
+}
+
+#[test]
+fn counts_count_graphemes_as_single_characters() {
+    assert_eq!(
+        cm("e\u{301} 😮‍💨|").counts(),
+        ContentCounts {
+            characters: 3,
+            words: 1,
+            code_units: 8,
+        }
+    );
+}
+
+#[test]
+fn counts_include_line_breaks_and_mentions() {
+    let model = cm("a<br />\
+        <a href=\"https://matrix.to/#/@alice:matrix.org\" \
+        contenteditable=\"false\">Alice</a>|");
+    assert_eq!(
+        model.counts(),
+        ContentCounts {
+            characters: 7,
+            words: 2,
+            code_units: 7,
+        }
+    );
+}
+
+#[test]
+fn counts_separate_words_in_different_list_items() {
+    let model = cm("<ul><li>a</li><li>b|</li></ul>");
+    assert_eq!(model.counts().words, 2);
+}
+
+#[test]
+fn counts_ignore_placeholders() {
+    let model = cm("<ol><li>~|</li></ol>");
+    assert_eq!(model.counts().characters, 0);
+}
+
+#[test]
+fn counts_are_updated_after_each_edit() {
+    let mut model = cm("<ul><li>a</li><li>b|</li></ul>");
+    assert_eq!(model.counts().characters, 3);
+    model.replace_text(utf16(" c"));
+    assert_eq!(
+        model.counts(),
+        ContentCounts {
+            characters: 5,
+            words: 3,
+            code_units: 5,
+        }
+    );
+    model.enter();
+    model.replace_text(utf16("d"));
+    assert_eq!(model.counts().words, 4);
+    model.undo();
+    model.undo();
+    assert_eq!(model.counts().words, 3);
+}
+
+#[test]
+fn counts_of_nested_blocks_are_on_lines_of_their_own() {
+    let model = cm("a<ul><li>b<ul><li>c</li></ul>d|</li></ul>e");
+    assert_eq!(
+        model.counts(),
+        ContentCounts {
+            characters: 9,
+            words: 5,
+            code_units: 9,
+        }
+    );
+}
+
+#[test]
+fn content_is_empty_with_only_whitespace_and_line_breaks() {
+    assert!(cm("|").is_content_empty());
+    assert!(cm(" <br />\u{a0}<br />|").is_content_empty());
+    assert!(cm("<strong> </strong>|").is_content_empty());
+}
+
+#[test]
+fn content_is_empty_with_only_empty_list_items() {
+    assert!(cm("<ul><li>~|</li></ul>").is_content_empty());
+}
+
+#[test]
+fn content_is_not_empty_with_text() {
+    assert!(!cm("<br />a|").is_content_empty());
+}
+
+#[test]
+fn content_is_not_empty_with_a_mention_or_image() {
+    assert!(!cm("<a href=\"https://matrix.to/#/@alice:matrix.org\" \
+        contenteditable=\"false\">Alice</a>|")
+    .is_content_empty());
+    assert!(!cm("<img src=\"mxc://matrix.org/a\" />|").is_content_empty());
+}
+
+#[test]
+#[cfg(feature = "icu-segmentation")]
+fn counts_words_of_thai_written_without_spaces() {
+    let model = cm("ฉันกินข้าว|");
+    assert_eq!(model.counts().words, 3);
+}
@@ -68,7 +68,9 @@ fn backspacing_a_newline_deletes_it() {
     let replace_all = match update.text_update {
         TextUpdate::Keep => panic!("expected ReplaceAll"),
         TextUpdate::ReplaceAll(replace_all) => replace_all,
+        TextUpdate::ReplaceRange(_) => panic!("expected ReplaceAll"),
         TextUpdate::Select(_) => panic!("expected ReplaceAll"),
+        TextUpdate::Send => panic!("expected ReplaceAll"),
     };
 
     assert_eq!(replace_all.start, 4);
@@ -240,6 +242,36 @@ fn test_backspace_complex_emoji() {
     assert_eq!(tx(&model), "Test|");
 }
 
+#[test]
+fn backspace_removes_a_character_with_its_combining_mark() {
+    let mut model = cm("ae\u{301}|b");
+    model.backspace();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn backspace_removes_an_emoji_with_its_variation_selector() {
+    let mut model = cm("a\u{2764}\u{fe0f}|b");
+    model.backspace();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn backspace_inside_a_grapheme_removes_all_of_it() {
+    let mut model = cm("Test😮‍💨|");
+    // Between the ZWJ and the last emoji of the sequence
+    model.select(7.into(), 7.into());
+    model.backspace();
+    assert_eq!(tx(&model), "Test|");
+}
+
+#[test]
+fn backspace_an_emoji_at_the_end_of_formatting() {
+    let mut model = cm("<strong>a😄|</strong>b");
+    model.backspace();
+    assert_eq!(tx(&model), "<strong>a|</strong>b");
+}
+
 #[test]
 fn test_delete_emoji() {
     let mut model = cm("😄|😅");
@@ -257,6 +289,28 @@ fn test_delete_complex_emoji() {
     assert_eq!(tx(&model), "Test|");
 }
 
+#[test]
+fn delete_removes_a_character_with_its_combining_mark() {
+    let mut model = cm("a|e\u{301}b");
+    model.delete();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn delete_inside_a_grapheme_removes_all_of_it() {
+    let mut model = cm("a|\u{2764}\u{fe0f}b");
+    model.select(2.into(), 2.into());
+    model.delete();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn delete_an_emoji_at_the_start_of_formatting() {
+    let mut model = cm("a|<em>😄b</em>");
+    model.delete();
+    assert_eq!(tx(&model), "a|<em>b</em>");
+}
+
 #[test]
 fn test_delete_complex_grapheme() {
     let mut model = cm("Test|О́");
@@ -270,3 +324,138 @@ fn test_backspace_complex_grapheme() {
     model.backspace();
     assert_eq!(tx(&model), "Test|");
 }
+
+#[test]
+fn delete_word_backward_removes_the_word_before_the_cursor() {
+    let mut model = cm("hello world|");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "hello&nbsp;|");
+}
+
+#[test]
+fn delete_word_backward_skips_whitespace_before_the_cursor() {
+    let mut model = cm("hello world  |");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "hello&nbsp;|");
+}
+
+#[test]
+fn delete_word_backward_from_inside_a_word_removes_its_start() {
+    let mut model = cm("hello wo|rld");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "hello |rld");
+}
+
+#[test]
+fn delete_word_backward_spans_formatting() {
+    let mut model = cm("one tw<strong>o</strong>|");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "one |");
+}
+
+#[test]
+fn delete_word_backward_stops_at_a_line_break() {
+    let mut model = cm("abc<br />|def");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "abc|def");
+}
+
+#[test]
+fn delete_word_backward_at_start_of_list_item_merges_it() {
+    let mut model = cm("<ol><li>abc</li><li>~|def</li></ol>");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "<ol><li>abc|def</li></ol>");
+}
+
+#[test]
+fn delete_word_backward_handles_non_latin_words() {
+    let mut model = cm("Привет мир|");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "Привет&nbsp;|");
+}
+
+#[test]
+fn delete_word_backward_with_selection_deletes_selection() {
+    let mut model = cm("he{llo wo}|rld");
+    model.delete_word_backward();
+    assert_eq!(tx(&model), "he|rld");
+}
+
+#[test]
+fn delete_word_forward_removes_the_word_after_the_cursor() {
+    let mut model = cm("|hello world");
+    model.delete_word_forward();
+    assert_eq!(tx(&model), "| world");
+}
+
+#[test]
+fn delete_word_forward_skips_whitespace_after_the_cursor() {
+    let mut model = cm("hello| world");
+    model.delete_word_forward();
+    assert_eq!(tx(&model), "hello|");
+}
+
+#[test]
+fn delete_word_forward_at_end_of_text_behaves_like_delete() {
+    let mut model = cm("abc|<br />def");
+    model.delete_word_forward();
+    assert_eq!(tx(&model), "abc|def");
+}
+
+#[test]
+fn delete_word_can_be_undone() {
+    let mut model = cm("hello world|");
+    model.delete_word_backward();
+    model.undo();
+    assert_eq!(tx(&model), "hello world|");
+}
+
+#[test]
+fn delete_to_start_of_block_removes_text_before_the_cursor() {
+    let mut model = cm("abc <strong>def</strong> gh|i");
+    model.delete_to_start_of_block();
+    assert_eq!(tx(&model), "|i");
+}
+
+#[test]
+fn delete_to_start_of_block_stops_at_a_line_break() {
+    let mut model = cm("abc<br />def gh|i");
+    model.delete_to_start_of_block();
+    assert_eq!(tx(&model), "abc<br />|i");
+}
+
+#[test]
+fn delete_to_start_of_block_stays_in_the_list_item() {
+    let mut model = cm("<ol><li>abc</li><li>~de|f</li></ol>");
+    model.delete_to_start_of_block();
+    assert_eq!(tx(&model), "<ol><li>abc</li><li>~|f</li></ol>");
+}
+
+#[test]
+fn delete_to_start_of_block_at_start_of_line_behaves_like_backspace() {
+    let mut model = cm("abc<br />|def");
+    model.delete_to_start_of_block();
+    assert_eq!(tx(&model), "abc|def");
+}
+
+#[test]
+fn delete_to_end_of_block_removes_text_after_the_cursor() {
+    let mut model = cm("a|bc <em>def</em><br />ghi");
+    model.delete_to_end_of_block();
+    assert_eq!(tx(&model), "a|<br />ghi");
+}
+
+#[test]
+fn delete_to_end_of_block_stops_before_a_nested_list() {
+    let mut model = cm("<ul><li>a|bc<ul><li>~def</li></ul></li></ul>");
+    model.delete_to_end_of_block();
+    assert_eq!(tx(&model), "<ul><li>a|<ul><li>~def</li></ul></li></ul>");
+}
+
+#[test]
+fn delete_to_start_of_block_is_a_single_undo_step() {
+    let mut model = cm("abc def|");
+    model.delete_to_start_of_block();
+    model.undo();
+    assert_eq!(tx(&model), "abc def|");
+}
@@ -0,0 +1,81 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::DomHandle;
+
+#[test]
+fn typing_reports_the_text_node_it_changed() {
+    let mut model = cm("a<em>b|</em>");
+    let update = model.replace_text(utf16("c"));
+    assert_eq!(update.dom_changes.modified, handles(&[&[1, 0]]));
+    assert!(update.dom_changes.created.is_empty());
+    assert!(update.dom_changes.deleted.is_empty());
+}
+
+#[test]
+fn the_reported_document_shares_its_nodes_instead_of_copying_them() {
+    let mut model = cm("<p>a</p><p>b|</p>");
+    let update = model.replace_text(utf16("c"));
+    assert_eq!(update.dom_changes.modified, handles(&[&[1, 0]]));
+    let update = model.enter();
+    assert!(!update.dom_changes.is_empty());
+    assert!(model
+        .reported_dom
+        .document()
+        .shares_children_with(model.state.dom.document()));
+}
+
+#[test]
+fn formatting_reports_the_nodes_it_replaced() {
+    let mut model = cm("a{b}|");
+    let update = model.bold();
+    assert_eq!(tx(&model), "a<strong>{b}|</strong>");
+    assert_eq!(update.dom_changes.modified, handles(&[&[0], &[]]));
+    assert_eq!(update.dom_changes.created, handles(&[&[1]]));
+    assert!(update.dom_changes.deleted.is_empty());
+}
+
+#[test]
+fn changes_are_reported_once() {
+    let mut model = cm("ab|");
+    model.enter();
+    let update = model.select(0.into(), 0.into());
+    assert!(update.dom_changes.is_empty());
+    let update = model.undo();
+    assert!(!update.dom_changes.is_empty());
+}
+
+#[test]
+fn nodes_keep_their_ids_when_their_handles_change() {
+    let mut model = cm("a{b}|<em>c</em>");
+    model.set_node_ids_enabled(true);
+    let dom = &model.state.dom;
+    let em = dom.lookup_node(&DomHandle::from_raw(vec![1])).id().unwrap();
+
+    model.bold();
+
+    let dom = &model.state.dom;
+    assert_eq!(tx(&model), "a<strong>{b}|</strong><em>c</em>");
+    assert_eq!(dom.find_node_by_id(em), Some(DomHandle::from_raw(vec![2])));
+    assert!(dom.iter().all(|(_, node)| node.id().is_some()));
+}
+
+fn handles(paths: &[&[usize]]) -> Vec<DomHandle> {
+    paths
+        .iter()
+        .map(|p| DomHandle::from_raw(p.to_vec()))
+        .collect()
+}
@@ -0,0 +1,93 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::Location;
+
+fn ranges(model_ranges: Vec<(Location, Location)>) -> Vec<(usize, usize)> {
+    model_ranges
+        .into_iter()
+        .map(|(start, end)| (start.into(), end.into()))
+        .collect()
+}
+
+#[test]
+fn find_returns_every_match() {
+    let model = cm("abcab|");
+    assert_eq!(ranges(model.find(&utf16("ab"))), vec![(0, 2), (3, 5)]);
+}
+
+#[test]
+fn find_matches_across_formatting() {
+    let model = cm("a<strong>bc</strong>d|");
+    assert_eq!(ranges(model.find(&utf16("abcd"))), vec![(0, 4)]);
+}
+
+#[test]
+fn find_does_not_match_across_line_breaks() {
+    let model = cm("ab<br />cd|");
+    assert!(model.find(&utf16("bc")).is_empty());
+    assert_eq!(ranges(model.find(&utf16("cd"))), vec![(3, 5)]);
+}
+
+#[test]
+fn find_positions_are_in_code_units() {
+    let model = cm("😄a😄a|");
+    assert_eq!(ranges(model.find(&utf16("a"))), vec![(2, 3), (5, 6)]);
+}
+
+#[test]
+fn find_an_empty_pattern_finds_nothing() {
+    let model = cm("abc|");
+    assert!(model.find(&utf16("")).is_empty());
+}
+
+#[test]
+fn replace_all_replaces_every_match() {
+    let mut model = cm("a cat and a cat|");
+    model.replace_all(&utf16("cat"), &utf16("dog"));
+    assert_eq!(tx(&model), "a dog and a dog|");
+}
+
+#[test]
+fn replace_all_keeps_formatting() {
+    let mut model = cm("|<em>cat</em> and <strong>cat</strong>");
+    model.replace_all(&utf16("cat"), &utf16("mouse"));
+    assert_eq!(tx(&model), "|<em>mouse</em> and <strong>mouse</strong>");
+}
+
+#[test]
+fn replace_all_keeps_the_selection_on_the_same_text() {
+    let mut model = cm("cat {and}| cat");
+    model.replace_all(&utf16("cat"), &utf16("mouse"));
+    assert_eq!(tx(&model), "mouse {and}| mouse");
+}
+
+#[test]
+fn replace_all_is_a_single_undo_step() {
+    let mut model = cm("a a a|");
+    model.replace_all(&utf16("a"), &utf16("b"));
+    assert_eq!(tx(&model), "b b b|");
+    model.undo();
+    assert_eq!(tx(&model), "a a a|");
+}
+
+#[test]
+fn replace_all_without_matches_does_nothing() {
+    let mut model = cm("abc|");
+    model.replace_all(&utf16("x"), &utf16("y"));
+    assert_eq!(tx(&model), "abc|");
+    assert!(model.previous_states.is_empty());
+}
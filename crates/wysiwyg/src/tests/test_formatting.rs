@@ -257,3 +257,52 @@ fn formatting_in_an_empty_model_applies_formatting() {
     model.replace_text(utf16("d"));
     assert_eq!(tx(&model), "<strong>d|</strong>");
 }
+
+#[test]
+fn adjacent_identical_formatting_is_joined_after_setting_content() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&utf16("<b>a</b><b>b</b>"));
+    assert_eq!(tx(&model), "<b>ab|</b>");
+}
+
+#[test]
+fn adjacent_identical_formatting_is_joined_after_deleting_between() {
+    let mut model = cm("<em>a</em>{x}|<em>b</em>");
+    model.delete();
+    assert_eq!(tx(&model), "<em>a|b</em>");
+}
+
+#[test]
+fn nested_identical_formatting_is_joined_too() {
+    let mut model = cm("<b><i>a</i></b>{x}|<b><i>b</i></b>");
+    model.delete();
+    assert_eq!(tx(&model), "<b><i>a|b</i></b>");
+}
+
+#[test]
+fn adjacent_different_formatting_is_not_joined() {
+    let mut model = cm("<b>a</b>{x}|<i>b</i>");
+    model.delete();
+    assert_eq!(tx(&model), "<b>a|</b><i>b</i>");
+}
+
+#[test]
+fn nested_identical_formatting_is_unwrapped_after_setting_content() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&utf16("<b>a<strong>b</strong>c</b>"));
+    assert_eq!(tx(&model), "<b>abc|</b>");
+}
+
+#[test]
+fn nested_equivalent_formatting_tags_are_unwrapped() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&utf16("<em>a<u><i>b</i></u></em>"));
+    assert_eq!(tx(&model), "<em>a<u>b|</u></em>");
+}
+
+#[test]
+fn pasting_formatting_inside_the_same_formatting_does_not_nest_it() {
+    let mut model = cm("<b>a|c</b>");
+    model.paste_html(&utf16("<b>b</b>"));
+    assert_eq!(tx(&model), "<b>ab|c</b>");
+}
@@ -0,0 +1,175 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use widestring::Utf16String;
+
+use crate::dom::nodes::DomNode;
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{Location, TextUpdate, ToMarkdown, ToRawText, ToTree};
+
+const BLOB: &str = "<img src=\"mxc://matrix.org/blob\" alt=\"blob\" />";
+
+fn attrs(pairs: &[(&str, &str)]) -> Vec<(Utf16String, Utf16String)> {
+    pairs.iter().map(|(n, v)| (utf16(n), utf16(v))).collect()
+}
+
+#[test]
+fn insert_image_adds_an_img_at_the_cursor() {
+    let mut model = cm("a|b");
+    model.insert_image(attrs(&[
+        ("src", "mxc://matrix.org/blob"),
+        ("alt", "blob"),
+    ]));
+    assert_eq!(tx(&model), format!("a{BLOB}|b"));
+}
+
+#[test]
+fn insert_image_writes_attributes_in_a_fixed_order() {
+    let mut model = cm("|");
+    model.insert_image(attrs(&[
+        ("height", "32"),
+        ("width", "16"),
+        ("onload", "evil()"),
+        ("alt", "blob"),
+        ("src", "mxc://matrix.org/blob"),
+    ]));
+    assert_eq!(
+        tx(&model),
+        "<img src=\"mxc://matrix.org/blob\" alt=\"blob\" width=\"16\" \
+        height=\"32\" />|"
+    );
+}
+
+#[test]
+fn insert_image_without_src_does_nothing() {
+    let mut model = cm("a|");
+    let update = model.insert_image(attrs(&[("alt", "blob")]));
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn insert_image_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.insert_image(attrs(&[
+        ("src", "mxc://matrix.org/blob"),
+        ("alt", "blob"),
+    ]));
+    assert_eq!(tx(&model), format!("a{BLOB}|d"));
+}
+
+#[test]
+fn image_counts_as_a_single_character() {
+    let model = cm(&format!("a{BLOB}|b"));
+    assert_eq!(model.state.start, Location::from(2));
+    assert_eq!(model.state.dom.text_len(), 3);
+}
+
+#[test]
+fn cm_and_tx_roundtrip_around_images() {
+    assert_eq!(tx(&cm(&format!("|{BLOB}"))), format!("|{BLOB}"));
+    assert_eq!(tx(&cm(&format!("a{{{BLOB}}}|b"))), format!("a{{{BLOB}}}|b"));
+}
+
+#[test]
+fn backspace_deletes_an_image() {
+    let mut model = cm(&format!("a{BLOB}|b"));
+    model.backspace();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn delete_removes_an_image() {
+    let mut model = cm(&format!("a|{BLOB}b"));
+    model.delete();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn images_are_atomic_inline_nodes() {
+    let model = cm(&format!("{BLOB}|"));
+    assert!(model.state.dom.document().children()[0].is_atomic_inline());
+}
+
+#[test]
+fn image_raw_text_is_its_alt_text() {
+    let model = cm(&format!("a{BLOB}|"));
+    assert_eq!(model.state.dom.to_raw_text(), utf16("ablob"));
+}
+
+#[test]
+fn image_is_written_as_a_markdown_image() {
+    let model = cm(&format!("a{BLOB}|"));
+    assert_eq!(
+        model.state.dom.to_markdown().unwrap(),
+        utf16("a![blob](<mxc://matrix.org/blob>)")
+    );
+}
+
+#[test]
+fn image_attribute_values_are_escaped() {
+    let mut model = cm("|");
+    model.insert_image(attrs(&[("src", "x"), ("alt", "\"quoted\"")]));
+    assert_eq!(
+        model.state.dom.to_string(),
+        "<img src=\"x\" alt=\"&quot;quoted&quot;\" />"
+    );
+}
+
+#[test]
+fn set_content_from_html_parses_images_and_emoticons() {
+    let mut model = cm("|");
+    model.set_content_from_html(&utf16(
+        "<p>a <strong><img src=\"mxc://matrix.org/blob\" alt=\"blob\" \
+            width=\"32\" height=\"32\" /></strong> \
+            <img alt=\":cat:\" data-mx-emoticon=\"\" \
+            src=\"mxc://matrix.org/cat\" /></p>",
+    ));
+    assert_eq!(
+        tx(&model),
+        "<p>a <strong><img src=\"mxc://matrix.org/blob\" alt=\"blob\" \
+        width=\"32\" height=\"32\" /></strong> \
+        <img src=\"mxc://matrix.org/cat\" alt=\":cat:\" \
+        data-mx-emoticon=\"\" />|</p>"
+    );
+    assert_eq!(model.state.dom.text_len(), 5);
+}
+
+#[test]
+fn image_with_data_mx_emoticon_is_an_emoticon() {
+    let model = cm(&format!(
+        "{BLOB}<img src=\"mxc://matrix.org/cat\" alt=\":cat:\" \
+        data-mx-emoticon=\"\" />|"
+    ));
+    let children = model.state.dom.document().children();
+    let is_emoticon = |i: usize| match &children[i] {
+        DomNode::Image(image) => image.is_emoticon(),
+        _ => panic!("Expected an image"),
+    };
+    assert!(!is_emoticon(0));
+    assert!(is_emoticon(1));
+}
+
+#[test]
+fn emoticon_tree_shows_it_is_an_emoticon() {
+    let model = cm("<img src=\"mxc://matrix.org/cat\" alt=\":cat:\" \
+        data-mx-emoticon=\"\" />|");
+    assert_eq!(
+        model.state.dom.to_tree(),
+        "generic [] len 1\n\
+        ├>img emoticon \"mxc://matrix.org/cat\" [0] len 1\n\
+        └>text \"\" [1] len 0\n"
+    );
+}
@@ -12,24 +12,204 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::tests::testutils_composer_model::cm;
+use crate::tests::testutils_composer_model::{cm, tx};
 use crate::tests::testutils_conversion::utf16;
 
-use crate::TextUpdate;
+use crate::{ComposerAction, ComposerError, DomHandle, DomNode, TextUpdate};
 
 #[test]
 fn cant_set_link_to_empty_selection() {
     let mut model = cm("hello |world");
-    let update = model.set_link(utf16("https://element.io"));
+    let update = model.set_link(utf16("https://element.io")).unwrap();
     assert!(matches!(update.text_update, TextUpdate::Keep));
 }
 
 #[test]
 fn set_link_wraps_selection_in_link_tag() {
     let mut model = cm("{hello}| world");
-    model.set_link(utf16("https://element.io"));
+    model.set_link(utf16("https://element.io")).unwrap();
     assert_eq!(
         model.state.dom.to_string(),
         "<a href=\"https://element.io\">hello</a> world"
     );
 }
+
+#[test]
+fn set_link_with_attributes_writes_them_after_href() {
+    let mut model = cm("{hello}| world");
+    model
+        .set_link_with_attributes(
+            utf16("https://element.io"),
+            vec![
+                (utf16("rel"), utf16("noopener")),
+                (utf16("title"), utf16("Element")),
+            ],
+        )
+        .unwrap();
+    assert_eq!(
+        model.state.dom.to_string(),
+        "<a href=\"https://element.io\" rel=\"noopener\" \
+        title=\"Element\">hello</a> world"
+    );
+}
+
+#[test]
+fn link_attributes_can_be_read_back() {
+    let model = cm("<a href=\"https://element.io\" title=\"Element\">hi|</a>");
+    let link = match model.state.dom.lookup_node(&DomHandle::from_raw(vec![0]))
+    {
+        DomNode::Container(link) => link,
+        _ => panic!("Expected a link"),
+    };
+    assert_eq!(link.get_attribute("title"), Some(&utf16("Element")));
+    assert_eq!(
+        link.get_attribute("href"),
+        Some(&utf16("https://element.io"))
+    );
+    assert_eq!(link.get_attribute("rel"), None);
+}
+
+#[test]
+fn remove_links_unwraps_link_around_cursor() {
+    let mut model = cm("<a href=\"https://element.io\">hel|lo</a> world");
+    model.remove_links();
+    assert_eq!(tx(&model), "hel|lo world");
+}
+
+#[test]
+fn remove_links_unwraps_all_links_in_selection() {
+    let mut model = cm("<a href=\"https://matrix.org\">a{b</a>c\
+        <a href=\"https://element.io\">d}|e</a>f");
+    model.remove_links();
+    assert_eq!(tx(&model), "a{bcd}|ef");
+}
+
+#[test]
+fn remove_links_keeps_formatting_inside_link() {
+    let mut model =
+        cm("x<a href=\"https://element.io\"><b>{bold}|</b> text</a>");
+    model.remove_links();
+    assert_eq!(tx(&model), "x<b>{bold}|</b> text");
+}
+
+#[test]
+fn remove_links_without_links_does_nothing() {
+    let mut model = cm("{hello}| world");
+    let update = model.remove_links();
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn remove_links_can_be_undone() {
+    let mut model = cm("<a href=\"https://element.io\">{link}|</a>");
+    model.remove_links();
+    model.undo();
+    assert_eq!(tx(&model), "<a href=\"https://element.io\">{link}|</a>");
+}
+
+#[test]
+fn set_link_with_text_inserts_link_at_cursor() {
+    let mut model = cm("hello | world");
+    model.set_link_with_text(utf16("https://element.io"), utf16("Element"));
+    assert_eq!(
+        tx(&model),
+        "hello <a href=\"https://element.io\">Element|</a> world"
+    );
+}
+
+#[test]
+fn set_link_with_text_in_empty_model() {
+    let mut model = cm("|");
+    model.set_link_with_text(utf16("https://element.io"), utf16("Element"));
+    assert_eq!(tx(&model), "<a href=\"https://element.io\">Element|</a>");
+}
+
+#[test]
+fn set_link_with_text_replaces_selection() {
+    let mut model = cm("hello {there}| world");
+    model.set_link_with_text(utf16("https://element.io"), utf16("Element"));
+    assert_eq!(
+        tx(&model),
+        "hello <a href=\"https://element.io\">Element|</a> world"
+    );
+}
+
+#[test]
+fn set_link_with_empty_text_does_nothing() {
+    let mut model = cm("hello |world");
+    let update =
+        model.set_link_with_text(utf16("https://element.io"), utf16(""));
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+}
+
+#[test]
+fn get_link_at_selection_with_cursor_inside_link() {
+    let model = cm("a <a href=\"https://element.io\">Ele|ment</a> b");
+    assert_eq!(
+        model.get_link_at_selection(),
+        Some((utf16("https://element.io"), utf16("Element")))
+    );
+}
+
+#[test]
+fn get_link_at_selection_with_selection_inside_link() {
+    let model = cm("a <a href=\"https://element.io\">E{lem}|ent</a> b");
+    assert_eq!(
+        model.get_link_at_selection(),
+        Some((utf16("https://element.io"), utf16("Element")))
+    );
+}
+
+#[test]
+fn get_link_at_selection_finds_link_around_formatting() {
+    let model = cm("<a href=\"https://element.io\">E<b>lem|</b>ent</a>");
+    assert_eq!(
+        model.get_link_at_selection(),
+        Some((utf16("https://element.io"), utf16("Element")))
+    );
+}
+
+#[test]
+fn get_link_at_selection_is_none_outside_links() {
+    let model = cm("a| <a href=\"https://element.io\">Element</a> b");
+    assert_eq!(model.get_link_at_selection(), None);
+}
+
+#[test]
+fn get_link_at_selection_is_none_with_cursor_just_before_link() {
+    let model = cm("a |<a href=\"https://element.io\">Element</a> b");
+    assert_eq!(model.get_link_at_selection(), None);
+}
+
+#[test]
+fn get_link_at_selection_is_none_with_cursor_just_after_link() {
+    let model = cm("a <a href=\"https://element.io\">Element</a>| b");
+    assert_eq!(model.get_link_at_selection(), None);
+}
+
+#[test]
+fn get_link_at_selection_with_whole_link_selected() {
+    let model = cm("a {<a href=\"https://element.io\">Element</a>}| b");
+    assert_eq!(
+        model.get_link_at_selection(),
+        Some((utf16("https://element.io"), utf16("Element")))
+    );
+}
+
+#[test]
+fn get_link_at_selection_is_none_when_selection_leaves_link() {
+    let model = cm("{a <a href=\"https://element.io\">Ele}|ment</a> b");
+    assert_eq!(model.get_link_at_selection(), None);
+}
+
+#[test]
+fn set_link_across_several_nodes_returns_an_error() {
+    let mut model = cm("{a<em>b}|</em>");
+    assert_eq!(
+        model.set_link(utf16("https://element.io")),
+        Err(ComposerError::UnsupportedSelection(ComposerAction::Link))
+    );
+    assert_eq!(tx(&model), "{a<em>b}|</em>");
+    assert!(!model.can_undo());
+}
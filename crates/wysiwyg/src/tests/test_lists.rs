@@ -17,12 +17,15 @@ use widestring::Utf16String;
 use crate::tests::testutils_composer_model::{cm, tx};
 use crate::tests::testutils_conversion::utf16;
 
-use crate::ComposerModel;
+use crate::{
+    ComposerAction, ComposerError, ComposerModel, ListNumberingType, Location,
+    TextUpdate,
+};
 
 #[test]
 fn creating_ordered_list_and_writing() {
     let mut model = cm("|");
-    model.ordered_list();
+    model.ordered_list().unwrap();
     assert_eq!(tx(&model), "<ol><li>|</li></ol>");
     replace_text(&mut model, "abcd");
     assert_eq!(tx(&model), "<ol><li>abcd|</li></ol>");
@@ -35,14 +38,14 @@ fn creating_ordered_list_and_writing() {
 #[test]
 fn creating_unordered_list() {
     let mut model = cm("|");
-    model.unordered_list();
+    model.unordered_list().unwrap();
     assert_eq!(tx(&model), "<ul><li>|</li></ul>");
 }
 
 #[test]
 fn can_create_list_in_empty_model() {
     let mut model = ComposerModel::new();
-    model.unordered_list();
+    model.unordered_list().unwrap();
     assert_eq!(tx(&model), "<ul><li>|</li></ul>");
 }
 
@@ -65,6 +68,67 @@ fn removing_list_item() {
     assert_eq!(tx(&model), "|");
 }
 
+#[test]
+fn entering_twice_at_the_end_of_a_list_exits_it() {
+    let mut model = cm("<ul><li>abc|</li></ul>");
+    model.enter();
+    model.enter();
+    assert_eq!(tx(&model), "<ul><li>abc</li></ul>~|");
+    replace_text(&mut model, "d");
+    assert_eq!(tx(&model), "<ul><li>abc</li></ul>~d|");
+}
+
+#[test]
+fn entering_in_an_empty_item_keeps_text_after_the_list_in_place() {
+    let mut model = cm("<ul><li>abc</li><li>~|</li></ul>def");
+    model.enter();
+    assert_eq!(tx(&model), "<ul><li>abc</li></ul>~|def");
+}
+
+#[test]
+fn entering_in_an_empty_item_in_the_middle_splits_the_list() {
+    let mut model = cm("<ul><li>abc</li><li>~|</li><li>~def</li></ul>");
+    model.enter();
+    assert_eq!(tx(&model), "<ul><li>abc</li></ul>~|<ul><li>~def</li></ul>");
+}
+
+#[test]
+fn splitting_an_ordered_list_continues_its_numbering() {
+    let mut model = cm("<ol start=\"3\" type=\"a\"><li>abc</li><li>~|</li>\
+        <li>~def</li></ol>");
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<ol start=\"3\" type=\"a\"><li>abc</li></ol>~|\
+        <ol start=\"4\" type=\"a\"><li>~def</li></ol>"
+    );
+}
+
+#[test]
+fn entering_in_an_empty_nested_item_unindents_it() {
+    let mut model =
+        cm("<ul><li>abc<ul><li>~def</li><li>~|</li></ul></li></ul>");
+    model.enter();
+    assert_eq!(
+        tx(&model),
+        "<ul><li>abc<ul><li>~def</li></ul></li><li>~|</li></ul>"
+    );
+    model.enter();
+    assert_eq!(tx(&model), "<ul><li>abc<ul><li>~def</li></ul></li></ul>~|");
+}
+
+#[test]
+fn entering_in_an_empty_nested_item_can_be_undone() {
+    let mut model =
+        cm("<ul><li>abc<ul><li>~def</li><li>~|</li></ul></li></ul>");
+    model.enter();
+    model.undo();
+    assert_eq!(
+        tx(&model),
+        "<ul><li>abc<ul><li>~def</li><li>~|</li></ul></li></ul>"
+    );
+}
+
 #[test]
 fn backspacing_in_list_leaving_some_text_keeps_items_unchanged() {
     let mut model = cm("<ol><li>abcd</li><li>~ef{gh}|</li></ol>");
@@ -126,7 +190,7 @@ fn entering_mid_text_node_with_selection() {
 #[test]
 fn removing_list() {
     let mut model = cm("|");
-    model.ordered_list();
+    model.ordered_list().unwrap();
     model.enter();
     assert_eq!(tx(&model), "|");
 }
@@ -134,44 +198,57 @@ fn removing_list() {
 #[test]
 fn updating_list_type() {
     let mut model = cm("<ol><li>ab</li><li>cd|</li></ol>");
-    model.unordered_list();
+    model.unordered_list().unwrap();
     assert_eq!(tx(&model), "<ul><li>ab</li><li>cd|</li></ul>");
-    model.ordered_list();
+    model.ordered_list().unwrap();
     assert_eq!(tx(&model), "<ol><li>ab</li><li>cd|</li></ol>");
 }
 
 #[test]
 fn moving_list_item_content_out() {
     let mut model = cm("<ol><li>ab</li><li>cd|</li></ol>");
-    model.ordered_list();
+    model.ordered_list().unwrap();
     assert_eq!(tx(&model), "<ol><li>ab</li></ol>cd|");
 }
 
 #[test]
 fn appending_new_list_to_previous() {
     let mut model = cm("<ol><li>ab</li></ol>cd|");
-    model.ordered_list();
+    model.ordered_list().unwrap();
     assert_eq!(tx(&model), "<ol><li>ab</li><li>cd|</li></ol>");
 }
 
 #[test]
 fn creating_list_of_different_type_doesnt_merge() {
     let mut model = cm("<ul><li>foo</li></ul>bar|");
-    model.ordered_list();
+    model.ordered_list().unwrap();
     assert_eq!(tx(&model), "<ul><li>foo</li></ul><ol><li>bar|</li></ol>");
 }
 
 #[test]
 fn creating_a_new_list_immediately_after_an_old_one_joins_them() {
     let mut model = cm("abc|");
-    model.unordered_list();
+    model.unordered_list().unwrap();
     model.enter();
     model.enter();
     model.replace_text(Utf16String::from_str("def"));
-    model.unordered_list();
+    model.unordered_list().unwrap();
     assert_eq!(tx(&model), "<ul><li>abc</li><li>~def|</li></ul>");
 }
 
+#[test]
+fn toggling_list_across_several_nodes_returns_an_error() {
+    let mut model = cm("{a<strong>b}|</strong>");
+    assert_eq!(
+        model.ordered_list(),
+        Err(ComposerError::UnsupportedSelection(
+            ComposerAction::OrderedList
+        ))
+    );
+    assert_eq!(tx(&model), "{a<strong>b}|</strong>");
+    assert!(!model.can_undo());
+}
+
 #[test]
 fn indent_several_list_items_simple_case_works() {
     let mut model = cm(
@@ -232,6 +309,89 @@ fn un_indent_nested_lists_with_remnants_works() {
     )
 }
 
+#[test]
+fn indenting_and_unindenting_text_outside_a_list_or_paragraph_does_nothing() {
+    let mut model = cm("abc<br />d|ef");
+    model.indent();
+    assert_eq!(tx(&model), "abc<br />d|ef");
+    model.unindent();
+    assert_eq!(tx(&model), "abc<br />d|ef");
+}
+
+#[test]
+fn indenting_a_paragraph_gives_it_an_indentation_level() {
+    let mut model = cm("<p>a|b</p><p>c</p>");
+    model.indent();
+    assert_eq!(tx(&model), "<p data-indent=\"1\">a|b</p><p>c</p>");
+    model.indent();
+    assert_eq!(tx(&model), "<p data-indent=\"2\">a|b</p><p>c</p>");
+}
+
+#[test]
+fn unindenting_a_paragraph_removes_a_level() {
+    let mut model = cm("<p data-indent=\"2\">a|b</p>");
+    model.unindent();
+    assert_eq!(tx(&model), "<p data-indent=\"1\">a|b</p>");
+    model.unindent();
+    assert_eq!(tx(&model), "<p>a|b</p>");
+    model.unindent();
+    assert_eq!(tx(&model), "<p>a|b</p>");
+}
+
+#[test]
+fn indenting_a_selection_indents_each_quote_once() {
+    let mut model =
+        cm("<blockquote>a{b<br />c</blockquote><blockquote>d}|</blockquote>");
+    model.indent();
+    assert_eq!(
+        tx(&model),
+        "<blockquote data-indent=\"1\">a{b<br />c</blockquote>\
+        <blockquote data-indent=\"1\">d}|</blockquote>"
+    );
+}
+
+#[test]
+fn indenting_a_list_in_a_quote_nests_its_items() {
+    let mut model =
+        cm("<blockquote><ul><li>a</li><li>b|</li></ul></blockquote>");
+    model.indent();
+    assert_eq!(
+        tx(&model),
+        "<blockquote><ul><li>a<ul><li>b|</li></ul></li></ul></blockquote>"
+    );
+}
+
+#[test]
+fn undoing_an_indent_restores_the_paragraph() {
+    let mut model = cm("<p>a|</p>");
+    model.indent();
+    model.undo();
+    assert_eq!(tx(&model), "<p>a|</p>");
+}
+
+#[test]
+fn indent_is_limited_by_max_list_depth() {
+    let mut model = cm("<ul><li>a</li><li>~b</li><li>~c|</li></ul>");
+    model.set_max_list_depth(Some(2));
+    model.indent();
+    assert_eq!(
+        tx(&model),
+        "<ul><li>a</li><li>~b<ul><li>~c|</li></ul></li></ul>"
+    );
+    model.select(Location::from(3), Location::from(3));
+    model.indent();
+    assert_eq!(
+        tx(&model),
+        "<ul><li>a<ul><li>~b|<ul><li>~c</li></ul></li></ul></li></ul>"
+    );
+    model.select(Location::from(5), Location::from(5));
+    model.indent();
+    assert_eq!(
+        tx(&model),
+        "<ul><li>a<ul><li>~b<ul><li>~c|</li></ul></li></ul></li></ul>"
+    );
+}
+
 #[test]
 fn replacing_text_with_newline_characters_inserts_list_items() {
     let mut model = cm("<ul><li>abc|</li></ul>");
@@ -247,6 +407,137 @@ fn replacing_cross_list_item_selection_with_text_containing_newline_works() {
     assert_eq!(tx(&model), "<ul><li>aghi</li><li>~jkl|f</li></ul>");
 }
 
+#[test]
+fn setting_ordered_list_start() {
+    let mut model = cm("<ol><li>abc|</li></ol>");
+    model.set_ordered_list_start(5);
+    assert_eq!(tx(&model), "<ol start=\"5\"><li>abc|</li></ol>");
+    model.set_ordered_list_start(1);
+    assert_eq!(tx(&model), "<ol><li>abc|</li></ol>");
+}
+
+#[test]
+fn setting_ordered_list_numbering_type() {
+    let mut model = cm("<ol start=\"2\"><li>abc|</li></ol>");
+    model.set_ordered_list_numbering_type(ListNumberingType::LowerRoman);
+    assert_eq!(tx(&model), "<ol start=\"2\" type=\"i\"><li>abc|</li></ol>");
+    model.set_ordered_list_numbering_type(ListNumberingType::Decimal);
+    assert_eq!(tx(&model), "<ol start=\"2\"><li>abc|</li></ol>");
+}
+
+#[test]
+fn setting_list_numbering_applies_to_the_closest_list() {
+    let mut model = cm("<ol><li>a<ol><li>b|</li></ol></li></ol>");
+    model.set_ordered_list_start(3);
+    assert_eq!(
+        tx(&model),
+        "<ol><li>a<ol start=\"3\"><li>b|</li></ol></li></ol>"
+    );
+}
+
+#[test]
+fn setting_list_numbering_outside_an_ordered_list_does_nothing() {
+    let mut model = cm("<ul><li>abc|</li></ul>");
+    let update = model.set_ordered_list_start(3);
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    let mut model = cm("abc|");
+    let update =
+        model.set_ordered_list_numbering_type(ListNumberingType::UpperAlpha);
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+}
+
+#[test]
+fn setting_list_numbering_can_be_undone() {
+    let mut model = cm("<ol><li>abc|</li></ol>");
+    model.set_ordered_list_start(4);
+    model.undo();
+    assert_eq!(tx(&model), "<ol><li>abc|</li></ol>");
+}
+
+#[test]
+fn changing_to_unordered_list_drops_numbering() {
+    let mut model = cm("<ol start=\"3\" type=\"a\"><li>abc|</li></ol>");
+    model.unordered_list().unwrap();
+    assert_eq!(tx(&model), "<ul><li>abc|</li></ul>");
+}
+
+#[test]
+fn backspacing_at_start_of_item_merges_it_into_previous_item() {
+    let mut model = cm("<ol><li>abc</li><li>~|def</li></ol>");
+    model.backspace();
+    assert_eq!(tx(&model), "<ol><li>abc|def</li></ol>");
+}
+
+#[test]
+fn backspacing_at_start_of_item_keeps_following_items() {
+    let mut model = cm("<ul><li>abc</li><li>~|def</li><li>~ghi</li></ul>");
+    model.backspace();
+    assert_eq!(tx(&model), "<ul><li>abc|def</li><li>~ghi</li></ul>");
+}
+
+#[test]
+fn backspacing_at_start_of_item_merges_into_nested_previous_item() {
+    let mut model =
+        cm("<ul><li>abc<ul><li>~def</li></ul></li><li>~|ghi</li></ul>");
+    model.backspace();
+    assert_eq!(
+        tx(&model),
+        "<ul><li>abc<ul><li>~def|ghi</li></ul></li></ul>"
+    );
+}
+
+#[test]
+fn backspacing_at_start_of_first_item_lifts_it_out_of_the_list() {
+    let mut model = cm("abc<ol><li>~|def</li><li>~ghi</li></ol>");
+    model.backspace();
+    assert_eq!(tx(&model), "abc|def<ol><li>~ghi</li></ol>");
+}
+
+#[test]
+fn backspacing_at_start_of_only_item_removes_the_list() {
+    let mut model = cm("abc<ol><li>~|def</li></ol>");
+    model.backspace();
+    assert_eq!(tx(&model), "abc|def");
+}
+
+#[test]
+fn backspacing_at_start_of_first_nested_item_unindents_it() {
+    let mut model = cm("<ul><li>abc<ul><li>~|def</li></ul></li></ul>");
+    model.backspace();
+    assert_eq!(tx(&model), "<ul><li>abc</li><li>~|def</li></ul>");
+}
+
+#[test]
+fn backspacing_at_start_of_line_after_list_merges_it_into_last_item() {
+    let mut model = cm("<ol><li>abc</li></ol>~|def<br />ghi");
+    model.backspace();
+    assert_eq!(tx(&model), "<ol><li>abc|def</li></ol><br />ghi");
+}
+
+#[test]
+fn backspacing_inside_an_emoji_after_a_line_break_does_not_panic() {
+    let mut model = cm("|");
+    model.set_content_from_html(&utf16("<br />\u{1f600}"));
+    model.select(Location::from(3), Location::from(3));
+    model.backspace();
+    assert_eq!(tx(&model), "<br />|");
+}
+
+#[test]
+fn backspacing_an_emoji_after_a_list_does_not_panic() {
+    let mut model = cm("<ol><li>abc</li></ol>\u{1f600}|");
+    model.backspace();
+    assert_eq!(tx(&model), "<ol><li>abc|</li></ol>");
+}
+
+#[test]
+fn backspacing_at_start_of_item_can_be_undone() {
+    let mut model = cm("<ol><li>abc</li><li>~|def</li></ol>");
+    model.backspace();
+    model.undo();
+    assert_eq!(tx(&model), "<ol><li>abc</li><li>~|def</li></ol>");
+}
+
 fn replace_text(model: &mut ComposerModel<Utf16String>, new_text: &str) {
     model.replace_text(utf16(new_text));
 }
@@ -0,0 +1,196 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{DomHandle, Location, ToMarkdown, ToRawText, ToTree};
+
+const ALICE: &str = "<a href=\"https://matrix.to/#/@alice:matrix.org\" \
+    contenteditable=\"false\">Alice</a>";
+
+#[test]
+fn insert_mention_adds_a_pill_at_the_cursor() {
+    let mut model = cm("Hi |!");
+    model.insert_mention(
+        utf16("https://matrix.to/#/@alice:matrix.org"),
+        utf16("Alice"),
+    );
+    assert_eq!(tx(&model), format!("Hi {ALICE}|!"));
+}
+
+#[test]
+fn insert_mention_replaces_the_selection() {
+    let mut model = cm("Hi {@ali}|!");
+    model.insert_mention(
+        utf16("https://matrix.to/#/@alice:matrix.org"),
+        utf16("Alice"),
+    );
+    assert_eq!(tx(&model), format!("Hi {ALICE}|!"));
+}
+
+#[test]
+fn mention_counts_as_a_single_character() {
+    let model = cm(&format!("Hi {ALICE}|"));
+    assert_eq!(model.state.start, Location::from(4));
+    assert_eq!(model.state.dom.text_len(), 4);
+}
+
+#[test]
+fn cm_and_tx_roundtrip_around_mentions() {
+    assert_eq!(tx(&cm(&format!("|{ALICE}"))), format!("|{ALICE}"));
+    assert_eq!(tx(&cm(&format!("a{ALICE}|b"))), format!("a{ALICE}|b"));
+    assert_eq!(
+        tx(&cm(&format!("a{{{ALICE}}}|b"))),
+        format!("a{{{ALICE}}}|b")
+    );
+}
+
+#[test]
+fn backspace_deletes_the_whole_mention() {
+    let mut model = cm(&format!("Hi {ALICE}|!"));
+    model.backspace();
+    assert_eq!(tx(&model), "Hi |!");
+}
+
+#[test]
+fn delete_removes_the_whole_mention() {
+    let mut model = cm(&format!("Hi |{ALICE}!"));
+    model.delete();
+    assert_eq!(tx(&model), "Hi |!");
+}
+
+#[test]
+fn typing_after_a_mention_adds_text_outside_it() {
+    let mut model = cm(&format!("Hi {ALICE}|"));
+    model.replace_text(utf16("!"));
+    assert_eq!(tx(&model), format!("Hi {ALICE}!|"));
+}
+
+#[test]
+fn typing_before_a_mention_adds_text_outside_it() {
+    let mut model = cm(&format!("|{ALICE}"));
+    model.replace_text(utf16("@"));
+    assert_eq!(tx(&model), format!("@|{ALICE}"));
+}
+
+#[test]
+fn selecting_a_mention_and_typing_replaces_it() {
+    let mut model = cm(&format!("Hi {{{ALICE}}}|!"));
+    model.replace_text(utf16("Bob"));
+    assert_eq!(tx(&model), "Hi Bob|!");
+}
+
+#[test]
+fn mention_url_is_escaped() {
+    let mut model = cm("|");
+    model.insert_mention(
+        utf16("https://matrix.to/#/@a\" onmouseover=\"alert(1)"),
+        utf16("a"),
+    );
+    assert_eq!(
+        tx(&model),
+        "<a href=\"https://matrix.to/#/@a&quot; onmouseover=&quot;alert(1)\" \
+        contenteditable=\"false\">a</a>|"
+    );
+}
+
+#[test]
+fn undo_insert_mention() {
+    let mut model = cm("Hi|");
+    model.insert_mention(
+        utf16("https://matrix.to/#/@alice:matrix.org"),
+        utf16("Alice"),
+    );
+    model.undo();
+    assert_eq!(tx(&model), "Hi|");
+}
+
+#[test]
+fn mention_raw_text_is_its_display_text() {
+    let model = cm(&format!("Hi {ALICE}|"));
+    assert_eq!(model.state.dom.to_raw_text(), utf16("Hi Alice"));
+}
+
+#[test]
+fn mention_is_written_as_a_markdown_link() {
+    let model = cm(&format!("Hi {ALICE}|"));
+    assert_eq!(
+        model.state.dom.to_markdown().unwrap(),
+        utf16("Hi [Alice](<https://matrix.to/#/@alice:matrix.org>)")
+    );
+}
+
+#[test]
+fn mention_tree_shows_text_and_url() {
+    let model = cm(&format!("{ALICE}|"));
+    assert_eq!(
+        model.state.dom.to_tree(),
+        "generic [] len 1\n\
+        ├>mention \"Alice\" https://matrix.to/#/@alice:matrix.org [0] len 1\n\
+        └>text \"\" [1] len 0\n"
+    );
+}
+
+#[test]
+fn mentions_are_atomic_inline_nodes() {
+    let model = cm(&format!("a<a href=\"https://element.io\">b</a>{ALICE}|"));
+    let children = model.state.dom.document().children();
+    assert!(!children[0].is_atomic_inline());
+    assert!(!children[1].is_atomic_inline());
+    assert!(children[2].is_atomic_inline());
+}
+
+#[test]
+fn backspace_after_text_and_mention_only_deletes_the_mention() {
+    let mut model = cm(&format!("ab{ALICE}|"));
+    model.backspace();
+    assert_eq!(tx(&model), "ab|");
+}
+
+#[test]
+fn delete_between_text_nodes_removes_only_the_mention() {
+    let mut model = cm(&format!("a|{ALICE}b"));
+    model.delete();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn find_range_covers_the_whole_mention() {
+    let model = cm(&format!("a{ALICE}b|"));
+    let range = model.state.dom.find_range(1, 2);
+    let mention = range
+        .leaves()
+        .find(|l| l.node_handle == DomHandle::from_raw(vec![1]))
+        .unwrap();
+    assert_eq!((mention.start_offset, mention.end_offset), (0, 1));
+    assert_eq!(mention.length, 1);
+}
+
+#[test]
+fn selecting_around_a_mention_keeps_the_selection() {
+    let mut model = cm(&format!("a{ALICE}b|"));
+    model.select(Location::from(1), Location::from(2));
+    assert_eq!(tx(&model), format!("a{{{ALICE}}}|b"));
+}
+
+#[test]
+fn select_word_next_to_a_mention_selects_the_mention() {
+    let mut model = cm("Hi |!");
+    model.insert_mention(
+        utf16("https://matrix.to/#/@alice:matrix.org"),
+        utf16("Alice"),
+    );
+    model.select_word();
+    assert_eq!(tx(&model), format!("Hi {{{ALICE}}}|!"));
+}
@@ -12,20 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+
 use widestring::Utf16String;
 
 use crate::tests::testutils_composer_model::cm;
 use crate::tests::testutils_conversion::utf16;
 
-use crate::{ComposerAction, ComposerModel, Location};
+use crate::{
+    ActionState, BlockType, ComposerAction, ComposerModel, DisabledReason,
+    Location, MenuState, MenuStateUpdate,
+};
 
 #[test]
 fn creating_and_deleting_lists_updates_reversed_actions() {
     let mut model = cm("|");
-    model.ordered_list();
+    model.ordered_list().unwrap();
     assert!(model.action_is_reversed(ComposerAction::OrderedList));
     assert!(model.action_is_enabled(ComposerAction::UnorderedList));
-    model.unordered_list();
+    model.unordered_list().unwrap();
     assert!(model.action_is_enabled(ComposerAction::OrderedList));
     assert!(model.action_is_reversed(ComposerAction::UnorderedList));
     model.backspace();
@@ -167,3 +172,206 @@ fn selecting_after_a_line_break_inside_formatting_nodes_reversed_actions() {
 fn replace_text(model: &mut ComposerModel<Utf16String>, new_text: &str) {
     model.replace_text(utf16(new_text));
 }
+
+#[test]
+fn table_is_reversed_inside_a_table() {
+    let mut model = cm("a|");
+    assert!(model.action_is_enabled(ComposerAction::Table));
+    model.insert_table(1, 1);
+    assert!(model.action_is_reversed(ComposerAction::Table));
+    model.select(Location::from(1), Location::from(1));
+    assert!(model.action_is_enabled(ComposerAction::Table));
+}
+
+#[test]
+fn indent_is_disabled_at_max_list_depth() {
+    let mut model = cm("<ul><li>a<ul><li>~b</li><li>~c|</li></ul></li></ul>");
+    model.set_max_list_depth(Some(2));
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Indent),
+        Some(&ActionState::Disabled(DisabledReason::MaxListDepth))
+    );
+    model.set_max_list_depth(None);
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Indent),
+        Some(&ActionState::Enabled)
+    );
+}
+
+#[test]
+fn clearing_history_disables_undo_and_redo() {
+    let mut model = cm("|");
+    replace_text(&mut model, "a");
+    model.enter();
+    model.undo();
+    assert!(model.action_is_enabled(ComposerAction::Undo));
+    assert!(model.action_is_enabled(ComposerAction::Redo));
+
+    model.clear_history();
+    assert!(model.action_is_disabled(ComposerAction::Undo));
+    assert!(model.action_is_disabled(ComposerAction::Redo));
+    assert_eq!(model.state.dom.to_string(), "a");
+}
+
+#[test]
+fn menu_state_reports_the_block_type() {
+    let mut model = cm("a|");
+    assert_eq!(model.block_type, BlockType::Paragraph);
+    model.ordered_list().unwrap();
+    assert_eq!(model.block_type, BlockType::OrderedList);
+    model.unordered_list().unwrap();
+    assert_eq!(model.block_type, BlockType::UnorderedList);
+}
+
+#[test]
+fn menu_state_reports_the_closest_block_type() {
+    let mut model = cm("<table><tr><td>a</td><td>\
+        <ul><li>b</li></ul></td></tr></table>|");
+    model.select(Location::from(0), Location::from(0));
+    assert_eq!(model.block_type, BlockType::TableCell);
+    let update = model.select(Location::from(2), Location::from(2));
+    assert!(matches!(
+        update.menu_state,
+        MenuState::Update(MenuStateUpdate {
+            block_type: BlockType::UnorderedList,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn menu_state_reports_the_url_of_the_link_around_the_cursor() {
+    let mut model = cm("<a href=\"https://matrix.org\">li|nk</a> text");
+    model.select(Location::from(2), Location::from(2));
+    assert_eq!(model.link_url, Some("https://matrix.org".to_owned()));
+    assert!(!model.spans_multiple_links);
+    model.select(Location::from(6), Location::from(6));
+    assert_eq!(model.link_url, None);
+}
+
+#[test]
+fn menu_state_reports_a_selection_spanning_multiple_links() {
+    let mut model = cm("<a href=\"https://matrix.org\">one</a> \
+        <a href=\"https://element.io\">two</a>|");
+    let update = model.select(Location::from(1), Location::from(6));
+    assert!(matches!(
+        update.menu_state,
+        MenuState::Update(MenuStateUpdate {
+            link_url: None,
+            spans_multiple_links: true,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn typing_inside_a_word_keeps_the_menu_state() {
+    let mut model = cm("<strong>ab|c</strong>");
+    replace_text(&mut model, "x");
+    let update = model.replace_text(utf16("y"));
+    assert_eq!(update.menu_state, MenuState::Keep);
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Bold),
+        Some(&ActionState::Reversed)
+    );
+}
+
+#[test]
+fn typing_inside_a_word_reports_undo_becoming_enabled() {
+    let mut model = cm("ab|c");
+    assert!(model.action_is_disabled(ComposerAction::Undo));
+    let update = model.replace_text(utf16("x"));
+    assert_eq!(
+        update.menu_state,
+        MenuState::PartialUpdate(HashMap::from([(
+            ComposerAction::Undo,
+            ActionState::Enabled
+        )]))
+    );
+    assert!(model.action_is_enabled(ComposerAction::Undo));
+}
+
+#[test]
+fn typing_after_undoing_reports_redo_becoming_disabled() {
+    let mut model = cm("ab|c");
+    replace_text(&mut model, "x ");
+    model.undo();
+    assert!(model.action_is_enabled(ComposerAction::Redo));
+    let update = model.replace_text(utf16("y"));
+    assert_eq!(
+        update.menu_state,
+        MenuState::PartialUpdate(HashMap::from([
+            (ComposerAction::Undo, ActionState::Enabled),
+            (
+                ComposerAction::Redo,
+                ActionState::Disabled(DisabledReason::NothingToRedo)
+            ),
+        ]))
+    );
+    assert!(model.action_is_disabled(ComposerAction::Redo));
+}
+
+#[test]
+fn disabled_undo_and_redo_report_an_empty_history() {
+    let model = cm("abc|");
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Undo),
+        Some(&ActionState::Disabled(DisabledReason::NothingToUndo))
+    );
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Redo),
+        Some(&ActionState::Disabled(DisabledReason::NothingToRedo))
+    );
+}
+
+#[test]
+fn indent_is_enabled_in_a_paragraph_and_unindent_once_it_is_indented() {
+    let mut model = cm("<p>a|</p>");
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Indent),
+        Some(&ActionState::Enabled)
+    );
+    assert_eq!(
+        model.action_states().get(&ComposerAction::UnIndent),
+        Some(&ActionState::Disabled(DisabledReason::NotInNestedList))
+    );
+    model.indent();
+    assert_eq!(
+        model.action_states().get(&ComposerAction::UnIndent),
+        Some(&ActionState::Enabled)
+    );
+}
+
+#[test]
+fn disabled_indent_reports_why() {
+    let model = cm("abc|");
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Indent),
+        Some(&ActionState::Disabled(DisabledReason::NotInList))
+    );
+    let model = cm("<ul><li>a|</li><li>b</li></ul>");
+    assert_eq!(
+        model.action_states().get(&ComposerAction::Indent),
+        Some(&ActionState::Disabled(DisabledReason::FirstListItem))
+    );
+    assert_eq!(
+        model.action_states().get(&ComposerAction::UnIndent),
+        Some(&ActionState::Disabled(DisabledReason::NotInNestedList))
+    );
+}
+
+#[test]
+fn changing_only_action_states_sends_just_the_changed_ones() {
+    let mut model = cm("<ul><li>a</li><li>b|</li></ul>");
+    let update = model.indent();
+    assert_eq!(
+        update.menu_state,
+        MenuState::PartialUpdate(HashMap::from([
+            (
+                ComposerAction::Indent,
+                ActionState::Disabled(DisabledReason::FirstListItem)
+            ),
+            (ComposerAction::UnIndent, ActionState::Enabled),
+        ]))
+    );
+}
@@ -16,7 +16,7 @@ use widestring::Utf16String;
 
 use crate::{
     tests::testutils_composer_model::{cm, tx},
-    ComposerModel,
+    ComposerModel, EnterMode, TextUpdate,
 };
 
 #[test]
@@ -178,3 +178,90 @@ fn backspace_merges_formatting_nodes() {
     model.backspace();
     assert_eq!(tx(&model), "<b>a|b</b>");
 }
+
+#[test]
+fn pressing_enter_in_message_mode_asks_to_send() {
+    let mut model = cm("abc|");
+    model.set_enter_mode(EnterMode::Message);
+    let update = model.enter();
+    assert!(matches!(update.text_update, TextUpdate::Send));
+    assert_eq!(tx(&model), "abc|");
+}
+
+#[test]
+fn pressing_enter_in_message_mode_does_not_add_history() {
+    let mut model = cm("abc|");
+    model.set_enter_mode(EnterMode::Message);
+    model.enter();
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn pressing_shift_enter_in_message_mode_makes_a_new_line() {
+    let mut model = cm("abc|");
+    model.set_enter_mode(EnterMode::Message);
+    model.shift_enter();
+    assert_eq!(tx(&model), "abc<br />|");
+}
+
+#[test]
+fn pressing_shift_enter_in_message_mode_adds_a_list_item() {
+    let mut model = cm("<ol><li>abc|</li></ol>");
+    model.set_enter_mode(EnterMode::Message);
+    model.shift_enter();
+    assert_eq!(tx(&model), "<ol><li>abc</li><li>~|</li></ol>");
+}
+
+#[test]
+fn enter_mode_defaults_to_document_mode() {
+    let mut model = cm("abc|");
+    assert_eq!(model.enter_mode(), &EnterMode::Document);
+    model.enter();
+    assert_eq!(tx(&model), "abc<br />|");
+}
+
+#[test]
+fn inserting_a_soft_break_in_text() {
+    let mut model = cm("ab|c");
+    model.insert_soft_break();
+    assert_eq!(tx(&model), "ab<br />|c");
+}
+
+#[test]
+fn inserting_a_soft_break_in_an_empty_model() {
+    let mut model = ComposerModel::new();
+    model.insert_soft_break();
+    assert_eq!(tx(&model), "<br />|");
+}
+
+#[test]
+fn inserting_a_soft_break_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.insert_soft_break();
+    assert_eq!(tx(&model), "a<br />|d");
+}
+
+#[test]
+fn inserting_a_soft_break_stays_in_the_list_item() {
+    let mut model = cm("<ol><li>ab|c</li></ol>");
+    model.insert_soft_break();
+    assert_eq!(tx(&model), "<ol><li>ab<br />|c</li></ol>");
+}
+
+#[test]
+fn inserting_a_soft_break_stays_in_the_table_cell() {
+    let mut model = cm("<table><tr><td>a|</td><td>b</td></tr></table>");
+    model.insert_soft_break();
+    assert_eq!(
+        tx(&model),
+        "<table><tr><td>a<br />|</td><td>b</td></tr></table>"
+    );
+}
+
+#[test]
+fn inserting_a_soft_break_can_be_undone() {
+    let mut model = cm("ab|c");
+    model.insert_soft_break();
+    model.undo();
+    assert_eq!(tx(&model), "ab|c");
+}
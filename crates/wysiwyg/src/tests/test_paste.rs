@@ -0,0 +1,327 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::ToTree;
+
+#[test]
+fn paste_html_inserts_formatting_at_the_cursor() {
+    let mut model = cm("ab|cd");
+    model.paste_html(&utf16("<b>X</b>"));
+    assert_eq!(tx(&model), "ab<b>X|</b>cd");
+}
+
+#[test]
+fn paste_html_drops_the_comments_browsers_put_around_the_fragment() {
+    let mut model = cm("a|");
+    model.paste_html(&utf16(
+        "<html><body><!--StartFragment--><b>x</b><!--EndFragment-->\
+        </body></html>",
+    ));
+    assert_eq!(tx(&model), "a<b>x|</b>");
+}
+
+#[test]
+fn paste_html_of_a_whole_document_keeps_its_body() {
+    let mut model = cm("a|");
+    model.paste_html(&utf16(
+        "<!DOCTYPE html><html><body><template>t</template><b>x</b>\
+        </body></html>",
+    ));
+    assert_eq!(tx(&model), "a<b>x|</b>");
+}
+
+#[test]
+fn paste_html_drops_comments_inside_text() {
+    let mut model = cm("|");
+    model.paste_html(&utf16("a<!-- c -->b<?xml version=\"1.0\"?>c"));
+    assert_eq!(tx(&model), "abc|");
+}
+
+#[test]
+fn paste_html_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.paste_html(&utf16("<em>X</em>"));
+    assert_eq!(tx(&model), "a<em>X|</em>d");
+}
+
+#[test]
+fn paste_html_joins_pasted_text_with_the_text_around_it() {
+    let mut model = cm("ab|cd");
+    model.paste_html(&utf16("<span>X</span>"));
+    assert_eq!(tx(&model), "abX|cd");
+    assert_eq!(
+        model.state.dom.to_tree(),
+        r#"generic [] len 5
+└>text "abXcd" [0] len 5
+"#,
+    );
+}
+
+#[test]
+fn paste_html_drops_scripts_and_styles() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<style>b { color: red }</style>a<script>alert(1)</script>b",
+    ));
+    assert_eq!(tx(&model), "ab|");
+}
+
+#[test]
+fn paste_html_maps_inline_styles_to_formatting() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<span style=\"font-weight:700\">a</span>\
+        <span style=\"text-decoration: line-through\">b</span>",
+    ));
+    assert_eq!(tx(&model), "<strong>a</strong><del>b|</del>");
+}
+
+#[test]
+fn paste_html_maps_inline_styles_of_links_and_formatting() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<a href=\"https://matrix.org\" style=\"font-style:italic\">a</a>\
+        <b style=\"font-weight:bold;text-decoration:underline\">b</b>",
+    ));
+    assert_eq!(
+        tx(&model),
+        "<a href=\"https://matrix.org\"><em>a</em></a><b><u>b|</u></b>"
+    );
+}
+
+#[test]
+fn paste_html_maps_inline_styles_of_list_items_around_nested_lists() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<ul><li style=\"font-weight:bold\">a<ul><li>b</li></ul></li></ul>",
+    ));
+    assert_eq!(
+        tx(&model),
+        "<ul><li><strong>a</strong><ul><li>b|</li></ul></li></ul>"
+    );
+}
+
+#[test]
+fn set_content_from_html_keeps_inline_styles_as_they_are() {
+    let mut model = cm("|");
+    model.set_content_from_html(&utf16(
+        "<a href=\"https://matrix.org\" style=\"font-style:italic\">a</a>",
+    ));
+    assert_eq!(
+        tx(&model),
+        "<a href=\"https://matrix.org\" style=\"font-style:italic\">a|</a>"
+    );
+}
+
+#[test]
+fn paste_html_with_matrix_sanitization_drops_images_from_other_servers() {
+    let mut model = cm("a|");
+    model.set_matrix_sanitization(true);
+    model.paste_html(&utf16(
+        "<img src=\"https://x.org/a.png\" alt=\"a\" />\
+        <a href=\"tel:123\">b</a>",
+    ));
+    assert_eq!(tx(&model), "ab|");
+    let report = model.last_sanitize_report();
+    assert_eq!(report.removed_tags, vec!["img"]);
+    assert_eq!(report.removed_attributes[0].name, "href");
+}
+
+#[test]
+fn paste_html_puts_paragraphs_on_their_own_lines() {
+    let mut model = cm("|");
+    model.paste_html(&utf16("<p>a</p>\n<h1>b</h1>\n<div>c</div>\n"));
+    assert_eq!(tx(&model), "a<br />b<br />c|");
+}
+
+#[test]
+fn paste_html_from_google_docs_keeps_its_formatting() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<meta charset=\"utf-8\">\
+        <b style=\"font-weight:normal;\" id=\"docs-internal-guid-1\">\
+        <p dir=\"ltr\" style=\"line-height:1.38;margin-top:0pt;\">\
+        <span style=\"font-size:11pt;font-weight:700;\">a</span>\
+        <span style=\"font-size:11pt;font-weight:400;\"> b </span>\
+        <span style=\"font-style:italic;\">c</span></p>\
+        <p dir=\"ltr\"><span style=\"font-family:'Courier New';\">d</span>\
+        </p></b>",
+    ));
+    assert_eq!(
+        tx(&model),
+        "<strong>a</strong> b <em>c</em><br /><code>d|</code>"
+    );
+}
+
+#[test]
+fn paste_html_from_word_keeps_its_formatting() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<p class=MsoNormal><b><span style='mso-bidi-font-weight:normal'>\
+        a</span></b><o:p></o:p></p>\n\
+        <p class=MsoNormal><o:p>&nbsp;</o:p></p>\n\
+        <p class=MsoListParagraph style='mso-list:l0 level1 lfo1'>\
+        <span style='mso-list:Ignore'>·<span>&nbsp;&nbsp;</span></span>\
+        <i>b</i><o:p></o:p></p>",
+    ));
+    assert_eq!(tx(&model), "<b>a</b><br /><br /><i>b|</i>");
+}
+
+#[test]
+fn paste_html_from_word_ignores_its_conditional_comments() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<html xmlns:o=\"urn:schemas-microsoft-com:office:office\">\n\
+        <head><meta name=ProgId content=Word.Document>\n\
+        <!--[if gte mso 9]><xml><o:OfficeDocumentSettings>\
+        <o:AllowPNG/></o:OfficeDocumentSettings></xml><![endif]-->\n\
+        <style><!-- p.MsoNormal { margin:0cm; } --></style></head>\n\
+        <body lang=EN-GB style='tab-interval:36.0pt'>\n\
+        <!--StartFragment-->\n\
+        <p class=MsoNormal><b>a</b> b<o:p></o:p></p>\n\
+        <p class=MsoListParagraphCxSpFirst \
+        style='text-indent:-18.0pt;mso-list:l0 level1 lfo1'>\
+        <![if !supportLists]><span style='font-family:Symbol'>\
+        <span style='mso-list:Ignore'>·<span style='font:7.0pt \"Times New \
+        Roman\"'>&nbsp;&nbsp; </span></span></span><![endif]>\
+        <i>c</i><o:p></o:p></p>\n\
+        <!--EndFragment-->\n\
+        </body>\n\
+        </html>",
+    ));
+    assert_eq!(tx(&model), "<b>a</b> b<br /><i>c|</i>");
+}
+
+#[test]
+fn paste_html_keeps_the_text_of_script_links() {
+    let mut model = cm("|");
+    model.paste_html(&utf16(
+        "<a href=\"javascript:alert(1)\">a</a>\
+        <a href=\"https://matrix.org\">b</a>",
+    ));
+    assert_eq!(tx(&model), "a<a href=\"https://matrix.org\">b|</a>");
+}
+
+#[test]
+fn paste_html_of_nothing_visible_does_nothing() {
+    let mut model = cm("a|");
+    model.paste_html(&utf16("<script>alert(1)</script>"));
+    assert_eq!(tx(&model), "a|");
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn paste_html_can_be_undone() {
+    let mut model = cm("a|");
+    model.paste_html(&utf16("<b>b</b>"));
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn paste_text_inserts_text_at_the_cursor() {
+    let mut model = cm("a{bc}|d");
+    model.paste_text(&utf16("*X*"));
+    assert_eq!(tx(&model), "a*X*|d");
+}
+
+#[test]
+fn paste_text_puts_each_line_on_a_line_of_its_own() {
+    let mut model = cm("a|");
+    model.paste_text(&utf16("b\r\nc\n\nd"));
+    assert_eq!(tx(&model), "ab<br />c<br /><br />d|");
+}
+
+#[test]
+fn paste_text_turns_web_addresses_into_links() {
+    let mut model = cm("|");
+    model.paste_text(&utf16("see https://matrix.org/docs now"));
+    assert_eq!(
+        tx(&model),
+        "see <a href=\"https://matrix.org/docs\">https://matrix.org/docs</a> now|"
+    );
+}
+
+#[test]
+fn paste_text_links_addresses_starting_with_www() {
+    let mut model = cm("|");
+    model.paste_text(&utf16("www.matrix.org"));
+    assert_eq!(
+        tx(&model),
+        "<a href=\"https://www.matrix.org\">www.matrix.org|</a>"
+    );
+}
+
+#[test]
+fn paste_text_leaves_punctuation_around_addresses_out_of_links() {
+    let mut model = cm("|");
+    model.paste_text(&utf16(
+        "(https://en.wikipedia.org/wiki/Matrix_(protocol)), http://a.b.",
+    ));
+    assert_eq!(
+        tx(&model),
+        "(<a href=\"https://en.wikipedia.org/wiki/Matrix_(protocol)\">\
+        https://en.wikipedia.org/wiki/Matrix_(protocol)</a>), \
+        <a href=\"http://a.b\">http://a.b</a>.|"
+    );
+}
+
+#[test]
+fn paste_text_does_not_link_a_bare_scheme() {
+    let mut model = cm("|");
+    model.paste_text(&utf16("https:// is a scheme"));
+    assert_eq!(tx(&model), "https:// is a scheme|");
+}
+
+#[test]
+fn paste_text_can_be_undone() {
+    let mut model = cm("a|");
+    model.paste_text(&utf16("b\nc"));
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn insert_html_at_cursor_inserts_a_snippet_and_moves_the_cursor_after_it() {
+    let mut model = cm("Hi|");
+    model.insert_html_at_cursor(&utf16(
+        "<p>Thanks,</p><p><em>Alice</em> <a href=\"https://matrix.org\">\
+        matrix.org</a></p>",
+    ));
+    assert_eq!(
+        tx(&model),
+        "HiThanks,<br /><em>Alice</em> \
+        <a href=\"https://matrix.org\">matrix.org|</a>"
+    );
+}
+
+#[test]
+fn insert_html_at_cursor_sanitizes_the_snippet() {
+    let mut model = cm("a{b}|c");
+    model.insert_html_at_cursor(&utf16(
+        "<span onclick=\"x()\">X</span><script>alert(1)</script>",
+    ));
+    assert_eq!(tx(&model), "aX|c");
+}
+
+#[test]
+fn insert_html_at_cursor_drops_comments() {
+    let mut model = cm("a|b");
+    model.insert_html_at_cursor(&utf16(
+        "<!-- signature --><em>X</em><!--[if mso]>Y<![endif]-->",
+    ));
+    assert_eq!(tx(&model), "a<em>X|</em>b");
+}
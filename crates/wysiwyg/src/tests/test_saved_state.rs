@@ -0,0 +1,154 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use widestring::Utf16String;
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{ComposerModel, DecodeError, InlineFormatType, ToHtml};
+
+fn restore(bytes: &[u8]) -> ComposerModel<Utf16String> {
+    ComposerModel::from_bytes(bytes).unwrap()
+}
+
+#[test]
+fn content_and_selection_are_restored() {
+    let model = cm("<ol><li>a<b>{bc</b></li><li><em>d}|</em></li></ol>");
+    let restored = restore(&model.to_bytes(false));
+    assert_eq!(tx(&restored), tx(&model));
+    assert_eq!(restored.state, model.state);
+}
+
+#[test]
+fn toggled_formatting_is_restored() {
+    let mut model = cm("abc|");
+    model.bold();
+    let restored = restore(&model.to_bytes(false));
+    assert_eq!(
+        restored.state.toggled_format_types,
+        vec![InlineFormatType::Bold]
+    );
+}
+
+#[test]
+fn mentions_images_and_attributes_are_restored() {
+    let model = cm("<a href=\"https://matrix.org\" title=\"M\">a</a>\
+        <a href=\"https://matrix.to/#/@alice:matrix.org\" \
+        contenteditable=\"false\">Alice</a>\
+        <img src=\"mxc://matrix.org/a\" alt=\"pic\" />\
+        <custom-tag>b</custom-tag>|");
+    let restored = restore(&model.to_bytes(false));
+    assert_eq!(restored.state.dom.to_html(), model.state.dom.to_html());
+    assert_eq!(restored.state, model.state);
+}
+
+/// A model with two undo steps and one redo step
+fn model_with_history() -> ComposerModel<Utf16String> {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.bold();
+    model.replace_text(utf16("b"));
+    model.enter();
+    model.replace_text(utf16("c"));
+    model.undo();
+    model
+}
+
+#[test]
+fn history_is_only_restored_when_asked_for() {
+    let model = model_with_history();
+    let restored = restore(&model.to_bytes(false));
+    assert!(restored.previous_states.is_empty());
+    assert!(restored.next_states.is_empty());
+}
+
+#[test]
+fn undo_and_redo_history_is_restored() {
+    let mut model = model_with_history();
+    assert!(model.previous_states.len() >= 2);
+    assert_eq!(model.next_states.len(), 1);
+    let mut restored = restore(&model.to_bytes(true));
+    assert_eq!(restored.previous_states.len(), model.previous_states.len());
+    while !model.previous_states.is_empty() {
+        model.undo();
+        restored.undo();
+        assert_eq!(tx(&restored), tx(&model));
+    }
+    while !model.next_states.is_empty() {
+        model.redo();
+        restored.redo();
+        assert_eq!(tx(&restored), tx(&model));
+    }
+}
+
+#[test]
+fn small_models_take_few_bytes() {
+    let bytes = cm("abc|").to_bytes(false);
+    assert!(bytes.len() < 16, "{:?}", bytes);
+}
+
+#[test]
+fn newer_formats_are_refused() {
+    let mut bytes = cm("abc|").to_bytes(false);
+    bytes[0] = 99;
+    assert_eq!(
+        ComposerModel::<Utf16String>::from_bytes(&bytes).err(),
+        Some(DecodeError::UnsupportedVersion(99))
+    );
+}
+
+#[test]
+fn truncated_or_padded_bytes_are_refused() {
+    let mut model = cm("<b>a</b>c|");
+    model.replace_text(utf16("d"));
+    let bytes = model.to_bytes(true);
+    for len in 0..bytes.len() {
+        assert!(
+            ComposerModel::<Utf16String>::from_bytes(&bytes[..len]).is_err(),
+            "{} bytes",
+            len
+        );
+    }
+    let mut padded = bytes;
+    padded.push(0);
+    assert!(matches!(
+        ComposerModel::<Utf16String>::from_bytes(&padded),
+        Err(DecodeError::Invalid(_))
+    ));
+}
+
+#[test]
+fn selections_outside_the_content_are_refused() {
+    let mut bytes = cm("a|").to_bytes(false);
+    // Version, 1 node, text, 1 byte "a", then the selection start
+    assert_eq!(bytes[5], 1);
+    bytes[5] = 5;
+    assert!(matches!(
+        ComposerModel::<Utf16String>::from_bytes(&bytes),
+        Err(DecodeError::Invalid(_))
+    ));
+}
+
+#[test]
+fn restoring_keeps_settings_and_leaves_the_model_alone_on_failure() {
+    let saved = model_with_history().to_bytes(true);
+    let mut model = cm("x|");
+    model.set_history_limit(Some(1));
+    model.restore_from_bytes(&saved).unwrap();
+    assert_eq!(tx(&model), tx(&model_with_history()));
+    assert_eq!(model.previous_states.len(), 1);
+
+    assert!(model.restore_from_bytes(&saved[..3]).is_err());
+    assert_eq!(tx(&model), tx(&model_with_history()));
+}
@@ -12,9 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use widestring::Utf16String;
+
 use crate::tests::testutils_composer_model::{cm, tx};
 
-use crate::{Location, TextUpdate};
+use crate::{
+    ComposerModel, DomHandle, DomPoint, Location, MoveDirection,
+    MoveGranularity, TextUpdate,
+};
 
 #[test]
 fn selecting_ascii_characters() {
@@ -111,3 +116,230 @@ fn selecting_creates_a_selection_update() {
         panic!("TextUpdate should be a selection")
     }
 }
+
+#[test]
+fn select_word_selects_the_word_around_the_cursor() {
+    let mut model = cm("hello wo|rld again");
+    model.select_word();
+    assert_eq!(tx(&model), "hello {world}| again");
+}
+
+#[test]
+fn select_word_at_end_of_word_selects_it() {
+    let mut model = cm("hello| world");
+    model.select_word();
+    assert_eq!(tx(&model), "{hello}| world");
+}
+
+#[test]
+fn select_word_spans_formatting() {
+    let mut model = cm("one t|w<em>o</em> three");
+    model.select_word();
+    assert_eq!(tx(&model), "one {tw<em>o}|</em> three");
+}
+
+#[test]
+fn select_word_uses_unicode_word_boundaries() {
+    let mut model = cm("Привет, ми|р!");
+    model.select_word();
+    assert_eq!(tx(&model), "Привет, {мир}|!");
+}
+
+#[test]
+#[cfg(feature = "icu-segmentation")]
+fn select_word_finds_thai_words_without_spaces() {
+    let mut model = cm("ฉันก|ินข้าว");
+    model.select_word();
+    assert_eq!(tx(&model), "ฉัน{กิน}|ข้าว");
+}
+
+#[test]
+fn select_word_in_whitespace_does_nothing() {
+    let mut model = cm("abc  |  def");
+    model.select_word();
+    assert_eq!(tx(&model), "abc&nbsp;&nbsp;|&nbsp;&nbsp;def");
+}
+
+#[test]
+fn select_block_selects_the_list_item() {
+    let mut model = cm("<ol><li>abc</li><li>~d|ef</li><li>~ghi</li></ol>");
+    model.select_block();
+    assert_eq!(
+        tx(&model),
+        "<ol><li>abc</li><li>~{def}|</li><li>~ghi</li></ol>"
+    );
+}
+
+#[test]
+fn select_block_selects_the_line_between_line_breaks() {
+    let mut model = cm("abc<br />d<strong>e|f</strong><br />ghi");
+    model.select_block();
+    assert_eq!(tx(&model), "abc<br />{d<strong>ef}|</strong><br />ghi");
+}
+
+#[test]
+fn select_block_leaves_out_nested_lists() {
+    let mut model = cm("<ul><li>a|bc<ul><li>~def</li></ul></li></ul>");
+    model.select_block();
+    assert_eq!(tx(&model), "<ul><li>{abc}|<ul><li>~def</li></ul></li></ul>");
+}
+
+#[test]
+fn select_all_selects_the_whole_content() {
+    let mut model = cm("a<strong>b|c</strong><ol><li>def</li></ol>");
+    model.select_all();
+    assert_eq!(tx(&model), "{a<strong>bc</strong><ol><li>def}|</li></ol>");
+}
+
+#[test]
+fn collapse_to_start_moves_the_cursor_to_the_selection_start() {
+    let mut model = cm("a{bc}|d");
+    model.collapse_to_start();
+    assert_eq!(tx(&model), "a|bcd");
+}
+
+#[test]
+fn collapse_to_start_works_with_a_backwards_selection() {
+    let mut model = cm("a|{bc}d");
+    model.collapse_to_start();
+    assert_eq!(tx(&model), "a|bcd");
+}
+
+#[test]
+fn collapse_to_end_moves_the_cursor_to_the_selection_end() {
+    let mut model = cm("a|{bc}d");
+    model.collapse_to_end();
+    assert_eq!(tx(&model), "abc|d");
+}
+
+#[test]
+fn move_cursor_by_character() {
+    let mut model = cm("ab|cd");
+    model.move_cursor(
+        MoveDirection::Forward,
+        MoveGranularity::Character,
+        false,
+    );
+    assert_eq!(tx(&model), "abc|d");
+    model.move_cursor(
+        MoveDirection::Backward,
+        MoveGranularity::Character,
+        false,
+    );
+    model.move_cursor(
+        MoveDirection::Backward,
+        MoveGranularity::Character,
+        false,
+    );
+    assert_eq!(tx(&model), "a|bcd");
+}
+
+#[test]
+fn move_cursor_by_character_steps_over_whole_graphemes() {
+    let mut model = cm("a|\u{1F469}\u{1F3FF}\u{200D}\u{1F680}b");
+    model.move_cursor(
+        MoveDirection::Forward,
+        MoveGranularity::Character,
+        false,
+    );
+    assert_eq!(tx(&model), "a\u{1F469}\u{1F3FF}\u{200D}\u{1F680}|b");
+}
+
+#[test]
+fn move_cursor_by_character_stops_at_the_edges() {
+    let mut model = cm("|ab");
+    model.move_cursor(
+        MoveDirection::Backward,
+        MoveGranularity::Character,
+        false,
+    );
+    assert_eq!(tx(&model), "|ab");
+    model.select(Location::from(2), Location::from(2));
+    model.move_cursor(
+        MoveDirection::Forward,
+        MoveGranularity::Character,
+        false,
+    );
+    assert_eq!(tx(&model), "ab|");
+}
+
+#[test]
+fn move_cursor_by_character_collapses_a_selection() {
+    let mut model = cm("a{bc}|d");
+    model.move_cursor(
+        MoveDirection::Backward,
+        MoveGranularity::Character,
+        false,
+    );
+    assert_eq!(tx(&model), "a|bcd");
+}
+
+#[test]
+fn move_cursor_extending_the_selection() {
+    let mut model = cm("a|bcd");
+    model.move_cursor(MoveDirection::Forward, MoveGranularity::Character, true);
+    model.move_cursor(MoveDirection::Forward, MoveGranularity::Character, true);
+    assert_eq!(tx(&model), "a{bc}|d");
+    model.move_cursor(MoveDirection::Backward, MoveGranularity::Word, true);
+    assert_eq!(tx(&model), "|{a}bcd");
+}
+
+#[test]
+fn move_cursor_by_word() {
+    let mut model = cm("|hello big world");
+    model.move_cursor(MoveDirection::Forward, MoveGranularity::Word, false);
+    assert_eq!(tx(&model), "hello| big world");
+    model.move_cursor(MoveDirection::Forward, MoveGranularity::Word, false);
+    assert_eq!(tx(&model), "hello big| world");
+    model.move_cursor(MoveDirection::Backward, MoveGranularity::Word, false);
+    assert_eq!(tx(&model), "hello |big world");
+}
+
+#[test]
+fn move_cursor_by_block() {
+    let mut model = cm("abc<br />d|ef<br />ghi");
+    model.move_cursor(MoveDirection::Forward, MoveGranularity::Block, false);
+    assert_eq!(tx(&model), "abc<br />def|<br />ghi");
+    model.move_cursor(MoveDirection::Forward, MoveGranularity::Block, false);
+    assert_eq!(tx(&model), "abc<br />def<br />|ghi");
+    model.move_cursor(MoveDirection::Backward, MoveGranularity::Block, true);
+    assert_eq!(tx(&model), "abc<br />def|{<br />}ghi");
+}
+
+#[test]
+fn dom_selection_points_into_text_nodes() {
+    let mut model = cm("ab<strong>cd</strong>|");
+    let update = model.select(Location::from(1), Location::from(3));
+    let selection = update.dom_selection.unwrap();
+    assert_eq!(selection.anchor, point(&[0], 1));
+    assert_eq!(selection.focus, point(&[1, 0], 1));
+}
+
+#[test]
+fn dom_selection_prefers_the_end_of_the_first_node() {
+    let model = cm("ab|<strong>cd</strong>");
+    assert_eq!(model.dom_selection().focus, point(&[0], 2));
+    let model = cm("|ab<strong>cd</strong>");
+    assert_eq!(model.dom_selection().focus, point(&[0], 0));
+}
+
+#[test]
+fn dom_selection_keeps_the_direction_of_the_selection() {
+    let model = cm("ab|{cd}");
+    let selection = model.dom_selection();
+    assert_eq!(selection.anchor, point(&[0], 4));
+    assert_eq!(selection.focus, point(&[0], 2));
+}
+
+#[test]
+fn dom_selection_in_an_empty_document_is_the_root() {
+    let model = ComposerModel::<Utf16String>::new();
+    assert_eq!(model.dom_selection().focus, point(&[], 0));
+}
+
+fn point(path: &[usize], offset: usize) -> DomPoint {
+    DomPoint {
+        handle: DomHandle::from_raw(path.to_vec()),
+        offset,
+    }
+}
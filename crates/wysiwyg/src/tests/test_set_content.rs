@@ -14,7 +14,9 @@
 
 use widestring::Utf16String;
 
-use crate::{tests::testutils_composer_model::tx, ComposerModel};
+use crate::{
+    tests::testutils_composer_model::tx, ComposerModel, SanitizePolicy,
+};
 
 use super::testutils_composer_model::cm;
 
@@ -46,3 +48,122 @@ fn clear() {
     model.clear();
     assert_eq!(tx(&model), "");
 }
+
+#[test]
+fn set_content_from_html_keeps_unknown_tags() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&Utf16String::from(
+        r#"<span data-mx-spoiler="">secret</span> <sup>2</sup>"#,
+    ));
+    assert_eq!(
+        tx(&model),
+        r#"<span data-mx-spoiler="">secret</span> <sup>2|</sup>"#
+    );
+}
+
+#[test]
+fn set_content_from_html_drops_scripts() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&Utf16String::from(
+        "a<script>alert(1)</script>",
+    ));
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn text_inside_unknown_tags_can_be_edited() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&Utf16String::from("<sup>2</sup>"));
+    model.replace_text("3".into());
+    assert_eq!(tx(&model), "<sup>23|</sup>");
+}
+
+#[test]
+fn set_content_from_html_collapses_source_whitespace() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&Utf16String::from(
+        "<ol>\n    <li>one  <b>two</b>\n    </li>\n</ol>\n",
+    ));
+    assert_eq!(tx(&model), "<ol><li>one <b>two|</b></li></ol>");
+}
+
+#[test]
+fn set_content_from_html_with_matrix_sanitization_reports_what_it_removed() {
+    let mut model = ComposerModel::new();
+    model.set_matrix_sanitization(true);
+    model.set_content_from_html(&Utf16String::from(
+        "<section>a <span class=\"x\">b</span></section>",
+    ));
+    assert_eq!(tx(&model), "a <span>b|</span>");
+    let report = model.last_sanitize_report();
+    assert_eq!(report.removed_tags, vec!["section"]);
+    assert_eq!(report.removed_attributes.len(), 1);
+}
+
+#[test]
+fn set_content_from_html_without_matrix_sanitization_keeps_unknown_tags() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html(&Utf16String::from("<section>a</section>"));
+    assert_eq!(tx(&model), "<section>a|</section>");
+    assert!(model.last_sanitize_report().is_empty());
+}
+
+#[test]
+fn set_content_from_html_uses_the_sanitize_policy_set() {
+    let mut model = ComposerModel::new();
+    let mut policy = SanitizePolicy::matrix();
+    policy.allowed_tags.push("section".to_owned());
+    model.set_sanitize_policy(Some(policy));
+    model.set_content_from_html(&Utf16String::from(
+        "<section>a<q>b</q></section>",
+    ));
+    assert_eq!(tx(&model), "<section>ab|</section>");
+    assert_eq!(model.last_sanitize_report().removed_tags, vec!["q"]);
+
+    model.set_sanitize_policy(None);
+    model.set_content_from_html(&Utf16String::from("<q>b</q>"));
+    assert_eq!(tx(&model), "<q>b|</q>");
+}
+
+#[test]
+fn set_content_from_html_lenient_corrects_misnested_tags() {
+    let mut model = ComposerModel::new();
+    model
+        .set_content_from_html_lenient(&Utf16String::from("<b>a<i>b</b>c</i>"));
+    assert_eq!(tx(&model), "<b>a<i>b</i></b>c|");
+    let corrections: Vec<String> = model
+        .last_parse_corrections()
+        .iter()
+        .map(|c| c.description.clone())
+        .collect();
+    assert_eq!(corrections, vec!["Closed <i> before </b>", "Dropped </i>"]);
+}
+
+#[test]
+fn set_content_from_html_lenient_closes_tags_left_open() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html_lenient(&Utf16String::from("a<em>b"));
+    assert_eq!(tx(&model), "a<em>b|</em>");
+    assert_eq!(model.last_parse_corrections()[0].position, Some(6));
+}
+
+#[test]
+fn set_content_from_html_lenient_of_well_formed_html_corrects_nothing() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html_lenient(&Utf16String::from("a<em>b</em>"));
+    assert_eq!(tx(&model), "a<em>b|</em>");
+    assert!(model.last_parse_corrections().is_empty());
+}
+
+#[test]
+fn set_content_from_html_lenient_drops_comments() {
+    let mut model = ComposerModel::new();
+    model.set_content_from_html_lenient(&Utf16String::from("a<!-- c -->b<b>x"));
+    assert_eq!(tx(&model), "ab<b>x|</b>");
+    let corrections: Vec<String> = model
+        .last_parse_corrections()
+        .iter()
+        .map(|c| c.description.clone())
+        .collect();
+    assert_eq!(corrections, vec!["Closed <b> at the end"]);
+}
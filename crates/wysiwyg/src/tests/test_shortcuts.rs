@@ -0,0 +1,56 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use strum::IntoEnumIterator;
+use widestring::Utf16String;
+
+use crate::{ComposerAction, ComposerModel, Shortcut};
+
+#[test]
+fn formatting_actions_have_default_shortcuts() {
+    let model = ComposerModel::<Utf16String>::new();
+    let descriptor = |action| model.shortcut(&action).map(|s| s.to_string());
+    assert_eq!(descriptor(ComposerAction::Bold).as_deref(), Some("Mod+B"));
+    assert_eq!(
+        descriptor(ComposerAction::Redo).as_deref(),
+        Some("Mod+Shift+Z")
+    );
+    assert_eq!(
+        descriptor(ComposerAction::UnIndent).as_deref(),
+        Some("Shift+Tab")
+    );
+    assert_eq!(descriptor(ComposerAction::Table), None);
+}
+
+#[test]
+fn default_shortcuts_are_all_different() {
+    let shortcuts = Shortcut::defaults();
+    for action in ComposerAction::iter() {
+        if let Some(shortcut) = shortcuts.get(&action) {
+            let same = shortcuts.values().filter(|s| *s == shortcut).count();
+            assert_eq!(same, 1, "{:?} shares its shortcut", action);
+        }
+    }
+}
+
+#[test]
+fn shortcuts_can_be_overridden_and_removed() {
+    let mut model = ComposerModel::<Utf16String>::new();
+    let alt_b = Shortcut::new("B", false, false, true);
+    model.set_shortcut(ComposerAction::Bold, Some(alt_b.clone()));
+    assert_eq!(model.shortcut(&ComposerAction::Bold), Some(&alt_b));
+    model.set_shortcut(ComposerAction::Bold, None);
+    assert_eq!(model.shortcut(&ComposerAction::Bold), None);
+    assert_eq!(model.shortcuts().len(), Shortcut::defaults().len() - 1);
+}
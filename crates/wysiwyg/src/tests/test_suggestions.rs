@@ -0,0 +1,204 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use widestring::Utf16String;
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{
+    ComposerModel, ComposerUpdate, Location, MenuAction, PatternKey,
+    SuggestionPattern, SuggestionTrigger, TriggerBoundary,
+};
+
+#[test]
+fn typing_an_at_suggests_mentions() {
+    let mut model = cm("Hello |");
+    let update = replace_text(&mut model, "@");
+    assert_eq!(update.menu_action, suggestion(PatternKey::At, "", 6, 7));
+    let update = replace_text(&mut model, "al");
+    assert_eq!(update.menu_action, suggestion(PatternKey::At, "al", 6, 9));
+}
+
+#[test]
+fn typing_a_hash_suggests_rooms() {
+    let mut model = cm("|");
+    let update = replace_text(&mut model, "#ro");
+    assert_eq!(update.menu_action, suggestion(PatternKey::Hash, "ro", 0, 3));
+}
+
+#[test]
+fn slash_commands_are_only_suggested_at_the_start() {
+    let mut model = cm("|");
+    let update = replace_text(&mut model, "/sh");
+    assert_eq!(
+        update.menu_action,
+        suggestion(PatternKey::Slash, "sh", 0, 3)
+    );
+
+    let mut model = cm("a |");
+    let update = replace_text(&mut model, "/sh");
+    assert_eq!(update.menu_action, MenuAction::None);
+}
+
+#[test]
+fn finishing_the_word_stops_suggesting() {
+    let mut model = cm("@alice|");
+    let update = replace_text(&mut model, " ");
+    assert_eq!(update.menu_action, MenuAction::None);
+}
+
+#[test]
+fn keys_inside_words_are_not_patterns() {
+    let mut model = cm("|");
+    let update = replace_text(&mut model, "me@host");
+    assert_eq!(update.menu_action, MenuAction::None);
+}
+
+#[test]
+fn moving_into_a_pattern_suggests_the_whole_word() {
+    let mut model = cm("@alice and|");
+    let update = model.select(Location::from(3), Location::from(3));
+    assert_eq!(
+        update.menu_action,
+        suggestion(PatternKey::At, "alice", 0, 6)
+    );
+}
+
+#[test]
+fn deleting_back_into_a_pattern_suggests_it() {
+    let mut model = cm("#room |");
+    let update = model.backspace();
+    assert_eq!(
+        update.menu_action,
+        suggestion(PatternKey::Hash, "room", 0, 5)
+    );
+}
+
+#[test]
+fn custom_triggers_wait_for_their_minimum_length() {
+    let mut model = cm("|");
+    model.set_suggestion_triggers(vec![SuggestionTrigger::new(
+        "!",
+        2,
+        TriggerBoundary::StartOfWord,
+    )]);
+    let update = replace_text(&mut model, "!s");
+    assert_eq!(update.menu_action, MenuAction::None);
+    let update = replace_text(&mut model, "m");
+    assert_eq!(
+        update.menu_action,
+        suggestion(PatternKey::Custom("!".to_owned()), "sm", 0, 3)
+    );
+}
+
+#[test]
+fn triggers_allowed_anywhere_match_inside_words() {
+    let mut model = cm("|");
+    model.set_suggestion_triggers(vec![SuggestionTrigger::new(
+        "!",
+        0,
+        TriggerBoundary::Anywhere,
+    )]);
+    let update = replace_text(&mut model, "see!ji");
+    assert_eq!(
+        update.menu_action,
+        suggestion(PatternKey::Custom("!".to_owned()), "ji", 3, 6)
+    );
+}
+
+#[test]
+fn the_longest_matching_prefix_wins() {
+    let mut model = cm("|");
+    let mut triggers = SuggestionTrigger::defaults();
+    triggers.push(SuggestionTrigger::new(
+        "@@",
+        0,
+        TriggerBoundary::StartOfWord,
+    ));
+    model.set_suggestion_triggers(triggers);
+    let update = replace_text(&mut model, "@@bot");
+    assert_eq!(
+        update.menu_action,
+        suggestion(PatternKey::Custom("@@".to_owned()), "bot", 0, 5)
+    );
+}
+
+#[test]
+fn removing_the_triggers_stops_suggesting() {
+    let mut model = cm("@al|");
+    let update = model.set_suggestion_triggers(Vec::new());
+    assert_eq!(update.menu_action, MenuAction::None);
+}
+
+#[test]
+fn typing_a_shortcode_suggests_emoji() {
+    let mut model = cm("Hi |");
+    let update = replace_text(&mut model, ":s");
+    assert_eq!(update.menu_action, MenuAction::None);
+    let update = replace_text(&mut model, "m");
+    assert_eq!(
+        update.menu_action,
+        suggestion(PatternKey::Colon, "sm", 3, 6)
+    );
+}
+
+#[test]
+fn accepting_an_emoji_replaces_the_shortcode() {
+    let mut model = cm("Hi |");
+    let update = replace_text(&mut model, ":smi");
+    let pattern = match update.menu_action {
+        MenuAction::Suggestion(pattern) => pattern,
+        _ => panic!("Expected a suggestion"),
+    };
+    let update = model.accept_emoji_suggestion(utf16("😄"), &pattern);
+    assert_eq!(update.menu_action, MenuAction::None);
+    assert_eq!(tx(&model), "Hi 😄|");
+    model.undo();
+    assert_eq!(tx(&model), "Hi :smi|");
+}
+
+#[test]
+fn accepting_a_stale_emoji_suggestion_does_nothing() {
+    let mut model = cm("Hi :smi|");
+    let pattern = SuggestionPattern {
+        key: PatternKey::Colon,
+        text: "sm".to_owned(),
+        start: 3,
+        end: 6,
+    };
+    let update = model.accept_emoji_suggestion(utf16("😄"), &pattern);
+    assert_eq!(update, ComposerUpdate::keep());
+    assert_eq!(tx(&model), "Hi :smi|");
+}
+
+fn replace_text(
+    model: &mut ComposerModel<Utf16String>,
+    new_text: &str,
+) -> ComposerUpdate<Utf16String> {
+    model.replace_text(utf16(new_text))
+}
+
+fn suggestion(
+    key: PatternKey,
+    text: &str,
+    start: usize,
+    end: usize,
+) -> MenuAction {
+    MenuAction::Suggestion(SuggestionPattern {
+        key,
+        text: text.to_owned(),
+        start,
+        end,
+    })
+}
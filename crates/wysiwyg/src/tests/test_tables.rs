@@ -0,0 +1,358 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{TextUpdate, ToMarkdown, ToTree};
+
+const EMPTY_CELL: &str = "<td>~</td>";
+
+fn table(rows: &[&[&str]]) -> String {
+    let mut html = String::from("<table>");
+    for cells in rows {
+        html.push_str("<tr>");
+        html.push_str(&cells.concat());
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+#[test]
+fn insert_table_puts_the_cursor_in_the_first_cell() {
+    let mut model = cm("a|");
+    model.insert_table(2, 2);
+    assert_eq!(
+        tx(&model),
+        format!(
+            "a{}",
+            table(&[&["<td>~|</td>", EMPTY_CELL], &[EMPTY_CELL, EMPTY_CELL]])
+        )
+    );
+}
+
+#[test]
+fn insert_table_replaces_the_selection() {
+    let mut model = cm("a{bc}|d");
+    model.insert_table(1, 1);
+    assert_eq!(tx(&model), format!("a{}d", table(&[&["<td>~|</td>"]])));
+}
+
+#[test]
+fn insert_table_splits_formatting_around_it() {
+    let mut model = cm("<b>a<i>b|c</i></b>d");
+    model.insert_table(1, 1);
+    assert_eq!(
+        tx(&model),
+        format!(
+            "<b>a<i>b</i></b>{}<b><i>c</i></b>d",
+            table(&[&["<td>~|</td>"]])
+        )
+    );
+}
+
+#[test]
+fn insert_table_at_the_end_of_formatting_leaves_no_empty_half() {
+    let mut model = cm("<b>ab|</b>");
+    model.insert_table(1, 1);
+    assert_eq!(
+        tx(&model),
+        format!("<b>ab</b>{}", table(&[&["<td>~|</td>"]]))
+    );
+}
+
+#[test]
+fn insert_table_inside_a_list_item_stays_in_it() {
+    let mut model = cm("<ul><li><b>a|b</b></li></ul>");
+    model.insert_table(1, 1);
+    assert_eq!(
+        tx(&model),
+        format!(
+            "<ul><li><b>a</b>{}<b>b</b></li></ul>",
+            table(&[&["<td>~|</td>"]])
+        )
+    );
+}
+
+#[test]
+fn unindent_in_a_table_in_a_nested_list_item_does_nothing() {
+    let mut model = cm("<ul><li>a<ul><li>b|</li></ul></li></ul>");
+    model.insert_table(2, 2);
+    let before = tx(&model);
+    let update = model.unindent();
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    assert_eq!(tx(&model), before);
+}
+
+#[test]
+fn html_of_empty_cells_has_no_placeholder() {
+    let mut model = cm("a|");
+    model.insert_table(1, 2);
+    model.replace_text(utf16("b"));
+    assert_eq!(
+        model.get_content_as_html(),
+        utf16(&format!("a{}", table(&[&["<td>b</td>", "<td></td>"]])))
+    );
+}
+
+#[test]
+fn insert_table_with_no_rows_or_columns_does_nothing() {
+    let mut model = cm("a|");
+    let update = model.insert_table(0, 3);
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    let update = model.insert_table(3, 0);
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn insert_table_can_be_undone() {
+    let mut model = cm("a|");
+    model.insert_table(2, 2);
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn typing_in_a_cell_adds_text_to_it() {
+    let mut model = cm("|");
+    model.insert_table(1, 2);
+    model.replace_text(utf16("x"));
+    assert_eq!(tx(&model), table(&[&["<td>~x|</td>", EMPTY_CELL]]));
+}
+
+#[test]
+fn table_next_cell_moves_along_rows_then_down() {
+    let mut model = cm("|");
+    model.insert_table(2, 2);
+    model.table_next_cell();
+    assert_eq!(
+        tx(&model),
+        table(&[&[EMPTY_CELL, "<td>~|</td>"], &[EMPTY_CELL, EMPTY_CELL]])
+    );
+    model.table_next_cell();
+    assert_eq!(
+        tx(&model),
+        table(&[&[EMPTY_CELL, EMPTY_CELL], &["<td>~|</td>", EMPTY_CELL]])
+    );
+}
+
+#[test]
+fn table_previous_cell_moves_back_up_rows() {
+    let mut model = cm(&table(&[
+        &["<td>a</td>", "<td>b</td>"],
+        &["<td>c|</td>", "<td>d</td>"],
+    ]));
+    model.table_previous_cell();
+    assert_eq!(
+        tx(&model),
+        table(&[
+            &["<td>a</td>", "<td>b|</td>"],
+            &["<td>c</td>", "<td>d</td>"]
+        ])
+    );
+}
+
+#[test]
+fn moving_past_either_end_of_the_table_does_nothing() {
+    let mut model = cm(&table(&[&["<td>a|</td>", "<td>b</td>"]]));
+    let update = model.table_previous_cell();
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    model.table_next_cell();
+    let update = model.table_next_cell();
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+    assert_eq!(tx(&model), table(&[&["<td>a</td>", "<td>b|</td>"]]));
+}
+
+#[test]
+fn moving_between_cells_outside_a_table_does_nothing() {
+    let mut model = cm("a|b");
+    let update = model.table_next_cell();
+    assert!(matches!(update.text_update, TextUpdate::Keep));
+}
+
+#[test]
+fn backspacing_the_last_character_of_a_cell_leaves_a_placeholder() {
+    let mut model = cm(&table(&[&["<td>a</td>", "<td>b|</td>"]]));
+    model.backspace();
+    assert_eq!(tx(&model), table(&[&["<td>a</td>", "<td>~|</td>"]]));
+    model.undo();
+    assert_eq!(tx(&model), table(&[&["<td>a</td>", "<td>{b}|</td>"]]));
+}
+
+#[test]
+fn backspace_keeps_the_placeholder_of_an_empty_cell() {
+    let mut model = cm("|");
+    model.insert_table(1, 2);
+    model.table_next_cell();
+    model.backspace();
+    assert_eq!(tx(&model), table(&[&[EMPTY_CELL, "<td>~|</td>"]]));
+}
+
+#[test]
+fn backspace_deletes_text_inside_a_cell() {
+    let mut model = cm(&table(&[&["<td>a</td>", "<td>bc|</td>"]]));
+    model.backspace();
+    assert_eq!(tx(&model), table(&[&["<td>a</td>", "<td>b|</td>"]]));
+}
+
+#[test]
+fn table_is_written_as_a_markdown_table() {
+    let model = cm(&table(&[
+        &["<th>a|</th>", "<th>b</th>"],
+        &["<td>1</td>", "<td>x|y</td>"],
+    ]));
+    assert_eq!(
+        model.state.dom.to_markdown().unwrap(),
+        utf16("| a | b |\n| --- | --- |\n| 1 | x\\|y |")
+    );
+}
+
+#[test]
+fn table_tree_shows_rows_and_cells() {
+    let model = cm(&table(&[&["<td>a|</td>"]]));
+    assert_eq!(
+        model.state.dom.to_tree(),
+        r#"generic [] len 1
+└>table table [0] len 1
+  └>tr table row [0, 0] len 1
+    └>td table cell [0, 0, 0] len 1
+      └>text "a" [0, 0, 0, 0] len 1
+"#
+    );
+}
+
+#[test]
+fn table_insert_row_before_keeps_the_cursor_in_its_cell() {
+    let mut model = cm(&table(&[&["<td>a</td>", "<td>b|c</td>"]]));
+    model.table_insert_row_before().unwrap();
+    assert_eq!(
+        tx(&model),
+        table(&[&[EMPTY_CELL, EMPTY_CELL], &["<td>a</td>", "<td>b|c</td>"]])
+    );
+}
+
+#[test]
+fn table_insert_row_after_keeps_the_cursor_in_its_cell() {
+    let mut model = cm(&table(&[&["<td>a</td>", "<td>{b}|c</td>"]]));
+    model.table_insert_row_after().unwrap();
+    assert_eq!(
+        tx(&model),
+        table(&[&["<td>a</td>", "<td>{b}|c</td>"], &[EMPTY_CELL, EMPTY_CELL]])
+    );
+}
+
+#[test]
+fn table_insert_column_adds_a_cell_to_every_row() {
+    let mut model = cm(&table(&[
+        &["<td>a</td>", "<td>b</td>"],
+        &["<td>c|</td>", "<td>d</td>"],
+    ]));
+    model.table_insert_column().unwrap();
+    assert_eq!(
+        tx(&model),
+        table(&[
+            &["<td>a</td>", EMPTY_CELL, "<td>b</td>"],
+            &["<td>c|</td>", EMPTY_CELL, "<td>d</td>"]
+        ])
+    );
+}
+
+#[test]
+fn table_delete_row_moves_the_cursor_to_the_next_row() {
+    let mut model = cm(&table(&[
+        &["<td>a|</td>", "<td>b</td>"],
+        &["<td>c</td>", "<td>d</td>"],
+    ]));
+    model.table_delete_row().unwrap();
+    assert_eq!(tx(&model), table(&[&["<td>c|</td>", "<td>d</td>"]]));
+}
+
+#[test]
+fn table_delete_last_row_moves_the_cursor_to_the_previous_row() {
+    let mut model = cm(&table(&[
+        &["<td>a</td>", "<td>b</td>"],
+        &["<td>c</td>", "<td>d|</td>"],
+    ]));
+    model.table_delete_row().unwrap();
+    assert_eq!(tx(&model), table(&[&["<td>a</td>", "<td>b|</td>"]]));
+}
+
+#[test]
+fn table_delete_column_moves_the_cursor_to_the_previous_column() {
+    let mut model = cm(&table(&[
+        &["<td>a</td>", "<td>b|</td>"],
+        &["<td>c</td>", "<td>d</td>"],
+    ]));
+    model.table_delete_column().unwrap();
+    assert_eq!(tx(&model), table(&[&["<td>a|</td>"], &["<td>c</td>"]]));
+}
+
+#[test]
+fn deleting_the_only_row_removes_the_table() {
+    let mut model = cm(&format!("a{}b", table(&[&["<td>x|</td>"]])));
+    model.table_delete_row().unwrap();
+    assert_eq!(tx(&model), "a|b");
+}
+
+#[test]
+fn deleting_the_only_column_removes_the_table() {
+    let mut model =
+        cm(&format!("a{}", table(&[&["<td>x|</td>"], &["<td>y</td>"]])));
+    model.table_delete_column().unwrap();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn table_editing_can_be_undone() {
+    let mut model = cm(&table(&[&["<td>a|</td>"]]));
+    model.table_insert_column().unwrap();
+    model.table_insert_row_after().unwrap();
+    model.undo();
+    model.undo();
+    assert_eq!(tx(&model), table(&[&["<td>a|</td>"]]));
+}
+
+#[test]
+fn table_editing_outside_a_table_does_nothing() {
+    let mut model = cm("a|");
+    for update in [
+        model.table_insert_row_before().unwrap(),
+        model.table_insert_row_after().unwrap(),
+        model.table_insert_column().unwrap(),
+        model.table_delete_row().unwrap(),
+        model.table_delete_column().unwrap(),
+    ] {
+        assert!(matches!(update.text_update, TextUpdate::Keep));
+    }
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn tables_set_from_html_can_be_edited() {
+    let mut model = cm("|");
+    model.set_content_from_html(&utf16(
+        "<table><caption>Scores</caption>\
+        <thead><tr><th>Name</th></tr></thead>\
+        <tbody><tr><td>Alice</td></tr></tbody></table>",
+    ));
+    model.table_insert_row_after().unwrap();
+    assert_eq!(
+        tx(&model),
+        format!(
+            "<p>Scores</p>{}",
+            table(&[&["<th>Name</th>"], &["<td>Alice|</td>"], &[EMPTY_CELL]])
+        )
+    );
+}
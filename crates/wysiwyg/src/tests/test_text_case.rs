@@ -0,0 +1,102 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::tests::testutils_composer_model::{cm, tx};
+use crate::tests::testutils_conversion::utf16;
+use crate::{Location, TextCase};
+
+#[test]
+fn upper_case_changes_only_the_selection() {
+    let mut model = cm("ab{cd}|ef");
+    model.transform_case(TextCase::Upper);
+    assert_eq!(tx(&model), "ab{CD}|ef");
+}
+
+#[test]
+fn lower_case_keeps_formatting_and_a_backwards_selection() {
+    let mut model = cm("|{A<b>BC</b><i>D}E</i>");
+    model.transform_case(TextCase::Lower);
+    assert_eq!(tx(&model), "|{a<b>bc</b><i>d}E</i>");
+}
+
+#[test]
+fn title_case_finds_words_across_formatting() {
+    let mut model = cm("{hE<b>LLO wo</b>RLD, <i>it's</i> ME}|");
+    model.transform_case(TextCase::Title);
+    assert_eq!(tx(&model), "{He<b>llo Wo</b>rld, <i>It's</i> Me}|");
+}
+
+#[test]
+fn title_case_of_part_of_a_word_lower_cases_it() {
+    let mut model = cm("HE{LLO}| WORLD");
+    model.transform_case(TextCase::Title);
+    assert_eq!(tx(&model), "HE{llo}| WORLD");
+}
+
+#[test]
+fn changing_case_keeps_mentions() {
+    let alice = "<a href=\"https://matrix.to/#/@alice:matrix.org\" \
+        contenteditable=\"false\">Alice</a>";
+    let mut model = cm(&format!("{{a {alice} b}}|"));
+    model.transform_case(TextCase::Upper);
+    assert_eq!(tx(&model), format!("{{A {alice} B}}|"));
+}
+
+#[test]
+fn selection_grows_with_text_that_gets_longer() {
+    let mut model = cm("{straße}|!");
+    model.transform_case(TextCase::Upper);
+    assert_eq!(tx(&model), "{STRASSE}|!");
+}
+
+#[test]
+fn changing_case_is_a_single_undo_step() {
+    let mut model = cm("{a<b>b</b>c}|");
+    model.transform_case(TextCase::Upper);
+    model.undo();
+    assert_eq!(tx(&model), "{a<b>b</b>c}|");
+}
+
+#[test]
+fn changing_case_with_nothing_selected_does_nothing() {
+    let mut model = cm("abc|");
+    model.transform_case(TextCase::Upper);
+    assert_eq!(tx(&model), "abc|");
+    assert!(!model.can_undo());
+}
+
+#[test]
+fn turkish_casing_uses_dotted_and_dotless_i() {
+    let mut model = cm("{istanbul IRMAK}|");
+    model.transform_case_in_locale(TextCase::Upper, Some("tr-TR"));
+    assert_eq!(tx(&model), "{İSTANBUL IRMAK}|");
+    model.transform_case_in_locale(TextCase::Lower, Some("tr"));
+    assert_eq!(tx(&model), "{istanbul ırmak}|");
+}
+
+#[test]
+fn changing_case_with_the_selection_inside_an_emoji_does_not_panic() {
+    let mut model = cm("|");
+    model.set_content_from_html(&utf16("ab\u{1f600}cd"));
+    model.select(Location::from(0), Location::from(3));
+    model.transform_case(TextCase::Upper);
+    assert_eq!(model.get_content_as_html(), utf16("AB\u{1f600}cd"));
+    model.select(Location::from(3), Location::from(6));
+    model.transform_case(TextCase::Upper);
+    assert_eq!(model.get_content_as_html(), utf16("AB\u{1f600}CD"));
+    assert_eq!(
+        model.get_selection(),
+        (Location::from(3), Location::from(6))
+    );
+}
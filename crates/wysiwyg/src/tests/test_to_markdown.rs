@@ -193,6 +193,24 @@ fn list_ordered() {
     );
 }
 
+#[test]
+fn list_ordered_with_start() {
+    assert_to_md(
+        r#"<ol start="3"><li>item1</li><li>item2</li></ol>"#,
+        r#"3. item1
+4. item2"#,
+    );
+}
+
+#[test]
+fn list_ordered_with_numbering_type() {
+    assert_to_md_no_roundtrip(
+        r#"<ol type="a"><li>item1</li><li>item2</li></ol>"#,
+        r#"1. item1
+2. item2"#,
+    );
+}
+
 #[test]
 fn list_ordered_and_unordered() {
     assert_to_md_no_roundtrip(
@@ -20,12 +20,12 @@ fn single_nested_tag_produces_tree() {
     let model = cm("<b>abc<i>def</i></b>|");
     assert_eq!(
         model.state.dom.to_tree(),
-        r#"
-├>b
-│ ├>"abc"
-│ └>i
-│   └>"def"
-└>""
+        r#"generic [] len 6
+├>b formatting [0] len 6
+│ ├>text "abc" [0, 0] len 3
+│ └>i formatting [0, 1] len 3
+│   └>text "def" [0, 1, 0] len 3
+└>text "" [1] len 0
 "#,
     );
     // TODO: trailing "" needs fixing in parse
@@ -37,17 +37,17 @@ fn multiple_tags_nested_inside_one_produce_tree() {
         cm("<ul><li>ab</li><li><b>cd</b></li><li><i><b>ef|</b></i></li></ul>");
     assert_eq!(
         model.state.dom.to_tree(),
-        r#"
-└>ul
-  ├>li
-  │ └>"ab"
-  ├>li
-  │ └>b
-  │   └>"cd"
-  └>li
-    └>i
-      └>b
-        └>"ef"
+        r#"generic [] len 6
+└>ul list [0] len 6
+  ├>li list item [0, 0] len 2
+  │ └>text "ab" [0, 0, 0] len 2
+  ├>li list item [0, 1] len 2
+  │ └>b formatting [0, 1, 0] len 2
+  │   └>text "cd" [0, 1, 0, 0] len 2
+  └>li list item [0, 2] len 2
+    └>i formatting [0, 2, 0] len 2
+      └>b formatting [0, 2, 0, 0] len 2
+        └>text "ef" [0, 2, 0, 0, 0] len 2
 "#,
     );
 }
@@ -57,12 +57,12 @@ fn tree_display_converts_zwsp() {
     let model = cm("<ol><li>ab</li><li>\u{200b}cd|</li></ol>");
     assert_eq!(
         model.state.dom.to_tree(),
-        "
-└>ol
-  ├>li
-  │ └>\"ab\"
-  └>li
-    └>\"~cd\"
+        "generic [] len 5
+└>ol list [0] len 5
+  ├>li list item [0, 0] len 2
+  │ └>text \"ab\" [0, 0, 0] len 2
+  └>li list item [0, 1] len 3
+    └>text \"~cd\" [0, 1, 0] len 3
 ",
     );
 }
@@ -72,10 +72,10 @@ fn br_within_text_shows_up_in_tree() {
     let model = cm("a<br />|b");
     assert_eq!(
         model.state.dom.to_tree(),
-        r#"
-├>"a"
-├>br
-└>"b"
+        r#"generic [] len 3
+├>text "a" [0] len 1
+├>br [1] len 1
+└>text "b" [2] len 1
 "#,
     );
 }
@@ -85,10 +85,20 @@ fn link_href_shows_up_in_tree() {
     let model = cm("Some <a href=\"https://matrix.org\">url|</a>");
     assert_eq!(
         model.state.dom.to_tree(),
-        r#"
-├>"Some "
-└>a "https://matrix.org"
-  └>"url"
+        r#"generic [] len 8
+├>text "Some " [0] len 5
+└>a link "https://matrix.org" [1] len 3
+  └>text "url" [1, 0] len 3
 "#,
     );
 }
+
+#[test]
+fn long_text_is_cut_short_in_tree() {
+    let model = cm("The quick brown fox jumps over the lazy dog|");
+    assert_eq!(
+        model.state.dom.to_tree(),
+        "generic [] len 43\n\
+        └>text \"The quick brown fox \"\u{2026} [0] len 43\n",
+    );
+}
@@ -15,7 +15,7 @@
 use crate::tests::testutils_composer_model::{cm, tx};
 
 use crate::dom::nodes::{DomNode, TextNode};
-use crate::{InlineFormatType, Location};
+use crate::{ComposerHistory, HistoryEntry, InlineFormatType, Location};
 
 use crate::tests::testutils_conversion::utf16;
 
@@ -85,7 +85,7 @@ fn undoing_action_adds_popped_state_to_next_states() {
 
     model.undo();
 
-    assert_eq!(model.next_states[0], model.state);
+    assert_eq!(model.next_states.last(), Some(&model.state));
 }
 
 #[test]
@@ -105,7 +105,7 @@ fn redoing_action_adds_popped_state_to_previous_states() {
 
     model.redo();
 
-    assert_eq!(model.previous_states[0], model.state);
+    assert_eq!(model.previous_states.last(), Some(&model.state));
 }
 
 #[test]
@@ -177,3 +177,239 @@ fn replacing_text_with_newlines_only_adds_one_to_undo_stack() {
     model.undo();
     assert_eq!(tx(&model), "abc|");
 }
+
+#[test]
+fn a_transaction_is_undone_in_one_step() {
+    let mut model = cm("a|");
+    model.begin_transaction();
+    model.replace_text(utf16("b"));
+    model.bold();
+    model.replace_text(utf16("c"));
+    model.end_transaction();
+    assert_eq!(tx(&model), "ab<strong>c|</strong>");
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+    model.redo();
+    assert_eq!(tx(&model), "ab<strong>c|</strong>");
+}
+
+#[test]
+fn nested_transactions_make_one_undo_step() {
+    let mut model = cm("a|");
+    model.transact(|model| {
+        model.replace_text(utf16("b"));
+        model.transact(|model| model.replace_text(utf16("c")));
+        model.replace_text(utf16("d"));
+    });
+    model.replace_text(utf16("e"));
+    model.undo();
+    assert_eq!(tx(&model), "abcd|");
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn a_transaction_that_changes_nothing_leaves_no_undo_step() {
+    let mut model = cm("a|");
+    model.transact(|_| {});
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn undo_ends_an_open_transaction() {
+    let mut model = cm("a|");
+    model.begin_transaction();
+    model.replace_text(utf16("b"));
+    model.undo();
+    model.replace_text(utf16("c"));
+    model.enter();
+    model.undo();
+    assert_eq!(tx(&model), "ac|");
+}
+
+#[test]
+fn typing_a_word_is_undone_in_one_step() {
+    let mut model = cm("|");
+    for c in "hello world".chars() {
+        model.replace_text(utf16(&c.to_string()));
+    }
+    assert_eq!(model.previous_states.len(), 2);
+    model.undo();
+    assert_eq!(tx(&model), "hello&nbsp;|");
+    model.undo();
+    assert_eq!(tx(&model), "|");
+}
+
+#[test]
+fn moving_the_cursor_breaks_typing_into_separate_undo_steps() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.select(Location::from(0), Location::from(0));
+    model.select(Location::from(1), Location::from(1));
+    model.replace_text(utf16("b"));
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn toggling_formatting_breaks_typing_into_separate_undo_steps() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.bold();
+    model.replace_text(utf16("b"));
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn a_typing_pause_breaks_typing_into_separate_undo_steps() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.mark_typing_pause();
+    model.replace_text(utf16("b"));
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn typing_after_other_changes_is_a_separate_undo_step() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.enter();
+    model.replace_text(utf16("b"));
+    model.backspace();
+    model.replace_text(utf16("c"));
+    model.undo();
+    assert_eq!(tx(&model), "a<br />|");
+}
+
+#[test]
+fn history_beyond_the_limit_forgets_the_oldest_states() {
+    let mut model = cm("|");
+    model.set_history_limit(Some(2));
+    model.replace_text(utf16("a "));
+    model.replace_text(utf16("b "));
+    model.replace_text(utf16("c "));
+    assert_eq!(model.history_depth(), 2);
+    model.undo();
+    model.undo();
+    assert_eq!(tx(&model), "a&nbsp;|");
+    model.undo();
+    assert_eq!(tx(&model), "a&nbsp;|");
+}
+
+#[test]
+fn lowering_the_history_limit_forgets_the_oldest_states() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a "));
+    model.replace_text(utf16("b "));
+    model.replace_text(utf16("c "));
+    assert_eq!(model.history_depth(), 3);
+    model.set_history_limit(Some(1));
+    assert_eq!(model.history_depth(), 1);
+    model.undo();
+    assert_eq!(tx(&model), "a b&nbsp;|");
+    assert_eq!(model.history_depth(), 0);
+}
+
+#[test]
+fn imported_history_can_be_undone_and_redone() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a "));
+    model.bold();
+    model.replace_text(utf16("b "));
+    model.replace_text(utf16("c "));
+    model.undo();
+    let history = model.export_history();
+
+    let mut restored = cm("|");
+    restored.import_history(&history);
+    assert_eq!(tx(&restored), tx(&model));
+    restored.undo();
+    assert_eq!(tx(&restored), "a&nbsp;|");
+    restored.redo();
+    restored.redo();
+    assert_eq!(tx(&restored), "a <strong>b c&nbsp;|</strong>");
+}
+
+#[test]
+fn importing_history_keeps_the_selection_within_the_content() {
+    let mut model = cm("|");
+    model.import_history(&ComposerHistory {
+        previous: Vec::new(),
+        current: HistoryEntry {
+            html: utf16("abc"),
+            start: 1,
+            end: 10,
+        },
+        next: Vec::new(),
+    });
+    assert_eq!(tx(&model), "a{bc}|");
+}
+
+#[test]
+fn reverting_to_a_checkpoint_restores_its_state() {
+    let mut model = cm("|");
+    model.replace_text(utf16("draft "));
+    model.checkpoint(&utf16("draft loaded"));
+    model.replace_text(utf16("more "));
+    model.enter();
+    model.replace_text(utf16("text"));
+    model.revert_to_checkpoint(&utf16("draft loaded"));
+    assert_eq!(tx(&model), "draft&nbsp;|");
+    model.undo();
+    assert_eq!(tx(&model), "draft more <br />text|");
+}
+
+#[test]
+fn reverting_to_an_unknown_checkpoint_does_nothing() {
+    let mut model = cm("abc|");
+    model.checkpoint(&utf16("a"));
+    model.revert_to_checkpoint(&utf16("b"));
+    assert_eq!(tx(&model), "abc|");
+    assert!(model.previous_states.is_empty());
+}
+
+#[test]
+fn typing_after_clearing_history_can_be_undone() {
+    let mut model = cm("|");
+    model.replace_text(utf16("a"));
+    model.clear_history();
+    model.replace_text(utf16("b"));
+    model.undo();
+    assert_eq!(tx(&model), "a|");
+}
+
+#[test]
+fn can_undo_and_redo_follow_the_history() {
+    let mut model = cm("|");
+    assert!(!model.can_undo());
+    assert!(!model.can_redo());
+    model.replace_text(utf16("a"));
+    assert!(model.can_undo());
+    assert!(!model.can_redo());
+    model.undo();
+    assert!(!model.can_undo());
+    assert!(model.can_redo());
+}
+
+#[test]
+fn previous_states_share_the_paragraphs_that_did_not_change() {
+    let mut model = cm("<p>a</p><p>b</p><p>c|</p>");
+    model.replace_text(utf16("d"));
+    let previous = model.previous_states.last().unwrap();
+    let shared = |i: usize| match (
+        &previous.dom.children()[i],
+        &model.state.dom.children()[i],
+    ) {
+        (DomNode::Container(before), DomNode::Container(after)) => {
+            before.shares_children_with(after)
+        }
+        _ => false,
+    };
+    assert!(shared(0));
+    assert!(shared(1));
+    assert!(!shared(2));
+    model.undo();
+    assert_eq!(tx(&model), "<p>a</p><p>b</p><p>c|</p>");
+}
@@ -0,0 +1,89 @@
+// Copyright 2022 The Matrix.org Foundation C.I.C.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The model with UTF-8 strings, where offsets count bytes
+
+use crate::{ComposerModel, Location, ToRawText};
+
+fn model(text: &str) -> ComposerModel<String> {
+    let mut model = ComposerModel::new();
+    model.replace_text(text.into());
+    model
+}
+
+fn select(model: &mut ComposerModel<String>, start: usize, end: usize) {
+    model.select(Location::from(start), Location::from(end));
+}
+
+#[test]
+fn typing_counts_offsets_in_bytes() {
+    let model = model("aé😀");
+    assert_eq!(
+        model.get_selection(),
+        (Location::from(7), Location::from(7))
+    );
+}
+
+#[test]
+fn backspace_removes_a_whole_emoji() {
+    let mut model = model("a😀");
+    model.backspace();
+    assert_eq!(model.get_content_as_html(), "a");
+    assert_eq!(
+        model.get_selection(),
+        (Location::from(1), Location::from(1))
+    );
+}
+
+#[test]
+fn formatting_a_multibyte_selection() {
+    let mut model = model("aéb");
+    select(&mut model, 1, 3);
+    model.bold();
+    assert_eq!(model.get_content_as_html(), "a<strong>é</strong>b");
+}
+
+#[test]
+fn formatting_with_a_cursor_then_typing() {
+    let mut model = model("aé");
+    model.bold();
+    model.replace_text("ü".into());
+    assert_eq!(model.get_content_as_html(), "aé<strong>ü</strong>");
+    assert_eq!(
+        model.get_selection(),
+        (Location::from(5), Location::from(5))
+    );
+}
+
+#[test]
+fn enter_in_a_list_then_typing() {
+    let mut model = model("é");
+    model.ordered_list().unwrap();
+    model.enter();
+    model.replace_text("ü".into());
+    assert_eq!(
+        model.get_content_as_html(),
+        "<ol><li>é</li><li>\u{200b}ü</li></ol>"
+    );
+}
+
+#[test]
+fn leaving_a_list_with_enter_then_typing() {
+    let mut model = model("é");
+    model.ordered_list().unwrap();
+    model.enter();
+    model.enter();
+    model.replace_text("ü".into());
+    assert_eq!(model.state.dom.to_raw_text(), "é\u{200b}ü");
+}
@@ -21,7 +21,12 @@ where
 {
     Keep,
     ReplaceAll(ReplaceAll<S>),
+    /// Only part of the content changed, so the rest may be left as it is
+    ReplaceRange(ReplaceRange<S>),
     Select(Selection),
+    /// The user asked for the content to be sent, e.g. by pressing Enter
+    /// in message mode. The content is unchanged.
+    Send,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,6 +39,23 @@ where
     pub end: Location,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaceRange<S>
+where
+    S: UnicodeString,
+{
+    /// Where the replaced content was, before this update. It is text in a
+    /// single text node, with more of that node's text before it.
+    pub start: Location,
+    pub end: Location,
+    /// The HTML of the text that replaces it, which has the same formatting
+    /// as the character before start
+    pub replacement_html: S,
+    /// The selection after this update
+    pub selection_start: Location,
+    pub selection_end: Location,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Selection {
     pub start: Location,
@@ -21,8 +21,8 @@ import io.element.android.wysiwyg.inputhandlers.InterceptInputConnection
 import io.element.android.wysiwyg.inputhandlers.models.EditorInputAction
 import io.element.android.wysiwyg.inputhandlers.models.InlineFormat
 import io.element.android.wysiwyg.inputhandlers.models.ReplaceTextResult
+import io.element.android.wysiwyg.inputhandlers.models.replaceText
 import io.element.android.wysiwyg.utils.*
-import io.element.android.wysiwyg.utils.HtmlToSpansParser.FormattingSpans.removeFormattingSpans
 import io.element.android.wysiwyg.viewmodel.EditorViewModel
 import uniffi.wysiwyg_composer.ActionState
 import uniffi.wysiwyg_composer.ComposerAction
@@ -291,8 +291,7 @@ class EditorEditText : TextInputEditText {
 
     private fun setTextFromComposerUpdate(result: ReplaceTextResult) {
         beginBatchEdit()
-        editableText.removeFormattingSpans()
-        editableText.replace(0, editableText.length, result.text)
+        editableText.replaceText(result)
         endBatchEdit()
     }
 
@@ -12,8 +12,9 @@ import android.widget.TextView
 import androidx.annotation.RequiresApi
 import androidx.annotation.VisibleForTesting
 import io.element.android.wysiwyg.inputhandlers.models.EditorInputAction
+import io.element.android.wysiwyg.inputhandlers.models.ReplaceTextResult
+import io.element.android.wysiwyg.inputhandlers.models.replaceText
 import io.element.android.wysiwyg.utils.EditorIndexMapper
-import io.element.android.wysiwyg.utils.HtmlToSpansParser.FormattingSpans.removeFormattingSpans
 import io.element.android.wysiwyg.viewmodel.EditorViewModel
 import kotlinx.coroutines.CoroutineScope
 import kotlinx.coroutines.Dispatchers
@@ -111,7 +112,11 @@ internal class InterceptInputConnection(
         }
 
         return if (result != null) {
-            val newText = result.text.subSequence(start, start + (text?.length ?: 0))
+            val newText = if (result.replacedRange != null) {
+                result.text
+            } else {
+                result.text.subSequence(start, start + (text?.length ?: 0))
+            }
 
             // Calculate the new composition range.
             // If the composer has inserted a zero width whitespace as a list delimiter,
@@ -124,9 +129,10 @@ internal class InterceptInputConnection(
             // Here we restore the background color spans from the IME input. This seems to be
             // important for Japanese input.
             if (text is Spannable && result.text is Spannable) {
-                copyImeHighlightSpans(text, result.text, start)
+                val offset = if (result.replacedRange != null) 0 else start
+                copyImeHighlightSpans(text, result.text, offset)
             }
-            replaceAll(result.text, compositionStart = compositionStart, compositionEnd = compositionEnd)
+            applyResult(result, compositionStart = compositionStart, compositionEnd = compositionEnd)
             setSelectionOnEditable(editable, result.selection.last, result.selection.last)
             true
         } else {
@@ -147,7 +153,7 @@ internal class InterceptInputConnection(
         }
 
         return if (result != null) {
-            replaceAll(result.text, compositionStart = end, compositionEnd = end)
+            applyResult(result, compositionStart = end, compositionEnd = end)
             setSelectionOnEditable(editable, result.selection.last, result.selection.last)
             true
         } else {
@@ -226,7 +232,7 @@ internal class InterceptInputConnection(
                 processInput(action)
             }
             if (result != null) {
-                replaceAll(result.text, 0, editable.length)
+                applyResult(result, 0, editable.length)
                 setSelectionOnEditable(editable, result.selection.first, result.selection.last)
                 setComposingRegion(result.selection.first, result.selection.last)
             }
@@ -242,7 +248,7 @@ internal class InterceptInputConnection(
                 processInput(EditorInputAction.BackPress)
             }
             if (result != null) {
-                replaceAll(result.text, 0, editable.length)
+                applyResult(result, 0, editable.length)
                 setSelectionOnEditable(editable, result.selection.first, result.selection.last)
                 setComposingRegion(result.selection.first, result.selection.last)
             }
@@ -276,14 +282,13 @@ internal class InterceptInputConnection(
         return start to end
     }
 
-    private fun replaceAll(
-        charSequence: CharSequence,
+    private fun applyResult(
+        result: ReplaceTextResult,
         compositionStart: Int,
         compositionEnd: Int,
     ) {
         beginBatchEdit()
-        editable.removeFormattingSpans()
-        editable.replace(0, editable.length, charSequence)
+        editable.replaceText(result)
         setComposingRegion(compositionStart, compositionEnd)
         endBatchEdit()
     }
@@ -1,15 +1,41 @@
 package io.element.android.wysiwyg.inputhandlers.models
 
+import android.text.Editable
 import android.text.Spanned
 import android.widget.EditText
+import io.element.android.wysiwyg.utils.EditorIndexMapper
+import io.element.android.wysiwyg.utils.HtmlToSpansParser.FormattingSpans.removeFormattingSpans
+import io.element.android.wysiwyg.utils.HtmlToSpansParser.FormattingSpans.replaceWithFormattingBefore
 import uniffi.wysiwyg_composer.TextUpdate.ReplaceAll
+import uniffi.wysiwyg_composer.TextUpdate.ReplaceRange
 
 /**
- * Mapped model of [ReplaceAll] from the Rust code to be applied to the [EditText].
+ * Mapped model of [ReplaceAll] or [ReplaceRange] from the Rust code to be applied to the
+ * [EditText].
  */
 internal data class ReplaceTextResult(
-    /** Text in [Spanned] format after being parsed from HTML. */
+    /**
+     * Text in [Spanned] format after being parsed from HTML. This is all the text, or only the
+     * text replacing [replacedRange] if it is set.
+     */
     val text: CharSequence,
     /** Selection to apply to the editor. */
     val selection: IntRange,
+    /** The indexes in the composer of the text that [text] replaces, if not all of it. */
+    val replacedRange: IntRange? = null,
 )
+
+/**
+ * Replace all the text of this [Editable] with the text of [result], or only the range of it
+ * that changed.
+ */
+internal fun Editable.replaceText(result: ReplaceTextResult) {
+    val range = result.replacedRange
+    if (range == null) {
+        removeFormattingSpans()
+        replace(0, length, result.text)
+    } else {
+        val (start, end) = EditorIndexMapper.fromComposerToEditor(range.first, range.last + 1, this)
+        replaceWithFormattingBefore(start, end, result.text)
+    }
+}
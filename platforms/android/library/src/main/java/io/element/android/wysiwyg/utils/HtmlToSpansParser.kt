@@ -238,6 +238,22 @@ internal class HtmlToSpansParser(
                 removeSpan(it)
             }
 
+        /**
+         * Replace [start]..[end] with [text], which has the formatting of the character before
+         * [start]. The spans are all exclusive, so the ones ending at [start] are stretched to
+         * cover the new text.
+         */
+        fun Editable.replaceWithFormattingBefore(start: Int, end: Int, text: CharSequence) {
+            val spansEndingAtStart = spans
+                .filter { it != ExtraCharacterSpan::class.java }
+                .flatMap { type -> getSpans(start, start, type).toList() }
+                .filter { getSpanStart(it) < start && getSpanEnd(it) == start }
+            replace(start, end, text)
+            spansEndingAtStart.forEach {
+                setSpan(it, getSpanStart(it), start + text.length, getSpanFlags(it))
+            }
+        }
+
         fun Spanned.assertOnlyAllowedSpans() =
             assert(getSpans(0, length, Any::class.java).all {
                 spans.contains(it.javaClass)
@@ -22,9 +22,25 @@ internal class EditorViewModel(
 
     private var actionStatesCallback: ((Map<ComposerAction, ActionState>) -> Unit)? = null
 
+    // The last action states reported, which partial menu state updates
+    // are merged into
+    private var currentActionStates: Map<ComposerAction, ActionState> = emptyMap()
+
     fun setActionStatesCallback(callback: ((Map<ComposerAction, ActionState>) -> Unit)?) {
         this.actionStatesCallback = callback
-        actionStates()?.let { actionStatesCallback?.invoke(it) }
+        actionStates()?.let {
+            currentActionStates = it
+            actionStatesCallback?.invoke(it)
+        }
+    }
+
+    private fun handleMenuState(menuState: MenuState?) {
+        currentActionStates = when (menuState) {
+            is MenuState.Update -> menuState.actionStates
+            is MenuState.PartialUpdate -> currentActionStates + menuState.actionStates
+            is MenuState.Keep, null -> return
+        }
+        actionStatesCallback?.invoke(currentActionStates)
     }
 
     fun updateSelection(editable: Editable, start: Int, end: Int) {
@@ -37,10 +53,7 @@ internal class EditorViewModel(
             rustErrorCollector?.onRustError(error)
             error.throwIfDebugBuild()
         }.getOrNull()
-        val menuState = update?.menuState()
-        if (menuState is MenuState.Update) {
-            actionStatesCallback?.invoke(menuState.actionStates)
-        }
+        handleMenuState(update?.menuState())
         composer?.log()
     }
 
@@ -80,18 +93,21 @@ internal class EditorViewModel(
 
         composer?.log()
 
-        val menuState = update?.menuState()
-        if (menuState is MenuState.Update) {
-            actionStatesCallback?.invoke(menuState.actionStates)
-        }
+        handleMenuState(update?.menuState())
 
         return when (val textUpdate = update?.textUpdate()) {
             is TextUpdate.ReplaceAll -> ReplaceTextResult(
                 text = stringToSpans(textUpdate.replacementHtml.string()),
                 selection = textUpdate.startUtf16Codeunit.toInt()..textUpdate.endUtf16Codeunit.toInt(),
             )
+            is TextUpdate.ReplaceRange -> ReplaceTextResult(
+                text = stringToSpans(textUpdate.replacementHtml.string()),
+                selection = textUpdate.selectionStartUtf16Codeunit.toInt()..textUpdate.selectionEndUtf16Codeunit.toInt(),
+                replacedRange = textUpdate.startUtf16Codeunit.toInt() until textUpdate.endUtf16Codeunit.toInt(),
+            )
             is TextUpdate.Select,
             is TextUpdate.Keep,
+            is TextUpdate.Send,
             null -> null
         }
     }
@@ -3,6 +3,7 @@ package io.element.android.wysiwyg.mocks
 import io.mockk.every
 import io.mockk.mockk
 import uniffi.wysiwyg_composer.ComposerUpdate
+import uniffi.wysiwyg_composer.MenuAction
 import uniffi.wysiwyg_composer.MenuState
 import uniffi.wysiwyg_composer.TextUpdate
 
@@ -10,8 +11,10 @@ object MockComposerUpdateFactory {
     fun create(
         menuState: MenuState = MenuState.Keep,
         textUpdate: TextUpdate = TextUpdate.Keep,
+        menuAction: MenuAction = MenuAction.Keep,
     ): ComposerUpdate = mockk {
         every { menuState() } returns menuState
         every { textUpdate() } returns textUpdate
+        every { menuAction() } returns menuAction
     }
 }
@@ -13,4 +13,18 @@ object MockTextUpdateFactory {
         startUtf16Codeunit = start.toUInt(),
         endUtf16Codeunit = end.toUInt()
     )
+
+    fun createReplaceRange(
+        html: String = "",
+        start: Int = 0,
+        end: Int = 0,
+        selectionStart: Int = 0,
+        selectionEnd: Int = 0,
+    ) = TextUpdate.ReplaceRange(
+        startUtf16Codeunit = start.toUInt(),
+        endUtf16Codeunit = end.toUInt(),
+        replacementHtml = html.toUShortList(),
+        selectionStartUtf16Codeunit = selectionStart.toUInt(),
+        selectionEndUtf16Codeunit = selectionEnd.toUInt(),
+    )
 }
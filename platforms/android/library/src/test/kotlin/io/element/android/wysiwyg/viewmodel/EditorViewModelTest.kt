@@ -16,6 +16,7 @@ import org.hamcrest.Matchers.notNullValue
 import org.junit.Before
 import org.junit.Test
 import uniffi.wysiwyg_composer.ActionState
+import uniffi.wysiwyg_composer.BlockType
 import uniffi.wysiwyg_composer.ComposerAction
 import uniffi.wysiwyg_composer.MenuState
 
@@ -58,7 +59,12 @@ internal class EditorViewModelTest {
 
         private val composerStateUpdate = MockComposerUpdateFactory.create(
             textUpdate = MockTextUpdateFactory.createReplaceAll(updatedParagraph, 2, 3),
-            menuState = MenuState.Update(actionStates = actionStates),
+            menuState = MenuState.Update(
+                actionStates = actionStates,
+                blockType = BlockType.PARAGRAPH,
+                linkUrl = null,
+                spansMultipleLinks = false,
+            ),
         )
         private val replaceTextResult = ReplaceTextResult(updatedParagraph, 2..3)
     }
@@ -95,6 +101,22 @@ internal class EditorViewModelTest {
         assertThat(result, equalTo(replaceTextResult))
     }
 
+    @Test
+    fun `when replace text only changes a range, it returns just that range`() {
+        composer.givenReplaceTextResult(
+            MockComposerUpdateFactory.create(
+                textUpdate = MockTextUpdateFactory.createReplaceRange("x", 2, 3, 3, 3),
+            )
+        )
+
+        val result = viewModel.processInput(EditorInputAction.ReplaceText("x"))
+
+        verify(inverse = true) {
+            composer.instance.getContentAsHtml()
+        }
+        assertThat(result, equalTo(ReplaceTextResult("x", 3..3, 2 until 3)))
+    }
+
     @Test
     fun `when process insert paragraph action, it returns a text update`() {
         composer.givenEnterResult(composerStateUpdate)
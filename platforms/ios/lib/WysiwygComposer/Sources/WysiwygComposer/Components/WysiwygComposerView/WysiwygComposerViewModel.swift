@@ -108,6 +108,8 @@ public class WysiwygComposerViewModel: WysiwygComposerViewModelProtocol, Observa
         self.maxExpandedHeight = maxExpandedHeight
         self.textColor = textColor
         model = newComposerModel()
+        // Partial menu state updates are merged into these.
+        actionStates = model.actionStates()
         // Publish composer empty state.
         $attributedContent.sink { [unowned self] content in
             self.isContentEmpty = content.text.length == 0
@@ -153,9 +155,15 @@ public extension WysiwygComposerViewModel {
         Logger.viewModel.logDebug([attributedContent.logSelection,
                                    "Apply action: \(action)"],
                                   functionName: #function)
-        let update = model.apply(action)
-        applyUpdate(update)
-        updateTextView()
+        do {
+            let update = try model.apply(action)
+            applyUpdate(update)
+            updateTextView()
+        } catch {
+            Logger.viewModel.logError(["Failed to apply action: \(action)",
+                                       error.localizedDescription],
+                                      functionName: #function)
+        }
     }
 
     /// Sets given HTML as the current content of the composer.
@@ -289,16 +297,28 @@ private extension WysiwygComposerViewModel {
                              startUtf16Codeunit: start,
                              endUtf16Codeunit: end):
             applyReplaceAll(codeUnits: codeUnits, start: start, end: end)
+        case let .replaceRange(startUtf16Codeunit: start,
+                               endUtf16Codeunit: end,
+                               replacementHtml: codeUnits,
+                               selectionStartUtf16Codeunit: selectionStart,
+                               selectionEndUtf16Codeunit: selectionEnd):
+            applyReplaceRange(start: start,
+                              end: end,
+                              codeUnits: codeUnits,
+                              selectionStart: selectionStart,
+                              selectionEnd: selectionEnd)
         case let .select(startUtf16Codeunit: start,
                          endUtf16Codeunit: end):
             applySelect(start: start, end: end)
-        case .keep:
+        case .keep, .send:
             break
         }
 
         switch update.menuState() {
-        case let .update(actionStates: actionStates):
+        case let .update(actionStates: actionStates, blockType: _, linkUrl: _, spansMultipleLinks: _):
             self.actionStates = actionStates
+        case let .partialUpdate(actionStates: changedStates):
+            self.actionStates.merge(changedStates) { _, new in new }
         default:
             break
         }
@@ -333,6 +353,61 @@ private extension WysiwygComposerViewModel {
         }
     }
 
+    /// Apply a replaceRange update to the composer. The replaced text is inside a single
+    /// text node, after some of its text, so the replacement gets the attributes of the
+    /// character before it and the rest of the attributed string is kept.
+    ///
+    /// - Parameters:
+    ///   - start: Start location of the replaced text.
+    ///   - end: End location of the replaced text.
+    ///   - codeUnits: Array of UTF16 code units representing the HTML of the replacement.
+    ///   - selectionStart: Start location for the selection.
+    ///   - selectionEnd: End location for the selection.
+    func applyReplaceRange(start: UInt32,
+                           end: UInt32,
+                           codeUnits: [UInt16],
+                           selectionStart: UInt32,
+                           selectionEnd: UInt32) {
+        do {
+            let html = String(utf16CodeUnits: codeUnits, count: codeUnits.count)
+            // The replacement is text with only these characters escaped
+            let replacement = html
+                .replacingOccurrences(of: "&lt;", with: "<")
+                .replacingOccurrences(of: "&gt;", with: ">")
+                .replacingOccurrences(of: "&amp;", with: "&")
+            let text = NSMutableAttributedString(attributedString: attributedContent.text)
+            let htmlRange = NSRange(location: Int(start), length: Int(end - start))
+            let range = try text.attributedRange(from: htmlRange,
+                                                 shouldIgnoreTrailingNewline: false)
+            guard range.location > 0 else {
+                throw AttributedRangeError.outOfBoundsHtmlIndex(index: Int(start))
+            }
+            let attributes = text.attributes(at: range.location - 1, effectiveRange: nil)
+            text.replaceCharacters(in: range,
+                                   with: NSAttributedString(string: replacement, attributes: attributes))
+            let htmlSelection = NSRange(location: Int(selectionStart),
+                                        length: Int(selectionEnd - selectionStart))
+            let textSelection = try text.attributedRange(from: htmlSelection,
+                                                         shouldIgnoreTrailingNewline: false)
+            attributedContent = WysiwygComposerAttributedContent(text: text, selection: textSelection)
+            Logger.viewModel.logDebug(["Sel(att): \(textSelection)",
+                                       "Sel: \(htmlSelection)",
+                                       "Range: \(htmlRange)",
+                                       "HTML: \"\(html)\"",
+                                       "replaceRange"],
+                                      functionName: #function)
+        } catch {
+            Logger.viewModel.logError(["Range: {\(start), \(end - start)}",
+                                       "Error: \(error.localizedDescription)",
+                                       "replaceRange"],
+                                      functionName: #function)
+            // Fall back to rebuilding all the content
+            applyReplaceAll(codeUnits: Array(model.getContentAsHtml().utf16),
+                            start: selectionStart,
+                            end: selectionEnd)
+        }
+    }
+
     /// Apply a select update to the composer
     ///
     /// - Parameters:
@@ -19,7 +19,9 @@ extension ComposerModel {
     ///
     /// - Parameters:
     ///   - action: Action to apply.
-    func apply(_ action: WysiwygAction) -> ComposerUpdateProtocol {
+    /// - Throws: `ComposerError` if the action can't be applied to the
+    ///   current selection. The model is left unchanged.
+    func apply(_ action: WysiwygAction) throws -> ComposerUpdateProtocol {
         let update: ComposerUpdateProtocol
         switch action {
         case .bold:
@@ -33,15 +35,15 @@ extension ComposerModel {
         case .inlineCode:
             update = inlineCode()
         case let .link(url: url):
-            update = setLink(newText: url)
+            update = try setLink(newText: url)
         case .undo:
             update = undo()
         case .redo:
             update = redo()
         case .orderedList:
-            update = orderedList()
+            update = try orderedList()
         case .unorderedList:
-            update = unorderedList()
+            update = try unorderedList()
         }
 
         return update
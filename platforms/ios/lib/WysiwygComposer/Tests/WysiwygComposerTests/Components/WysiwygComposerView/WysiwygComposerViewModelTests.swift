@@ -87,6 +87,26 @@ final class WysiwygComposerViewModelTests: XCTestCase {
         XCTAssertEqual(viewModel.textView?.selectedRange, NSRange(location: 1, length: 0))
     }
 
+    func testTypingInFormattedTextOnlyReplacesItsRange() {
+        _ = viewModel.replaceText(range: .zero,
+                                  replacementText: "Some bold text")
+        viewModel.textView?.attributedText = NSAttributedString(string: "Some bold text")
+        viewModel.select(range: .init(location: 10, length: 4))
+        viewModel.apply(.bold)
+        let boldAttributes = viewModel.attributedContent.text.attributes(at: 13, effectiveRange: nil)
+
+        viewModel.select(range: .init(location: 14, length: 0))
+        _ = viewModel.replaceText(range: .init(location: 14, length: 0),
+                                  replacementText: "<s")
+
+        let text = viewModel.attributedContent.text
+        XCTAssertEqual(text.string, "Some bold text<s")
+        XCTAssertEqual(text.attributes(at: 15, effectiveRange: nil) as NSDictionary,
+                       boldAttributes as NSDictionary)
+        XCTAssertEqual(viewModel.attributedContent.selection, NSRange(location: 16, length: 0))
+        XCTAssertEqual(viewModel.content.html, "Some bold <strong>text&lt;s</strong>")
+    }
+
     func testPlainTextMode() {
         _ = viewModel.replaceText(range: .zero,
                                   replacementText: "Some bold text")
@@ -27,7 +27,7 @@ final class WysiwygComposerTests: XCTestCase {
         let composer = newComposerModel()
         let update = composer.replaceText(newText: TestConstants.testStringWithEmojis)
         switch update.textUpdate() {
-        case .keep, .select:
+        case .keep, .replaceRange, .select, .send:
             XCTFail("Expected replace all HTML update")
         case let .replaceAll(replacementHtml: codeUnits,
                              startUtf16Codeunit: start,
@@ -49,7 +49,7 @@ final class WysiwygComposerTests: XCTestCase {
 
         let update = composer.backspace()
         switch update.textUpdate() {
-        case .keep, .select:
+        case .keep, .replaceRange, .select, .send:
             XCTFail("Expected replace all HTML update")
         case let .replaceAll(replacementHtml: codeUnits,
                              startUtf16Codeunit: start,
@@ -68,7 +68,7 @@ final class WysiwygComposerTests: XCTestCase {
         _ = composer.select(startUtf16Codeunit: 8, endUtf16Codeunit: 12)
         let update = composer.bold()
         switch update.textUpdate() {
-        case .keep, .select:
+        case .keep, .replaceRange, .select, .send:
             XCTFail("Expected replace all HTML update")
         case let .replaceAll(replacementHtml: codeUnits,
                              startUtf16Codeunit: start,
@@ -91,27 +91,27 @@ final class WysiwygComposerTests: XCTestCase {
         XCTAssertEqual(
             tree,
             """
-
-            ├>\"This is \"
-            ├>strong
-            │ └>\"bold\"
-            └>\" text\"
+            generic [] len 17
+            ├>text \"This is \" [0] len 8
+            ├>strong formatting [1] len 4
+            │ └>text \"bold\" [1, 0] len 4
+            └>text \" text\" [2] len 5
 
             """
         )
     }
 
     // swiftlint:disable:next function_body_length
-    func testLists() {
+    func testLists() throws {
         let composer = newComposerModel()
-        _ = composer.orderedList()
+        _ = try composer.orderedList()
         _ = composer.replaceText(newText: "Item 1")
         _ = composer.enter()
         _ = composer.replaceText(newText: "Item 2")
         // Add a third list item
         let update = composer.enter()
         switch update.textUpdate() {
-        case .keep, .select:
+        case .keep, .replaceRange, .select, .send:
             XCTFail("Expected replace all HTML update")
         case let .replaceAll(replacementHtml: codeUnits,
                              startUtf16Codeunit: start,
@@ -129,7 +129,7 @@ final class WysiwygComposerTests: XCTestCase {
         // Remove it
         let update2 = composer.enter()
         switch update2.textUpdate() {
-        case .keep, .select:
+        case .keep, .replaceRange, .select, .send:
             XCTFail("Expected replace all HTML update")
         case let .replaceAll(replacementHtml: codeUnits,
                              startUtf16Codeunit: start,
@@ -146,20 +146,25 @@ final class WysiwygComposerTests: XCTestCase {
         // Insert some text afterwards
         let update3 = composer.replaceText(newText: "Some text")
         switch update3.textUpdate() {
-        case .keep, .select:
-            XCTFail("Expected replace all HTML update")
-        case let .replaceAll(replacementHtml: codeUnits,
-                             startUtf16Codeunit: start,
-                             endUtf16Codeunit: end):
+        case .keep, .replaceAll, .select, .send:
+            XCTFail("Expected replace range HTML update")
+        case let .replaceRange(startUtf16Codeunit: start,
+                               endUtf16Codeunit: end,
+                               replacementHtml: codeUnits,
+                               selectionStartUtf16Codeunit: selectionStart,
+                               selectionEndUtf16Codeunit: selectionEnd):
             let html = String(utf16CodeUnits: codeUnits, count: codeUnits.count)
-            XCTAssertEqual(html,
-                           "<ol><li>Item 1</li><li>"
-                               + Constants.zwsp
-                               + "Item 2</li></ol>"
-                               + Constants.zwsp
-                               + "Some text")
+            XCTAssertEqual(html, "Some text")
             XCTAssertEqual(start, end)
-            XCTAssertEqual(start, 23)
+            XCTAssertEqual(start, 14)
+            XCTAssertEqual(selectionStart, selectionEnd)
+            XCTAssertEqual(selectionStart, 23)
         }
+        XCTAssertEqual(composer.getContentAsHtml(),
+                       "<ol><li>Item 1</li><li>"
+                           + Constants.zwsp
+                           + "Item 2</li></ol>"
+                           + Constants.zwsp
+                           + "Some text")
     }
 }
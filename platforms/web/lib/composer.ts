@@ -14,7 +14,7 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
-import { ComposerModel } from '../generated/wysiwyg';
+import { ComposerModel, ComposerUpdate } from '../generated/wysiwyg';
 import {
     WysiwygInputEvent,
     InputEventProcessor,
@@ -35,6 +35,23 @@ function processEvent(
     }
 }
 
+/**
+ * Apply an action that the model may refuse, e.g. a list over a selection
+ * it can't handle yet. A refused action leaves the model unchanged.
+ */
+function tryAction(
+    update: () => ComposerUpdate,
+    action: TestUtilities['traceAction'],
+    name: string,
+) {
+    try {
+        return action(update(), name);
+    } catch (e) {
+        console.error(`Could not apply ${name}: ${e}`);
+        return;
+    }
+}
+
 export function processInput(
     e: WysiwygInputEvent,
     composerModel: ComposerModel,
@@ -89,7 +106,11 @@ export function processInput(
             // InputEvent, only in the original ClipboardEvent.
             return;
         case 'insertOrderedList':
-            return action(composerModel.ordered_list(), 'ordered_list');
+            return tryAction(
+                () => composerModel.ordered_list(),
+                action,
+                'ordered_list',
+            );
         case 'insertLineBreak':
         case 'insertParagraph':
             return action(composerModel.enter(), 'enter');
@@ -103,7 +124,11 @@ export function processInput(
             }
             break;
         case 'insertUnorderedList':
-            return action(composerModel.unordered_list(), 'unordered_list');
+            return tryAction(
+                () => composerModel.unordered_list(),
+                action,
+                'unordered_list',
+            );
         case 'sendMessage':
             // We create this event type when the user presses Ctrl+Enter.
             // We don't do anythign here, but the user may want to hook in
@@ -18,6 +18,7 @@ import {
     computeNodeAndOffset,
     countCodeunit,
     getCurrentSelection,
+    replaceEditorRange,
 } from './dom';
 
 let beforeEditor: HTMLDivElement;
@@ -212,6 +213,42 @@ describe('computeNodeAndOffset', () => {
     });
 });
 
+describe('replaceEditorRange', () => {
+    it('Should replace text inside a formatted text node', () => {
+        // When
+        setEditorHtml('a<strong>bcd</strong>e');
+        const boldText = editor.childNodes[1].childNodes[0];
+        const replaced = replaceEditorRange(editor, 2, 3, '&lt;x', 4, 4);
+
+        // Then
+        expect(replaced).toBe(true);
+        expect(editor.innerHTML).toBe('a<strong>b&lt;xd</strong>e<br>');
+        expect(editor.childNodes[1].childNodes[0]).toBe(boldText);
+        const sel = document.getSelection();
+        expect(getCurrentSelection(editor, sel)).toEqual([4, 4]);
+    });
+
+    it('Should append to the end of a text node', () => {
+        // When
+        setEditorHtml('<em>ab</em>cd');
+        const replaced = replaceEditorRange(editor, 2, 2, 'x', 3, 3);
+
+        // Then
+        expect(replaced).toBe(true);
+        expect(editor.innerHTML).toBe('<em>abx</em>cd<br>');
+    });
+
+    it('Should change nothing at the start of a text node', () => {
+        // When
+        setEditorHtml('ab');
+        const replaced = replaceEditorRange(editor, 0, 0, 'x', 1, 1);
+
+        // Then
+        expect(replaced).toBe(false);
+        expect(editor.innerHTML).toBe('ab<br>');
+    });
+});
+
 describe('countCodeunit', () => {
     it('Should count ASCII', () => {
         // When
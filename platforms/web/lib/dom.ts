@@ -154,6 +154,49 @@ export function replaceEditor(
     selectContent(editor, startUtf16Codeunit, endUtf16Codeunit);
 }
 
+/**
+ * Replace the text between startUtf16Codeunit and endUtf16Codeunit with
+ * replacementHtml and select between selectionStartUtf16Codeunit and
+ * selectionEndUtf16Codeunit. The replaced text is inside a single text node,
+ * after some of its text, so the rest of the editor is left as it is.
+ *
+ * Returns false, changing nothing, if there is no such text node.
+ */
+export function replaceEditorRange(
+    editor: HTMLElement,
+    startUtf16Codeunit: number,
+    endUtf16Codeunit: number,
+    replacementHtml: string,
+    selectionStartUtf16Codeunit: number,
+    selectionEndUtf16Codeunit: number,
+): boolean {
+    const { node, offset } = computeNodeAndOffset(editor, startUtf16Codeunit);
+    const length = endUtf16Codeunit - startUtf16Codeunit;
+    if (
+        !node ||
+        node.nodeType !== Node.TEXT_NODE ||
+        offset === 0 ||
+        offset + length > (node.textContent?.length || 0)
+    ) {
+        return false;
+    }
+
+    // The replacement is escaped text, with no tags
+    const template = document.createElement('template');
+    template.innerHTML = replacementHtml;
+    (node as Text).replaceData(
+        offset,
+        length,
+        template.content.textContent || '',
+    );
+    selectContent(
+        editor,
+        selectionStartUtf16Codeunit,
+        selectionEndUtf16Codeunit,
+    );
+    return true;
+}
+
 /**
  * Find the node that is codeunits into currentNode, by traversing
  * its subnodes.
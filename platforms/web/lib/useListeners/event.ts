@@ -16,12 +16,17 @@ limitations under the License.
 
 import { MouseEvent as ReactMouseEvent } from 'react';
 
-import { ComposerModel, MenuStateUpdate } from '../../generated/wysiwyg';
+import {
+    ComposerModel,
+    MenuState,
+    MenuStateUpdate,
+} from '../../generated/wysiwyg';
 import { processInput } from '../composer';
 import {
     getCurrentSelection,
     refreshComposerView,
     replaceEditor,
+    replaceEditorRange,
 } from '../dom';
 import {
     BlockType,
@@ -110,6 +115,24 @@ export function extractActionStates(
     return mapToAllActionStates(menuStateUpdate.action_states);
 }
 
+/**
+ * Extract the action states that changed from the menu state of an update.
+ * A partial update only holds some actions, so the result must be merged
+ * into the states already known.
+ * @param {MenuState} menuState menu state from a composer update
+ * @returns {Partial<AllActionStates> | null} null if no state changed
+ */
+export function extractChangedActionStates(
+    menuState: MenuState,
+): Partial<AllActionStates> | null {
+    const menuStateUpdate = menuState.update();
+    if (menuStateUpdate) {
+        return extractActionStates(menuStateUpdate);
+    }
+    const partialUpdate = menuState.partial_update();
+    return partialUpdate ? mapToAllActionStates(partialUpdate) : null;
+}
+
 /**
  * Event listener for WysiwygInputEvent
  * @param {WysiwygInputEvent} e
@@ -132,7 +155,7 @@ export function handleInput(
 ):
     | {
           content?: string;
-          actionStates: AllActionStates | null;
+          actionStates: Partial<AllActionStates> | null;
       }
     | undefined {
     const update = processInput(
@@ -143,15 +166,40 @@ export function handleInput(
         inputEventProcessor,
     );
     if (update) {
-        const repl = update.text_update().replace_all;
+        const textUpdate = update.text_update();
+        const repl = textUpdate.replace_all;
+        const replRange = textUpdate.replace_range;
+        let content: string | undefined;
         if (repl) {
+            content = repl.replacement_html;
             replaceEditor(
                 editor,
-                repl.replacement_html,
+                content,
                 repl.start_utf16_codeunit,
                 repl.end_utf16_codeunit,
             );
-            testUtilities.setEditorHtml(repl.replacement_html);
+            testUtilities.setEditorHtml(content);
+        } else if (replRange) {
+            // Only the text that changed is replaced in the editor, but the
+            // whole content is still reported
+            content = composerModel.get_content_as_html();
+            const replaced = replaceEditorRange(
+                editor,
+                replRange.start_utf16_codeunit,
+                replRange.end_utf16_codeunit,
+                replRange.replacement_html,
+                replRange.selection_start_utf16_codeunit,
+                replRange.selection_end_utf16_codeunit,
+            );
+            if (!replaced) {
+                replaceEditor(
+                    editor,
+                    content,
+                    replRange.selection_start_utf16_codeunit,
+                    replRange.selection_end_utf16_codeunit,
+                );
+            }
+            testUtilities.setEditorHtml(content);
         }
         editor.focus();
 
@@ -160,12 +208,9 @@ export function handleInput(
             refreshComposerView(modelNode, composerModel);
         }
 
-        const menuStateUpdate = update.menu_state().update();
         const res = {
-            content: repl?.replacement_html,
-            actionStates: menuStateUpdate
-                ? extractActionStates(menuStateUpdate)
-                : null,
+            content,
+            actionStates: extractChangedActionStates(update.menu_state()),
         };
 
         return res;
@@ -183,7 +228,7 @@ export function handleSelectionChange(
     editor: HTMLElement,
     composeModel: ComposerModel,
     { traceAction, getSelectionAccordingToActions }: TestUtilities,
-): AllActionStates | undefined {
+): Partial<AllActionStates> | undefined {
     const [start, end] = getCurrentSelection(editor, document.getSelection());
 
     const prevStart = composeModel.selection_start();
@@ -215,9 +260,5 @@ export function handleSelectionChange(
     const update = composeModel.select(start, end);
     traceAction(null, 'select', start, end);
 
-    const menuStateUpdate = update.menu_state().update();
-
-    if (menuStateUpdate) {
-        return extractActionStates(menuStateUpdate);
-    }
+    return extractChangedActionStates(update.menu_state()) ?? undefined;
 }
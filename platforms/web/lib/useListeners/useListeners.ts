@@ -80,7 +80,7 @@ export function useListeners(
                 setState(({ content, actionStates }) => {
                     const newState: State = {
                         content,
-                        actionStates: res.actionStates || actionStates,
+                        actionStates: { ...actionStates, ...res.actionStates },
                     };
                     if (res.content !== undefined) {
                         newState.content = res.content;
@@ -128,9 +128,9 @@ export function useListeners(
             );
 
             if (actionStates) {
-                setState(({ content }) => ({
+                setState(({ content, actionStates: previous }) => ({
                     content,
-                    actionStates,
+                    actionStates: { ...previous, ...actionStates },
                 }));
             }
         };